@@ -1,11 +1,11 @@
 use cosmwasm_schema::write_api;
 
-use cl_vault::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use cl_vault::msg::{ContractExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 
 fn main() {
     write_api! {
         instantiate: InstantiateMsg,
-        execute: ExecuteMsg,
+        execute: ContractExecuteMsg,
         query: QueryMsg,
         migrate: MigrateMsg,
     }
@@ -1,42 +1,83 @@
 use crate::error::ContractError;
 use crate::helpers::getters::get_range_admin;
 use crate::helpers::prepend::prepend_claim_msg;
+use crate::helpers::share_seconds::init_share_seconds;
+use crate::helpers::shares::handle_share_hook_reply;
 use crate::instantiate::{
     handle_create_denom_reply, handle_instantiate, handle_instantiate_create_position_reply,
 };
 use crate::msg::{
-    ClQueryMsg, ExecuteMsg, ExtensionExecuteMsg, ExtensionQueryMsg, InstantiateMsg, MigrateMsg,
-    ModifyRangeMsg, QueryMsg,
+    ClQueryMsg, ContractExecuteMsg, ExecuteMsg, ExtensionExecuteMsg, ExtensionQueryMsg,
+    InstantiateMsg, MigrateMsg, ModifyRangeMsg, QueryMsg, SudoMsg, SuperfluidExecuteMsg,
+    WithdrawQueueQueryMsg,
 };
 use crate::query::{
-    query_active_users, query_assets_from_shares, query_dex_router, query_info, query_metadata,
-    query_pool, query_position, query_total_assets, query_total_vault_token_supply,
-    query_user_assets, query_user_balance, query_verify_tick_cache, RangeAdminResponse,
+    query_active_users, query_assets_from_shares, query_dex_router, query_idle_buffer, query_info,
+    query_lockup_duration, query_metadata, query_pool, query_position, query_position_health,
+    query_preview_deposit, query_preview_redeem, query_total_assets,
+    query_total_vault_token_supply, query_unlocking_position, query_unlocking_positions,
+    query_user_assets, query_user_balance, query_user_position, query_verify_tick_cache,
+    query_withdraw_batch, query_withdraw_queue, query_withdraw_queue_config,
+    query_withdraw_request, RangeAdminResponse,
+};
+use crate::query::{
+    query_allowed_depositors, query_auto_withdraw_status, query_collect_rewards_before_deposit,
+    query_cw20_deposit_assets, query_depositor_allowlist, query_ibc_transfers,
+    query_is_allowed_depositor, query_operator_allowance, query_operator_allowances,
+    query_referrer, query_reward_ledger, query_share_hooks, query_share_price_history,
+    query_share_seconds, query_superfluid, query_transient_state,
 };
 use crate::reply::Replies;
 use crate::vault::{
     admin::execute_admin,
+    auto_withdraw::{execute_auto_withdraw_user, handle_auto_withdraw_user_reply},
     autocompound::{execute_autocompound, handle_autocompound_reply, handle_merge_reply},
-    deposit::{execute_any_deposit, execute_exact_deposit, handle_any_deposit_swap_reply},
+    cw20_deposit::{
+        execute_cw20_any_deposit, execute_receive_cw20, handle_cw20_deposit_collect_rewards_reply,
+        handle_cw20_deposit_reply,
+    },
+    deposit::{
+        execute_any_deposit, execute_collect_rewards_and_deposit, execute_exact_deposit,
+        handle_any_deposit_swap_reply, handle_deposit_collect_rewards_reply,
+    },
     distribution::{
         execute_collect_rewards, handle_collect_incentives_reply,
         handle_collect_spread_rewards_reply,
     },
+    ibc_redeem::{execute_redeem_to_ibc, handle_ibc_lifecycle_complete, handle_ibc_transfer_reply},
+    idle_buffer::{
+        execute_buffer_withdraw, execute_rebalance_idle_buffer, get_buffer_withdraw_assets,
+    },
+    lockup::{assert_not_locked, execute_unlock, execute_withdraw_unlocked},
     merge::{
         execute_merge_position, handle_merge_create_position_reply,
         handle_merge_withdraw_position_reply,
     },
+    operator::{execute_approve_operator, execute_revoke_operator, spend_operator_allowance},
     range::{
         execute_update_range, handle_create_position, handle_swap_reply,
         handle_withdraw_position_reply,
     },
+    redeem_and_call::execute_redeem_and_call,
+    referral::execute_claim_referral_fees,
+    superfluid::{
+        execute_superfluid_delegate, execute_superfluid_undelegate, execute_superfluid_withdraw,
+        handle_superfluid_delegate_reply,
+    },
     swap::execute_swap_non_vault_funds,
     withdraw::{execute_withdraw, handle_withdraw_user_reply},
+    withdraw_queue::{
+        execute_claim_queued_withdraw, execute_process_withdraw_queue, execute_queue_withdraw,
+        handle_withdraw_queue_batch_reply, should_queue_withdraw,
+    },
 };
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response};
+use cosmwasm_std::{
+    to_json_binary, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Reply, Response, Uint128,
+};
 use cw2::set_contract_version;
+use quasar_types::cw_vault_multi_standard::extensions::lockup::{LockupExecuteMsg, LockupQueryMsg};
 use quasar_types::cw_vault_multi_standard::{VaultStandardExecuteMsg, VaultStandardQueryMsg};
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cl-vault";
@@ -55,6 +96,18 @@ pub fn instantiate(
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ContractExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ContractExecuteMsg::Receive { receive } => execute_receive_cw20(deps, env, info, receive),
+        ContractExecuteMsg::Vault(msg) => execute_vault_msg(deps, env, info, msg),
+    }
+}
+
+fn execute_vault_msg(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
@@ -62,27 +115,30 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         VaultStandardExecuteMsg::AnyDeposit {
+            amount,
+            asset,
+            recipient,
+            max_slippage,
+        } => any_deposit(
+            deps,
+            env,
+            info,
+            amount,
+            asset,
             recipient,
+            None,
             max_slippage,
-            .. // asset and amount fields are not used in this implementation, they are for CW20 tokens
-        } => execute_any_deposit(deps, env, info, recipient, max_slippage),
+        ),
         VaultStandardExecuteMsg::ExactDeposit { recipient } => {
-            execute_exact_deposit(deps, env, info, recipient)
+            exact_deposit(deps, env, info, recipient, None)
         }
         VaultStandardExecuteMsg::Redeem { recipient, amount } => {
-            prepend_claim_msg(
-                &env,
-                execute_withdraw(deps, &env, info, recipient, amount.into())?,
-            )
+            redeem(deps, env, info, recipient, amount)
         }
         VaultStandardExecuteMsg::VaultExtension(vault_msg) => {
             match vault_msg {
-                ExtensionExecuteMsg::Admin(admin_msg) => {
-                    execute_admin(deps, env, info, admin_msg)
-                }
-                ExtensionExecuteMsg::Merge(msg) => {
-                    execute_merge_position(deps, env, info, msg)
-                }
+                ExtensionExecuteMsg::Admin(admin_msg) => execute_admin(deps, env, info, admin_msg),
+                ExtensionExecuteMsg::Merge(msg) => execute_merge_position(deps, env, info, msg),
                 ExtensionExecuteMsg::Autocompound {} => {
                     prepend_claim_msg(&env, execute_autocompound(deps, &env, info)?)
                 }
@@ -112,15 +168,200 @@ pub fn execute(
                 ExtensionExecuteMsg::SwapNonVaultFunds {
                     swap_operations,
                     twap_window_seconds,
-                } => execute_swap_non_vault_funds(deps, env, info, swap_operations, twap_window_seconds),
-                ExtensionExecuteMsg::CollectRewards {} => {
-                    execute_collect_rewards(deps, env)
+                } => execute_swap_non_vault_funds(
+                    deps,
+                    env,
+                    info,
+                    swap_operations,
+                    twap_window_seconds,
+                ),
+                ExtensionExecuteMsg::CollectRewards {} => execute_collect_rewards(deps, env),
+                ExtensionExecuteMsg::ProcessWithdrawQueue { limit } => prepend_claim_msg(
+                    &env,
+                    execute_process_withdraw_queue(deps, &env, info, limit)?,
+                ),
+                ExtensionExecuteMsg::ClaimQueuedWithdraw { request_id } => {
+                    execute_claim_queued_withdraw(deps, info, request_id)
+                }
+                ExtensionExecuteMsg::ReferredAnyDeposit {
+                    amount,
+                    asset,
+                    recipient,
+                    max_slippage,
+                    referrer,
+                } => any_deposit(
+                    deps,
+                    env,
+                    info,
+                    amount,
+                    asset,
+                    recipient,
+                    Some(referrer),
+                    max_slippage,
+                ),
+                ExtensionExecuteMsg::ReferredExactDeposit {
+                    recipient,
+                    referrer,
+                } => exact_deposit(deps, env, info, recipient, Some(referrer)),
+                ExtensionExecuteMsg::ClaimReferralFees {} => {
+                    execute_claim_referral_fees(deps, info)
+                }
+                ExtensionExecuteMsg::Superfluid(msg) => match msg {
+                    SuperfluidExecuteMsg::Delegate { amount0, amount1 } => {
+                        execute_superfluid_delegate(deps, env, info, amount0, amount1)
+                    }
+                    SuperfluidExecuteMsg::Undelegate { position_id } => {
+                        execute_superfluid_undelegate(deps, env, info, position_id)
+                    }
+                    SuperfluidExecuteMsg::Withdraw { position_id } => {
+                        execute_superfluid_withdraw(deps, env, info, position_id)
+                    }
+                },
+                ExtensionExecuteMsg::RebalanceIdleBuffer {} => {
+                    prepend_claim_msg(&env, execute_rebalance_idle_buffer(deps, &env, info)?)
+                }
+                ExtensionExecuteMsg::RedeemToIbc {
+                    amount,
+                    destination,
+                } => {
+                    assert_not_locked(&deps)?;
+                    prepend_claim_msg(
+                        &env,
+                        execute_redeem_to_ibc(deps, &env, info, amount, destination)?,
+                    )
+                }
+                ExtensionExecuteMsg::RedeemAndCall {
+                    amount,
+                    contract,
+                    msg,
+                } => {
+                    assert_not_locked(&deps)?;
+                    prepend_claim_msg(
+                        &env,
+                        execute_redeem_and_call(deps, &env, info, amount, contract, msg)?,
+                    )
                 }
+                ExtensionExecuteMsg::ApproveOperator {
+                    operator,
+                    amount,
+                    expires,
+                } => execute_approve_operator(deps, &env, info, operator, amount, expires),
+                ExtensionExecuteMsg::RevokeOperator { operator } => {
+                    execute_revoke_operator(deps, info, operator)
+                }
+                ExtensionExecuteMsg::AutoWithdrawUser { user, amount } => {
+                    execute_auto_withdraw_user(deps, env, info, user, amount)
+                }
+                ExtensionExecuteMsg::RedeemFrom {
+                    owner,
+                    amount,
+                    recipient,
+                } => {
+                    let owner = deps.api.addr_validate(&owner)?;
+                    spend_operator_allowance(deps.storage, &env, &owner, &info.sender, amount)?;
+                    let operator = info.sender.clone();
+                    // redeem as if the owner sent the message, the shares are debited from the owner
+                    let info = MessageInfo {
+                        sender: owner,
+                        funds: info.funds,
+                    };
+                    Ok(redeem(deps, env, info, recipient, amount)?
+                        .add_attribute("operator", operator))
+                }
+                ExtensionExecuteMsg::Lockup(msg) => match msg {
+                    LockupExecuteMsg::Unlock { amount } => {
+                        execute_unlock(deps, &env, info, amount, "unlock")
+                    }
+                    LockupExecuteMsg::EmergencyUnlock { amount } => {
+                        execute_unlock(deps, &env, info, amount, "emergency_unlock")
+                    }
+                    LockupExecuteMsg::WithdrawUnlocked {
+                        recipient,
+                        lockup_id,
+                    } => prepend_claim_msg(
+                        &env,
+                        execute_withdraw_unlocked(deps, &env, info, recipient, lockup_id)?,
+                    ),
+                },
             }
         }
     }
 }
 
+/// Deposit any ratio of the vault assets, or a single CW20 asset if no funds are sent
+#[allow(clippy::too_many_arguments)]
+fn any_deposit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+    asset: String,
+    recipient: Option<String>,
+    referrer: Option<String>,
+    max_slippage: Decimal,
+) -> Result<Response, ContractError> {
+    // asset and amount are only used for CW20 deposits, native deposits are sent as funds
+    if info.funds.is_empty() {
+        execute_cw20_any_deposit(
+            deps,
+            env,
+            info,
+            asset,
+            amount,
+            recipient,
+            referrer,
+            max_slippage,
+        )
+    } else if query_collect_rewards_before_deposit(deps.as_ref())? {
+        execute_collect_rewards_and_deposit(
+            deps,
+            env,
+            info,
+            recipient,
+            referrer,
+            Some(max_slippage),
+        )
+    } else {
+        execute_any_deposit(deps, env, info, recipient, referrer, max_slippage)
+    }
+}
+
+/// Deposit the vault assets in the ratio of the current position
+fn exact_deposit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+    referrer: Option<String>,
+) -> Result<Response, ContractError> {
+    if query_collect_rewards_before_deposit(deps.as_ref())? {
+        execute_collect_rewards_and_deposit(deps, env, info, recipient, referrer, None)
+    } else {
+        execute_exact_deposit(deps, env, info, recipient, referrer)
+    }
+}
+
+/// Redeem `amount` shares of the sender, through the withdraw queue or the idle buffer if those apply
+pub(crate) fn redeem(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    assert_not_locked(&deps)?;
+    if should_queue_withdraw(deps.as_ref(), &env, amount)? {
+        execute_queue_withdraw(deps, &env, info, recipient, amount)
+    } else if let Some(assets) = get_buffer_withdraw_assets(deps.as_ref(), &env, amount)? {
+        execute_buffer_withdraw(deps, &env, info, recipient, amount, assets)
+    } else {
+        prepend_claim_msg(
+            &env,
+            execute_withdraw(deps, &env, info, recipient, amount.into())?,
+        )
+    }
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
     match msg {
@@ -128,11 +369,13 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractErro
             todo!()
         }
         VaultStandardQueryMsg::Info {} => Ok(to_json_binary(&query_info(deps)?)?),
-        VaultStandardQueryMsg::PreviewDeposit { assets: _ } => todo!(),
+        VaultStandardQueryMsg::PreviewDeposit { assets } => {
+            Ok(to_json_binary(&query_preview_deposit(deps, env, assets)?)?)
+        }
         VaultStandardQueryMsg::DepositRatio => todo!(),
-        VaultStandardQueryMsg::PreviewRedeem { amount: shares } => Ok(to_json_binary(
-            &query_assets_from_shares(deps, env, shares)?,
-        )?),
+        VaultStandardQueryMsg::PreviewRedeem { amount: shares } => {
+            Ok(to_json_binary(&query_preview_redeem(deps, env, shares)?)?)
+        }
         VaultStandardQueryMsg::TotalAssets {} => {
             Ok(to_json_binary(&query_total_assets(deps, env)?)?)
         }
@@ -153,6 +396,9 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractErro
                 crate::msg::UserBalanceQueryMsg::UserAssetsBalance { user } => {
                     Ok(to_json_binary(&query_user_assets(deps, env, user)?)?)
                 }
+                crate::msg::UserBalanceQueryMsg::UserPosition { user } => {
+                    Ok(to_json_binary(&query_user_position(deps, env, user)?)?)
+                }
             },
             ExtensionQueryMsg::ConcentratedLiquidity(msg) => match msg {
                 ClQueryMsg::Pool {} => Ok(to_json_binary(&query_pool(deps)?)?),
@@ -164,6 +410,7 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractErro
                     })?)
                 }
                 ClQueryMsg::VerifyTickCache => Ok(to_json_binary(&query_verify_tick_cache(deps)?)?),
+                ClQueryMsg::PositionHealth {} => Ok(to_json_binary(&query_position_health(deps)?)?),
             },
             ExtensionQueryMsg::Users {
                 start_bound_exclusive,
@@ -173,6 +420,88 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractErro
                 start_bound_exclusive.map(|s| deps.api.addr_validate(s.as_str()).unwrap()),
                 limit,
             )?)?),
+            ExtensionQueryMsg::WithdrawQueue(msg) => match msg {
+                WithdrawQueueQueryMsg::Config {} => {
+                    Ok(to_json_binary(&query_withdraw_queue_config(deps)?)?)
+                }
+                WithdrawQueueQueryMsg::Request { request_id } => {
+                    Ok(to_json_binary(&query_withdraw_request(deps, request_id)?)?)
+                }
+                WithdrawQueueQueryMsg::Queue { start_after, limit } => Ok(to_json_binary(
+                    &query_withdraw_queue(deps, start_after, limit)?,
+                )?),
+                WithdrawQueueQueryMsg::Batch { epoch } => {
+                    Ok(to_json_binary(&query_withdraw_batch(deps, epoch)?)?)
+                }
+            },
+            ExtensionQueryMsg::ShareSeconds { user, start, end } => Ok(to_json_binary(
+                &query_share_seconds(deps, env, user, start, end)?,
+            )?),
+            ExtensionQueryMsg::ShareHooks {} => Ok(to_json_binary(&query_share_hooks(deps)?)?),
+            ExtensionQueryMsg::RewardLedger {} => Ok(to_json_binary(&query_reward_ledger(deps)?)?),
+            ExtensionQueryMsg::SharePriceHistory { limit } => {
+                Ok(to_json_binary(&query_share_price_history(deps, limit)?)?)
+            }
+            ExtensionQueryMsg::CollectRewardsBeforeDeposit {} => Ok(to_json_binary(
+                &query_collect_rewards_before_deposit(deps)?,
+            )?),
+            ExtensionQueryMsg::IdleBuffer {} => Ok(to_json_binary(&query_idle_buffer(deps, env)?)?),
+            ExtensionQueryMsg::IbcTransfers { start_after, limit } => Ok(to_json_binary(
+                &query_ibc_transfers(deps, start_after, limit)?,
+            )?),
+            ExtensionQueryMsg::TransientState {} => {
+                Ok(to_json_binary(&query_transient_state(deps)?)?)
+            }
+            ExtensionQueryMsg::AutoWithdrawStatus { start_after, limit } => Ok(to_json_binary(
+                &query_auto_withdraw_status(deps, start_after, limit)?,
+            )?),
+            ExtensionQueryMsg::OperatorAllowance { owner, operator } => Ok(to_json_binary(
+                &query_operator_allowance(deps, owner, operator)?,
+            )?),
+            ExtensionQueryMsg::OperatorAllowances {
+                owner,
+                start_after,
+                limit,
+            } => Ok(to_json_binary(&query_operator_allowances(
+                deps,
+                owner,
+                start_after,
+                limit,
+            )?)?),
+            ExtensionQueryMsg::DepositorAllowlist {} => {
+                Ok(to_json_binary(&query_depositor_allowlist(deps)?)?)
+            }
+            ExtensionQueryMsg::AllowedDepositors { start_after, limit } => Ok(to_json_binary(
+                &query_allowed_depositors(deps, start_after, limit)?,
+            )?),
+            ExtensionQueryMsg::IsAllowedDepositor { address } => {
+                Ok(to_json_binary(&query_is_allowed_depositor(deps, address)?)?)
+            }
+            ExtensionQueryMsg::Referrer { address } => {
+                Ok(to_json_binary(&query_referrer(deps, address)?)?)
+            }
+            ExtensionQueryMsg::Cw20DepositAssets { start_after, limit } => Ok(to_json_binary(
+                &query_cw20_deposit_assets(deps, start_after, limit)?,
+            )?),
+            ExtensionQueryMsg::Superfluid {} => Ok(to_json_binary(&query_superfluid(deps)?)?),
+            ExtensionQueryMsg::Lockup(msg) => match msg {
+                LockupQueryMsg::UnlockingPositions {
+                    owner,
+                    start_after,
+                    limit,
+                } => Ok(to_json_binary(&query_unlocking_positions(
+                    deps,
+                    owner,
+                    start_after,
+                    limit,
+                )?)?),
+                LockupQueryMsg::UnlockingPosition { lockup_id } => {
+                    Ok(to_json_binary(&query_unlocking_position(deps, lockup_id)?)?)
+                }
+                LockupQueryMsg::LockupDuration {} => {
+                    Ok(to_json_binary(&query_lockup_duration(deps)?)?)
+                }
+            },
         },
     }
 }
@@ -189,20 +518,41 @@ pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractEr
         Replies::CreatePosition => handle_create_position(deps, env, msg.result),
         Replies::Swap => handle_swap_reply(deps, env),
         Replies::Merge => handle_merge_reply(deps, env, msg.result),
-        Replies::CreateDenom => handle_create_denom_reply(deps, msg.result),
-        Replies::WithdrawUser => handle_withdraw_user_reply(deps, msg.result),
+        Replies::CreateDenom => handle_create_denom_reply(deps, env, msg.result),
+        Replies::WithdrawUser => handle_withdraw_user_reply(deps, env, msg.result),
         Replies::WithdrawMerge => handle_merge_withdraw_position_reply(deps, env, msg.result),
         Replies::CreatePositionMerge => handle_merge_create_position_reply(deps, env, msg.result),
         Replies::Autocompound => handle_autocompound_reply(deps, env, msg.result),
         Replies::AnyDepositSwap => handle_any_deposit_swap_reply(deps, env, msg.result),
-        Replies::Unknown => unimplemented!(),
+        Replies::WithdrawQueueBatch => handle_withdraw_queue_batch_reply(deps, msg.result),
+        Replies::ShareHook => handle_share_hook_reply(msg.result),
+        Replies::IbcTransfer => handle_ibc_transfer_reply(deps, msg.result),
+        Replies::AutoWithdrawUser => handle_auto_withdraw_user_reply(deps, msg.result),
+        Replies::Cw20Deposit => handle_cw20_deposit_reply(deps, env, msg.result),
+        Replies::SuperfluidDelegate => handle_superfluid_delegate_reply(deps, msg.result),
+        Replies::DepositCollectRewards => {
+            handle_deposit_collect_rewards_reply(deps, env, msg.result)
+        }
+        Replies::Cw20DepositCollectRewards => {
+            handle_cw20_deposit_collect_rewards_reply(deps, env, msg.result)
+        }
+        Replies::Unknown => Err(ContractError::UnknownReplyId { id: msg.id }),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, _env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::IbcLifecycleComplete(msg) => handle_ibc_lifecycle_complete(deps, msg),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
     let previous_version =
         cw2::ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    // existing share balances accumulate share-seconds from the migration on
+    init_share_seconds(deps.storage, env.block.time.seconds())?;
     let response = Response::new()
         .add_attribute("migrate", "successful")
         .add_attribute("previous version", previous_version.to_string())
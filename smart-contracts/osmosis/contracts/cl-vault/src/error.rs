@@ -1,8 +1,11 @@
-use crate::state::{PoolConfig, ADMIN_ADDRESS, RANGE_ADMIN, VAULT_CONFIG};
+use crate::state::{
+    PoolConfig, ADMIN_ADDRESS, IDLE_BUFFER_CONFIG, RANGE_ADMIN, VAULT_CONFIG, WITHDRAW_QUEUE_CONFIG,
+};
 use cosmwasm_std::{
     Addr, CheckedFromRatioError, CheckedMultiplyFractionError, CheckedMultiplyRatioError, Coin,
     CoinFromStrError, ConversionOverflowError, Decimal, Decimal256, Decimal256RangeExceeded,
-    DecimalRangeExceeded, DivideByZeroError, OverflowError, StdError, Storage, Uint128,
+    DecimalRangeExceeded, DivideByZeroError, OverflowError, SignedDecimal256RangeExceeded,
+    SignedDecimalRangeExceeded, StdError, Storage, Uint128,
 };
 use cw2::VersionError;
 use cw_utils::PaymentError;
@@ -85,6 +88,12 @@ pub enum ContractError {
     #[error("Mismatch in old and new pool tokens")]
     PoolTokenMismatch {},
 
+    #[error("Vault already uses pool {pool_id}")]
+    SamePool { pool_id: u64 },
+
+    #[error("Price range is empty at the tick spacing {tick_spacing} of the new pool")]
+    EmptyMigrationRange { tick_spacing: i64 },
+
     /// This function compares the address of the message sender (caller) with the current admin
     /// address stored in the state. This provides a convenient way to verify if the caller
     /// is the admin in a single line.
@@ -124,6 +133,150 @@ pub enum ContractError {
     #[error("Invalid token for paginated query")]
     InvalidToken {},
 
+    #[error("Withdraw queue is not enabled")]
+    WithdrawQueueDisabled {},
+
+    #[error("Withdraw queue share threshold should be >0 and <=1")]
+    InvalidShareThreshold {},
+
+    #[error("Withdraw request {id} not found")]
+    WithdrawRequestNotFound { id: u64 },
+
+    #[error("Withdraw request {id} is not settled yet")]
+    WithdrawRequestNotSettled { id: u64 },
+
+    #[error("Withdraw queue is empty")]
+    EmptyWithdrawQueue {},
+
+    #[error("Withdraw epoch has not ended yet, next batch can be processed at {next_batch_time}")]
+    WithdrawEpochNotEnded { next_batch_time: u64 },
+
+    #[error("Vault shares are locked, use Unlock and WithdrawUnlocked to redeem")]
+    LockupEnabled {},
+
+    #[error("Lockup is not enabled")]
+    LockupDisabled {},
+
+    #[error("Unlocking position {id} not found")]
+    UnlockingPositionNotFound { id: u64 },
+
+    #[error("Unlocking position {id} has not finished unlocking yet")]
+    UnlockingPositionNotExpired { id: u64 },
+
+    #[error("Idle buffer is not enabled")]
+    IdleBufferDisabled {},
+
+    #[error("Idle buffer target ratio should be >0 and <1, max withdraw ratio >0 and <=1")]
+    InvalidIdleBufferRatio {},
+
+    #[error("Share hook {address} is already registered")]
+    ShareHookAlreadyRegistered { address: String },
+
+    #[error("Share hook {address} is not registered")]
+    ShareHookNotFound { address: String },
+
+    #[error("Cannot register more than {max} share hooks")]
+    MaxShareHooksReached { max: usize },
+
+    #[error("Redeem would be queued, redeems to another chain have to be withdrawn directly")]
+    IbcRedeemQueued {},
+
+    #[error("Ibc transfer timeout should be greater than zero")]
+    InvalidIbcTimeout {},
+
+    #[error("Operator {operator} is not approved to redeem shares of {owner}")]
+    OperatorNotApproved { owner: String, operator: String },
+
+    #[error("Operator allowance of {operator} has expired")]
+    OperatorAllowanceExpired { operator: String },
+
+    #[error("Operator allowance of {allowance} is lower than the requested {amount} shares")]
+    InsufficientOperatorAllowance { allowance: Uint128, amount: Uint128 },
+
+    #[error("Cannot approve yourself as operator")]
+    CannotApproveSelf {},
+
+    #[error("Cannot approve an operator with an expiration in the past")]
+    OperatorAllowanceAlreadyExpired {},
+
+    #[error(
+        "Invalid symbol {symbol}, expected 3 to 128 alphanumeric characters starting with a letter"
+    )]
+    InvalidSymbol { symbol: String },
+
+    #[error("Deposit and exit fees cannot be more than 1000 bps, got {fee_bps}")]
+    InvalidEntryExitFee { fee_bps: u16 },
+
+    #[error("Address {address} is not allowed to deposit")]
+    DepositorNotAllowed { address: String },
+
+    #[error("Referral fee share should be <=1")]
+    InvalidReferralFeeShare {},
+
+    #[error("Cannot refer your own deposit")]
+    SelfReferral {},
+
+    #[error("A referrer can only be set on deposits to yourself")]
+    ReferrerForOtherRecipient {},
+
+    #[error("Reserved {reserved}{denom} exceeds the balance of {balance}{denom}")]
+    ReservedExceedsBalance {
+        denom: String,
+        reserved: Uint128,
+        balance: Uint128,
+    },
+
+    #[error("No referral fees to claim")]
+    NoReferralFees {},
+
+    #[error("CW20 token {token} is not accepted for deposits")]
+    UnsupportedCw20Deposit { token: String },
+
+    #[error("Superfluid staking is only available for pools paired with OSMO")]
+    SuperfluidNotOsmoPaired {},
+
+    #[error("Superfluid config needs at least one validator and a max ratio below 1")]
+    InvalidSuperfluidConfig {},
+
+    #[error("Superfluid staking is not enabled")]
+    SuperfluidDisabled {},
+
+    #[error("Superfluid positions cannot hold more than {max_ratio} of the vault value")]
+    SuperfluidRatioExceeded { max_ratio: Decimal },
+
+    #[error("Superfluid position {position_id} not found")]
+    SuperfluidPositionNotFound { position_id: u64 },
+
+    #[error("Superfluid position {position_id} is already unbonding")]
+    SuperfluidPositionUnbonding { position_id: u64 },
+
+    #[error("Superfluid position {position_id} has to be undelegated first")]
+    SuperfluidPositionNotUnbonding { position_id: u64 },
+
+    #[error("Superfluid positions have to be withdrawn first")]
+    SuperfluidPositionsOpen {},
+
+    #[error("No superfluid gauge found for lock {lock_id}")]
+    SuperfluidGaugeNotFound { lock_id: u64 },
+
+    #[error("Not enough liquid assets to redeem, superfluid positions have to be unbonded first")]
+    InsufficientLiquidAssets {},
+
+    #[error("Operation {operation} is in progress")]
+    OperationInProgress { operation: String },
+
+    #[error("Transient state cannot be reset in the block its operation started")]
+    TransientStateInUse {},
+
+    #[error("Unknown reply id {id}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("Invalid time range, start {start} is after end {end}")]
+    InvalidTimeRange { start: u64, end: u64 },
+
+    #[error("Redeem would be queued, redeems that call a contract have to be withdrawn directly")]
+    RedeemAndCallQueued {},
+
     // Imported errors
     #[error("{0}")]
     PaymentError(#[from] PaymentError),
@@ -161,6 +314,12 @@ pub enum ContractError {
     #[error("{0}")]
     Decimal256RangeExceededError(#[from] Decimal256RangeExceeded),
 
+    #[error("{0}")]
+    SignedDecimalRangeExceededError(#[from] SignedDecimalRangeExceeded),
+
+    #[error("{0}")]
+    SignedDecimal256RangeExceededError(#[from] SignedDecimal256RangeExceeded),
+
     #[error("{0}")]
     TryFromIntError(#[from] TryFromIntError),
 
@@ -197,6 +356,32 @@ pub fn assert_range_admin(storage: &dyn Storage, sender: &Addr) -> Result<(), Co
     Ok(())
 }
 
+pub fn assert_withdraw_queue_keeper(
+    storage: &dyn Storage,
+    sender: &Addr,
+) -> Result<(), ContractError> {
+    let config = WITHDRAW_QUEUE_CONFIG
+        .may_load(storage)?
+        .ok_or(ContractError::WithdrawQueueDisabled {})?;
+    if config.keeper.as_ref() != Some(sender) && ADMIN_ADDRESS.load(storage)? != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+pub fn assert_idle_buffer_keeper(
+    storage: &dyn Storage,
+    sender: &Addr,
+) -> Result<(), ContractError> {
+    let config = IDLE_BUFFER_CONFIG
+        .may_load(storage)?
+        .ok_or(ContractError::IdleBufferDisabled {})?;
+    if config.keeper.as_ref() != Some(sender) && ADMIN_ADDRESS.load(storage)? != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
 pub fn assert_swap_admin(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
     let vault_config = VAULT_CONFIG.load(storage)?;
     if vault_config.swap_admin != sender {
@@ -0,0 +1,120 @@
+use cosmwasm_std::{BankMsg, Coin, Uint128};
+
+use crate::{
+    helpers::generic::sort_tokens,
+    state::{FeeDestination, VaultConfig},
+    ContractError,
+};
+
+/// The highest deposit or exit fee the admin can configure, 10%
+pub const MAX_ENTRY_EXIT_FEE_BPS: u16 = 1_000;
+const BPS_DENOMINATOR: u128 = 10_000;
+
+pub fn validate_entry_exit_fees(config: &VaultConfig) -> Result<(), ContractError> {
+    for fee_bps in [config.deposit_fee_bps, config.exit_fee_bps] {
+        if fee_bps > MAX_ENTRY_EXIT_FEE_BPS {
+            return Err(ContractError::InvalidEntryExitFee { fee_bps });
+        }
+    }
+    Ok(())
+}
+
+fn fee_of(amount: Uint128, fee_bps: u16) -> Uint128 {
+    amount.multiply_ratio(fee_bps as u128, BPS_DENOMINATOR)
+}
+
+/// Split the shares minted for a deposit into the shares of the depositor and the deposit fee
+pub fn apply_deposit_fee(config: &VaultConfig, shares: Uint128) -> (Uint128, Uint128) {
+    let fee = fee_of(shares, config.deposit_fee_bps);
+    (shares - fee, fee)
+}
+
+/// The exit fee on a payout of `amount`
+pub fn exit_fee(config: &VaultConfig, amount: Uint128) -> Uint128 {
+    fee_of(amount, config.exit_fee_bps)
+}
+
+/// Split the assets paid out for a withdrawal into the payout of the user and the exit fee
+pub fn apply_exit_fee(config: &VaultConfig, assets: Vec<Coin>) -> (Vec<Coin>, Vec<Coin>) {
+    assets
+        .into_iter()
+        .map(|asset| {
+            let fee = exit_fee(config, asset.amount);
+            (
+                Coin {
+                    denom: asset.denom.clone(),
+                    amount: asset.amount - fee,
+                },
+                Coin {
+                    denom: asset.denom,
+                    amount: fee,
+                },
+            )
+        })
+        .unzip()
+}
+
+/// The message sending an exit fee to the treasury. An exit fee that stays in the vault needs
+/// no message, it is picked up by the next autocompound as part of the unused balances.
+pub fn exit_fee_msg(config: &VaultConfig, fee: Vec<Coin>) -> Option<BankMsg> {
+    let fee: Vec<Coin> = fee.into_iter().filter(|c| !c.amount.is_zero()).collect();
+    match config.deposit_exit_fee_destination {
+        FeeDestination::Treasury if !fee.is_empty() => Some(BankMsg::Send {
+            to_address: config.treasury.to_string(),
+            amount: sort_tokens(fee),
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{coin, Addr, Decimal};
+
+    use super::*;
+
+    fn fee_config(destination: FeeDestination) -> VaultConfig {
+        VaultConfig {
+            performance_fee: Decimal::percent(20),
+            treasury: Addr::unchecked("treasury"),
+            swap_max_slippage: Decimal::percent(1),
+            dex_router: Addr::unchecked("dex_router"),
+            swap_admin: Addr::unchecked("swap_admin"),
+            twap_window_seconds: 24,
+            deposit_fee_bps: 50,
+            exit_fee_bps: 100,
+            deposit_exit_fee_destination: destination,
+            referral_fee_share: Decimal::zero(),
+        }
+    }
+
+    #[test]
+    fn fees_are_taken_in_basis_points() {
+        let config = fee_config(FeeDestination::Treasury);
+        assert_eq!(
+            apply_deposit_fee(&config, Uint128::new(10_000)),
+            (Uint128::new(9_950), Uint128::new(50))
+        );
+
+        let (payout, fee) = apply_exit_fee(&config, vec![coin(10_000, "a"), coin(99, "b")]);
+        assert_eq!(payout, vec![coin(9_900, "a"), coin(99, "b")]);
+        assert_eq!(fee, vec![coin(100, "a"), coin(0, "b")]);
+        assert_eq!(
+            exit_fee_msg(&config, fee.clone()),
+            Some(BankMsg::Send {
+                to_address: "treasury".to_string(),
+                amount: vec![coin(100, "a")]
+            })
+        );
+        assert_eq!(exit_fee_msg(&fee_config(FeeDestination::Vault), fee), None);
+
+        let mut config = config;
+        config.exit_fee_bps = MAX_ENTRY_EXIT_FEE_BPS + 1;
+        assert_eq!(
+            validate_entry_exit_fees(&config).unwrap_err(),
+            ContractError::InvalidEntryExitFee {
+                fee_bps: MAX_ENTRY_EXIT_FEE_BPS + 1
+            }
+        );
+    }
+}
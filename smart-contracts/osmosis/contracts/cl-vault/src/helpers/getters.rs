@@ -2,6 +2,7 @@ use std::cmp::min;
 use std::str::FromStr;
 
 use osmosis_std::shim::Timestamp as OsmoTimestamp;
+use osmosis_std::types::osmosis::concentratedliquidity::v1beta1::FullPositionBreakdown;
 use osmosis_std::types::osmosis::poolmanager::v1beta1::PoolmanagerQuerier;
 use osmosis_std::types::osmosis::twap::v1beta1::TwapQuerier;
 use quasar_types::pool_pair::PoolPair;
@@ -9,7 +10,10 @@ use quasar_types::pool_pair::PoolPair;
 use crate::vault::concentrated_liquidity::get_position;
 use crate::{
     math::tick::tick_to_price,
-    state::{PoolConfig, POOL_CONFIG, RANGE_ADMIN},
+    state::{
+        PoolConfig, POOL_CONFIG, RANGE_ADMIN, REFERRAL_FEES_RESERVED, VAULT_CONFIG,
+        WITHDRAW_QUEUE_RESERVED,
+    },
     ContractError,
 };
 use cosmwasm_std::{
@@ -202,12 +206,59 @@ pub fn get_single_sided_deposit_1_to_0_swap_amount(
     Ok(swap_amount)
 }
 
+/// The balances of the contract that belong to the vault, excluding any assets reserved for
+/// settled withdraw batches and referral fees that are not claimed yet
 pub fn get_unused_balances(
+    storage: &dyn Storage,
     querier: &QuerierWrapper,
     addr: &Addr,
 ) -> Result<CoinList, ContractError> {
+    let mut balances = CoinList::from_coins(querier.query_all_balances(addr.to_string())?);
+    if let Some(reserved) = WITHDRAW_QUEUE_RESERVED.may_load(storage)? {
+        sub_reserved(&mut balances, &reserved)?;
+    }
+    if let Some(reserved) = REFERRAL_FEES_RESERVED.may_load(storage)? {
+        sub_reserved(&mut balances, &reserved)?;
+    }
+    Ok(balances)
+}
+
+/// Subtract `reserved` from `balances`, the reserved assets have to be held by the contract
+fn sub_reserved(balances: &mut CoinList, reserved: &CoinList) -> Result<(), ContractError> {
+    for coin in reserved.coins() {
+        let balance = balances.find(&coin.denom).amount;
+        if coin.amount > balance {
+            return Err(ContractError::ReservedExceedsBalance {
+                denom: coin.denom,
+                reserved: coin.amount,
+                balance,
+            });
+        }
+    }
+    balances.sub(reserved)
+}
+
+/// The claimable spread rewards and incentives of `position` that are denominated in pool tokens,
+/// net of the performance fee that is taken when they are collected. Rewards in other denoms
+/// only count toward the vault assets once they are collected and swapped into pool tokens.
+pub fn get_claimable_pool_rewards(
+    storage: &dyn Storage,
+    position: &FullPositionBreakdown,
+) -> Result<CoinList, ContractError> {
+    let pool_config = POOL_CONFIG.load(storage)?;
+    let performance_fee = VAULT_CONFIG.load(storage)?.performance_fee;
+
+    let mut rewards = CoinList::new();
+    rewards.update_rewards(&position.claimable_spread_rewards)?;
+    rewards.update_rewards(&position.claimable_incentives)?;
+    rewards.sub_ratio(performance_fee)?;
+
     Ok(CoinList::from_coins(
-        querier.query_all_balances(addr.to_string())?,
+        rewards
+            .coins()
+            .into_iter()
+            .filter(|c| pool_config.pool_contains_token(&c.denom))
+            .collect(),
     ))
 }
 
@@ -216,7 +267,7 @@ pub fn get_unused_pair(
     addr: &Addr,
     pool_config: &PoolConfig,
 ) -> Result<PoolPair<Coin, Coin>, ContractError> {
-    let unused_balances = get_unused_balances(&deps.querier, addr)?;
+    let unused_balances = get_unused_balances(deps.storage, &deps.querier, addr)?;
     Ok(get_vault_funds_or_zero(&unused_balances, pool_config))
 }
 
@@ -233,7 +284,9 @@ pub fn get_unused_pair_balances(
 mod tests {
     use super::*;
     use crate::math::tick::{build_tick_exp_cache, price_to_tick};
-    use cosmwasm_std::testing::mock_dependencies;
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_dependencies_with_balance, MOCK_CONTRACT_ADDR,
+    };
     use cosmwasm_std::{coin, Decimal256};
     use std::collections::HashMap;
     use std::str::FromStr;
@@ -241,6 +294,54 @@ mod tests {
     const TOKEN0: &str = "token0";
     const TOKEN1: &str = "token1";
 
+    #[test]
+    fn unused_balances_exclude_reserved_assets() {
+        let mut deps = mock_dependencies_with_balance(&[coin(1000, TOKEN0), coin(500, TOKEN1)]);
+        WITHDRAW_QUEUE_RESERVED
+            .save(
+                deps.as_mut().storage,
+                &CoinList::from_coins(vec![coin(600, TOKEN0), coin(200, TOKEN1)]),
+            )
+            .unwrap();
+        REFERRAL_FEES_RESERVED
+            .save(
+                deps.as_mut().storage,
+                &CoinList::from_coins(vec![coin(300, TOKEN0)]),
+            )
+            .unwrap();
+
+        let balances = get_unused_balances(
+            deps.as_ref().storage,
+            &deps.as_ref().querier,
+            &Addr::unchecked(MOCK_CONTRACT_ADDR),
+        )
+        .unwrap();
+        assert_eq!(balances.find(TOKEN0).amount, Uint128::new(100));
+        assert_eq!(balances.find(TOKEN1).amount, Uint128::new(300));
+
+        // reserved assets the contract does not hold are an accounting error, not unused funds
+        REFERRAL_FEES_RESERVED
+            .save(
+                deps.as_mut().storage,
+                &CoinList::from_coins(vec![coin(300, TOKEN0), coin(1, "uion")]),
+            )
+            .unwrap();
+        let err = get_unused_balances(
+            deps.as_ref().storage,
+            &deps.as_ref().querier,
+            &Addr::unchecked(MOCK_CONTRACT_ADDR),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ReservedExceedsBalance {
+                denom: "uion".to_string(),
+                reserved: Uint128::one(),
+                balance: Uint128::zero(),
+            }
+        );
+    }
+
     #[test]
     fn test_0_to_1_swap() {
         let mut deps = mock_dependencies();
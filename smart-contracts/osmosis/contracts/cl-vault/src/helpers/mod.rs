@@ -1,5 +1,10 @@
 pub mod coinlist;
+pub mod fees;
 pub mod generic;
 pub mod getters;
 pub mod msgs;
+pub mod operation_lock;
 pub mod prepend;
+pub mod share_price;
+pub mod share_seconds;
+pub mod shares;
@@ -1,8 +1,14 @@
-use crate::{state::POSITION, ContractError};
-use cosmwasm_std::{attr, Addr, Attribute, BankMsg, Coin, Deps, Env, Uint128};
+use crate::{
+    state::{Metadata, POSITION},
+    vault::superfluid::get_superfluid_position_ids,
+    ContractError,
+};
+use cosmwasm_std::{attr, Addr, Attribute, BankMsg, Coin, CosmosMsg, Deps, Env, Uint128};
+use osmosis_std::types::cosmos::bank::v1beta1::{DenomUnit, Metadata as BankMetadata};
 use osmosis_std::types::osmosis::concentratedliquidity::v1beta1::{
     MsgCollectIncentives, MsgCollectSpreadRewards,
 };
+use osmosis_std::types::osmosis::tokenfactory::v1beta1::MsgSetDenomMetadata;
 
 pub fn refund_bank_msg(
     receiver: Addr,
@@ -40,8 +46,10 @@ pub fn refund_bank_msg(
 
 pub fn collect_incentives_msg(deps: Deps, env: Env) -> Result<MsgCollectIncentives, ContractError> {
     let position = POSITION.load(deps.storage)?;
+    let mut position_ids = vec![position.position_id];
+    position_ids.extend(get_superfluid_position_ids(deps.storage)?);
     Ok(MsgCollectIncentives {
-        position_ids: vec![position.position_id],
+        position_ids,
         sender: env.contract.address.into(),
     })
 }
@@ -51,8 +59,106 @@ pub fn collect_spread_rewards_msg(
     env: Env,
 ) -> Result<MsgCollectSpreadRewards, ContractError> {
     let position = POSITION.load(deps.storage)?;
+    let mut position_ids = vec![position.position_id];
+    position_ids.extend(get_superfluid_position_ids(deps.storage)?);
     Ok(MsgCollectSpreadRewards {
-        position_ids: vec![position.position_id],
+        position_ids,
         sender: env.contract.address.into(),
     })
 }
+
+/// The exponent of the display unit of the vault share token
+pub const VAULT_DENOM_EXPONENT: u32 = 6;
+
+/// The symbol of the vault share token, the subdenom of the vault denom if the metadata sets none
+pub fn vault_denom_symbol(metadata: &Metadata, vault_denom: &str) -> String {
+    metadata.symbol.clone().unwrap_or_else(|| {
+        vault_denom
+            .rsplit('/')
+            .next()
+            .unwrap_or(vault_denom)
+            .to_string()
+    })
+}
+
+/// Returns an error if `symbol` cannot be used as the display denom of the vault share token
+pub fn validate_symbol(symbol: &str) -> Result<(), ContractError> {
+    let valid = (3..=128).contains(&symbol.len())
+        && symbol.starts_with(|c: char| c.is_ascii_alphabetic())
+        && symbol.chars().all(|c| c.is_ascii_alphanumeric());
+    if !valid {
+        return Err(ContractError::InvalidSymbol {
+            symbol: symbol.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Set the bank metadata of the vault share token, so wallets and explorers show the shares with
+/// the vault name and symbol. The display unit is the lowercase symbol with VAULT_DENOM_EXPONENT.
+pub fn set_denom_metadata_msg(env: &Env, vault_denom: &str, metadata: &Metadata) -> CosmosMsg {
+    let symbol = vault_denom_symbol(metadata, vault_denom);
+    let display = symbol.to_lowercase();
+    MsgSetDenomMetadata {
+        sender: env.contract.address.to_string(),
+        metadata: Some(BankMetadata {
+            description: metadata.thesis.clone(),
+            denom_units: vec![
+                DenomUnit {
+                    denom: vault_denom.to_string(),
+                    exponent: 0,
+                    aliases: vec![],
+                },
+                DenomUnit {
+                    denom: display.clone(),
+                    exponent: VAULT_DENOM_EXPONENT,
+                    aliases: vec![],
+                },
+            ],
+            base: vault_denom.to_string(),
+            display,
+            name: metadata.name.clone(),
+            symbol,
+            uri: String::new(),
+            uri_hash: String::new(),
+        }),
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::mock_env;
+
+    use super::*;
+
+    #[test]
+    fn set_denom_metadata_msg_derives_units_from_metadata() {
+        let vault_denom = "factory/contract/vaulttoken";
+        let mut metadata = Metadata {
+            thesis: "a wide range".to_string(),
+            name: "ATOM/OSMO wide".to_string(),
+            symbol: None,
+        };
+        let env = mock_env();
+
+        let CosmosMsg::Stargate { value, .. } =
+            set_denom_metadata_msg(&env, vault_denom, &metadata)
+        else {
+            panic!("expected a stargate msg")
+        };
+        let msg: MsgSetDenomMetadata = prost::Message::decode(value.as_slice()).unwrap();
+        let bank_metadata = msg.metadata.unwrap();
+        assert_eq!(bank_metadata.base, vault_denom);
+        assert_eq!(bank_metadata.symbol, "vaulttoken");
+        assert_eq!(bank_metadata.display, "vaulttoken");
+        assert_eq!(bank_metadata.description, "a wide range");
+        assert_eq!(bank_metadata.denom_units[1].exponent, VAULT_DENOM_EXPONENT);
+
+        metadata.symbol = Some("cvATOM".to_string());
+        assert_eq!(vault_denom_symbol(&metadata, vault_denom), "cvATOM");
+        assert!(validate_symbol("cvATOM").is_ok());
+        assert!(validate_symbol("cv ATOM").is_err());
+        assert!(validate_symbol("1cv").is_err());
+    }
+}
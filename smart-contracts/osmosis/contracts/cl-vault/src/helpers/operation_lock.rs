@@ -0,0 +1,59 @@
+use cosmwasm_std::{Env, Storage};
+
+use crate::{
+    state::{OperationLock, OPERATION_LOCK},
+    ContractError,
+};
+
+/// Take the operation lock for a flow that keeps state across replies. Only one such flow can run
+/// at a time, so a flow that is re-entered or started halfway through another one errors instead
+/// of overwriting its state.
+pub fn acquire_operation_lock(
+    storage: &mut dyn Storage,
+    env: &Env,
+    operation: &str,
+) -> Result<(), ContractError> {
+    if let Some(lock) = OPERATION_LOCK.may_load(storage)? {
+        return Err(ContractError::OperationInProgress {
+            operation: lock.operation,
+        });
+    }
+    OPERATION_LOCK.save(
+        storage,
+        &OperationLock {
+            operation: operation.to_string(),
+            height: env.block.height,
+        },
+    )?;
+    Ok(())
+}
+
+/// Release the operation lock in the final reply of a flow
+pub fn release_operation_lock(storage: &mut dyn Storage) {
+    OPERATION_LOCK.remove(storage);
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+
+    use super::*;
+
+    #[test]
+    fn operation_lock_blocks_overlapping_operations() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        acquire_operation_lock(deps.as_mut().storage, &env, "withdraw").unwrap();
+        let err = acquire_operation_lock(deps.as_mut().storage, &env, "modify_range").unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::OperationInProgress {
+                operation: "withdraw".to_string()
+            }
+        );
+
+        release_operation_lock(deps.as_mut().storage);
+        acquire_operation_lock(deps.as_mut().storage, &env, "modify_range").unwrap();
+    }
+}
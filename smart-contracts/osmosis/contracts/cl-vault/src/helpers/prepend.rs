@@ -7,15 +7,18 @@ use crate::{msg::ExecuteMsg, ContractError};
 /// cause Osmosis to collect rewards anyway, such as fully withdrawing a position
 /// or adding funds into a position
 pub fn prepend_claim_msg(env: &Env, response: Response) -> Result<Response, ContractError> {
-    let claim_msg = CosmosMsg::Wasm(cosmwasm_std::WasmMsg::Execute {
+    Ok(prepend_msg(response, SubMsg::new(claim_msg(env)?)))
+}
+
+/// A callback to the contract to claim any rewards
+pub fn claim_msg(env: &Env) -> Result<CosmosMsg, ContractError> {
+    Ok(CosmosMsg::Wasm(cosmwasm_std::WasmMsg::Execute {
         contract_addr: env.contract.address.to_string(),
         msg: to_json_binary(&ExecuteMsg::VaultExtension(
             crate::msg::ExtensionExecuteMsg::CollectRewards {},
         ))?,
         funds: vec![],
-    });
-
-    Ok(prepend_msg(response, SubMsg::new(claim_msg)))
+    }))
 }
 
 /// Prepend a msg to the start of the messages in a response
@@ -0,0 +1,223 @@
+use cosmwasm_std::{Decimal, Deps, DepsMut, Env, SignedDecimal, Storage};
+
+use crate::{
+    helpers::{coinlist::CoinList, getters::get_value_wrt_asset0},
+    query::{query_total_assets, query_total_vault_token_supply},
+    state::{SharePriceSnapshot, SHARE_PRICE_SNAPSHOTS},
+    ContractError,
+};
+
+/// The maximum amount of snapshots kept, the oldest snapshot is dropped once this is reached.
+/// The snapshot interval and max age keep the history below it.
+pub const MAX_SHARE_PRICE_SNAPSHOTS: u32 = 800;
+pub const DEFAULT_SHARE_PRICE_HISTORY_LIMIT: u32 = 100;
+
+pub const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+pub const SEVEN_DAYS_SECONDS: u64 = 7 * 24 * 60 * 60;
+pub const THIRTY_DAYS_SECONDS: u64 = 30 * 24 * 60 * 60;
+/// At most one snapshot is kept per interval, a later snapshot in the same interval replaces it
+pub const SHARE_PRICE_SNAPSHOT_INTERVAL: u64 = 60 * 60;
+/// Snapshots older than this are dropped, except for the newest of them, which stays as the start
+/// of the 30 day window
+pub const MAX_SHARE_PRICE_SNAPSHOT_AGE: u64 = THIRTY_DAYS_SECONDS + 24 * 60 * 60;
+
+/// Take a snapshot of the current share price. `pending_outflows` are assets that are still held
+/// by the contract but already on their way out in this transaction, such as strategist fees.
+pub fn get_share_price_snapshot(
+    deps: Deps,
+    env: &Env,
+    pending_outflows: &CoinList,
+) -> Result<SharePriceSnapshot, ContractError> {
+    let total_assets = query_total_assets(deps, env.clone())?;
+    let mut token0 = total_assets.token0;
+    let mut token1 = total_assets.token1;
+    token0.amount = token0
+        .amount
+        .saturating_sub(pending_outflows.find(&token0.denom).amount);
+    token1.amount = token1
+        .amount
+        .saturating_sub(pending_outflows.find(&token1.denom).amount);
+
+    let total_shares = query_total_vault_token_supply(deps)?.total;
+    let value = get_value_wrt_asset0(deps.storage, &deps.querier, token0.amount, token1.amount)?;
+    let price_per_share = if total_shares.is_zero() {
+        Decimal::zero()
+    } else {
+        Decimal::checked_from_ratio(value, total_shares)?
+    };
+
+    Ok(SharePriceSnapshot {
+        timestamp: env.block.time.seconds(),
+        token0,
+        token1,
+        total_shares,
+        price_per_share,
+    })
+}
+
+/// Record a snapshot of the current share price in the history
+pub fn record_share_price_snapshot(
+    deps: DepsMut,
+    env: &Env,
+    pending_outflows: &CoinList,
+) -> Result<SharePriceSnapshot, ContractError> {
+    let snapshot = get_share_price_snapshot(deps.as_ref(), env, pending_outflows)?;
+    push_share_price_snapshot(deps.storage, &snapshot)?;
+    Ok(snapshot)
+}
+
+pub fn push_share_price_snapshot(
+    storage: &mut dyn Storage,
+    snapshot: &SharePriceSnapshot,
+) -> Result<(), ContractError> {
+    let interval = snapshot.timestamp / SHARE_PRICE_SNAPSHOT_INTERVAL;
+    if let Some(last) = SHARE_PRICE_SNAPSHOTS.back(storage)? {
+        if last.timestamp / SHARE_PRICE_SNAPSHOT_INTERVAL == interval {
+            SHARE_PRICE_SNAPSHOTS.pop_back(storage)?;
+        }
+    }
+    SHARE_PRICE_SNAPSHOTS.push_back(storage, snapshot)?;
+
+    while let Some(next) = SHARE_PRICE_SNAPSHOTS.get(storage, 1)? {
+        if next.timestamp.saturating_add(MAX_SHARE_PRICE_SNAPSHOT_AGE) > snapshot.timestamp {
+            break;
+        }
+        SHARE_PRICE_SNAPSHOTS.pop_front(storage)?;
+    }
+    while SHARE_PRICE_SNAPSHOTS.len(storage)? > MAX_SHARE_PRICE_SNAPSHOTS {
+        SHARE_PRICE_SNAPSHOTS.pop_front(storage)?;
+    }
+    Ok(())
+}
+
+/// The annualised return of the share price between the latest snapshot and the latest snapshot
+/// at least `window_seconds` older. None if the history does not cover the window yet.
+pub fn get_annualised_return(
+    snapshots: &[SharePriceSnapshot],
+    window_seconds: u64,
+) -> Result<Option<SignedDecimal>, ContractError> {
+    let Some(end) = snapshots.last() else {
+        return Ok(None);
+    };
+    let Some(start) = snapshots
+        .iter()
+        .rev()
+        .find(|s| s.timestamp.saturating_add(window_seconds) <= end.timestamp)
+    else {
+        return Ok(None);
+    };
+    if start.price_per_share.is_zero() || start.timestamp == end.timestamp {
+        return Ok(None);
+    }
+
+    let growth = SignedDecimal::try_from(end.price_per_share.checked_div(start.price_per_share)?)?
+        .checked_sub(SignedDecimal::one())?;
+    let annualise = SignedDecimal::checked_from_ratio(
+        i128::from(SECONDS_PER_YEAR),
+        i128::from(end.timestamp - start.timestamp),
+    )?;
+    Ok(Some(growth.checked_mul(annualise)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{coin, testing::mock_dependencies, Uint128};
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn snapshot(timestamp: u64, price_per_share: &str) -> SharePriceSnapshot {
+        SharePriceSnapshot {
+            timestamp,
+            token0: coin(0, "token0"),
+            token1: coin(0, "token1"),
+            total_shares: Uint128::zero(),
+            price_per_share: Decimal::from_str(price_per_share).unwrap(),
+        }
+    }
+
+    #[test]
+    fn snapshots_are_rate_limited_and_pruned_by_age() {
+        let mut deps = mock_dependencies();
+        let hour = SHARE_PRICE_SNAPSHOT_INTERVAL;
+
+        // snapshots within the same interval replace each other, so spamming them keeps one
+        for i in 0..1000 {
+            push_share_price_snapshot(deps.as_mut().storage, &snapshot(i, "1")).unwrap();
+        }
+        assert_eq!(SHARE_PRICE_SNAPSHOTS.len(deps.as_ref().storage).unwrap(), 1);
+        assert_eq!(
+            SHARE_PRICE_SNAPSHOTS
+                .back(deps.as_ref().storage)
+                .unwrap()
+                .unwrap()
+                .timestamp,
+            999
+        );
+
+        // an hourly history of 40 days keeps the last 31 days and the snapshot before them
+        for h in 1..=40 * 24 {
+            push_share_price_snapshot(deps.as_mut().storage, &snapshot(h * hour, "1")).unwrap();
+        }
+        assert_eq!(
+            SHARE_PRICE_SNAPSHOTS.len(deps.as_ref().storage).unwrap(),
+            31 * 24 + 1
+        );
+        assert_eq!(
+            SHARE_PRICE_SNAPSHOTS
+                .front(deps.as_ref().storage)
+                .unwrap()
+                .unwrap()
+                .timestamp,
+            9 * 24 * hour
+        );
+        let snapshots = SHARE_PRICE_SNAPSHOTS
+            .iter(deps.as_ref().storage)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert!(get_annualised_return(&snapshots, THIRTY_DAYS_SECONDS)
+            .unwrap()
+            .is_some());
+
+        // after a long pause the last snapshot before it is kept as the start of the window
+        push_share_price_snapshot(deps.as_mut().storage, &snapshot(100 * 24 * hour, "1")).unwrap();
+        assert_eq!(SHARE_PRICE_SNAPSHOTS.len(deps.as_ref().storage).unwrap(), 2);
+    }
+
+    #[test]
+    fn annualised_return_over_window() {
+        let day = 24 * 60 * 60;
+        let snapshots = vec![
+            snapshot(0, "1"),
+            snapshot(20 * day, "0.99"),
+            snapshot(28 * day, "1"),
+            snapshot(35 * day, "1.01"),
+        ];
+
+        // 7 days: 1% over 7 days
+        assert_eq!(
+            get_annualised_return(&snapshots, SEVEN_DAYS_SECONDS).unwrap(),
+            Some(SignedDecimal::percent(1) * SignedDecimal::from_ratio(365i128, 7i128))
+        );
+        // 30 days: the latest snapshot at least 30 days old is at day 0, 1% over 35 days
+        assert_eq!(
+            get_annualised_return(&snapshots, THIRTY_DAYS_SECONDS).unwrap(),
+            Some(SignedDecimal::percent(1) * SignedDecimal::from_ratio(365i128, 35i128))
+        );
+
+        // losses are negative
+        let apr = get_annualised_return(&snapshots[..2], 10 * day)
+            .unwrap()
+            .unwrap();
+        assert!(apr.is_negative());
+
+        // a history shorter than the window has no return
+        assert!(get_annualised_return(&snapshots[1..], THIRTY_DAYS_SECONDS)
+            .unwrap()
+            .is_none());
+        assert!(get_annualised_return(&[], SEVEN_DAYS_SECONDS)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,231 @@
+use cosmwasm_std::{Addr, Order, Storage, Uint128, Uint256};
+use cw_storage_plus::Bound;
+
+use crate::{
+    state::{
+        ShareSecondsCheckpoint, SHARES, SHARE_SECONDS_START, TOTAL_SHARE_SECONDS,
+        USER_SHARE_SECONDS,
+    },
+    ContractError,
+};
+
+/// Start the share-seconds accumulators at `time`, share balances that exist at that point
+/// accumulate from `time` on. Does nothing if the accumulators are already started.
+pub fn init_share_seconds(storage: &mut dyn Storage, time: u64) -> Result<(), ContractError> {
+    if SHARE_SECONDS_START.may_load(storage)?.is_some() {
+        return Ok(());
+    }
+
+    let total = SHARES
+        .range(storage, None, None, Order::Ascending)
+        .try_fold(Uint128::zero(), |acc, item| -> Result<_, ContractError> {
+            Ok(acc.checked_add(item?.1)?)
+        })?;
+    SHARE_SECONDS_START.save(storage, &time)?;
+    TOTAL_SHARE_SECONDS.save(
+        storage,
+        time,
+        &ShareSecondsCheckpoint {
+            shares: total,
+            share_seconds: Uint256::zero(),
+        },
+    )?;
+    Ok(())
+}
+
+/// Checkpoint the share-seconds of `user` and of the total when the shares of `user` change
+/// from `old` to `new` at `time`. This must be called before the new shares are saved.
+pub fn checkpoint_share_seconds(
+    storage: &mut dyn Storage,
+    time: u64,
+    user: &Addr,
+    old: Uint128,
+    new: Uint128,
+) -> Result<(), ContractError> {
+    init_share_seconds(storage, time)?;
+    let start = SHARE_SECONDS_START.load(storage)?;
+
+    // a user holding shares from before the accumulators started gets a checkpoint at the start
+    if !old.is_zero() && !has_user_checkpoints(storage, user) {
+        USER_SHARE_SECONDS.save(
+            storage,
+            (user.clone(), start),
+            &ShareSecondsCheckpoint {
+                shares: old,
+                share_seconds: Uint256::zero(),
+            },
+        )?;
+    }
+    let user_share_seconds = user_share_seconds_at(storage, user, time)?;
+    USER_SHARE_SECONDS.save(
+        storage,
+        (user.clone(), time),
+        &ShareSecondsCheckpoint {
+            shares: new,
+            share_seconds: user_share_seconds,
+        },
+    )?;
+
+    let total_shares = last_total_checkpoint(storage, time)?
+        .map(|(_, checkpoint)| checkpoint.shares)
+        .unwrap_or_default();
+    let total_share_seconds = total_share_seconds_at(storage, time)?;
+    TOTAL_SHARE_SECONDS.save(
+        storage,
+        time,
+        &ShareSecondsCheckpoint {
+            // saturating, so an inconsistent total can never block a withdrawal
+            shares: total_shares.saturating_sub(old).checked_add(new)?,
+            share_seconds: total_share_seconds,
+        },
+    )?;
+    Ok(())
+}
+
+/// The cumulative share-seconds of `user` from the start of the accumulators until `at`
+pub fn user_share_seconds_at(
+    storage: &dyn Storage,
+    user: &Addr,
+    at: u64,
+) -> Result<Uint256, ContractError> {
+    let last = USER_SHARE_SECONDS
+        .prefix(user.clone())
+        .range(storage, None, Some(Bound::inclusive(at)), Order::Descending)
+        .next()
+        .transpose()?;
+    if let Some((time, checkpoint)) = last {
+        return accumulate(&checkpoint, time, at);
+    }
+
+    // users without any checkpoint held their current shares since the start
+    let Some(start) = SHARE_SECONDS_START.may_load(storage)? else {
+        return Ok(Uint256::zero());
+    };
+    if has_user_checkpoints(storage, user) || at < start {
+        return Ok(Uint256::zero());
+    }
+    let shares = SHARES.may_load(storage, user.clone())?.unwrap_or_default();
+    accumulate(
+        &ShareSecondsCheckpoint {
+            shares,
+            share_seconds: Uint256::zero(),
+        },
+        start,
+        at,
+    )
+}
+
+/// The cumulative share-seconds of all users from the start of the accumulators until `at`
+pub fn total_share_seconds_at(storage: &dyn Storage, at: u64) -> Result<Uint256, ContractError> {
+    match last_total_checkpoint(storage, at)? {
+        Some((time, checkpoint)) => accumulate(&checkpoint, time, at),
+        None => Ok(Uint256::zero()),
+    }
+}
+
+fn last_total_checkpoint(
+    storage: &dyn Storage,
+    at: u64,
+) -> Result<Option<(u64, ShareSecondsCheckpoint)>, ContractError> {
+    Ok(TOTAL_SHARE_SECONDS
+        .range(storage, None, Some(Bound::inclusive(at)), Order::Descending)
+        .next()
+        .transpose()?)
+}
+
+fn has_user_checkpoints(storage: &dyn Storage, user: &Addr) -> bool {
+    USER_SHARE_SECONDS
+        .prefix(user.clone())
+        .keys_raw(storage, None, None, Order::Ascending)
+        .next()
+        .is_some()
+}
+
+fn accumulate(
+    checkpoint: &ShareSecondsCheckpoint,
+    time: u64,
+    at: u64,
+) -> Result<Uint256, ContractError> {
+    Ok(checkpoint.share_seconds.checked_add(
+        Uint256::from(checkpoint.shares).checked_mul(Uint256::from(at.saturating_sub(time)))?,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::mock_dependencies;
+
+    use super::*;
+
+    #[test]
+    fn share_seconds_accumulate_between_checkpoints() {
+        let mut deps = mock_dependencies();
+        let alice = Addr::unchecked("alice");
+        let bob = Addr::unchecked("bob");
+
+        // alice holds shares from before the accumulators started
+        SHARES
+            .save(deps.as_mut().storage, alice.clone(), &Uint128::new(100))
+            .unwrap();
+        init_share_seconds(deps.as_mut().storage, 1000).unwrap();
+
+        // alice has no checkpoints yet and accumulates with the current shares
+        assert_eq!(
+            user_share_seconds_at(deps.as_ref().storage, &alice, 1010).unwrap(),
+            Uint256::from(1000u128)
+        );
+
+        // bob deposits 300 shares at 1010
+        checkpoint_share_seconds(
+            deps.as_mut().storage,
+            1010,
+            &bob,
+            Uint128::zero(),
+            Uint128::new(300),
+        )
+        .unwrap();
+        SHARES
+            .save(deps.as_mut().storage, bob.clone(), &Uint128::new(300))
+            .unwrap();
+
+        // alice withdraws 50 shares at 1020
+        checkpoint_share_seconds(
+            deps.as_mut().storage,
+            1020,
+            &alice,
+            Uint128::new(100),
+            Uint128::new(50),
+        )
+        .unwrap();
+        SHARES
+            .save(deps.as_mut().storage, alice.clone(), &Uint128::new(50))
+            .unwrap();
+
+        // alice: 100 * 20 + 50 * 10
+        assert_eq!(
+            user_share_seconds_at(deps.as_ref().storage, &alice, 1030).unwrap(),
+            Uint256::from(2500u128)
+        );
+        // before the first change alice still accumulates from the start
+        assert_eq!(
+            user_share_seconds_at(deps.as_ref().storage, &alice, 1005).unwrap(),
+            Uint256::from(500u128)
+        );
+        // bob: 300 * 20
+        assert_eq!(
+            user_share_seconds_at(deps.as_ref().storage, &bob, 1030).unwrap(),
+            Uint256::from(6000u128)
+        );
+        assert!(user_share_seconds_at(deps.as_ref().storage, &bob, 1005)
+            .unwrap()
+            .is_zero());
+        // total: 100 * 10 + 400 * 10 + 350 * 10
+        assert_eq!(
+            total_share_seconds_at(deps.as_ref().storage, 1030).unwrap(),
+            Uint256::from(8500u128)
+        );
+        assert!(total_share_seconds_at(deps.as_ref().storage, 999)
+            .unwrap()
+            .is_zero());
+    }
+}
@@ -0,0 +1,147 @@
+use cosmwasm_std::{
+    to_json_binary, Addr, Env, Response, Storage, SubMsg, SubMsgResult, Uint128, WasmMsg,
+};
+
+use crate::{
+    helpers::share_seconds::checkpoint_share_seconds,
+    msg::ShareHookMsg,
+    reply::Replies,
+    state::{SHARES, SHARE_HOOKS},
+    vault::referral::cap_referral,
+    ContractError,
+};
+
+/// The maximum amount of share hooks that can be registered
+pub const MAX_SHARE_HOOKS: usize = 5;
+/// The gas limit of a single share hook call, a hook running out of gas does not fail the vault operation
+pub const SHARE_HOOK_GAS_LIMIT: u64 = 250_000;
+
+/// Update the shares of `user`, checkpoint the share-seconds accumulators and build the sub
+/// messages notifying the registered share hooks. Every change to SHARES should go through here
+/// so hooks and accumulators never miss a change.
+pub fn update_user_shares(
+    storage: &mut dyn Storage,
+    env: &Env,
+    user: &Addr,
+    action: impl FnOnce(Uint128) -> Result<Uint128, ContractError>,
+) -> Result<Vec<SubMsg>, ContractError> {
+    let old = SHARES.may_load(storage, user.clone())?.unwrap_or_default();
+    let new = action(old)?;
+    checkpoint_share_seconds(storage, env.block.time.seconds(), user, old, new)?;
+    SHARES.save(storage, user.clone(), &new)?;
+    if new < old {
+        cap_referral(storage, user, new)?;
+    }
+
+    share_hook_msgs(storage, user, old, new)
+}
+
+/// Build a `SharesChanged` sub message for every registered hook. Hook errors are caught in the
+/// reply so a misbehaving hook can not block deposits or withdrawals.
+pub fn share_hook_msgs(
+    storage: &dyn Storage,
+    user: &Addr,
+    old: Uint128,
+    new: Uint128,
+) -> Result<Vec<SubMsg>, ContractError> {
+    if old == new {
+        return Ok(vec![]);
+    }
+    let hooks = SHARE_HOOKS.may_load(storage)?.unwrap_or_default();
+    if hooks.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let msg = to_json_binary(&ShareHookMsg::SharesChanged {
+        user: user.to_string(),
+        old,
+        new,
+    })?;
+    Ok(hooks
+        .into_iter()
+        .map(|hook| {
+            SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: hook.to_string(),
+                    msg: msg.clone(),
+                    funds: vec![],
+                },
+                Replies::ShareHook as u64,
+            )
+            .with_gas_limit(SHARE_HOOK_GAS_LIMIT)
+        })
+        .collect())
+}
+
+/// Share hooks are only replied to on error, the error is recorded and otherwise ignored
+pub fn handle_share_hook_reply(result: SubMsgResult) -> Result<Response, ContractError> {
+    let mut response = Response::new()
+        .add_attribute("method", "reply")
+        .add_attribute("action", "handle_share_hook");
+    if let SubMsgResult::Err(err) = result {
+        response = response.add_attribute("error", err);
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+
+    use super::*;
+
+    #[test]
+    fn update_user_shares_notifies_hooks() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let user = Addr::unchecked("alice");
+
+        // without hooks only the shares are updated
+        let msgs = update_user_shares(deps.as_mut().storage, &env, &user, |old| {
+            Ok(old + Uint128::new(100))
+        })
+        .unwrap();
+        assert!(msgs.is_empty());
+        assert_eq!(
+            SHARES.load(deps.as_ref().storage, user.clone()).unwrap(),
+            Uint128::new(100)
+        );
+
+        SHARE_HOOKS
+            .save(
+                deps.as_mut().storage,
+                &vec![Addr::unchecked("hook1"), Addr::unchecked("hook2")],
+            )
+            .unwrap();
+        let msgs = update_user_shares(deps.as_mut().storage, &env, &user, |old| {
+            Ok(old - Uint128::new(40))
+        })
+        .unwrap();
+        assert_eq!(msgs.len(), 2);
+        assert_eq!(msgs[1].gas_limit, Some(SHARE_HOOK_GAS_LIMIT));
+        assert_eq!(
+            msgs[1].msg,
+            WasmMsg::Execute {
+                contract_addr: "hook2".to_string(),
+                msg: to_json_binary(&ShareHookMsg::SharesChanged {
+                    user: "alice".to_string(),
+                    old: Uint128::new(100),
+                    new: Uint128::new(60),
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into()
+        );
+
+        // failing actions do not touch the shares
+        update_user_shares(deps.as_mut().storage, &env, &user, |_| {
+            Err(ContractError::InsufficientFunds)
+        })
+        .unwrap_err();
+        assert_eq!(
+            SHARES.load(deps.as_ref().storage, user).unwrap(),
+            Uint128::new(60)
+        );
+    }
+}
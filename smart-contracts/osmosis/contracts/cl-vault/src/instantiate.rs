@@ -12,7 +12,10 @@ use osmosis_std::types::osmosis::tokenfactory::v1beta1::{
 };
 
 use crate::error::assert_deposits;
+use crate::helpers::fees::validate_entry_exit_fees;
 use crate::helpers::getters::{get_unused_pair_balances, get_value_wrt_asset0};
+use crate::helpers::msgs::set_denom_metadata_msg;
+use crate::helpers::share_seconds::init_share_seconds;
 use crate::math::tick::{build_tick_exp_cache, verify_tick_exp_cache};
 use crate::msg::InstantiateMsg;
 use crate::reply::Replies;
@@ -20,7 +23,8 @@ use crate::state::{
     Metadata, PoolConfig, Position, ADMIN_ADDRESS, METADATA, POOL_CONFIG, POSITION, RANGE_ADMIN,
     VAULT_CONFIG, VAULT_DENOM,
 };
-use crate::vault::concentrated_liquidity::{create_position, get_position};
+use crate::vault::concentrated_liquidity::{create_position, get_position, record_range_entry};
+use crate::vault::referral::validate_referral_fee_share;
 use crate::ContractError;
 
 pub fn handle_instantiate(
@@ -35,6 +39,8 @@ pub fn handle_instantiate(
             "performance fee cannot be more than 1.0",
         )));
     }
+    validate_entry_exit_fees(&msg.config)?;
+    validate_referral_fee_share(msg.config.referral_fee_share)?;
 
     build_tick_exp_cache(deps.storage)?;
     verify_tick_exp_cache(deps.storage)?;
@@ -42,6 +48,7 @@ pub fn handle_instantiate(
     deps.api.addr_validate(msg.config.dex_router.as_str())?;
 
     VAULT_CONFIG.save(deps.storage, &msg.config)?;
+    init_share_seconds(deps.storage, env.block.time.seconds())?;
 
     let pool: Pool = PoolmanagerQuerier::new(&deps.querier)
         .pool(msg.pool_id)?
@@ -63,6 +70,7 @@ pub fn handle_instantiate(
         &Metadata {
             thesis: msg.thesis,
             name: msg.name,
+            symbol: None,
         },
     )?;
 
@@ -102,12 +110,20 @@ pub fn handle_instantiate(
 
 pub fn handle_create_denom_reply(
     deps: DepsMut,
+    env: Env,
     data: SubMsgResult,
 ) -> Result<Response, ContractError> {
     let response: MsgCreateDenomResponse = data.try_into()?;
     VAULT_DENOM.save(deps.storage, &response.new_token_denom)?;
+    let metadata = METADATA.load(deps.storage)?;
 
-    Ok(Response::new().add_attribute("vault_denom", response.new_token_denom))
+    Ok(Response::new()
+        .add_message(set_denom_metadata_msg(
+            &env,
+            &response.new_token_denom,
+            &metadata,
+        ))
+        .add_attribute("vault_denom", response.new_token_denom))
 }
 
 pub fn handle_instantiate_create_position_reply(
@@ -124,6 +140,13 @@ pub fn handle_instantiate_create_position_reply(
             claim_after: None,
         },
     )?;
+    record_range_entry(
+        deps.storage,
+        &deps.querier,
+        &env,
+        response.lower_tick,
+        response.upper_tick,
+    )?;
 
     let position_info = get_position(deps.storage, &deps.querier)?;
     // Check if asset0 and asset1 are present, and handle the case where they are not.
@@ -1,14 +1,24 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::{to_json_binary, Binary, Coin, CosmosMsg, Decimal, StdResult, Uint128, WasmMsg};
+use cw20::Cw20ReceiveMsg;
+use cw_utils::{Duration, Expiration};
 use osmosis_std::types::osmosis::poolmanager::v1beta1::SwapAmountInRoute;
+use quasar_types::cw_vault_multi_standard::extensions::lockup::{LockupExecuteMsg, LockupQueryMsg};
 use quasar_types::cw_vault_multi_standard::{VaultStandardExecuteMsg, VaultStandardQueryMsg};
+use quasar_types::ibc::IbcLifecycleComplete;
 
 #[cfg(not(target_arch = "wasm32"))]
 use crate::query::{
-    AssetsBalanceResponse, PoolResponse, PositionResponse, RangeAdminResponse,
-    UserSharesBalanceResponse, VerifyTickCacheResponse,
+    AssetsBalanceResponse, PoolResponse, PositionHealthResponse, PositionResponse,
+    RangeAdminResponse, UserPositionResponse, UserSharesBalanceResponse, VerifyTickCacheResponse,
+    WithdrawQueueResponse, WithdrawRequestResponse,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::state::WithdrawBatch;
+use crate::state::{
+    Cw20DepositAsset, DepositorAllowlist, IbcDestination, IdleBufferConfig, Metadata,
+    SuperfluidConfig, VaultConfig, WithdrawQueueConfig,
 };
-use crate::state::{Metadata, VaultConfig};
 
 /// Extension execute messages for an apollo autocompounding vault
 #[cw_serde]
@@ -29,6 +39,97 @@ pub enum ExtensionExecuteMsg {
         swap_operations: Vec<SwapOperation>,
         twap_window_seconds: Option<u64>,
     },
+    /// Process queued withdraw requests as a single batch, callable by the admin or the withdraw queue keeper
+    ProcessWithdrawQueue {
+        /// max amount of requests to process in this batch
+        limit: Option<u32>,
+    },
+    /// Claim the assets of a queued withdraw request once its batch has settled
+    ClaimQueuedWithdraw { request_id: u64 },
+    /// Move the idle buffer back toward its target, callable by the admin or the idle buffer keeper
+    RebalanceIdleBuffer {},
+    /// Lockup extension, redemptions go through Unlock and WithdrawUnlocked while a lockup duration is set
+    Lockup(LockupExecuteMsg),
+    /// Redeem `amount` shares and transfer the withdrawn assets to an address on another chain.
+    /// Transfers that fail or time out are refunded to the owner of the shares on this chain.
+    RedeemToIbc {
+        amount: Uint128,
+        destination: IbcDestination,
+    },
+    /// Allow `operator` to redeem up to `amount` shares of the sender through RedeemFrom,
+    /// replacing any existing allowance. The allowance never expires if `expires` is None
+    ApproveOperator {
+        operator: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    /// Remove the allowance of `operator`
+    RevokeOperator { operator: String },
+    /// Withdraw `amount` shares of `user` to the user, only callable by the vault itself as part
+    /// of AutoWithdraw. The withdraw is gated like a Redeem of the user, including the lockup
+    /// and the withdraw queue
+    AutoWithdrawUser { user: String, amount: Uint128 },
+    /// Redeem `amount` shares of `owner` using the allowance the owner approved for the sender.
+    /// The withdrawn assets go to `recipient`, or to the owner if no recipient is given
+    RedeemFrom {
+        owner: String,
+        amount: Uint128,
+        recipient: Option<String>,
+    },
+    /// AnyDeposit that attributes the shares of the depositor to `referrer`, the partner that
+    /// referred them. A referrer can only be set on deposits to the depositor themselves
+    ReferredAnyDeposit {
+        amount: Uint128,
+        asset: String,
+        recipient: Option<String>,
+        max_slippage: Decimal,
+        referrer: String,
+    },
+    /// ExactDeposit that attributes the shares of the depositor to `referrer`, the partner that
+    /// referred them. A referrer can only be set on deposits to the depositor themselves
+    ReferredExactDeposit {
+        recipient: Option<String>,
+        referrer: String,
+    },
+    /// Claim the referral fees accrued by the sender as a referrer
+    ClaimReferralFees {},
+    /// Manage the superfluid delegated positions, callable by the range admin
+    Superfluid(SuperfluidExecuteMsg),
+    /// Redeem `amount` shares and send the withdrawn assets to `contract` with a `ReceiveRedeem`
+    /// call carrying `msg`, so the contract can act on the assets in the same transaction
+    RedeemAndCall {
+        amount: Uint128,
+        contract: String,
+        msg: Binary,
+    },
+}
+
+/// The message a contract receives together with the assets of a `RedeemAndCall`
+#[cw_serde]
+pub struct RedeemReceiveMsg {
+    /// the owner of the redeemed shares
+    pub owner: String,
+    /// the amount of redeemed shares
+    pub shares: Uint128,
+    pub msg: Binary,
+}
+
+impl RedeemReceiveMsg {
+    /// Wrap the message in a `ReceiveRedeem` execute of `contract` that carries `funds`
+    pub fn into_cosmos_msg(self, contract: String, funds: Vec<Coin>) -> StdResult<CosmosMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: contract,
+            msg: to_json_binary(&RedeemReceiverExecuteMsg::ReceiveRedeem(self))?,
+            funds,
+        }
+        .into())
+    }
+}
+
+/// The execute message a contract has to accept to receive the assets of a `RedeemAndCall`
+#[cw_serde]
+pub enum RedeemReceiverExecuteMsg {
+    ReceiveRedeem(RedeemReceiveMsg),
 }
 
 /// Apollo extension messages define functionality that is part of all apollo
@@ -56,8 +157,89 @@ pub enum AdminExtensionExecuteMsg {
     },
     /// Build tick exponent cache
     BuildTickCache {},
-    /// Auto claim endpoint
-    AutoWithdraw { users: Vec<(String, Uint128)> },
+    /// Withdraw the shares of users to the users themselves. Every user is withdrawn in its own
+    /// submessage, so a failing user does not revert the others. With `users` set, the given
+    /// amounts of those users are withdrawn, otherwise all shares of the next `limit` users in
+    /// SHARES after the progress cursor
+    AutoWithdraw {
+        users: Option<Vec<(String, Uint128)>>,
+        limit: Option<u32>,
+    },
+    /// Update the withdraw queue configuration, None disables queueing of new redemptions
+    UpdateWithdrawQueueConfig { config: Option<WithdrawQueueConfig> },
+    /// Register a contract that is notified of every share balance change
+    AddShareHook { address: String },
+    /// Remove a registered share hook
+    RemoveShareHook { address: String },
+    /// Update the idle buffer configuration, None disables serving redemptions from the buffer
+    UpdateIdleBufferConfig { config: Option<IdleBufferConfig> },
+    /// Update the lockup duration, None disables the lockup mode
+    UpdateLockupDuration { duration: Option<Duration> },
+    /// Enable or disable collecting the position rewards before every deposit
+    UpdateCollectRewardsBeforeDeposit { enabled: bool },
+    /// Clear the state kept across replies and release the operation lock, to recover from an
+    /// operation that was left half-done. Refused while the lock was taken in the current block
+    ResetTransientState {},
+    /// Move the position to another pool of the same token pair, within the given price range
+    MigratePool {
+        new_pool_id: u64,
+        lower_price: Decimal,
+        upper_price: Decimal,
+        max_slippage: Decimal,
+        /// Window of the TWAP of the new pool the swap into the new range is checked against,
+        /// defaults to the window of the vault config. Has to fit in the history of the new pool.
+        twap_window_seconds: Option<u64>,
+    },
+    /// Update how depositors are allowed, None opens deposits to everyone
+    UpdateDepositorAllowlist {
+        allowlist: Option<DepositorAllowlist>,
+    },
+    /// Add and remove addresses of the admin managed depositor allowlist
+    UpdateAllowedDepositors {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Accept deposits of the CW20 token `token`, None stops accepting them
+    UpdateCw20DepositAsset {
+        token: String,
+        asset: Option<Cw20DepositAsset>,
+    },
+    /// Enable superfluid positions for an OSMO paired pool, None stops creating new ones
+    UpdateSuperfluidConfig { config: Option<SuperfluidConfig> },
+}
+
+#[cw_serde]
+pub enum SuperfluidExecuteMsg {
+    /// Create a full range position of unused funds and superfluid delegate it
+    Delegate { amount0: Uint128, amount1: Uint128 },
+    /// Undelegate a superfluid position and start unbonding it
+    Undelegate { position_id: u64 },
+    /// Withdraw a superfluid position once it is unbonded, callable by anyone
+    Withdraw { position_id: u64 },
+}
+
+/// The query an external depositor allowlist contract has to answer
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum AllowlistQueryMsg {
+    #[returns(IsAllowedResponse)]
+    IsAllowed { address: String },
+}
+
+#[cw_serde]
+pub struct IsAllowedResponse {
+    pub allowed: bool,
+}
+
+/// Messages sent to registered share hook contracts
+#[cw_serde]
+pub enum ShareHookMsg {
+    /// The share balance of `user` changed from `old` to `new`
+    SharesChanged {
+        user: String,
+        old: Uint128,
+        new: Uint128,
+    },
 }
 
 #[cw_serde]
@@ -110,6 +292,62 @@ pub enum ExtensionQueryMsg {
         /// Limit for the search
         limit: u64,
     },
+    /// Queries related to the withdraw queue
+    WithdrawQueue(WithdrawQueueQueryMsg),
+    /// Queries related to the lockup extension
+    Lockup(LockupQueryMsg),
+    /// Get the share-seconds accumulated by `user` and by all users between the `start` and `end`
+    /// timestamps, an `end` in the future is capped at the current block time
+    ShareSeconds { user: String, start: u64, end: u64 },
+    /// Get the registered share hook contracts
+    ShareHooks {},
+    /// Get the lifetime totals of collected rewards, paid fees and compounded funds
+    RewardLedger {},
+    /// Get the most recent share price snapshots, oldest first, together with the trailing
+    /// 7 day and 30 day annualised returns
+    SharePriceHistory { limit: Option<u32> },
+    /// Get the idle buffer config together with the current and target idle amounts
+    IdleBuffer {},
+    /// Get whether rewards are collected from the position before every deposit
+    CollectRewardsBeforeDeposit {},
+    /// Get the transfers of redeems to other chains that have not been acked or timed out yet
+    IbcTransfers {
+        start_after: Option<(String, u64)>,
+        limit: Option<u32>,
+    },
+    /// Get the operation lock and all state kept across replies by the vault's operations
+    TransientState {},
+    /// Get the allowance `owner` approved for `operator`, a zero allowance if there is none
+    OperatorAllowance { owner: String, operator: String },
+    /// Get the progress of AutoWithdraw together with the users whose last auto withdraw failed
+    AutoWithdrawStatus {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Get all operator allowances approved by `owner`
+    OperatorAllowances {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Get how depositors are allowed, None if deposits are open to everyone
+    DepositorAllowlist {},
+    /// Get the addresses of the admin managed depositor allowlist
+    AllowedDepositors {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Get whether `address` is allowed to deposit
+    IsAllowedDepositor { address: String },
+    /// Get the shares referred by `address` and the referral fees it can claim
+    Referrer { address: String },
+    /// Get the CW20 tokens accepted for deposits
+    Cw20DepositAssets {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Get the superfluid config together with the superfluid positions and their assets
+    Superfluid {},
 }
 
 /// Extension query messages for user balance related queries
@@ -120,6 +358,9 @@ pub enum UserBalanceQueryMsg {
     UserSharesBalance { user: String },
     #[returns(AssetsBalanceResponse)]
     UserAssetsBalance { user: String },
+    /// The cost basis of the user combined with the current value of their shares
+    #[returns(UserPositionResponse)]
+    UserPosition { user: String },
 }
 
 /// Extension query messages for related concentrated liquidity
@@ -135,11 +376,53 @@ pub enum ClQueryMsg {
     RangeAdmin {},
     #[returns(VerifyTickCacheResponse)]
     VerifyTickCache,
+    /// Where the pool price is relative to the range of the position, and the impermanent loss
+    /// of the position since the range was created
+    #[returns(PositionHealthResponse)]
+    PositionHealth {},
+}
+
+/// Extension query messages for the withdraw queue
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum WithdrawQueueQueryMsg {
+    #[returns(Option<WithdrawQueueConfig>)]
+    Config {},
+    /// Get a withdraw request together with its status and position in the queue
+    #[returns(WithdrawRequestResponse)]
+    Request { request_id: u64 },
+    /// Get the requests that are still waiting in the queue, in queue order
+    #[returns(WithdrawQueueResponse)]
+    Queue {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(WithdrawBatch)]
+    Batch { epoch: u64 },
 }
 
 /// ExecuteMsg for an Autocompounding Vault.
 pub type ExecuteMsg = VaultStandardExecuteMsg<ExtensionExecuteMsg>;
 
+/// The messages accepted by the execute entry point. CW20 tokens call the `Receive` hook with a
+/// top level `{"receive": {..}}`, which is not part of the vault standard, so it is tried before
+/// the vault messages
+#[cw_serde]
+#[serde(untagged)]
+pub enum ContractExecuteMsg {
+    /// Deposit CW20 tokens sent with a `Cw20HookMsg::Deposit`
+    Receive {
+        receive: Cw20ReceiveMsg,
+    },
+    Vault(ExecuteMsg),
+}
+
+impl From<ExecuteMsg> for ContractExecuteMsg {
+    fn from(msg: ExecuteMsg) -> Self {
+        ContractExecuteMsg::Vault(msg)
+    }
+}
+
 /// QueryMsg for an Autocompounding Vault.
 pub type QueryMsg = VaultStandardQueryMsg<ExtensionQueryMsg>;
 
@@ -168,3 +451,10 @@ pub struct InstantiateMsg {
 
 #[cw_serde]
 pub struct MigrateMsg {}
+
+/// Messages sent to the vault by the chain
+#[cw_serde]
+pub enum SudoMsg {
+    /// ibc-hooks notification of the ack or timeout of an outgoing transfer
+    IbcLifecycleComplete(IbcLifecycleComplete),
+}
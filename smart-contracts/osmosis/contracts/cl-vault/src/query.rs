@@ -1,15 +1,67 @@
 use crate::helpers::coinlist::CoinList;
-use crate::helpers::getters::get_unused_balances;
+use crate::helpers::fees::{apply_deposit_fee, apply_exit_fee};
+use crate::helpers::getters::{
+    get_claimable_pool_rewards, get_depositable_tokens, get_unused_balances,
+    get_unused_pair_balances, get_value_wrt_asset0,
+};
+use crate::helpers::msgs::{vault_denom_symbol, VAULT_DENOM_EXPONENT};
+use crate::helpers::share_price::{
+    get_annualised_return, DEFAULT_SHARE_PRICE_HISTORY_LIMIT, MAX_SHARE_PRICE_SNAPSHOTS,
+    SEVEN_DAYS_SECONDS, THIRTY_DAYS_SECONDS,
+};
+use crate::helpers::share_seconds::{total_share_seconds_at, user_share_seconds_at};
+use crate::math::tick::tick_to_price;
 use crate::math::tick::verify_tick_exp_cache;
 use crate::state::{
-    PoolConfig, ADMIN_ADDRESS, METADATA, POOL_CONFIG, POSITION, SHARES, VAULT_CONFIG, VAULT_DENOM,
+    AutoWithdrawProgress, DepositorAllowlist, IbcTransfer, OperatorAllowance, RewardLedger,
+    SharePriceSnapshot, UserCostBasis, ALLOWED_DEPOSITORS, AUTO_WITHDRAW_FAILURES,
+    AUTO_WITHDRAW_PROGRESS, COLLECT_REWARDS_BEFORE_DEPOSIT, DEPOSITOR_ALLOWLIST, IBC_TRANSFERS,
+    OPERATOR_ALLOWANCES, RANGE_ENTRY, REWARD_LEDGER, SHARE_HOOKS, SHARE_PRICE_SNAPSHOTS,
+};
+use crate::state::{
+    CurrentCw20Deposit, CurrentMergePosition, CurrentSwap, Cw20DepositAsset, IbcDestination,
+    ModifyRangeState, OperationLock, PendingDeposit, RedeemCallback, SwapDepositMergeState,
+    CURRENT_CW20_DEPOSIT, CURRENT_MERGE, CURRENT_MERGE_POSITION, CURRENT_SUPERFLUID_VALIDATOR,
+    CURRENT_SWAP_INFO, CURRENT_WITHDRAWER, CURRENT_WITHDRAWER_CALLBACK, CURRENT_WITHDRAWER_DUST,
+    CURRENT_WITHDRAWER_IBC, MODIFY_RANGE_STATE, OPERATION_LOCK, PENDING_AUTO_WITHDRAWS,
+    PENDING_DEPOSIT, PENDING_IBC_TRANSFERS, SWAP_DEPOSIT_MERGE_STATE,
+};
+use crate::state::{
+    IdleBufferConfig, PoolConfig, SuperfluidConfig, SuperfluidPosition, WithdrawBatch,
+    WithdrawQueueConfig, WithdrawRequest, ADMIN_ADDRESS, CW20_DEPOSIT_ASSETS, IDLE_BUFFER_CONFIG,
+    LOCKUP_DURATION, METADATA, POOL_CONFIG, POSITION, SHARES, SUPERFLUID_CONFIG,
+    SUPERFLUID_POSITIONS, UNLOCKING_POSITIONS, UNLOCKING_POSITION_OWNERS, VAULT_CONFIG,
+    VAULT_DENOM, WITHDRAW_BATCHES, WITHDRAW_QUEUE, WITHDRAW_QUEUE_CONFIG, WITHDRAW_REQUESTS,
+};
+use crate::vault::allowlist::{
+    is_allowed_depositor, DEFAULT_ALLOWED_DEPOSITORS_LIMIT, MAX_ALLOWED_DEPOSITORS_LIMIT,
+};
+use crate::vault::auto_withdraw::{DEFAULT_AUTO_WITHDRAW_LIMIT, MAX_AUTO_WITHDRAW_LIMIT};
+use crate::vault::concentrated_liquidity::{
+    get_amounts_from_liquidity, get_cl_pool_info, get_position,
+};
+use crate::vault::cost_basis::{get_cost_basis, get_shares_value};
+use crate::vault::cw20_deposit::{
+    DEFAULT_CW20_DEPOSIT_ASSETS_LIMIT, MAX_CW20_DEPOSIT_ASSETS_LIMIT,
 };
-use crate::vault::concentrated_liquidity::get_position;
+use crate::vault::ibc_redeem::{DEFAULT_IBC_TRANSFERS_LIMIT, MAX_IBC_TRANSFERS_LIMIT};
+use crate::vault::idle_buffer::get_buffer_targets;
+use crate::vault::lockup::{DEFAULT_UNLOCKING_POSITIONS_LIMIT, MAX_UNLOCKING_POSITIONS_LIMIT};
+use crate::vault::merge::CurrentMergeWithdraw;
+use crate::vault::operator::{DEFAULT_OPERATOR_ALLOWANCES_LIMIT, MAX_OPERATOR_ALLOWANCES_LIMIT};
+use crate::vault::referral::get_settled_referrer;
+use crate::vault::superfluid::get_superfluid_assets;
+use crate::vault::withdraw_queue::{get_request_assets, DEFAULT_BATCH_LIMIT, MAX_BATCH_LIMIT};
 use crate::ContractError;
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{coin, Addr, Coin, Decimal, Deps, Env, StdError, Uint128};
+use cosmwasm_std::{
+    coin, Addr, Coin, Decimal, Decimal256, Deps, Env, Int128, SignedDecimal, SignedDecimal256,
+    StdError, Uint128, Uint256,
+};
 use cw_storage_plus::Bound;
+use cw_utils::{Duration, Expiration};
 use osmosis_std::types::cosmos::bank::v1beta1::BankQuerier;
+use quasar_types::cw_vault_multi_standard::extensions::lockup::UnlockingPosition;
 use quasar_types::cw_vault_multi_standard::VaultInfoResponse;
 
 #[cw_serde]
@@ -38,11 +90,55 @@ pub struct PositionResponse {
     pub position_ids: Vec<u64>,
 }
 
+#[cw_serde]
+pub struct PositionHealthResponse {
+    pub in_range: bool,
+    pub current_tick: i64,
+    pub lower_tick: i64,
+    pub upper_tick: i64,
+    /// current_tick - lower_tick, negative if the pool is below the range
+    pub ticks_to_lower: i64,
+    /// upper_tick - current_tick, negative if the pool is above the range
+    pub ticks_to_upper: i64,
+    /// prices are in token1 per token0
+    pub current_price: Decimal256,
+    pub lower_price: Decimal256,
+    pub upper_price: Decimal256,
+    /// current_price - lower_price
+    pub price_to_lower: SignedDecimal256,
+    /// upper_price - current_price
+    pub price_to_upper: SignedDecimal256,
+    /// the share of token0 in the value of the position
+    pub token0_ratio: Decimal256,
+    /// the share of token0 in the value of the position when the range was created, None if
+    /// the creation of the range was not recorded
+    pub entry_token0_ratio: Option<Decimal256>,
+    /// the loss of the position relative to holding the assets it held when the range was
+    /// created, excluding fees and incentives
+    pub impermanent_loss: Option<Decimal256>,
+}
+
 #[cw_serde]
 pub struct AssetsBalanceResponse {
     pub balances: Vec<Coin>,
 }
 
+#[cw_serde]
+pub struct UserPositionResponse {
+    pub cost_basis: UserCostBasis,
+    /// the shares in the balance of the user
+    pub shares: Uint128,
+    /// the assets of the shares in the balance of the user
+    pub assets: Vec<Coin>,
+    /// the current value of `assets` in token0
+    pub value: Uint128,
+    pub realised_pnl: Int128,
+    /// the current value of the tracked shares minus their cost basis
+    pub unrealised_pnl: Int128,
+    /// the token0 value paid per tracked share, None if no tracked shares are left
+    pub average_entry_price: Option<Decimal>,
+}
+
 #[cw_serde]
 pub struct UserSharesBalanceResponse {
     pub balance: Uint128,
@@ -80,6 +176,56 @@ pub struct ActiveUsersResponse {
     pub next_token: Option<Addr>,    // Token for the next page
 }
 
+#[cw_serde]
+pub struct ShareSecondsResponse {
+    pub user_share_seconds: Uint256,
+    pub total_share_seconds: Uint256,
+}
+
+#[cw_serde]
+pub struct SharePriceHistoryResponse {
+    pub snapshots: Vec<SharePriceSnapshot>,
+    /// annualised return over the trailing 7 days, None until the history covers 7 days
+    pub apr_7d: Option<SignedDecimal>,
+    /// annualised return over the trailing 30 days, None until the history covers 30 days
+    pub apr_30d: Option<SignedDecimal>,
+}
+
+#[cw_serde]
+pub struct ShareHooksResponse {
+    pub hooks: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct IdleBufferResponse {
+    pub config: Option<IdleBufferConfig>,
+    /// the idle vault assets, as [token0, token1]
+    pub idle: Vec<Coin>,
+    /// the idle amounts the buffer aims for, empty if the buffer is disabled
+    pub target: Vec<Coin>,
+}
+
+#[cw_serde]
+pub enum WithdrawRequestStatus {
+    /// The request is waiting in the queue, position 0 is processed first
+    Queued { position: u64 },
+    /// The request is part of a batch that did not settle yet
+    Processing { epoch: u64 },
+    /// The batch of the request has settled and the assets can be claimed
+    Claimable { epoch: u64, assets: Vec<Coin> },
+}
+
+#[cw_serde]
+pub struct WithdrawRequestResponse {
+    pub request: WithdrawRequest,
+    pub status: WithdrawRequestStatus,
+}
+
+#[cw_serde]
+pub struct WithdrawQueueResponse {
+    pub requests: Vec<WithdrawRequest>,
+}
+
 pub fn query_verify_tick_cache(deps: Deps) -> Result<VerifyTickCacheResponse, ContractError> {
     verify_tick_exp_cache(deps.storage)
         .err()
@@ -100,13 +246,14 @@ pub fn query_metadata(deps: Deps) -> Result<MetadataResponse, ContractError> {
     let total_supply = query_total_vault_token_supply(deps)?.total;
 
     let admin = ADMIN_ADDRESS.load(deps.storage)?.to_string();
+    let symbol = vault_denom_symbol(&metadata, &vault_denom);
 
     Ok(MetadataResponse {
         thesis: metadata.thesis,
         name: metadata.name,
         total_supply,
-        symbol: vault_denom,
-        decimals: 6,
+        symbol,
+        decimals: VAULT_DENOM_EXPONENT as u8,
         admin,
     })
 }
@@ -133,6 +280,86 @@ pub fn query_pool(deps: Deps) -> Result<PoolResponse, ContractError> {
     Ok(PoolResponse { pool_config })
 }
 
+/// The nominal liquidity position health ratios are computed for. The token amounts of a range
+/// scale linearly with liquidity, so the ratios do not depend on the actual liquidity.
+const HEALTH_LIQUIDITY: u128 = 1_000_000_000_000_000_000;
+
+pub fn query_position_health(deps: Deps) -> Result<PositionHealthResponse, ContractError> {
+    let pool_config = POOL_CONFIG.load(deps.storage)?;
+    let pool = get_cl_pool_info(&deps.querier, pool_config.pool_id)?;
+    let position = get_position(deps.storage, &deps.querier)?
+        .position
+        .ok_or(ContractError::PositionNotFound)?;
+    let (current_tick, lower_tick, upper_tick) =
+        (pool.current_tick, position.lower_tick, position.upper_tick);
+
+    let current_price = tick_to_price(current_tick)?;
+    let lower_price = tick_to_price(lower_tick)?;
+    let upper_price = tick_to_price(upper_tick)?;
+
+    let liquidity = Uint256::from(HEALTH_LIQUIDITY);
+    let (amount0, amount1) =
+        get_amounts_from_liquidity(liquidity, current_tick, lower_tick, upper_tick)?;
+    let (value0, value) = value_in_token0(amount0, amount1, current_price)?;
+
+    // a range entry of an earlier range says nothing about the current one
+    let entry = RANGE_ENTRY
+        .may_load(deps.storage)?
+        .filter(|entry| entry.lower_tick == lower_tick && entry.upper_tick == upper_tick);
+    let (entry_token0_ratio, impermanent_loss) = match entry {
+        Some(entry) => {
+            let (entry0, entry1) =
+                get_amounts_from_liquidity(liquidity, entry.tick, lower_tick, upper_tick)?;
+            let (entry_value0, entry_value) =
+                value_in_token0(entry0, entry1, tick_to_price(entry.tick)?)?;
+            let (_, hold_value) = value_in_token0(entry0, entry1, current_price)?;
+            (
+                Some(ratio_or_zero(entry_value0, entry_value)?),
+                Some(Decimal256::one().saturating_sub(ratio_or_zero(value, hold_value)?)),
+            )
+        }
+        None => (None, None),
+    };
+
+    Ok(PositionHealthResponse {
+        in_range: lower_tick <= current_tick && current_tick < upper_tick,
+        current_tick,
+        lower_tick,
+        upper_tick,
+        ticks_to_lower: current_tick - lower_tick,
+        ticks_to_upper: upper_tick - current_tick,
+        current_price,
+        lower_price,
+        upper_price,
+        price_to_lower: SignedDecimal256::try_from(current_price)?
+            .checked_sub(SignedDecimal256::try_from(lower_price)?)?,
+        price_to_upper: SignedDecimal256::try_from(upper_price)?
+            .checked_sub(SignedDecimal256::try_from(current_price)?)?,
+        token0_ratio: ratio_or_zero(value0, value)?,
+        entry_token0_ratio,
+        impermanent_loss,
+    })
+}
+
+/// Returns the value of `amount0` and the total value of both amounts in token0, at a `price`
+/// in token1 per token0
+fn value_in_token0(
+    amount0: Uint256,
+    amount1: Uint256,
+    price: Decimal256,
+) -> Result<(Decimal256, Decimal256), ContractError> {
+    let value0 = Decimal256::from_ratio(amount0, 1u128);
+    let value1 = Decimal256::from_ratio(amount1, 1u128).checked_div(price)?;
+    Ok((value0, value0.checked_add(value1)?))
+}
+
+fn ratio_or_zero(part: Decimal256, total: Decimal256) -> Result<Decimal256, ContractError> {
+    if total.is_zero() {
+        return Ok(Decimal256::zero());
+    }
+    Ok(part.checked_div(total)?)
+}
+
 pub fn query_position(deps: Deps) -> Result<PositionResponse, ContractError> {
     let position_id = POSITION.load(deps.storage)?.position_id;
     Ok(PositionResponse {
@@ -157,6 +384,51 @@ pub fn query_assets_from_shares(
     })
 }
 
+/// The shares a deposit of `assets` would mint to the depositor, net of the deposit fee. The
+/// part of the assets that does not fit the position would be refunded.
+pub fn query_preview_deposit(
+    deps: Deps,
+    env: Env,
+    assets: Vec<Coin>,
+) -> Result<Uint128, ContractError> {
+    let pool_config = POOL_CONFIG.load(deps.storage)?;
+    let deposit = get_depositable_tokens(&deps, assets, &pool_config)?;
+    let value = get_value_wrt_asset0(
+        deps.storage,
+        &deps.querier,
+        deposit.base_deposit,
+        deposit.quote_deposit,
+    )?;
+
+    let total_shares = query_total_vault_token_supply(deps)?.total;
+    let total_assets = query_total_assets(deps, env)?;
+    let total_value = get_value_wrt_asset0(
+        deps.storage,
+        &deps.querier,
+        total_assets.token0.amount,
+        total_assets.token1.amount,
+    )?;
+    let shares = if total_shares.is_zero() || total_value.is_zero() {
+        value
+    } else {
+        total_shares.multiply_ratio(value, total_value)
+    };
+
+    let (shares, _) = apply_deposit_fee(&VAULT_CONFIG.load(deps.storage)?, shares);
+    Ok(shares)
+}
+
+/// The assets redeeming `shares` would pay out, net of the exit fee
+pub fn query_preview_redeem(
+    deps: Deps,
+    env: Env,
+    shares: Uint128,
+) -> Result<AssetsBalanceResponse, ContractError> {
+    let assets = query_assets_from_shares(deps, env, shares)?.balances;
+    let (balances, _) = apply_exit_fee(&VAULT_CONFIG.load(deps.storage)?, assets);
+    Ok(AssetsBalanceResponse { balances })
+}
+
 /// User assets is the users assets EXCLUDING any rewards claimable by that user
 pub fn query_user_assets(
     deps: Deps,
@@ -169,6 +441,43 @@ pub fn query_user_assets(
     Ok(user_assets)
 }
 
+pub fn query_user_position(
+    deps: Deps,
+    env: Env,
+    user: String,
+) -> Result<UserPositionResponse, ContractError> {
+    let user = deps.api.addr_validate(&user)?;
+    let cost_basis = get_cost_basis(deps.storage, &user)?;
+    let shares = SHARES
+        .may_load(deps.storage, user.clone())?
+        .unwrap_or_default();
+    let assets = if shares.is_zero() {
+        vec![]
+    } else {
+        query_assets_from_shares(deps, env.clone(), shares)?.balances
+    };
+    let (_, _, value) = get_shares_value(deps, &env, shares)?;
+
+    // tracked shares can be escrowed in unlocking positions or withdraw requests, so they are
+    // valued separately from the balance
+    let (_, _, tracked_value) = get_shares_value(deps, &env, cost_basis.shares)?;
+    let unrealised_pnl =
+        Int128::try_from(tracked_value)?.checked_sub(Int128::try_from(cost_basis.cost_basis)?)?;
+    let average_entry_price = (!cost_basis.shares.is_zero())
+        .then(|| Decimal::checked_from_ratio(cost_basis.cost_basis, cost_basis.shares))
+        .transpose()?;
+
+    Ok(UserPositionResponse {
+        realised_pnl: cost_basis.realised_pnl,
+        cost_basis,
+        shares,
+        assets,
+        value,
+        unrealised_pnl,
+        average_entry_price,
+    })
+}
+
 pub fn query_user_balance(
     deps: Deps,
     user: String,
@@ -208,11 +517,199 @@ pub fn query_active_users(
     Ok(ActiveUsersResponse { users, next_token })
 }
 
-/// Vault base assets is the vault assets EXCLUDING any rewards claimable by strategist or users
+pub fn query_withdraw_queue_config(
+    deps: Deps,
+) -> Result<Option<WithdrawQueueConfig>, ContractError> {
+    Ok(WITHDRAW_QUEUE_CONFIG.may_load(deps.storage)?)
+}
+
+pub fn query_withdraw_request(
+    deps: Deps,
+    request_id: u64,
+) -> Result<WithdrawRequestResponse, ContractError> {
+    let request = WITHDRAW_REQUESTS
+        .may_load(deps.storage, request_id)?
+        .ok_or(ContractError::WithdrawRequestNotFound { id: request_id })?;
+
+    let status = match request.epoch {
+        None => {
+            let position = WITHDRAW_QUEUE
+                .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+                .position(|item| matches!(item, Ok((_, id)) if id == request_id))
+                .ok_or(ContractError::WithdrawRequestNotFound { id: request_id })?;
+            WithdrawRequestStatus::Queued {
+                position: position as u64,
+            }
+        }
+        Some(epoch) => {
+            let batch = WITHDRAW_BATCHES.load(deps.storage, epoch)?;
+            if batch.settled {
+                let pool_config = POOL_CONFIG.load(deps.storage)?;
+                WithdrawRequestStatus::Claimable {
+                    epoch,
+                    assets: get_request_assets(&batch, request.shares, &pool_config)?,
+                }
+            } else {
+                WithdrawRequestStatus::Processing { epoch }
+            }
+        }
+    };
+
+    Ok(WithdrawRequestResponse { request, status })
+}
+
+pub fn query_withdraw_queue(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<WithdrawQueueResponse, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_BATCH_LIMIT).min(MAX_BATCH_LIMIT) as usize;
+
+    let requests: Result<Vec<WithdrawRequest>, ContractError> = WITHDRAW_QUEUE
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .filter(|item| match (item, start_after) {
+            (Ok((_, id)), Some(start_after)) => *id > start_after,
+            _ => true,
+        })
+        .take(limit)
+        .map(|item| Ok(WITHDRAW_REQUESTS.load(deps.storage, item?.1)?))
+        .collect();
+
+    Ok(WithdrawQueueResponse {
+        requests: requests?,
+    })
+}
+
+pub fn query_withdraw_batch(deps: Deps, epoch: u64) -> Result<WithdrawBatch, ContractError> {
+    Ok(WITHDRAW_BATCHES.load(deps.storage, epoch)?)
+}
+
+pub fn query_unlocking_positions(
+    deps: Deps,
+    owner: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Vec<UnlockingPosition>, ContractError> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let limit = limit
+        .unwrap_or(DEFAULT_UNLOCKING_POSITIONS_LIMIT)
+        .min(MAX_UNLOCKING_POSITIONS_LIMIT) as usize;
+
+    UNLOCKING_POSITIONS
+        .prefix(owner)
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            cosmwasm_std::Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| Ok(item?.1))
+        .collect()
+}
+
+pub fn query_unlocking_position(
+    deps: Deps,
+    lockup_id: u64,
+) -> Result<UnlockingPosition, ContractError> {
+    let owner = UNLOCKING_POSITION_OWNERS
+        .may_load(deps.storage, lockup_id)?
+        .ok_or(ContractError::UnlockingPositionNotFound { id: lockup_id })?;
+    Ok(UNLOCKING_POSITIONS.load(deps.storage, (owner, lockup_id))?)
+}
+
+pub fn query_share_seconds(
+    deps: Deps,
+    env: Env,
+    user: String,
+    start: u64,
+    end: u64,
+) -> Result<ShareSecondsResponse, ContractError> {
+    if start > end {
+        return Err(ContractError::InvalidTimeRange { start, end });
+    }
+    let user = deps.api.addr_validate(&user)?;
+    let end = end.min(env.block.time.seconds());
+    let start = start.min(end);
+
+    Ok(ShareSecondsResponse {
+        user_share_seconds: user_share_seconds_at(deps.storage, &user, end)?
+            .checked_sub(user_share_seconds_at(deps.storage, &user, start)?)?,
+        total_share_seconds: total_share_seconds_at(deps.storage, end)?
+            .checked_sub(total_share_seconds_at(deps.storage, start)?)?,
+    })
+}
+
+pub fn query_share_price_history(
+    deps: Deps,
+    limit: Option<u32>,
+) -> Result<SharePriceHistoryResponse, ContractError> {
+    let snapshots = SHARE_PRICE_SNAPSHOTS
+        .iter(deps.storage)?
+        .collect::<Result<Vec<_>, _>>()?;
+    let apr_7d = get_annualised_return(&snapshots, SEVEN_DAYS_SECONDS)?;
+    let apr_30d = get_annualised_return(&snapshots, THIRTY_DAYS_SECONDS)?;
+
+    let limit = limit
+        .unwrap_or(DEFAULT_SHARE_PRICE_HISTORY_LIMIT)
+        .min(MAX_SHARE_PRICE_SNAPSHOTS) as usize;
+    let skip = snapshots.len().saturating_sub(limit);
+
+    Ok(SharePriceHistoryResponse {
+        snapshots: snapshots.into_iter().skip(skip).collect(),
+        apr_7d,
+        apr_30d,
+    })
+}
+
+pub fn query_reward_ledger(deps: Deps) -> Result<RewardLedger, ContractError> {
+    Ok(REWARD_LEDGER.may_load(deps.storage)?.unwrap_or_default())
+}
+
+pub fn query_share_hooks(deps: Deps) -> Result<ShareHooksResponse, ContractError> {
+    Ok(ShareHooksResponse {
+        hooks: SHARE_HOOKS.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+pub fn query_idle_buffer(deps: Deps, env: Env) -> Result<IdleBufferResponse, ContractError> {
+    let config = IDLE_BUFFER_CONFIG.may_load(deps.storage)?;
+    let pool_config = POOL_CONFIG.load(deps.storage)?;
+    let idle = get_unused_pair_balances(&deps, &env, &pool_config)?;
+    let target = config
+        .as_ref()
+        .map(|config| get_buffer_targets(deps, &env, config.target_ratio))
+        .transpose()?
+        .unwrap_or_default();
+
+    Ok(IdleBufferResponse {
+        config,
+        idle,
+        target,
+    })
+}
+
+pub fn query_collect_rewards_before_deposit(deps: Deps) -> Result<bool, ContractError> {
+    Ok(COLLECT_REWARDS_BEFORE_DEPOSIT
+        .may_load(deps.storage)?
+        .unwrap_or_default())
+}
+
+pub fn query_lockup_duration(deps: Deps) -> Result<Duration, ContractError> {
+    LOCKUP_DURATION
+        .may_load(deps.storage)?
+        .ok_or(ContractError::LockupDisabled {})
+}
+
+/// Vault base assets is the vault assets INCLUDING the rewards claimable by the position that are
+/// denominated in pool tokens, net of the performance fee
 pub fn query_total_assets(deps: Deps, env: Env) -> Result<TotalAssetsResponse, ContractError> {
     let position = get_position(deps.storage, &deps.querier)?;
     let pool = POOL_CONFIG.load(deps.storage)?;
-    let unused_balance = get_unused_balances(&deps.querier, &env.contract.address)?;
+    let mut unused_balance =
+        get_unused_balances(deps.storage, &deps.querier, &env.contract.address)?;
+    unused_balance.add(get_claimable_pool_rewards(deps.storage, &position)?)?;
+    unused_balance.add(get_superfluid_assets(deps.storage, &deps.querier)?)?;
 
     // add token0 unused balance to what's in the position
     let mut token0 = position
@@ -259,10 +756,382 @@ pub fn query_total_vault_token_supply(
     Ok(TotalVaultTokenSupplyResponse { total })
 }
 
+#[cw_serde]
+pub struct IbcTransfersResponse {
+    pub transfers: Vec<IbcTransfer>,
+}
+
+pub fn query_ibc_transfers(
+    deps: Deps,
+    start_after: Option<(String, u64)>,
+    limit: Option<u32>,
+) -> Result<IbcTransfersResponse, ContractError> {
+    let limit = limit
+        .unwrap_or(DEFAULT_IBC_TRANSFERS_LIMIT)
+        .min(MAX_IBC_TRANSFERS_LIMIT) as usize;
+
+    let transfers: Result<Vec<IbcTransfer>, ContractError> = IBC_TRANSFERS
+        .range(
+            deps.storage,
+            start_after
+                .as_ref()
+                .map(|(channel, sequence)| Bound::exclusive((channel.as_str(), *sequence))),
+            None,
+            cosmwasm_std::Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| Ok(item?.1))
+        .collect();
+
+    Ok(IbcTransfersResponse {
+        transfers: transfers?,
+    })
+}
+
+#[cw_serde]
+pub struct TransientStateResponse {
+    pub operation_lock: Option<OperationLock>,
+    pub current_swap_info: Option<CurrentSwap>,
+    pub pending_deposit: Option<PendingDeposit>,
+    pub current_cw20_deposit: Option<CurrentCw20Deposit>,
+    pub current_superfluid_validator: Option<String>,
+    pub current_merge: Vec<CurrentMergeWithdraw>,
+    pub current_merge_position: Option<CurrentMergePosition>,
+    pub modify_range_state: Option<ModifyRangeState>,
+    pub swap_deposit_merge_state: Option<SwapDepositMergeState>,
+    pub current_withdrawer: Option<Addr>,
+    pub current_withdrawer_dust: Option<(Uint128, Uint128)>,
+    pub current_withdrawer_callback: Option<RedeemCallback>,
+    pub current_withdrawer_ibc: Option<IbcDestination>,
+    pub pending_ibc_transfers: Vec<IbcTransfer>,
+    pub pending_auto_withdraws: Vec<Addr>,
+}
+
+pub fn query_transient_state(deps: Deps) -> Result<TransientStateResponse, ContractError> {
+    Ok(TransientStateResponse {
+        operation_lock: OPERATION_LOCK.may_load(deps.storage)?,
+        current_swap_info: CURRENT_SWAP_INFO.may_load(deps.storage)?,
+        pending_deposit: PENDING_DEPOSIT.may_load(deps.storage)?,
+        current_cw20_deposit: CURRENT_CW20_DEPOSIT.may_load(deps.storage)?,
+        current_superfluid_validator: CURRENT_SUPERFLUID_VALIDATOR.may_load(deps.storage)?,
+        current_merge: CURRENT_MERGE
+            .iter(deps.storage)?
+            .collect::<Result<_, StdError>>()?,
+        current_merge_position: CURRENT_MERGE_POSITION.may_load(deps.storage)?,
+        modify_range_state: MODIFY_RANGE_STATE.may_load(deps.storage)?.flatten(),
+        swap_deposit_merge_state: SWAP_DEPOSIT_MERGE_STATE.may_load(deps.storage)?,
+        current_withdrawer: CURRENT_WITHDRAWER.may_load(deps.storage)?,
+        current_withdrawer_dust: CURRENT_WITHDRAWER_DUST.may_load(deps.storage)?,
+        current_withdrawer_callback: CURRENT_WITHDRAWER_CALLBACK.may_load(deps.storage)?,
+        current_withdrawer_ibc: CURRENT_WITHDRAWER_IBC.may_load(deps.storage)?,
+        pending_ibc_transfers: PENDING_IBC_TRANSFERS
+            .iter(deps.storage)?
+            .collect::<Result<_, StdError>>()?,
+        pending_auto_withdraws: PENDING_AUTO_WITHDRAWS
+            .iter(deps.storage)?
+            .collect::<Result<_, StdError>>()?,
+    })
+}
+
+#[cw_serde]
+pub struct AutoWithdrawStatusResponse {
+    pub progress: AutoWithdrawProgress,
+    /// users whose last auto withdraw failed, together with the error
+    pub failures: Vec<(Addr, String)>,
+}
+
+pub fn query_auto_withdraw_status(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<AutoWithdrawStatusResponse, ContractError> {
+    let start_after = start_after
+        .map(|user| deps.api.addr_validate(&user))
+        .transpose()?;
+    let limit = limit
+        .unwrap_or(DEFAULT_AUTO_WITHDRAW_LIMIT)
+        .min(MAX_AUTO_WITHDRAW_LIMIT) as usize;
+
+    let failures: Result<Vec<(Addr, String)>, StdError> = AUTO_WITHDRAW_FAILURES
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            cosmwasm_std::Order::Ascending,
+        )
+        .take(limit)
+        .collect();
+
+    Ok(AutoWithdrawStatusResponse {
+        progress: AUTO_WITHDRAW_PROGRESS
+            .may_load(deps.storage)?
+            .unwrap_or_default(),
+        failures: failures?,
+    })
+}
+
+#[cw_serde]
+pub struct OperatorAllowanceInfo {
+    pub operator: String,
+    pub allowance: Uint128,
+    pub expires: Expiration,
+}
+
+#[cw_serde]
+pub struct OperatorAllowancesResponse {
+    pub allowances: Vec<OperatorAllowanceInfo>,
+}
+
+#[cw_serde]
+pub struct DepositorAllowlistResponse {
+    pub allowlist: Option<DepositorAllowlist>,
+}
+
+#[cw_serde]
+pub struct AllowedDepositorsResponse {
+    pub depositors: Vec<String>,
+}
+
+#[cw_serde]
+pub struct IsAllowedDepositorResponse {
+    pub allowed: bool,
+}
+
+pub fn query_depositor_allowlist(deps: Deps) -> Result<DepositorAllowlistResponse, ContractError> {
+    Ok(DepositorAllowlistResponse {
+        allowlist: DEPOSITOR_ALLOWLIST.may_load(deps.storage)?,
+    })
+}
+
+pub fn query_allowed_depositors(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<AllowedDepositorsResponse, ContractError> {
+    let start_after = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    let limit = limit
+        .unwrap_or(DEFAULT_ALLOWED_DEPOSITORS_LIMIT)
+        .min(MAX_ALLOWED_DEPOSITORS_LIMIT) as usize;
+
+    let depositors: Result<Vec<String>, StdError> = ALLOWED_DEPOSITORS
+        .keys(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            cosmwasm_std::Order::Ascending,
+        )
+        .take(limit)
+        .map(|address| Ok(address?.to_string()))
+        .collect();
+
+    Ok(AllowedDepositorsResponse {
+        depositors: depositors?,
+    })
+}
+
+pub fn query_is_allowed_depositor(
+    deps: Deps,
+    address: String,
+) -> Result<IsAllowedDepositorResponse, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    Ok(IsAllowedDepositorResponse {
+        allowed: is_allowed_depositor(deps, &address)?,
+    })
+}
+
+#[cw_serde]
+pub struct ReferrerResponse {
+    /// the shares of the users referred by the referrer that are still held
+    pub referred_shares: Uint128,
+    /// the referral fees the referrer can claim
+    pub accrued_fees: Vec<Coin>,
+}
+
+pub fn query_referrer(deps: Deps, address: String) -> Result<ReferrerResponse, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    let referrer = get_settled_referrer(deps.storage, &address)?;
+    Ok(ReferrerResponse {
+        referred_shares: referrer.shares,
+        accrued_fees: referrer.accrued.coins(),
+    })
+}
+
+#[cw_serde]
+pub struct Cw20DepositAssetsResponse {
+    pub assets: Vec<(Addr, Cw20DepositAsset)>,
+}
+
+pub fn query_cw20_deposit_assets(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Cw20DepositAssetsResponse, ContractError> {
+    let start_after = start_after
+        .map(|token| deps.api.addr_validate(&token))
+        .transpose()?;
+    let limit = limit
+        .unwrap_or(DEFAULT_CW20_DEPOSIT_ASSETS_LIMIT)
+        .min(MAX_CW20_DEPOSIT_ASSETS_LIMIT) as usize;
+
+    let assets: Result<Vec<_>, StdError> = CW20_DEPOSIT_ASSETS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            cosmwasm_std::Order::Ascending,
+        )
+        .take(limit)
+        .collect();
+
+    Ok(Cw20DepositAssetsResponse { assets: assets? })
+}
+
+#[cw_serde]
+pub struct SuperfluidResponse {
+    pub config: Option<SuperfluidConfig>,
+    pub positions: Vec<SuperfluidPosition>,
+    /// the assets in the superfluid positions, including their claimable rewards
+    pub assets: Vec<Coin>,
+}
+
+pub fn query_superfluid(deps: Deps) -> Result<SuperfluidResponse, ContractError> {
+    let positions: Result<Vec<_>, StdError> = SUPERFLUID_POSITIONS
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(|item| Ok(item?.1))
+        .collect();
+    Ok(SuperfluidResponse {
+        config: SUPERFLUID_CONFIG.may_load(deps.storage)?,
+        positions: positions?,
+        assets: get_superfluid_assets(deps.storage, &deps.querier)?.coins(),
+    })
+}
+
+pub fn query_operator_allowance(
+    deps: Deps,
+    owner: String,
+    operator: String,
+) -> Result<OperatorAllowance, ContractError> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let operator = deps.api.addr_validate(&operator)?;
+    Ok(OPERATOR_ALLOWANCES
+        .may_load(deps.storage, (owner, operator))?
+        .unwrap_or(OperatorAllowance {
+            allowance: Uint128::zero(),
+            expires: Expiration::Never {},
+        }))
+}
+
+pub fn query_operator_allowances(
+    deps: Deps,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<OperatorAllowancesResponse, ContractError> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let start_after = start_after
+        .map(|operator| deps.api.addr_validate(&operator))
+        .transpose()?;
+    let limit = limit
+        .unwrap_or(DEFAULT_OPERATOR_ALLOWANCES_LIMIT)
+        .min(MAX_OPERATOR_ALLOWANCES_LIMIT) as usize;
+
+    let allowances: Result<Vec<OperatorAllowanceInfo>, ContractError> = OPERATOR_ALLOWANCES
+        .prefix(owner)
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            cosmwasm_std::Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            let (operator, allowance) = item?;
+            Ok(OperatorAllowanceInfo {
+                operator: operator.to_string(),
+                allowance: allowance.allowance,
+                expires: allowance.expires,
+            })
+        })
+        .collect();
+
+    Ok(OperatorAllowancesResponse {
+        allowances: allowances?,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use cosmwasm_std::{testing::mock_dependencies, Addr, Uint128};
+    use crate::test_helpers::{
+        instantiate_contract, mock_deps_with_querier, BASE_DENOM, QUOTE_DENOM,
+    };
+    use cosmwasm_std::{
+        testing::{mock_dependencies, mock_env},
+        Addr, Uint128,
+    };
+
+    #[test]
+    fn position_health_compares_against_the_range_entry() {
+        let mut deps = mock_deps_with_querier();
+        let env = mock_env();
+        instantiate_contract(deps.as_mut(), env.clone(), "admin");
+
+        // the mocked position ranges from tick 100 to 1000 with the pool at tick 500
+        let health = query_position_health(deps.as_ref()).unwrap();
+        assert!(health.in_range);
+        assert_eq!(health.ticks_to_lower, 400);
+        assert_eq!(health.ticks_to_upper, 500);
+        assert!(health.price_to_lower > SignedDecimal256::zero());
+        assert!(health.price_to_upper > SignedDecimal256::zero());
+        assert!(!health.token0_ratio.is_zero() && health.token0_ratio < Decimal256::one());
+        assert_eq!(health.entry_token0_ratio, None);
+        assert_eq!(health.impermanent_loss, None);
+
+        let entry = |tick, lower_tick| crate::state::RangeEntry {
+            tick,
+            lower_tick,
+            upper_tick: 1000,
+            time: env.block.time.seconds(),
+        };
+
+        // no loss while the pool is at the price the range was created at
+        RANGE_ENTRY
+            .save(deps.as_mut().storage, &entry(500, 100))
+            .unwrap();
+        let health = query_position_health(deps.as_ref()).unwrap();
+        assert_eq!(health.entry_token0_ratio, Some(health.token0_ratio));
+        assert_eq!(health.impermanent_loss, Some(Decimal256::zero()));
+
+        // the price fell since the range was created, so the position holds more token0
+        RANGE_ENTRY
+            .save(deps.as_mut().storage, &entry(900, 100))
+            .unwrap();
+        let health = query_position_health(deps.as_ref()).unwrap();
+        assert!(health.entry_token0_ratio.unwrap() < health.token0_ratio);
+        assert!(health.impermanent_loss.unwrap() > Decimal256::zero());
+
+        // an entry of another range is ignored
+        RANGE_ENTRY
+            .save(deps.as_mut().storage, &entry(500, 200))
+            .unwrap();
+        let health = query_position_health(deps.as_ref()).unwrap();
+        assert_eq!(health.impermanent_loss, None);
+    }
+
+    #[test]
+    fn total_assets_include_claimable_rewards() {
+        let mut deps = mock_deps_with_querier();
+        let env = mock_env();
+        instantiate_contract(deps.as_mut(), env.clone(), "admin");
+
+        // 1_000_000 of each token in the position and 100 of each token in claimable spread
+        // rewards, of which the 10% performance fee is excluded
+        let total_assets = query_total_assets(deps.as_ref(), env).unwrap();
+        assert_eq!(total_assets.token0, coin(1_000_090, BASE_DENOM));
+        assert_eq!(total_assets.token1, coin(1_000_090, QUOTE_DENOM));
+    }
 
     #[test]
     fn test_query_active_users_with_conditions() {
@@ -30,6 +30,22 @@ pub enum Replies {
     Autocompound,
     /// handle exact deposit swap reply
     AnyDepositSwap,
+    /// settle a batch of queued withdrawals after liquidity is removed from the position
+    WithdrawQueueBatch,
+    /// catch errors of share hook calls, so a failing hook does not fail the vault operation
+    ShareHook,
+    /// track the packet sequence of a transfer of a redeem to another chain
+    IbcTransfer,
+    /// record the result of the withdraw of a single user in AutoWithdraw
+    AutoWithdrawUser,
+    /// unwrap and swap the tokens of a CW20 deposit into a pool token
+    Cw20Deposit,
+    /// record the position and lock of a new superfluid position
+    SuperfluidDelegate,
+    /// continue a deposit once the rewards of the vault are collected
+    DepositCollectRewards,
+    /// take the balance snapshot of a CW20 deposit once the rewards of the vault are collected
+    Cw20DepositCollectRewards,
     #[default]
     Unknown,
 }
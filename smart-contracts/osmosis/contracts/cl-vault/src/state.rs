@@ -1,8 +1,11 @@
+use crate::helpers::coinlist::CoinList;
 use crate::vault::merge::CurrentMergeWithdraw;
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Coin, Decimal, Decimal256, Uint128};
+use cosmwasm_std::{Addr, Binary, Coin, Decimal, Decimal256, Empty, Int128, Uint128, Uint256};
 use cw_storage_plus::{Deque, Item, Map};
+use cw_utils::{Duration, Expiration};
 use osmosis_std::types::osmosis::poolmanager::v1beta1::SwapAmountInRoute;
+use quasar_types::cw_vault_multi_standard::extensions::lockup::UnlockingPosition;
 use quasar_types::pool_pair::PoolPair;
 
 #[cw_serde]
@@ -10,6 +13,8 @@ pub struct Metadata {
     /// the underlying thesis of the vault's positions, eg aggresive
     pub thesis: String,
     pub name: String,
+    /// the symbol of the vault share token, defaults to the subdenom of the vault denom
+    pub symbol: Option<String>,
 }
 
 pub const METADATA: Item<Metadata> = Item::new("metadata");
@@ -25,10 +30,34 @@ pub struct VaultConfig {
     pub dex_router: Addr,
     pub swap_admin: Addr,
     pub twap_window_seconds: u64,
+    /// fee on deposits in basis points, taken from the minted shares
+    #[serde(default)]
+    pub deposit_fee_bps: u16,
+    /// fee on withdrawals in basis points, taken from the paid out assets
+    #[serde(default)]
+    pub exit_fee_bps: u16,
+    #[serde(default)]
+    pub deposit_exit_fee_destination: FeeDestination,
+    /// share of the performance fee on the rewards of referred shares that goes to referrers
+    #[serde(default)]
+    pub referral_fee_share: Decimal,
+}
+
+/// Where deposit and exit fees go
+#[cw_serde]
+#[derive(Default)]
+pub enum FeeDestination {
+    /// the fee shares are minted to, and the fee assets sent to the treasury
+    #[default]
+    Treasury,
+    /// the fee stays in the vault, raising the share price for the remaining holders
+    Vault,
 }
 
 pub const VAULT_CONFIG: Item<VaultConfig> = Item::new("vault_config");
 pub const VAULT_DENOM: Item<String> = Item::new("vault_denom");
+/// when set, rewards are collected from the position before every deposit
+pub const COLLECT_REWARDS_BEFORE_DEPOSIT: Item<bool> = Item::new("collect_rewards_before_deposit");
 
 #[cw_serde]
 pub struct PoolConfig {
@@ -77,10 +106,25 @@ pub struct CurrentDeposit {
 pub struct CurrentSwap {
     pub recipient: Addr,
     pub vault_balance: PoolPair<Coin, Coin>,
+    #[serde(default)]
+    pub referrer: Option<Addr>,
 }
 
 pub const CURRENT_SWAP_INFO: Item<CurrentSwap> = Item::new("current_swap_recipient");
 
+/// A deposit that continues once the rewards of the vault are collected
+#[cw_serde]
+pub struct PendingDeposit {
+    pub sender: Addr,
+    pub funds: Vec<Coin>,
+    pub recipient: Option<String>,
+    pub referrer: Option<String>,
+    /// the max slippage of an any deposit, `None` for an exact deposit
+    pub max_slippage: Option<Decimal>,
+}
+
+pub const PENDING_DEPOSIT: Item<PendingDeposit> = Item::new("pending_deposit");
+
 pub const DEX_ROUTER: Item<Addr> = Item::new("dex_router");
 
 #[cw_serde]
@@ -118,6 +162,354 @@ pub const TICK_EXP_CACHE: Map<i64, TickExpIndexData> = Map::new("tick_exp_cache"
 pub const CURRENT_WITHDRAWER: Item<Addr> = Item::new("current_withdrawer");
 pub const CURRENT_WITHDRAWER_DUST: Item<(Uint128, Uint128)> = Item::new("current_withdrawer_dust");
 
+#[cw_serde]
+pub struct RedeemCallback {
+    /// the owner of the redeemed shares
+    pub owner: Addr,
+    pub shares: Uint128,
+    /// the message passed on to the receiving contract
+    pub msg: Binary,
+}
+
+/// set while a RedeemAndCall withdraws, the withdraw reply then calls CURRENT_WITHDRAWER with the assets
+pub const CURRENT_WITHDRAWER_CALLBACK: Item<RedeemCallback> =
+    Item::new("current_withdrawer_callback");
+
+#[cw_serde]
+pub struct IbcDestination {
+    /// the channel on this chain the withdrawn funds are transferred over
+    pub channel_id: String,
+    /// the address on the other chain receiving the funds
+    pub receiver: String,
+    /// the transfer times out this many seconds after the redeem
+    pub timeout_seconds: u64,
+}
+
+/// Set when the current withdraw is redeemed to another chain instead of CURRENT_WITHDRAWER
+pub const CURRENT_WITHDRAWER_IBC: Item<IbcDestination> = Item::new("current_withdrawer_ibc");
+
+#[cw_serde]
+pub struct IbcTransfer {
+    /// the owner of the redeemed shares, failed transfers are refunded to this address
+    pub owner: Addr,
+    pub receiver: String,
+    pub channel_id: String,
+    /// the packet sequence of the transfer, known once the transfer is sent
+    pub sequence: u64,
+    pub token: Coin,
+}
+
+/// Transfers waiting for their packet sequence in the transfer reply, in the order they are sent
+pub const PENDING_IBC_TRANSFERS: Deque<IbcTransfer> = Deque::new("pending_ibc_transfers");
+/// Transfers waiting for their ack or timeout, by source channel and packet sequence
+pub const IBC_TRANSFERS: Map<(&str, u64), IbcTransfer> = Map::new("ibc_transfers");
+
+#[cw_serde]
+pub struct WithdrawQueueConfig {
+    /// Redemptions of more than this ratio of the total vault shares are queued instead of withdrawn directly
+    pub share_threshold: Decimal,
+    /// minimum amount of seconds between two processed withdraw batches
+    pub epoch_seconds: u64,
+    /// optional keeper that is allowed to process batches next to the admin
+    pub keeper: Option<Addr>,
+}
+
+/// The withdraw queue is disabled when no config is set
+pub const WITHDRAW_QUEUE_CONFIG: Item<WithdrawQueueConfig> = Item::new("withdraw_queue_config");
+
+#[cw_serde]
+pub struct WithdrawRequest {
+    pub id: u64,
+    pub owner: Addr,
+    pub recipient: Addr,
+    /// the escrowed shares, these are debited from SHARES on enqueue and burned when the batch is processed
+    pub shares: Uint128,
+    pub created_at: u64,
+    /// the epoch of the batch this request was processed in, None while the request is still queued
+    pub epoch: Option<u64>,
+}
+
+#[cw_serde]
+pub struct WithdrawBatch {
+    pub epoch: u64,
+    pub total_shares: Uint128,
+    pub processed_at: u64,
+    /// the withdrawn amounts, including the dust belonging to the batch, set once the batch settles
+    pub amount0: Uint128,
+    pub amount1: Uint128,
+    pub settled: bool,
+    /// the shares and assets of the requests claimed so far
+    #[serde(default)]
+    pub claimed_shares: Uint128,
+    #[serde(default)]
+    pub claimed_amount0: Uint128,
+    #[serde(default)]
+    pub claimed_amount1: Uint128,
+}
+
+/// FIFO queue of withdraw request ids, managed through quasar_types::queue
+pub const WITHDRAW_QUEUE: Map<u128, u64> = Map::new("withdraw_queue");
+pub const WITHDRAW_REQUESTS: Map<u64, WithdrawRequest> = Map::new("withdraw_requests");
+pub const WITHDRAW_REQUEST_COUNT: Item<u64> = Item::new("withdraw_request_count");
+pub const WITHDRAW_BATCHES: Map<u64, WithdrawBatch> = Map::new("withdraw_batches");
+pub const CURRENT_WITHDRAW_EPOCH: Item<u64> = Item::new("current_withdraw_epoch");
+/// the epoch of the batch that is currently being withdrawn from the position
+pub const CURRENT_WITHDRAW_BATCH: Item<u64> = Item::new("current_withdraw_batch");
+/// assets of settled withdraw batches that are not claimed yet, these are held by the contract
+/// but no longer belong to the vault
+pub const WITHDRAW_QUEUE_RESERVED: Item<CoinList> = Item::new("withdraw_queue_reserved");
+
+#[cw_serde]
+pub struct IdleBufferConfig {
+    /// ratio of the vault assets of each token that is kept idle outside of the position
+    pub target_ratio: Decimal,
+    /// largest ratio of the total vault shares a single redemption may redeem out of the buffer,
+    /// larger redemptions withdraw from the position so they can not drain the buffer
+    pub max_withdraw_ratio: Decimal,
+    /// optional keeper that is allowed to rebalance the buffer next to the admin
+    pub keeper: Option<Addr>,
+}
+
+/// Redemptions are only served from the idle buffer when a config is set
+pub const IDLE_BUFFER_CONFIG: Item<IdleBufferConfig> = Item::new("idle_buffer_config");
+
+/// contracts that are notified of every change to SHARES
+pub const SHARE_HOOKS: Item<Vec<Addr>> = Item::new("share_hooks");
+
+#[cw_serde]
+pub struct ShareSecondsCheckpoint {
+    /// the share balance from the checkpoint on
+    pub shares: Uint128,
+    /// the cumulative share-seconds up to the checkpoint
+    pub share_seconds: Uint256,
+}
+
+/// the time from which share-seconds are accumulated
+pub const SHARE_SECONDS_START: Item<u64> = Item::new("share_seconds_start");
+/// share-seconds checkpoints per user and timestamp, written on every change to SHARES
+pub const USER_SHARE_SECONDS: Map<(Addr, u64), ShareSecondsCheckpoint> =
+    Map::new("user_share_seconds");
+/// share-seconds checkpoints of the sum of all SHARES per timestamp
+pub const TOTAL_SHARE_SECONDS: Map<u64, ShareSecondsCheckpoint> = Map::new("total_share_seconds");
+
+#[cw_serde]
+#[derive(Default)]
+pub struct RewardLedger {
+    /// spread rewards collected from the position, including the strategist fee
+    pub spread_rewards: CoinList,
+    /// incentives collected from the position, including the strategist fee
+    pub incentives: CoinList,
+    pub forfeited_incentives: CoinList,
+    /// performance fees sent to the treasury
+    pub strategist_fees: CoinList,
+    /// the part of the performance fees set aside for referrers
+    #[serde(default)]
+    pub referral_fees: CoinList,
+    /// superfluid staking rewards paid to the vault, including the strategist fee
+    #[serde(default)]
+    pub staking_rewards: CoinList,
+    /// idle funds deposited into the position by autocompounding
+    pub compounded: CoinList,
+}
+
+/// lifetime totals of everything collected, paid and compounded by the vault
+pub const REWARD_LEDGER: Item<RewardLedger> = Item::new("reward_ledger");
+
+#[cw_serde]
+pub struct SharePriceSnapshot {
+    pub timestamp: u64,
+    /// the total vault assets at the time of the snapshot
+    pub token0: Coin,
+    pub token1: Coin,
+    pub total_shares: Uint128,
+    /// the value of a single share denominated in token0
+    pub price_per_share: Decimal,
+}
+
+/// ring buffer of share price snapshots, taken on autocompounds, reward collections and range changes
+pub const SHARE_PRICE_SNAPSHOTS: Deque<SharePriceSnapshot> = Deque::new("share_price_snapshots");
+
+/// The vault runs in lockup mode when a duration is set, shares are then redeemed through Unlock and WithdrawUnlocked
+pub const LOCKUP_DURATION: Item<Duration> = Item::new("lockup_duration");
+/// unlocking positions indexed by owner and lockup id, base_token_amount holds the escrowed shares
+pub const UNLOCKING_POSITIONS: Map<(Addr, u64), UnlockingPosition> =
+    Map::new("unlocking_positions");
+pub const UNLOCKING_POSITION_OWNERS: Map<u64, Addr> = Map::new("unlocking_position_owners");
+pub const UNLOCKING_POSITION_COUNT: Item<u64> = Item::new("unlocking_position_count");
+
+#[cw_serde]
+pub struct OperatorAllowance {
+    /// the amount of shares the operator can still redeem on behalf of the owner
+    pub allowance: Uint128,
+    pub expires: Expiration,
+}
+
+/// share allowances of operators, indexed by owner and operator
+pub const OPERATOR_ALLOWANCES: Map<(Addr, Addr), OperatorAllowance> =
+    Map::new("operator_allowances");
+
+#[cw_serde]
+#[derive(Default)]
+pub struct AutoWithdrawProgress {
+    /// the last user of SHARES withdrawn by a paginated AutoWithdraw, None once all users are
+    /// processed, in which case the next paginated AutoWithdraw starts from the first user again
+    pub cursor: Option<Addr>,
+    /// lifetime counts of the user withdraws done by AutoWithdraw
+    pub succeeded: u64,
+    pub failed: u64,
+}
+
+pub const AUTO_WITHDRAW_PROGRESS: Item<AutoWithdrawProgress> = Item::new("auto_withdraw_progress");
+/// users of dispatched auto withdraws, in dispatch order, popped again in the replies
+pub const PENDING_AUTO_WITHDRAWS: Deque<Addr> = Deque::new("pending_auto_withdraws");
+/// the error of the last failed auto withdraw per user, removed once a later one succeeds
+pub const AUTO_WITHDRAW_FAILURES: Map<Addr, String> = Map::new("auto_withdraw_failures");
+
+#[cw_serde]
+pub struct OperationLock {
+    /// the flow holding the lock, e.g. withdraw or modify_range
+    pub operation: String,
+    /// the block height at which the lock was taken
+    pub height: u64,
+}
+
+/// held by flows that keep state across replies from their start until their final reply
+pub const OPERATION_LOCK: Item<OperationLock> = Item::new("operation_lock");
+
+/// The deposits and withdrawals of a user, values are in token0 at the time of each action
+#[cw_serde]
+#[derive(Default)]
+pub struct UserCostBasis {
+    pub deposited0: Uint128,
+    pub deposited1: Uint128,
+    pub deposited_value: Uint128,
+    pub withdrawn0: Uint128,
+    pub withdrawn1: Uint128,
+    pub withdrawn_value: Uint128,
+    /// the shares minted by tracked deposits that the user did not redeem yet
+    pub shares: Uint128,
+    /// the value paid for `shares`, at the average entry price
+    pub cost_basis: Uint128,
+    /// the value withdrawn minus the cost basis of the redeemed shares
+    pub realised_pnl: Int128,
+}
+
+pub const USER_COST_BASIS: Map<Addr, UserCostBasis> = Map::new("user_cost_basis");
+
+#[cw_serde]
+pub struct RangeEntry {
+    /// the pool tick at the time the range was created
+    pub tick: i64,
+    pub lower_tick: i64,
+    pub upper_tick: i64,
+    /// the block time at which the range was created
+    pub time: u64,
+}
+
+/// the pool state at the creation of the current range of the position
+pub const RANGE_ENTRY: Item<RangeEntry> = Item::new("range_entry");
+
+/// How depositors are allowed to deposit into the vault
+#[cw_serde]
+pub enum DepositorAllowlist {
+    /// only the addresses in ALLOWED_DEPOSITORS, managed by the admin
+    Admin,
+    /// the addresses the given contract answers AllowlistQueryMsg::IsAllowed with true for
+    Contract { address: Addr },
+}
+
+/// Deposits are only restricted when an allowlist is set, redemptions are always open
+pub const DEPOSITOR_ALLOWLIST: Item<DepositorAllowlist> = Item::new("depositor_allowlist");
+pub const ALLOWED_DEPOSITORS: Map<Addr, Empty> = Map::new("allowed_depositors");
+
+#[cw_serde]
+pub struct Referral {
+    /// the partner the user was first referred by
+    pub referrer: Addr,
+    /// the shares of the user attributed to the referrer, at most the share balance of the user
+    pub shares: Uint128,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct Referrer {
+    /// the shares of all users attributed to the referrer
+    pub shares: Uint128,
+    /// REFERRAL_FEE_INDEX at the last time the accrued fees were settled
+    pub index: Vec<(String, Decimal256)>,
+    /// the settled referral fees that are not claimed yet
+    pub accrued: CoinList,
+}
+
+pub const REFERRALS: Map<Addr, Referral> = Map::new("referrals");
+pub const REFERRERS: Map<Addr, Referrer> = Map::new("referrers");
+pub const TOTAL_REFERRED_SHARES: Item<Uint128> = Item::new("total_referred_shares");
+/// the referral fees paid per referred share since the first referral, per denom
+pub const REFERRAL_FEE_INDEX: Item<Vec<(String, Decimal256)>> = Item::new("referral_fee_index");
+/// the referral fees held by the vault until the referrers claim them
+pub const REFERRAL_FEES_RESERVED: Item<CoinList> = Item::new("referral_fees_reserved");
+
+/// How a CW20 token accepted for deposits is turned into a native token the dex router can swap
+#[cw_serde]
+pub struct Cw20DepositAsset {
+    /// the contract the CW20 tokens are sent to, it pays out `native_denom` to the vault
+    pub converter: Addr,
+    /// the native denom the converter pays out
+    pub native_denom: String,
+    /// the message sent along with the CW20 tokens to the converter
+    pub unwrap_msg: Binary,
+}
+
+/// The CW20 tokens accepted for deposits, keyed by token contract
+pub const CW20_DEPOSIT_ASSETS: Map<Addr, Cw20DepositAsset> = Map::new("cw20_deposit_assets");
+
+#[cw_serde]
+pub struct CurrentCw20Deposit {
+    /// the sender of the CW20 tokens
+    pub depositor: Addr,
+    pub recipient: Addr,
+    pub referrer: Option<Addr>,
+    pub max_slippage: Decimal,
+    /// the native denom the deposit is held in at the current step
+    pub denom: String,
+    /// the vault balance of `denom` before the current step
+    pub balance_before: Uint128,
+}
+
+pub const CURRENT_CW20_DEPOSIT: Item<CurrentCw20Deposit> = Item::new("current_cw20_deposit");
+
+#[cw_serde]
+pub struct SuperfluidConfig {
+    /// the validators superfluid positions are delegated to, in turn
+    pub validators: Vec<String>,
+    /// the highest share of the vault value that can be held in superfluid positions, the rest
+    /// stays liquid to pay out redemptions
+    pub max_ratio: Decimal,
+}
+
+/// Superfluid positions can only be created while a superfluid config is set
+pub const SUPERFLUID_CONFIG: Item<SuperfluidConfig> = Item::new("superfluid_config");
+
+#[cw_serde]
+pub struct SuperfluidPosition {
+    pub position_id: u64,
+    pub lock_id: u64,
+    pub validator: String,
+    /// the gauge of the intermediary account the position is delegated through
+    pub gauge_id: u64,
+    /// the time the lock started unbonding, the position can be withdrawn once unbonded
+    pub unbonding_since: Option<u64>,
+}
+
+/// The full range superfluid delegated positions of the vault, next to the range position
+pub const SUPERFLUID_POSITIONS: Map<u64, SuperfluidPosition> = Map::new("superfluid_positions");
+/// The OSMO each superfluid gauge the vault has bonded positions in had distributed as of the last
+/// staking rewards collection
+pub const SUPERFLUID_GAUGE_CHECKPOINTS: Map<u64, Uint128> =
+    Map::new("superfluid_gauge_checkpoints");
+/// The validator of the superfluid position being created
+pub const CURRENT_SUPERFLUID_VALIDATOR: Item<String> = Item::new("current_superfluid_validator");
+
 #[cfg(test)]
 mod tests {
     use super::PoolConfig;
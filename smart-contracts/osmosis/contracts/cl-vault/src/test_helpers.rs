@@ -4,12 +4,17 @@ use cosmwasm_std::testing::{mock_info, BankQuerier, MockApi, MockStorage, MOCK_C
 use cosmwasm_std::{
     coin, from_json, to_json_binary, Addr, BankQuery, Binary, Coin,
     ContractResult as CwContractResult, Decimal, DepsMut, Empty, Env, OwnedDeps, Querier,
-    QuerierResult, QueryRequest,
+    QuerierResult, QueryRequest, Uint128, WasmQuery,
 };
+use dex_router_osmosis::msg::{BestPathForPairResponse, QueryMsg as DexRouterQueryMsg};
 use osmosis_std::types::cosmos::bank::v1beta1::{QuerySupplyOfRequest, QuerySupplyOfResponse};
 use osmosis_std::types::osmosis::concentratedliquidity::v1beta1::Pool;
+use osmosis_std::types::osmosis::incentives::{Gauge, GaugeByIdResponse};
+use osmosis_std::types::osmosis::lockup::{
+    LockedDenomResponse, LockedResponse, PeriodLock, QueryCondition,
+};
 use osmosis_std::types::osmosis::poolmanager::{
-    v1beta1::{PoolResponse, SpotPriceResponse},
+    v1beta1::{PoolResponse, SpotPriceResponse, SwapAmountInRoute},
     v2::SpotPriceResponse as V2SpotPriceResponse,
 };
 use osmosis_std::types::osmosis::twap::v1beta1::ArithmeticTwapToNowResponse;
@@ -23,7 +28,7 @@ use osmosis_std::types::{
 use crate::contract::instantiate;
 use crate::math::tick::tick_to_price;
 use crate::msg::InstantiateMsg;
-use crate::state::{Position, VaultConfig, POSITION, VAULT_DENOM};
+use crate::state::{FeeDestination, Position, VaultConfig, POSITION, VAULT_DENOM};
 
 pub const POOL_ID: u64 = 1;
 pub const POSITION_ID: u64 = 101;
@@ -33,6 +38,9 @@ pub const TEST_VAULT_DENOM: &str = "uqsr";
 pub const INSTANTIATE_BASE_DEPOSIT_AMOUNT: u128 = 100;
 pub const INSTANTIATE_QUOTE_DEPOSIT_AMOUNT: u128 = 100;
 pub const TEST_VAULT_TOKEN_SUPPLY: u128 = 100_000;
+/// every superfluid gauge has distributed this much OSMO to the 400 liquidity locked in it, of
+/// which every lock holds 100
+pub const GAUGE_DISTRIBUTED_OSMO: u128 = 2000;
 
 pub struct QuasarQuerier {
     position: FullPositionBreakdown,
@@ -64,6 +72,12 @@ impl QuasarQuerier {
     pub fn update_balances(&mut self, balances: &[(&str, &[Coin])]) {
         self.bank = BankQuerier::new(balances);
     }
+
+    /// Empty the claimable rewards of the position, as collecting them does
+    pub fn clear_claimable_rewards(&mut self) {
+        self.position.claimable_spread_rewards = vec![];
+        self.position.claimable_incentives = vec![];
+    }
 }
 
 impl Querier for QuasarQuerier {
@@ -159,11 +173,65 @@ impl Querier for QuasarQuerier {
                         .unwrap(),
                     ))
                 }
+                "/osmosis.incentives.Query/GaugeByID" => QuerierResult::Ok(CwContractResult::Ok(
+                    to_json_binary(&GaugeByIdResponse {
+                        gauge: Some(Gauge {
+                            is_perpetual: true,
+                            distribute_to: Some(QueryCondition {
+                                denom: format!("cl/pool/{POOL_ID}/superbonding/val"),
+                                ..Default::default()
+                            }),
+                            distributed_coins: vec![OsmoCoin {
+                                denom: "uosmo".to_string(),
+                                amount: GAUGE_DISTRIBUTED_OSMO.to_string(),
+                            }],
+                            ..Default::default()
+                        }),
+                    })
+                    .unwrap(),
+                )),
+                "/osmosis.lockup.Query/LockedDenom" => QuerierResult::Ok(CwContractResult::Ok(
+                    to_json_binary(&LockedDenomResponse {
+                        amount: "400".to_string(),
+                    })
+                    .unwrap(),
+                )),
+                "/osmosis.lockup.Query/LockedByID" => QuerierResult::Ok(CwContractResult::Ok(
+                    to_json_binary(&LockedResponse {
+                        lock: Some(PeriodLock {
+                            coins: vec![OsmoCoin {
+                                denom: format!("cl/pool/{POOL_ID}"),
+                                amount: "100".to_string(),
+                            }],
+                            ..Default::default()
+                        }),
+                    })
+                    .unwrap(),
+                )),
                 &_ => QuerierResult::Err(cosmwasm_std::SystemError::UnsupportedRequest {
                     kind: format!("Unmocked stargate query path: {path:?}"),
                 }),
             },
             QueryRequest::Bank(query) => self.bank.query(&query),
+            // the dex router quotes a spot return of twice the offer over a single hop through
+            // the vault pool
+            QueryRequest::Wasm(WasmQuery::Smart { msg, .. }) => match from_json(msg).unwrap() {
+                DexRouterQueryMsg::BestPathForPair { offer, ask_denom } => {
+                    QuerierResult::Ok(CwContractResult::Ok(
+                        to_json_binary(&Some(BestPathForPairResponse {
+                            path: vec![SwapAmountInRoute {
+                                pool_id: POOL_ID,
+                                token_out_denom: ask_denom,
+                            }],
+                            return_amount: offer.amount * Uint128::new(2),
+                        }))
+                        .unwrap(),
+                    ))
+                }
+                msg => QuerierResult::Err(cosmwasm_std::SystemError::UnsupportedRequest {
+                    kind: format!("Unmocked dex router query: {msg:?}"),
+                }),
+            },
             _ => QuerierResult::Err(cosmwasm_std::SystemError::UnsupportedRequest {
                 kind: format!("Unmocked query type: {request:?}"),
             }),
@@ -292,6 +360,10 @@ pub fn get_init_msg(admin: &str) -> InstantiateMsg {
             dex_router: Addr::unchecked(admin),
             swap_admin: Addr::unchecked(admin),
             twap_window_seconds: 24u64,
+            deposit_fee_bps: 0,
+            exit_fee_bps: 0,
+            deposit_exit_fee_destination: FeeDestination::Treasury,
+            referral_fee_share: Decimal::zero(),
         },
         vault_token_subdenom: "utestvault".to_string(),
         range_admin: admin.to_string(),
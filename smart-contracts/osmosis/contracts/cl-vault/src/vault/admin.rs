@@ -1,10 +1,32 @@
 use crate::error::assert_admin;
+use crate::helpers::fees::validate_entry_exit_fees;
+use crate::helpers::msgs::{set_denom_metadata_msg, validate_symbol};
+use crate::helpers::prepend::prepend_claim_msg;
+use crate::helpers::shares::MAX_SHARE_HOOKS;
 use crate::math::tick::build_tick_exp_cache;
-use crate::state::{Metadata, VaultConfig, ADMIN_ADDRESS, METADATA, RANGE_ADMIN, VAULT_CONFIG};
-use crate::vault::withdraw::execute_withdraw;
+use crate::state::{
+    IdleBufferConfig, Metadata, VaultConfig, WithdrawQueueConfig, ADMIN_ADDRESS,
+    IDLE_BUFFER_CONFIG, LOCKUP_DURATION, METADATA, RANGE_ADMIN, SHARE_HOOKS, VAULT_CONFIG,
+    VAULT_DENOM, WITHDRAW_QUEUE_CONFIG,
+};
+use crate::state::{
+    COLLECT_REWARDS_BEFORE_DEPOSIT, CURRENT_CW20_DEPOSIT, CURRENT_MERGE, CURRENT_MERGE_POSITION,
+    CURRENT_SUPERFLUID_VALIDATOR, CURRENT_SWAP_INFO, CURRENT_WITHDRAWER,
+    CURRENT_WITHDRAWER_CALLBACK, CURRENT_WITHDRAWER_DUST, CURRENT_WITHDRAWER_IBC,
+    MODIFY_RANGE_STATE, OPERATION_LOCK, PENDING_AUTO_WITHDRAWS, PENDING_IBC_TRANSFERS,
+    SWAP_DEPOSIT_MERGE_STATE,
+};
+use crate::vault::allowlist::{
+    execute_update_allowed_depositors, execute_update_depositor_allowlist,
+};
+use crate::vault::auto_withdraw::execute_auto_withdraw;
+use crate::vault::cw20_deposit::execute_update_cw20_deposit_asset;
+use crate::vault::range::execute_migrate_pool;
+use crate::vault::referral::validate_referral_fee_share;
+use crate::vault::superfluid::execute_update_superfluid_config;
 use crate::{msg::AdminExtensionExecuteMsg, ContractError};
-use cosmwasm_std::{Addr, Decimal, DepsMut, Env, MessageInfo, Response, StdError, Uint128};
-use cw_utils::nonpayable;
+use cosmwasm_std::{Decimal, DepsMut, Env, MessageInfo, Response, StdError};
+use cw_utils::{nonpayable, Duration};
 
 pub(crate) fn execute_admin(
     deps: DepsMut,
@@ -20,15 +42,67 @@ pub(crate) fn execute_admin(
             execute_update_config(deps, info, updates)
         }
         AdminExtensionExecuteMsg::UpdateMetadata { updates } => {
-            execute_update_metadata(deps, info, updates)
+            execute_update_metadata(deps, &env, info, updates)
         }
         AdminExtensionExecuteMsg::UpdateRangeAdmin { address } => {
             execute_update_range_admin(deps, info, address)
         }
         AdminExtensionExecuteMsg::BuildTickCache {} => execute_build_tick_exp_cache(deps, info),
-        AdminExtensionExecuteMsg::AutoWithdraw { users } => {
-            execute_auto_claim(deps, &env, info, users)
+        AdminExtensionExecuteMsg::AutoWithdraw { users, limit } => {
+            execute_auto_withdraw(deps, &env, info, users, limit)
         }
+        AdminExtensionExecuteMsg::UpdateWithdrawQueueConfig { config } => {
+            execute_update_withdraw_queue_config(deps, info, config)
+        }
+        AdminExtensionExecuteMsg::AddShareHook { address } => {
+            execute_add_share_hook(deps, info, address)
+        }
+        AdminExtensionExecuteMsg::RemoveShareHook { address } => {
+            execute_remove_share_hook(deps, info, address)
+        }
+        AdminExtensionExecuteMsg::UpdateIdleBufferConfig { config } => {
+            execute_update_idle_buffer_config(deps, info, config)
+        }
+        AdminExtensionExecuteMsg::UpdateLockupDuration { duration } => {
+            execute_update_lockup_duration(deps, info, duration)
+        }
+        AdminExtensionExecuteMsg::UpdateCollectRewardsBeforeDeposit { enabled } => {
+            execute_update_collect_rewards_before_deposit(deps, info, enabled)
+        }
+        AdminExtensionExecuteMsg::UpdateDepositorAllowlist { allowlist } => {
+            execute_update_depositor_allowlist(deps, info, allowlist)
+        }
+        AdminExtensionExecuteMsg::UpdateAllowedDepositors { add, remove } => {
+            execute_update_allowed_depositors(deps, info, add, remove)
+        }
+        AdminExtensionExecuteMsg::UpdateCw20DepositAsset { token, asset } => {
+            execute_update_cw20_deposit_asset(deps, info, token, asset)
+        }
+        AdminExtensionExecuteMsg::UpdateSuperfluidConfig { config } => {
+            execute_update_superfluid_config(deps, info, config)
+        }
+        AdminExtensionExecuteMsg::ResetTransientState {} => {
+            execute_reset_transient_state(deps, &env, info)
+        }
+        AdminExtensionExecuteMsg::MigratePool {
+            new_pool_id,
+            lower_price,
+            upper_price,
+            max_slippage,
+            twap_window_seconds,
+        } => prepend_claim_msg(
+            &env,
+            execute_migrate_pool(
+                deps,
+                &env,
+                info,
+                new_pool_id,
+                lower_price,
+                upper_price,
+                max_slippage,
+                twap_window_seconds,
+            )?,
+        ),
     }
 }
 
@@ -101,6 +175,8 @@ pub fn execute_update_config(
             "performance fee cannot be more than 1.0",
         )));
     }
+    validate_entry_exit_fees(&updates)?;
+    validate_referral_fee_share(updates.referral_fee_share)?;
 
     VAULT_CONFIG.save(deps.storage, &updates)?;
 
@@ -110,22 +186,177 @@ pub fn execute_update_config(
         .add_attribute("updates", format!("{:?}", updates)))
 }
 
+/// Updates the metadata of the vault together with the bank metadata of the vault share token.
 pub fn execute_update_metadata(
     deps: DepsMut,
+    env: &Env,
     info: MessageInfo,
     updates: Metadata,
 ) -> Result<Response, ContractError> {
     nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
     assert_admin(deps.storage, &info.sender)?;
+    if let Some(symbol) = &updates.symbol {
+        validate_symbol(symbol)?;
+    }
 
     METADATA.save(deps.storage, &updates)?;
+    let vault_denom = VAULT_DENOM.load(deps.storage)?;
 
     Ok(Response::default()
+        .add_message(set_denom_metadata_msg(env, &vault_denom, &updates))
         .add_attribute("method", "execute")
         .add_attribute("action", "update_metadata")
         .add_attribute("updates", format!("{:?}", updates)))
 }
 
+/// Updates the withdraw queue configuration of the contract.
+///
+/// Setting the config to None disables queueing of new redemptions, requests that are already
+/// queued can still be processed and claimed.
+pub fn execute_update_withdraw_queue_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: Option<WithdrawQueueConfig>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    assert_admin(deps.storage, &info.sender)?;
+
+    match &config {
+        Some(config) => {
+            if config.share_threshold.is_zero() || config.share_threshold > Decimal::one() {
+                return Err(ContractError::InvalidShareThreshold {});
+            }
+            if let Some(keeper) = &config.keeper {
+                deps.api.addr_validate(keeper.as_str())?;
+            }
+            WITHDRAW_QUEUE_CONFIG.save(deps.storage, config)?;
+        }
+        None => WITHDRAW_QUEUE_CONFIG.remove(deps.storage),
+    }
+
+    Ok(Response::default()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "update_withdraw_queue_config")
+        .add_attribute("updates", format!("{:?}", config)))
+}
+
+/// Register a contract that receives a `SharesChanged` message on every share balance change
+pub fn execute_add_share_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    assert_admin(deps.storage, &info.sender)?;
+
+    let hook = deps.api.addr_validate(&address)?;
+    let mut hooks = SHARE_HOOKS.may_load(deps.storage)?.unwrap_or_default();
+    if hooks.contains(&hook) {
+        return Err(ContractError::ShareHookAlreadyRegistered { address });
+    }
+    if hooks.len() >= MAX_SHARE_HOOKS {
+        return Err(ContractError::MaxShareHooksReached {
+            max: MAX_SHARE_HOOKS,
+        });
+    }
+    hooks.push(hook);
+    SHARE_HOOKS.save(deps.storage, &hooks)?;
+
+    Ok(Response::default()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "add_share_hook")
+        .add_attribute("address", address))
+}
+
+pub fn execute_remove_share_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    assert_admin(deps.storage, &info.sender)?;
+
+    let mut hooks = SHARE_HOOKS.may_load(deps.storage)?.unwrap_or_default();
+    let len = hooks.len();
+    hooks.retain(|hook| hook.as_str() != address);
+    if hooks.len() == len {
+        return Err(ContractError::ShareHookNotFound { address });
+    }
+    SHARE_HOOKS.save(deps.storage, &hooks)?;
+
+    Ok(Response::default()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "remove_share_hook")
+        .add_attribute("address", address))
+}
+
+pub fn execute_update_idle_buffer_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: Option<IdleBufferConfig>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    assert_admin(deps.storage, &info.sender)?;
+
+    match &config {
+        Some(config) => {
+            if config.target_ratio.is_zero() || config.target_ratio >= Decimal::one() {
+                return Err(ContractError::InvalidIdleBufferRatio {});
+            }
+            if config.max_withdraw_ratio.is_zero() || config.max_withdraw_ratio > Decimal::one() {
+                return Err(ContractError::InvalidIdleBufferRatio {});
+            }
+            if let Some(keeper) = &config.keeper {
+                deps.api.addr_validate(keeper.as_str())?;
+            }
+            IDLE_BUFFER_CONFIG.save(deps.storage, config)?;
+        }
+        None => IDLE_BUFFER_CONFIG.remove(deps.storage),
+    }
+
+    Ok(Response::default()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "update_idle_buffer_config")
+        .add_attribute("updates", format!("{:?}", config)))
+}
+
+/// Enable, update or disable the lockup mode of the vault. Unlocking positions that already exist
+/// keep their release time and can still be withdrawn after the lockup is disabled.
+pub fn execute_update_lockup_duration(
+    deps: DepsMut,
+    info: MessageInfo,
+    duration: Option<Duration>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    assert_admin(deps.storage, &info.sender)?;
+
+    match &duration {
+        Some(duration) => LOCKUP_DURATION.save(deps.storage, duration)?,
+        None => LOCKUP_DURATION.remove(deps.storage),
+    }
+
+    Ok(Response::default()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "update_lockup_duration")
+        .add_attribute("updates", format!("{:?}", duration)))
+}
+
+pub fn execute_update_collect_rewards_before_deposit(
+    deps: DepsMut,
+    info: MessageInfo,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    assert_admin(deps.storage, &info.sender)?;
+
+    COLLECT_REWARDS_BEFORE_DEPOSIT.save(deps.storage, &enabled)?;
+
+    Ok(Response::default()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "update_collect_rewards_before_deposit")
+        .add_attribute("updates", format!("{:?}", enabled)))
+}
+
 // Rebuild the tick exponent cache as admin
 pub fn execute_build_tick_exp_cache(
     deps: DepsMut,
@@ -141,51 +372,61 @@ pub fn execute_build_tick_exp_cache(
         .add_attribute("action", "build_tick_exp_cache"))
 }
 
-pub fn execute_auto_claim(
-    mut deps: DepsMut,
+/// Clears all state kept across replies and releases the operation lock.
+///
+/// A lock taken in the current block can belong to an operation that is still running in this
+/// transaction, resetting then would corrupt that operation, so the reset is refused.
+pub fn execute_reset_transient_state(
+    deps: DepsMut,
     env: &Env,
     info: MessageInfo,
-    users: Vec<(String, Uint128)>,
 ) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
     assert_admin(deps.storage, &info.sender)?;
-    let mut res = Response::new();
 
-    // Iterate over each address and execute withdraw
-    for user_data in users {
-        deps.api.addr_validate(user_data.0.as_str())?;
-
-        let user_info = MessageInfo {
-            sender: Addr::unchecked(user_data.0.clone()),
-            funds: vec![],
-        };
-        let withdraw_response = execute_withdraw(
-            deps.branch(),
-            env,
-            user_info,
-            Some(user_data.0.to_string()),
-            user_data.1.into(),
-        )?;
-
-        let withdraw_messages = withdraw_response.messages.iter().map(|sm| sm.msg.clone());
-
-        res = res
-            .add_messages(withdraw_messages)
-            .add_attributes(withdraw_response.attributes);
+    let lock = OPERATION_LOCK.may_load(deps.storage)?;
+    if lock
+        .as_ref()
+        .is_some_and(|lock| lock.height >= env.block.height)
+    {
+        return Err(ContractError::TransientStateInUse {});
     }
 
-    Ok(res
+    OPERATION_LOCK.remove(deps.storage);
+    CURRENT_SWAP_INFO.remove(deps.storage);
+    CURRENT_CW20_DEPOSIT.remove(deps.storage);
+    CURRENT_SUPERFLUID_VALIDATOR.remove(deps.storage);
+    while CURRENT_MERGE.pop_back(deps.storage)?.is_some() {}
+    CURRENT_MERGE_POSITION.remove(deps.storage);
+    MODIFY_RANGE_STATE.save(deps.storage, &None)?;
+    SWAP_DEPOSIT_MERGE_STATE.remove(deps.storage);
+    CURRENT_WITHDRAWER.remove(deps.storage);
+    CURRENT_WITHDRAWER_DUST.remove(deps.storage);
+    CURRENT_WITHDRAWER_CALLBACK.remove(deps.storage);
+    CURRENT_WITHDRAWER_IBC.remove(deps.storage);
+    while PENDING_IBC_TRANSFERS.pop_back(deps.storage)?.is_some() {}
+    while PENDING_AUTO_WITHDRAWS.pop_back(deps.storage)?.is_some() {}
+
+    Ok(Response::new()
         .add_attribute("method", "execute")
-        .add_attribute("action", "auto_withdraw"))
+        .add_attribute("action", "reset_transient_state")
+        .add_attribute(
+            "released_lock",
+            lock.map_or("none".to_string(), |lock| lock.operation),
+        ))
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::helpers::operation_lock::acquire_operation_lock;
     use crate::math::tick::{build_tick_exp_cache, verify_tick_exp_cache};
+    use crate::query::query_transient_state;
+    use crate::state::FeeDestination;
 
     use super::*;
     use cosmwasm_std::{
         coin,
-        testing::{mock_dependencies, mock_info},
+        testing::{mock_dependencies, mock_env, mock_info},
         Addr, Decimal, Uint128,
     };
 
@@ -346,6 +587,10 @@ mod tests {
             dex_router: Addr::unchecked("old_dex_router"),
             swap_admin: Addr::unchecked("old_swap_admin"),
             twap_window_seconds: 0u64,
+            deposit_fee_bps: 0,
+            exit_fee_bps: 0,
+            deposit_exit_fee_destination: FeeDestination::Treasury,
+            referral_fee_share: Decimal::zero(),
         };
         let mut deps = mock_dependencies();
         ADMIN_ADDRESS.save(deps.as_mut().storage, &admin).unwrap();
@@ -360,6 +605,10 @@ mod tests {
             dex_router: Addr::unchecked("new_dex_router"),
             swap_admin: Addr::unchecked("new_swap_admin"),
             twap_window_seconds: 24u64,
+            deposit_fee_bps: 0,
+            exit_fee_bps: 0,
+            deposit_exit_fee_destination: FeeDestination::Treasury,
+            referral_fee_share: Decimal::zero(),
         };
         let info_admin: MessageInfo = mock_info("admin", &[]);
 
@@ -380,6 +629,10 @@ mod tests {
             dex_router: Addr::unchecked("old_dex_router"),
             swap_admin: Addr::unchecked("old_swap_admin"),
             twap_window_seconds: 0u64,
+            deposit_fee_bps: 0,
+            exit_fee_bps: 0,
+            deposit_exit_fee_destination: FeeDestination::Treasury,
+            referral_fee_share: Decimal::zero(),
         };
         let mut deps = mock_dependencies();
         ADMIN_ADDRESS.save(deps.as_mut().storage, &admin).unwrap();
@@ -394,6 +647,10 @@ mod tests {
             dex_router: Addr::unchecked("new_dex_router"),
             swap_admin: Addr::unchecked("new_swap_admin"),
             twap_window_seconds: 24u64,
+            deposit_fee_bps: 0,
+            exit_fee_bps: 0,
+            deposit_exit_fee_destination: FeeDestination::Treasury,
+            referral_fee_share: Decimal::zero(),
         };
         let info_not_admin = mock_info("not_admin", &[]);
 
@@ -414,6 +671,10 @@ mod tests {
             dex_router: Addr::unchecked("old_dex_router"),
             swap_admin: Addr::unchecked("old_swap_admin"),
             twap_window_seconds: 0u64,
+            deposit_fee_bps: 0,
+            exit_fee_bps: 0,
+            deposit_exit_fee_destination: FeeDestination::Treasury,
+            referral_fee_share: Decimal::zero(),
         };
         let mut deps = mock_dependencies();
         ADMIN_ADDRESS.save(deps.as_mut().storage, &admin).unwrap();
@@ -428,6 +689,10 @@ mod tests {
             dex_router: Addr::unchecked("new_dex_router"),
             swap_admin: Addr::unchecked("new_swap_admin"),
             twap_window_seconds: 24u64,
+            deposit_fee_bps: 0,
+            exit_fee_bps: 0,
+            deposit_exit_fee_destination: FeeDestination::Treasury,
+            referral_fee_share: Decimal::zero(),
         };
 
         let info_admin_with_funds = mock_info("admin", &[coin(1, "token")]);
@@ -446,6 +711,10 @@ mod tests {
             dex_router: Addr::unchecked("old_dex_router"),
             swap_admin: Addr::unchecked("old_swap_admin"),
             twap_window_seconds: 24u64,
+            deposit_fee_bps: 0,
+            exit_fee_bps: 0,
+            deposit_exit_fee_destination: FeeDestination::Treasury,
+            referral_fee_share: Decimal::zero(),
         };
         let mut deps = mock_dependencies();
         ADMIN_ADDRESS.save(deps.as_mut().storage, &admin).unwrap();
@@ -469,18 +738,32 @@ mod tests {
         let old_metadata = Metadata {
             name: "old_name".to_string(),
             thesis: "old_thesis".to_string(),
+            symbol: None,
         };
         let mut deps = mock_dependencies();
         ADMIN_ADDRESS.save(deps.as_mut().storage, &admin).unwrap();
         METADATA.save(deps.as_mut().storage, &old_metadata).unwrap();
+        VAULT_DENOM
+            .save(
+                deps.as_mut().storage,
+                &"factory/contract/vaulttoken".to_string(),
+            )
+            .unwrap();
 
         let new_metadata = Metadata {
             name: "new_name".to_string(),
             thesis: "new_thesis".to_string(),
+            symbol: Some("cvNEW".to_string()),
         };
         let info_admin: MessageInfo = mock_info("admin", &[]);
 
-        assert!(execute_update_metadata(deps.as_mut(), info_admin, new_metadata.clone()).is_ok());
+        assert!(execute_update_metadata(
+            deps.as_mut(),
+            &mock_env(),
+            info_admin,
+            new_metadata.clone()
+        )
+        .is_ok());
         assert_eq!(METADATA.load(deps.as_mut().storage).unwrap(), new_metadata);
     }
 
@@ -490,18 +773,29 @@ mod tests {
         let old_metadata = Metadata {
             name: "old_name".to_string(),
             thesis: "old_thesis".to_string(),
+            symbol: None,
         };
         let mut deps = mock_dependencies();
         ADMIN_ADDRESS.save(deps.as_mut().storage, &admin).unwrap();
         METADATA.save(deps.as_mut().storage, &old_metadata).unwrap();
+        VAULT_DENOM
+            .save(
+                deps.as_mut().storage,
+                &"factory/contract/vaulttoken".to_string(),
+            )
+            .unwrap();
 
         let new_metadata = Metadata {
             name: "new_name".to_string(),
             thesis: "new_thesis".to_string(),
+            symbol: Some("cvNEW".to_string()),
         };
         let info_not_admin = mock_info("not_admin", &[]);
 
-        assert!(execute_update_metadata(deps.as_mut(), info_not_admin, new_metadata).is_err());
+        assert!(
+            execute_update_metadata(deps.as_mut(), &mock_env(), info_not_admin, new_metadata)
+                .is_err()
+        );
         assert_eq!(METADATA.load(deps.as_mut().storage).unwrap(), old_metadata);
     }
 
@@ -511,19 +805,32 @@ mod tests {
         let old_metadata = Metadata {
             name: "old_name".to_string(),
             thesis: "old_thesis".to_string(),
+            symbol: None,
         };
         let mut deps = mock_dependencies();
         ADMIN_ADDRESS.save(deps.as_mut().storage, &admin).unwrap();
         METADATA.save(deps.as_mut().storage, &old_metadata).unwrap();
+        VAULT_DENOM
+            .save(
+                deps.as_mut().storage,
+                &"factory/contract/vaulttoken".to_string(),
+            )
+            .unwrap();
 
         let new_metadata = Metadata {
             name: "new_name".to_string(),
             thesis: "new_thesis".to_string(),
+            symbol: Some("cvNEW".to_string()),
         };
 
         let info_admin_with_funds = mock_info("admin", &[coin(1, "token")]);
 
-        let result = execute_update_metadata(deps.as_mut(), info_admin_with_funds, new_metadata);
+        let result = execute_update_metadata(
+            deps.as_mut(),
+            &mock_env(),
+            info_admin_with_funds,
+            new_metadata,
+        );
         assert!(result.is_err(), "Expected Err, but got: {:?}", result);
     }
 
@@ -533,18 +840,233 @@ mod tests {
         let old_metadata = Metadata {
             name: "old_name".to_string(),
             thesis: "old_thesis".to_string(),
+            symbol: None,
         };
         let mut deps = mock_dependencies();
         ADMIN_ADDRESS.save(deps.as_mut().storage, &admin).unwrap();
         METADATA.save(deps.as_mut().storage, &old_metadata).unwrap();
+        VAULT_DENOM
+            .save(
+                deps.as_mut().storage,
+                &"factory/contract/vaulttoken".to_string(),
+            )
+            .unwrap();
 
         let info_admin: MessageInfo = mock_info("admin", &[]);
 
-        let res = execute_update_metadata(deps.as_mut(), info_admin, old_metadata.clone());
+        let res =
+            execute_update_metadata(deps.as_mut(), &mock_env(), info_admin, old_metadata.clone());
         assert!(res.is_ok());
         assert_eq!(METADATA.load(deps.as_mut().storage).unwrap(), old_metadata);
     }
 
+    #[test]
+    fn test_execute_update_withdraw_queue_config() {
+        let admin = Addr::unchecked("admin");
+        let mut deps = mock_dependencies();
+        ADMIN_ADDRESS.save(deps.as_mut().storage, &admin).unwrap();
+
+        let config = WithdrawQueueConfig {
+            share_threshold: Decimal::percent(5),
+            epoch_seconds: 86400,
+            keeper: Some(Addr::unchecked("keeper")),
+        };
+
+        execute_update_withdraw_queue_config(
+            deps.as_mut(),
+            mock_info("not_admin", &[]),
+            Some(config.clone()),
+        )
+        .unwrap_err();
+
+        let err = execute_update_withdraw_queue_config(
+            deps.as_mut(),
+            mock_info("admin", &[]),
+            Some(WithdrawQueueConfig {
+                share_threshold: Decimal::zero(),
+                ..config.clone()
+            }),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidShareThreshold {});
+
+        execute_update_withdraw_queue_config(
+            deps.as_mut(),
+            mock_info("admin", &[]),
+            Some(config.clone()),
+        )
+        .unwrap();
+        assert_eq!(
+            WITHDRAW_QUEUE_CONFIG.load(deps.as_ref().storage).unwrap(),
+            config
+        );
+
+        execute_update_withdraw_queue_config(deps.as_mut(), mock_info("admin", &[]), None).unwrap();
+        assert!(WITHDRAW_QUEUE_CONFIG
+            .may_load(deps.as_ref().storage)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_execute_add_and_remove_share_hook() {
+        let admin = Addr::unchecked("admin");
+        let mut deps = mock_dependencies();
+        ADMIN_ADDRESS.save(deps.as_mut().storage, &admin).unwrap();
+
+        let err = execute_add_share_hook(
+            deps.as_mut(),
+            mock_info("not_admin", &[]),
+            "hook".to_string(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        for i in 0..MAX_SHARE_HOOKS {
+            execute_add_share_hook(deps.as_mut(), mock_info("admin", &[]), format!("hook{i}"))
+                .unwrap();
+        }
+        let err =
+            execute_add_share_hook(deps.as_mut(), mock_info("admin", &[]), "hook0".to_string())
+                .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ShareHookAlreadyRegistered {
+                address: "hook0".to_string()
+            }
+        );
+        let err =
+            execute_add_share_hook(deps.as_mut(), mock_info("admin", &[]), "extra".to_string())
+                .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MaxShareHooksReached {
+                max: MAX_SHARE_HOOKS
+            }
+        );
+
+        execute_remove_share_hook(deps.as_mut(), mock_info("admin", &[]), "hook0".to_string())
+            .unwrap();
+        assert_eq!(
+            SHARE_HOOKS.load(deps.as_ref().storage).unwrap().len(),
+            MAX_SHARE_HOOKS - 1
+        );
+        let err =
+            execute_remove_share_hook(deps.as_mut(), mock_info("admin", &[]), "hook0".to_string())
+                .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ShareHookNotFound {
+                address: "hook0".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_execute_update_idle_buffer_config() {
+        let admin = Addr::unchecked("admin");
+        let mut deps = mock_dependencies();
+        ADMIN_ADDRESS.save(deps.as_mut().storage, &admin).unwrap();
+
+        let config = IdleBufferConfig {
+            target_ratio: Decimal::percent(5),
+            max_withdraw_ratio: Decimal::percent(1),
+            keeper: None,
+        };
+
+        let err = execute_update_idle_buffer_config(
+            deps.as_mut(),
+            mock_info("admin", &[]),
+            Some(IdleBufferConfig {
+                target_ratio: Decimal::one(),
+                ..config.clone()
+            }),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidIdleBufferRatio {});
+
+        let err = execute_update_idle_buffer_config(
+            deps.as_mut(),
+            mock_info("admin", &[]),
+            Some(IdleBufferConfig {
+                max_withdraw_ratio: Decimal::zero(),
+                ..config.clone()
+            }),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidIdleBufferRatio {});
+
+        execute_update_idle_buffer_config(
+            deps.as_mut(),
+            mock_info("admin", &[]),
+            Some(config.clone()),
+        )
+        .unwrap();
+        assert_eq!(
+            IDLE_BUFFER_CONFIG.load(deps.as_ref().storage).unwrap(),
+            config
+        );
+
+        execute_update_idle_buffer_config(deps.as_mut(), mock_info("admin", &[]), None).unwrap();
+        assert!(IDLE_BUFFER_CONFIG
+            .may_load(deps.as_ref().storage)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_execute_update_lockup_duration() {
+        let admin = Addr::unchecked("admin");
+        let mut deps = mock_dependencies();
+        ADMIN_ADDRESS.save(deps.as_mut().storage, &admin).unwrap();
+
+        let err = execute_update_lockup_duration(
+            deps.as_mut(),
+            mock_info("not_admin", &[]),
+            Some(Duration::Time(86400)),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        execute_update_lockup_duration(
+            deps.as_mut(),
+            mock_info("admin", &[]),
+            Some(Duration::Time(86400)),
+        )
+        .unwrap();
+        assert_eq!(
+            LOCKUP_DURATION.load(deps.as_ref().storage).unwrap(),
+            Duration::Time(86400)
+        );
+
+        execute_update_lockup_duration(deps.as_mut(), mock_info("admin", &[]), None).unwrap();
+        assert!(LOCKUP_DURATION
+            .may_load(deps.as_ref().storage)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_execute_update_collect_rewards_before_deposit() {
+        let admin = Addr::unchecked("admin");
+        let mut deps = mock_dependencies();
+        ADMIN_ADDRESS.save(deps.as_mut().storage, &admin).unwrap();
+
+        let err = execute_update_collect_rewards_before_deposit(
+            deps.as_mut(),
+            mock_info("not_admin", &[]),
+            true,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        execute_update_collect_rewards_before_deposit(deps.as_mut(), mock_info("admin", &[]), true)
+            .unwrap();
+        assert!(COLLECT_REWARDS_BEFORE_DEPOSIT
+            .load(deps.as_ref().storage)
+            .unwrap());
+    }
+
     #[test]
     fn test_assert_admin() {
         let mut deps = mock_dependencies();
@@ -555,4 +1077,42 @@ mod tests {
         assert!(assert_admin(deps.as_ref().storage, &admin).is_ok());
         assert!(assert_admin(deps.as_ref().storage, &not_admin).is_err());
     }
+
+    #[test]
+    fn test_execute_reset_transient_state() {
+        let admin = Addr::unchecked("admin");
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        ADMIN_ADDRESS.save(deps.as_mut().storage, &admin).unwrap();
+
+        acquire_operation_lock(deps.as_mut().storage, &env, "withdraw").unwrap();
+        CURRENT_WITHDRAWER
+            .save(deps.as_mut().storage, &Addr::unchecked("bolice"))
+            .unwrap();
+        CURRENT_WITHDRAWER_DUST
+            .save(deps.as_mut().storage, &(Uint128::one(), Uint128::one()))
+            .unwrap();
+        PENDING_AUTO_WITHDRAWS
+            .push_back(deps.as_mut().storage, &Addr::unchecked("bolice"))
+            .unwrap();
+
+        let err = execute_reset_transient_state(deps.as_mut(), &env, mock_info("bolice", &[]))
+            .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // the operation might still be running in the block it started
+        let err = execute_reset_transient_state(deps.as_mut(), &env, mock_info("admin", &[]))
+            .unwrap_err();
+        assert_eq!(err, ContractError::TransientStateInUse {});
+
+        env.block.height += 1;
+        execute_reset_transient_state(deps.as_mut(), &env, mock_info("admin", &[])).unwrap();
+
+        let state = query_transient_state(deps.as_ref()).unwrap();
+        assert_eq!(state.operation_lock, None);
+        assert_eq!(state.current_withdrawer, None);
+        assert_eq!(state.current_withdrawer_dust, None);
+        assert!(state.pending_auto_withdraws.is_empty());
+        acquire_operation_lock(deps.as_mut().storage, &env, "modify_range").unwrap();
+    }
 }
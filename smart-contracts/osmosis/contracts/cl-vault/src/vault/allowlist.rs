@@ -0,0 +1,201 @@
+use cosmwasm_std::{Addr, Deps, DepsMut, Empty, MessageInfo, Response};
+use cw_utils::nonpayable;
+
+use crate::{
+    error::assert_admin,
+    msg::{AllowlistQueryMsg, IsAllowedResponse},
+    state::{DepositorAllowlist, ALLOWED_DEPOSITORS, DEPOSITOR_ALLOWLIST},
+    ContractError,
+};
+
+/// The amount of allowed depositors returned by a query if no limit is given
+pub const DEFAULT_ALLOWED_DEPOSITORS_LIMIT: u32 = 50;
+pub const MAX_ALLOWED_DEPOSITORS_LIMIT: u32 = 200;
+
+/// Update how depositors are allowed, None opens deposits to everyone again. The addresses
+/// allowed by the admin are kept, so the admin allowlist can be switched off and on again.
+pub fn execute_update_depositor_allowlist(
+    deps: DepsMut,
+    info: MessageInfo,
+    allowlist: Option<DepositorAllowlist>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    assert_admin(deps.storage, &info.sender)?;
+
+    match &allowlist {
+        Some(allowlist) => {
+            if let DepositorAllowlist::Contract { address } = allowlist {
+                deps.api.addr_validate(address.as_str())?;
+            }
+            DEPOSITOR_ALLOWLIST.save(deps.storage, allowlist)?;
+        }
+        None => DEPOSITOR_ALLOWLIST.remove(deps.storage),
+    }
+
+    Ok(Response::default()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "update_depositor_allowlist")
+        .add_attribute("updates", format!("{:?}", allowlist)))
+}
+
+/// Add and remove addresses of the admin managed depositor allowlist
+pub fn execute_update_allowed_depositors(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    assert_admin(deps.storage, &info.sender)?;
+
+    for address in &add {
+        ALLOWED_DEPOSITORS.save(deps.storage, deps.api.addr_validate(address)?, &Empty {})?;
+    }
+    for address in &remove {
+        ALLOWED_DEPOSITORS.remove(deps.storage, deps.api.addr_validate(address)?);
+    }
+
+    Ok(Response::default()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "update_allowed_depositors")
+        .add_attribute("added", add.len().to_string())
+        .add_attribute("removed", remove.len().to_string()))
+}
+
+/// Returns whether `address` is allowed to deposit, everyone is if no allowlist is set
+pub fn is_allowed_depositor(deps: Deps, address: &Addr) -> Result<bool, ContractError> {
+    Ok(match DEPOSITOR_ALLOWLIST.may_load(deps.storage)? {
+        None => true,
+        Some(DepositorAllowlist::Admin) => ALLOWED_DEPOSITORS.has(deps.storage, address.clone()),
+        Some(DepositorAllowlist::Contract { address: contract }) => {
+            let response: IsAllowedResponse = deps.querier.query_wasm_smart(
+                contract,
+                &AllowlistQueryMsg::IsAllowed {
+                    address: address.to_string(),
+                },
+            )?;
+            response.allowed
+        }
+    })
+}
+
+/// Both the sender of a deposit and the recipient of the minted shares have to be allowed
+pub fn assert_allowed_depositors(
+    deps: Deps,
+    sender: &Addr,
+    recipient: &Addr,
+) -> Result<(), ContractError> {
+    for address in [sender, recipient] {
+        if !is_allowed_depositor(deps, address)? {
+            return Err(ContractError::DepositorNotAllowed {
+                address: address.to_string(),
+            });
+        }
+        if sender == recipient {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        from_json,
+        testing::{mock_dependencies, mock_info},
+        to_json_binary, ContractResult, SystemResult, WasmQuery,
+    };
+
+    use super::*;
+    use crate::state::ADMIN_ADDRESS;
+
+    #[test]
+    fn admin_allowlist_restricts_sender_and_recipient() {
+        let mut deps = mock_dependencies();
+        ADMIN_ADDRESS
+            .save(deps.as_mut().storage, &Addr::unchecked("admin"))
+            .unwrap();
+        let (alice, bob) = (Addr::unchecked("alice"), Addr::unchecked("bob"));
+
+        // deposits are open without an allowlist
+        assert_allowed_depositors(deps.as_ref(), &alice, &bob).unwrap();
+
+        let err = execute_update_allowed_depositors(
+            deps.as_mut(),
+            mock_info("alice", &[]),
+            vec!["alice".to_string()],
+            vec![],
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        execute_update_depositor_allowlist(
+            deps.as_mut(),
+            mock_info("admin", &[]),
+            Some(DepositorAllowlist::Admin),
+        )
+        .unwrap();
+        execute_update_allowed_depositors(
+            deps.as_mut(),
+            mock_info("admin", &[]),
+            vec!["alice".to_string(), "bob".to_string()],
+            vec![],
+        )
+        .unwrap();
+        assert_allowed_depositors(deps.as_ref(), &alice, &bob).unwrap();
+
+        execute_update_allowed_depositors(
+            deps.as_mut(),
+            mock_info("admin", &[]),
+            vec![],
+            vec!["bob".to_string()],
+        )
+        .unwrap();
+        assert_allowed_depositors(deps.as_ref(), &alice, &alice).unwrap();
+        let err = assert_allowed_depositors(deps.as_ref(), &alice, &bob).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DepositorNotAllowed {
+                address: "bob".to_string()
+            }
+        );
+        let err = assert_allowed_depositors(deps.as_ref(), &bob, &alice).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DepositorNotAllowed {
+                address: "bob".to_string()
+            }
+        );
+
+        execute_update_depositor_allowlist(deps.as_mut(), mock_info("admin", &[]), None).unwrap();
+        assert_allowed_depositors(deps.as_ref(), &bob, &bob).unwrap();
+    }
+
+    #[test]
+    fn contract_allowlist_is_queried() {
+        let mut deps = mock_dependencies();
+        DEPOSITOR_ALLOWLIST
+            .save(
+                deps.as_mut().storage,
+                &DepositorAllowlist::Contract {
+                    address: Addr::unchecked("kyc"),
+                },
+            )
+            .unwrap();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "kyc" => {
+                let AllowlistQueryMsg::IsAllowed { address } = from_json(msg).unwrap();
+                SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&IsAllowedResponse {
+                        allowed: address == "alice",
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => panic!("unexpected query"),
+        });
+
+        assert!(is_allowed_depositor(deps.as_ref(), &Addr::unchecked("alice")).unwrap());
+        assert!(!is_allowed_depositor(deps.as_ref(), &Addr::unchecked("bob")).unwrap());
+    }
+}
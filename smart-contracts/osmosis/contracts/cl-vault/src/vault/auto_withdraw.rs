@@ -0,0 +1,377 @@
+use cosmwasm_std::{
+    to_json_binary, DepsMut, Env, MessageInfo, Order, Response, StdError, SubMsg, SubMsgResult,
+    Uint128, WasmMsg,
+};
+use cw_storage_plus::Bound;
+use cw_utils::nonpayable;
+
+use crate::{
+    contract::redeem,
+    error::assert_admin,
+    msg::{ExecuteMsg, ExtensionExecuteMsg},
+    reply::Replies,
+    state::{AUTO_WITHDRAW_FAILURES, AUTO_WITHDRAW_PROGRESS, PENDING_AUTO_WITHDRAWS, SHARES},
+    ContractError,
+};
+
+/// The amount of users withdrawn by a paginated AutoWithdraw if no limit is given
+pub const DEFAULT_AUTO_WITHDRAW_LIMIT: u32 = 20;
+pub const MAX_AUTO_WITHDRAW_LIMIT: u32 = 100;
+
+/// Withdraw the given users, or the next page of users in SHARES, each in a separate submessage.
+/// The submessages execute one after another, so every withdraw settles in its own reply before
+/// the next one starts.
+pub fn execute_auto_withdraw(
+    deps: DepsMut,
+    env: &Env,
+    info: MessageInfo,
+    users: Option<Vec<(String, Uint128)>>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    assert_admin(deps.storage, &info.sender)?;
+
+    let users = match users {
+        Some(users) => users
+            .into_iter()
+            .map(|(user, amount)| Ok((deps.api.addr_validate(&user)?, amount)))
+            .collect::<Result<Vec<_>, ContractError>>()?,
+        None => {
+            let limit = limit
+                .unwrap_or(DEFAULT_AUTO_WITHDRAW_LIMIT)
+                .min(MAX_AUTO_WITHDRAW_LIMIT) as usize;
+            let mut progress = AUTO_WITHDRAW_PROGRESS
+                .may_load(deps.storage)?
+                .unwrap_or_default();
+            let page = SHARES
+                .range(
+                    deps.storage,
+                    progress.cursor.clone().map(Bound::exclusive),
+                    None,
+                    Order::Ascending,
+                )
+                .take(limit)
+                .collect::<Result<Vec<_>, StdError>>()?;
+            progress.cursor = if page.len() == limit {
+                page.last().map(|(user, _)| user.clone())
+            } else {
+                None
+            };
+            AUTO_WITHDRAW_PROGRESS.save(deps.storage, &progress)?;
+            page
+        }
+    };
+
+    let mut response = Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "auto_withdraw");
+    let mut dispatched = 0u64;
+    for (user, amount) in users.into_iter().filter(|(_, amount)| !amount.is_zero()) {
+        PENDING_AUTO_WITHDRAWS.push_back(deps.storage, &user)?;
+        response = response.add_submessage(SubMsg::reply_always(
+            WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                msg: to_json_binary(&ExecuteMsg::VaultExtension(
+                    ExtensionExecuteMsg::AutoWithdrawUser {
+                        user: user.to_string(),
+                        amount,
+                    },
+                ))?,
+                funds: vec![],
+            },
+            Replies::AutoWithdrawUser as u64,
+        ));
+        dispatched += 1;
+    }
+
+    Ok(response.add_attribute("users", dispatched.to_string()))
+}
+
+/// Withdraw `amount` shares of `user` to the user, as if the user redeemed them. The withdraw
+/// goes through the same gating as a Redeem, so it fails in lockup mode and large withdraws are
+/// queued instead of withdrawn right away.
+pub fn execute_auto_withdraw_user(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    user: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let user = deps.api.addr_validate(&user)?;
+    let user_info = MessageInfo {
+        sender: user,
+        funds: vec![],
+    };
+    redeem(deps, env, user_info, None, amount)
+}
+
+/// Record the result of the withdraw of the oldest pending user. A failed withdraw is rolled
+/// back by the chain, so the shares stay with the user and the error is stored for a retry.
+pub fn handle_auto_withdraw_user_reply(
+    deps: DepsMut,
+    result: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let user = PENDING_AUTO_WITHDRAWS
+        .pop_front(deps.storage)?
+        .ok_or(StdError::generic_err("no pending auto withdraw"))?;
+    let mut progress = AUTO_WITHDRAW_PROGRESS
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+
+    let response = Response::new()
+        .add_attribute("method", "reply")
+        .add_attribute("action", "auto_withdraw_user")
+        .add_attribute("user", user.as_str());
+
+    let response = match result {
+        SubMsgResult::Ok(_) => {
+            progress.succeeded += 1;
+            AUTO_WITHDRAW_FAILURES.remove(deps.storage, user);
+            response.add_attribute("status", "success")
+        }
+        SubMsgResult::Err(error) => {
+            progress.failed += 1;
+            AUTO_WITHDRAW_FAILURES.save(deps.storage, user, &error)?;
+            response
+                .add_attribute("status", "failed")
+                .add_attribute("error", error)
+        }
+    };
+    AUTO_WITHDRAW_PROGRESS.save(deps.storage, &progress)?;
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        from_json,
+        testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR},
+        Addr, CosmosMsg, Decimal, SubMsgResponse,
+    };
+    use cw_utils::Duration;
+
+    use super::*;
+    use crate::{
+        state::{
+            AutoWithdrawProgress, WithdrawQueueConfig, ADMIN_ADDRESS, LOCKUP_DURATION,
+            WITHDRAW_QUEUE_CONFIG, WITHDRAW_REQUESTS,
+        },
+        test_helpers::{instantiate_contract, mock_deps_with_querier},
+    };
+
+    fn setup_users(deps: DepsMut, users: &[(&str, u128)]) {
+        ADMIN_ADDRESS
+            .save(deps.storage, &Addr::unchecked("admin"))
+            .unwrap();
+        for (user, shares) in users {
+            SHARES
+                .save(deps.storage, Addr::unchecked(*user), &Uint128::new(*shares))
+                .unwrap();
+        }
+    }
+
+    fn dispatched_users(response: &Response) -> Vec<(String, Uint128)> {
+        response
+            .messages
+            .iter()
+            .map(|sub_msg| {
+                assert_eq!(sub_msg.id, Replies::AutoWithdrawUser as u64);
+                let CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) = &sub_msg.msg else {
+                    panic!("expected a wasm execute")
+                };
+                let ExecuteMsg::VaultExtension(ExtensionExecuteMsg::AutoWithdrawUser {
+                    user,
+                    amount,
+                }) = from_json(msg).unwrap()
+                else {
+                    panic!("expected an auto withdraw user msg")
+                };
+                (user, amount)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn paginated_auto_withdraw_continues_from_cursor() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_users(
+            deps.as_mut(),
+            &[("alice", 100), ("bob", 0), ("carol", 300), ("dave", 400)],
+        );
+
+        let response =
+            execute_auto_withdraw(deps.as_mut(), &env, mock_info("admin", &[]), None, Some(3))
+                .unwrap();
+        // users without shares are skipped
+        assert_eq!(
+            dispatched_users(&response),
+            vec![
+                ("alice".to_string(), Uint128::new(100)),
+                ("carol".to_string(), Uint128::new(300))
+            ]
+        );
+        assert_eq!(
+            AUTO_WITHDRAW_PROGRESS
+                .load(deps.as_ref().storage)
+                .unwrap()
+                .cursor,
+            Some(Addr::unchecked("carol"))
+        );
+
+        let response =
+            execute_auto_withdraw(deps.as_mut(), &env, mock_info("admin", &[]), None, Some(3))
+                .unwrap();
+        assert_eq!(
+            dispatched_users(&response),
+            vec![("dave".to_string(), Uint128::new(400))]
+        );
+        assert_eq!(
+            AUTO_WITHDRAW_PROGRESS
+                .load(deps.as_ref().storage)
+                .unwrap()
+                .cursor,
+            None
+        );
+        assert_eq!(
+            PENDING_AUTO_WITHDRAWS.len(deps.as_ref().storage).unwrap(),
+            3
+        );
+
+        let err = execute_auto_withdraw(
+            deps.as_mut(),
+            &env,
+            mock_info("alice", &[]),
+            Some(vec![("bob".to_string(), Uint128::new(1))]),
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn auto_withdraw_replies_record_per_user_results() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_users(deps.as_mut(), &[("alice", 100), ("bob", 200)]);
+
+        execute_auto_withdraw(
+            deps.as_mut(),
+            &env,
+            mock_info("admin", &[]),
+            Some(vec![
+                ("alice".to_string(), Uint128::new(100)),
+                ("bob".to_string(), Uint128::new(200)),
+            ]),
+            None,
+        )
+        .unwrap();
+
+        handle_auto_withdraw_user_reply(
+            deps.as_mut(),
+            SubMsgResult::Err("insufficient funds".to_string()),
+        )
+        .unwrap();
+        let response = handle_auto_withdraw_user_reply(
+            deps.as_mut(),
+            SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        )
+        .unwrap();
+        assert!(response
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "user" && attr.value == "bob"));
+
+        assert_eq!(
+            AUTO_WITHDRAW_PROGRESS.load(deps.as_ref().storage).unwrap(),
+            AutoWithdrawProgress {
+                cursor: None,
+                succeeded: 1,
+                failed: 1
+            }
+        );
+        assert_eq!(
+            AUTO_WITHDRAW_FAILURES
+                .load(deps.as_ref().storage, Addr::unchecked("alice"))
+                .unwrap(),
+            "insufficient funds"
+        );
+        assert!(PENDING_AUTO_WITHDRAWS
+            .is_empty(deps.as_ref().storage)
+            .unwrap());
+
+        // only the vault itself can withdraw on behalf of a user
+        let err = execute_auto_withdraw_user(
+            deps.as_mut(),
+            env,
+            mock_info("admin", &[]),
+            "alice".to_string(),
+            Uint128::new(100),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn auto_withdraw_user_is_gated_like_redeem() {
+        let mut deps = mock_deps_with_querier();
+        let env = mock_env();
+        instantiate_contract(deps.as_mut(), env.clone(), "admin");
+        SHARES
+            .save(
+                deps.as_mut().storage,
+                Addr::unchecked("alice"),
+                &Uint128::new(20_000),
+            )
+            .unwrap();
+        WITHDRAW_QUEUE_CONFIG
+            .save(
+                deps.as_mut().storage,
+                &WithdrawQueueConfig {
+                    share_threshold: Decimal::percent(10),
+                    epoch_seconds: 3600,
+                    keeper: None,
+                },
+            )
+            .unwrap();
+
+        // in lockup mode shares are only redeemed through Unlock, so the withdraw fails like a Redeem
+        LOCKUP_DURATION
+            .save(deps.as_mut().storage, &Duration::Time(3600))
+            .unwrap();
+        let err = execute_auto_withdraw_user(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            "alice".to_string(),
+            Uint128::new(20_000),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::LockupEnabled {});
+        LOCKUP_DURATION.remove(deps.as_mut().storage);
+
+        // 20% of the supply is above the queue threshold, so the withdraw is queued
+        let response = execute_auto_withdraw_user(
+            deps.as_mut(),
+            env,
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            "alice".to_string(),
+            Uint128::new(20_000),
+        )
+        .unwrap();
+        assert!(response
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "action" && attr.value == "queue_withdraw"));
+        let request = WITHDRAW_REQUESTS.load(deps.as_ref().storage, 0).unwrap();
+        assert_eq!(request.owner, Addr::unchecked("alice"));
+        assert_eq!(request.shares, Uint128::new(20_000));
+    }
+}
@@ -1,14 +1,19 @@
 use cosmwasm_std::{
-    to_json_binary, DepsMut, Env, MessageInfo, Response, SubMsg, SubMsgResult, Uint128,
+    coin, to_json_binary, DepsMut, Env, MessageInfo, Response, SubMsg, SubMsgResult, Uint128,
 };
 use osmosis_std::types::osmosis::concentratedliquidity::v1beta1::ConcentratedliquidityQuerier;
 use osmosis_std::types::osmosis::concentratedliquidity::v1beta1::MsgCreatePositionResponse;
 
+use crate::helpers::coinlist::CoinList;
 use crate::helpers::getters::get_unused_pair_balances;
+use crate::helpers::share_price::record_share_price_snapshot;
 use crate::msg::{ExecuteMsg, MergePositionMsg};
 use crate::reply::Replies;
-use crate::state::{Position, POOL_CONFIG, POSITION};
-use crate::vault::{concentrated_liquidity::create_position, merge::MergeResponse};
+use crate::state::{Position, IDLE_BUFFER_CONFIG, POOL_CONFIG, POSITION};
+use crate::vault::{
+    concentrated_liquidity::create_position, distribution::update_reward_ledger,
+    idle_buffer::get_buffer_targets, merge::MergeResponse,
+};
 use crate::ContractError;
 
 pub fn execute_autocompound(
@@ -33,7 +38,15 @@ pub fn execute_autocompound(
         .ok_or(ContractError::PositionNotFound)?;
 
     let pool = POOL_CONFIG.load(deps.storage)?;
-    let balance = get_unused_pair_balances(&deps.as_ref(), env, &pool)?;
+    let mut balance = get_unused_pair_balances(&deps.as_ref(), env, &pool)?;
+
+    // keep the idle buffer target out of the position
+    if let Some(config) = IDLE_BUFFER_CONFIG.may_load(deps.storage)? {
+        let targets = get_buffer_targets(deps.as_ref(), env, config.target_ratio)?;
+        for (token, target) in balance.iter_mut().zip(targets) {
+            token.amount = token.amount.saturating_sub(target.amount);
+        }
+    }
 
     let token0 = balance[0].clone();
     let token1 = balance[1].clone();
@@ -46,6 +59,12 @@ pub fn execute_autocompound(
     if !token1.amount.is_zero() {
         coins_to_send.push(token1.clone());
     }
+    if coins_to_send.is_empty() {
+        return Ok(Response::new()
+            .add_attribute("method", "execute")
+            .add_attribute("action", "autocompound")
+            .add_attribute("status", "nothing_to_compound"));
+    }
 
     let create_position_msg = create_position(
         deps,
@@ -77,6 +96,13 @@ pub fn handle_autocompound_reply(
 ) -> Result<Response, ContractError> {
     let create_position_message: MsgCreatePositionResponse = data.try_into()?;
 
+    let pool = POOL_CONFIG.load(deps.storage)?;
+    let compounded = CoinList::from_coins(vec![
+        coin(create_position_message.amount0.parse()?, pool.token0),
+        coin(create_position_message.amount1.parse()?, pool.token1),
+    ]);
+    update_reward_ledger(deps.storage, |ledger| ledger.compounded.add(compounded))?;
+
     // set claim after
     let position_id = (POSITION.load(deps.storage)?).position_id;
     // call merge
@@ -118,10 +144,12 @@ pub fn handle_merge_reply(
             claim_after: position.claim_after,
         },
     )?;
+    let snapshot = record_share_price_snapshot(deps, &env, &CoinList::new())?;
 
     Ok(Response::new()
         .add_attribute("method", "reply")
         .add_attribute("action", "handle_merge_reply")
+        .add_attribute("price_per_share", snapshot.price_per_share.to_string())
         .add_attribute("swap_deposit_merge_status", "success")
         .add_attribute("status", "success"))
 }
@@ -7,8 +7,9 @@ use osmosis_std::types::osmosis::poolmanager::v1beta1::PoolmanagerQuerier;
 use prost::Message;
 
 use crate::helpers::generic::{round_up_to_nearest_multiple, sort_tokens};
+use crate::math::tick::tick_to_price;
 use crate::{
-    state::{POOL_CONFIG, POSITION},
+    state::{RangeEntry, POOL_CONFIG, POSITION, RANGE_ENTRY},
     ContractError,
 };
 
@@ -159,6 +160,45 @@ pub fn get_amount_from_liquidity_for_quote_token(
     Ok(liq.checked_mul_floor(delta_p)?)
 }
 
+/// Returns the base and quote amounts of `liq` in the range from `lower_tick` to `upper_tick`
+/// while the pool is at `tick`
+pub fn get_amounts_from_liquidity(
+    liq: Uint256,
+    tick: i64,
+    lower_tick: i64,
+    upper_tick: i64,
+) -> Result<(Uint256, Uint256), ContractError> {
+    let sqrt_p = tick_to_price(tick)?.sqrt();
+    let sqrt_pl = tick_to_price(lower_tick)?.sqrt();
+    let sqrt_pu = tick_to_price(upper_tick)?.sqrt();
+    Ok((
+        get_amount_from_liquidity_for_base_token(liq, sqrt_p, sqrt_pl, sqrt_pu)?,
+        get_amount_from_liquidity_for_quote_token(liq, sqrt_p, sqrt_pl, sqrt_pu)?,
+    ))
+}
+
+/// Save the current pool tick as the entry of the newly created range
+pub fn record_range_entry(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: &Env,
+    lower_tick: i64,
+    upper_tick: i64,
+) -> Result<(), ContractError> {
+    let pool_config = POOL_CONFIG.load(storage)?;
+    let pool = get_cl_pool_info(querier, pool_config.pool_id)?;
+    RANGE_ENTRY.save(
+        storage,
+        &RangeEntry {
+            tick: pool.current_tick,
+            lower_tick,
+            upper_tick,
+            time: env.block.time.seconds(),
+        },
+    )?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
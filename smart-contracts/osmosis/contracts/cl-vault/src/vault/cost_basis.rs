@@ -0,0 +1,176 @@
+use cosmwasm_std::{Addr, Coin, Deps, DepsMut, Env, Int128, Storage, Uint128};
+
+use crate::{
+    helpers::{fees::exit_fee, getters::get_value_wrt_asset0},
+    query::query_assets_from_shares,
+    state::{UserCostBasis, POOL_CONFIG, USER_COST_BASIS, VAULT_CONFIG},
+    ContractError,
+};
+
+/// Add a deposit of `amount0` and `amount1`, worth `value` in token0, for which `shares` were
+/// minted to the cost basis of `user`
+pub fn record_deposit(
+    storage: &mut dyn Storage,
+    user: &Addr,
+    amount0: Uint128,
+    amount1: Uint128,
+    value: Uint128,
+    shares: Uint128,
+) -> Result<(), ContractError> {
+    let mut basis = USER_COST_BASIS
+        .may_load(storage, user.clone())?
+        .unwrap_or_default();
+    basis.deposited0 = basis.deposited0.checked_add(amount0)?;
+    basis.deposited1 = basis.deposited1.checked_add(amount1)?;
+    basis.deposited_value = basis.deposited_value.checked_add(value)?;
+    basis.shares = basis.shares.checked_add(shares)?;
+    basis.cost_basis = basis.cost_basis.checked_add(value)?;
+    USER_COST_BASIS.save(storage, user.clone(), &basis)?;
+    Ok(())
+}
+
+/// Add the redemption of `shares` of `user` to the cost basis of the user. The redeemed assets
+/// are valued at the current share price net of the exit fee, so this has to be called before
+/// the shares are burned.
+pub fn record_withdraw(
+    deps: DepsMut,
+    env: &Env,
+    user: &Addr,
+    shares: Uint128,
+) -> Result<(), ContractError> {
+    let (amount0, amount1, _) = get_shares_value(deps.as_ref(), env, shares)?;
+    let vault_config = VAULT_CONFIG.load(deps.storage)?;
+    let amount0 = amount0 - exit_fee(&vault_config, amount0);
+    let amount1 = amount1 - exit_fee(&vault_config, amount1);
+    let value = get_value_wrt_asset0(deps.storage, &deps.querier, amount0, amount1)?;
+    let mut basis = USER_COST_BASIS
+        .may_load(deps.storage, user.clone())?
+        .unwrap_or_default();
+
+    // shares held from before the cost basis was tracked are taken at their current value,
+    // so they do not count towards the realised pnl
+    let tracked = shares.min(basis.shares);
+    let tracked_cost = if tracked.is_zero() {
+        Uint128::zero()
+    } else {
+        basis.cost_basis.multiply_ratio(tracked, basis.shares)
+    };
+    let untracked_cost = value.multiply_ratio(shares - tracked, shares);
+
+    basis.withdrawn0 = basis.withdrawn0.checked_add(amount0)?;
+    basis.withdrawn1 = basis.withdrawn1.checked_add(amount1)?;
+    basis.withdrawn_value = basis.withdrawn_value.checked_add(value)?;
+    basis.shares -= tracked;
+    basis.cost_basis -= tracked_cost;
+    basis.realised_pnl = basis.realised_pnl.checked_add(
+        Int128::try_from(value)?.checked_sub(Int128::try_from(tracked_cost + untracked_cost)?)?,
+    )?;
+    USER_COST_BASIS.save(deps.storage, user.clone(), &basis)?;
+    Ok(())
+}
+
+/// Returns the token0 and token1 amounts `shares` currently have a claim on, and their
+/// value in token0
+pub fn get_shares_value(
+    deps: Deps,
+    env: &Env,
+    shares: Uint128,
+) -> Result<(Uint128, Uint128, Uint128), ContractError> {
+    if shares.is_zero() {
+        return Ok((Uint128::zero(), Uint128::zero(), Uint128::zero()));
+    }
+    let pool_config = POOL_CONFIG.load(deps.storage)?;
+    let assets = query_assets_from_shares(deps, env.clone(), shares)?.balances;
+    let amount_of = |denom: &str| {
+        assets
+            .iter()
+            .find(|c: &&Coin| c.denom == denom)
+            .map(|c| c.amount)
+            .unwrap_or_default()
+    };
+    let amount0 = amount_of(&pool_config.token0);
+    let amount1 = amount_of(&pool_config.token1);
+    let value = get_value_wrt_asset0(deps.storage, &deps.querier, amount0, amount1)?;
+    Ok((amount0, amount1, value))
+}
+
+/// The cost basis of `user`, or an empty one if the user never deposited
+pub fn get_cost_basis(storage: &dyn Storage, user: &Addr) -> Result<UserCostBasis, ContractError> {
+    Ok(USER_COST_BASIS
+        .may_load(storage, user.clone())?
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{testing::mock_env, Decimal};
+
+    use super::*;
+    use crate::{
+        query::query_user_position,
+        state::SHARES,
+        test_helpers::{instantiate_contract, mock_deps_with_querier},
+    };
+
+    #[test]
+    fn withdrawals_realise_pnl_against_the_average_entry_price() {
+        let mut deps = mock_deps_with_querier();
+        let env = mock_env();
+        instantiate_contract(deps.as_mut(), env.clone(), "admin");
+        let user = Addr::unchecked("user");
+
+        let (amount0, amount1, value) =
+            get_shares_value(deps.as_ref(), &env, 1000u128.into()).unwrap();
+        assert!(!value.is_zero());
+
+        // the user paid twice the current value for 2000 shares
+        record_deposit(
+            deps.as_mut().storage,
+            &user,
+            amount0 * Uint128::new(4),
+            amount1 * Uint128::new(4),
+            value * Uint128::new(4),
+            Uint128::new(2000),
+        )
+        .unwrap();
+        SHARES
+            .save(deps.as_mut().storage, user.clone(), &Uint128::new(2000))
+            .unwrap();
+
+        record_withdraw(deps.as_mut(), &env, &user, Uint128::new(1000)).unwrap();
+        SHARES
+            .save(deps.as_mut().storage, user.clone(), &Uint128::new(1000))
+            .unwrap();
+
+        let basis = get_cost_basis(deps.as_ref().storage, &user).unwrap();
+        assert_eq!(basis.withdrawn0, amount0);
+        assert_eq!(basis.withdrawn1, amount1);
+        assert_eq!(basis.withdrawn_value, value);
+        assert_eq!(basis.shares, Uint128::new(1000));
+        assert_eq!(basis.cost_basis, value * Uint128::new(2));
+        let loss = -Int128::try_from(value).unwrap();
+        assert_eq!(basis.realised_pnl, loss);
+
+        let position = query_user_position(deps.as_ref(), env.clone(), "user".to_string()).unwrap();
+        assert_eq!(position.shares, Uint128::new(1000));
+        assert_eq!(position.value, value);
+        assert_eq!(position.realised_pnl, loss);
+        assert_eq!(position.unrealised_pnl, loss);
+        assert_eq!(
+            position.average_entry_price,
+            Some(Decimal::from_ratio(value * Uint128::new(2), 1000u128))
+        );
+
+        // shares beyond the tracked ones are taken at their current value, so only the
+        // remaining cost basis is realised
+        let (_, _, value_2000) = get_shares_value(deps.as_ref(), &env, Uint128::new(2000)).unwrap();
+        record_withdraw(deps.as_mut(), &env, &user, Uint128::new(2000)).unwrap();
+        let basis = get_cost_basis(deps.as_ref().storage, &user).unwrap();
+        assert_eq!(basis.shares, Uint128::zero());
+        assert_eq!(basis.cost_basis, Uint128::zero());
+        let untracked_value = value_2000.multiply_ratio(1000u128, 2000u128);
+        let realised = Int128::try_from(value_2000 - untracked_value).unwrap()
+            - Int128::try_from(value * Uint128::new(2)).unwrap();
+        assert_eq!(basis.realised_pnl, loss + realised);
+    }
+}
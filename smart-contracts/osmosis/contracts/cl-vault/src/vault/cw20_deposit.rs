@@ -0,0 +1,606 @@
+use apollo_cw_asset::Asset;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    coin, from_json, Addr, CosmosMsg, Decimal, DepsMut, Env, MessageInfo, Response, SubMsg,
+    SubMsgResult, Uint128,
+};
+use cw20::Cw20ReceiveMsg;
+use cw_utils::nonpayable;
+use dex_router_osmosis::msg::{BestPathForPairResponse, QueryMsg as DexRouterQueryMsg};
+
+use crate::{
+    error::assert_admin,
+    helpers::{
+        getters::get_twap_price,
+        operation_lock::{acquire_operation_lock, release_operation_lock},
+        prepend::claim_msg,
+    },
+    query::query_collect_rewards_before_deposit,
+    reply::Replies,
+    state::{
+        CurrentCw20Deposit, Cw20DepositAsset, CURRENT_CW20_DEPOSIT, CW20_DEPOSIT_ASSETS,
+        POOL_CONFIG, VAULT_CONFIG,
+    },
+    vault::{
+        allowlist::assert_allowed_depositors,
+        deposit::execute_any_deposit,
+        referral::validate_referrer,
+        swap::{estimate_swap_min_out_amount, swap_msg},
+    },
+    ContractError,
+};
+
+/// The amount of CW20 deposit assets returned by a query if no limit is given
+pub const DEFAULT_CW20_DEPOSIT_ASSETS_LIMIT: u32 = 10;
+pub const MAX_CW20_DEPOSIT_ASSETS_LIMIT: u32 = 30;
+
+/// The message a CW20 token has to be sent to the vault with
+#[cw_serde]
+pub enum Cw20HookMsg {
+    /// Deposit the sent tokens, the same as AnyDeposit with native tokens
+    Deposit {
+        recipient: Option<String>,
+        referrer: Option<String>,
+        max_slippage: Decimal,
+    },
+}
+
+/// Accept deposits of the CW20 token `token`, or stop accepting them if `asset` is None
+pub fn execute_update_cw20_deposit_asset(
+    deps: DepsMut,
+    info: MessageInfo,
+    token: String,
+    asset: Option<Cw20DepositAsset>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    assert_admin(deps.storage, &info.sender)?;
+
+    let token = deps.api.addr_validate(&token)?;
+    match &asset {
+        Some(asset) => {
+            deps.api.addr_validate(asset.converter.as_str())?;
+            CW20_DEPOSIT_ASSETS.save(deps.storage, token.clone(), asset)?;
+        }
+        None => CW20_DEPOSIT_ASSETS.remove(deps.storage, token.clone()),
+    }
+
+    Ok(Response::default()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "update_cw20_deposit_asset")
+        .add_attribute("token", token.as_str())
+        .add_attribute("updates", format!("{:?}", asset)))
+}
+
+/// Deposit CW20 tokens sent to the vault through the CW20 Receive hook
+pub fn execute_receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    let depositor = deps.api.addr_validate(&msg.sender)?;
+    let Cw20HookMsg::Deposit {
+        recipient,
+        referrer,
+        max_slippage,
+    } = from_json(&msg.msg)?;
+
+    start_cw20_deposit(
+        deps,
+        env,
+        info.sender,
+        msg.amount,
+        depositor,
+        recipient,
+        referrer,
+        max_slippage,
+        None,
+    )
+}
+
+/// Deposit `amount` of the CW20 token `asset` out of the allowance the sender gave the vault
+#[allow(clippy::too_many_arguments)]
+pub fn execute_cw20_any_deposit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    asset: String,
+    amount: Uint128,
+    recipient: Option<String>,
+    referrer: Option<String>,
+    max_slippage: Decimal,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    let token = deps.api.addr_validate(&asset)?;
+    let pull_msg = Asset::cw20(token.clone(), amount)
+        .transfer_from_msg(info.sender.as_str(), env.contract.address.as_str())?;
+
+    start_cw20_deposit(
+        deps,
+        env,
+        token,
+        amount,
+        info.sender,
+        recipient,
+        referrer,
+        max_slippage,
+        Some(pull_msg),
+    )
+}
+
+/// Send the CW20 tokens to their converter, the native tokens paid out are picked up in the reply
+#[allow(clippy::too_many_arguments)]
+fn start_cw20_deposit(
+    deps: DepsMut,
+    env: Env,
+    token: Addr,
+    amount: Uint128,
+    depositor: Addr,
+    recipient: Option<String>,
+    referrer: Option<String>,
+    max_slippage: Decimal,
+    pull_msg: Option<CosmosMsg>,
+) -> Result<Response, ContractError> {
+    let asset = CW20_DEPOSIT_ASSETS
+        .may_load(deps.storage, token.clone())?
+        .ok_or(ContractError::UnsupportedCw20Deposit {
+            token: token.to_string(),
+        })?;
+    if amount.is_zero() {
+        return Err(ContractError::IncorrectDepositFunds);
+    }
+    let recipient = recipient.map_or(Ok(depositor.clone()), |x| deps.api.addr_validate(&x))?;
+    assert_allowed_depositors(deps.as_ref(), &depositor, &recipient)?;
+    let referrer = validate_referrer(deps.api, referrer, &depositor, &recipient)?;
+
+    acquire_operation_lock(deps.storage, &env, "cw20_deposit")?;
+    // with rewards collected before deposits, the snapshot is taken again in the reply of the
+    // collection, so rewards paid out in the native denom are not credited to the depositor
+    let collect_rewards = query_collect_rewards_before_deposit(deps.as_ref())?;
+    let balance_before = deps
+        .querier
+        .query_balance(&env.contract.address, &asset.native_denom)?
+        .amount;
+    CURRENT_CW20_DEPOSIT.save(
+        deps.storage,
+        &CurrentCw20Deposit {
+            depositor,
+            recipient,
+            referrer,
+            max_slippage,
+            denom: asset.native_denom,
+            balance_before,
+        },
+    )?;
+
+    let unwrap_msg =
+        Asset::cw20(token.clone(), amount).send_msg(asset.converter, asset.unwrap_msg)?;
+
+    let mut response = Response::new();
+    if collect_rewards {
+        response = response.add_submessage(SubMsg::reply_on_success(
+            claim_msg(&env)?,
+            Replies::Cw20DepositCollectRewards.into(),
+        ));
+    }
+    Ok(response
+        .add_messages(pull_msg)
+        .add_submessage(SubMsg::reply_on_success(
+            unwrap_msg,
+            Replies::Cw20Deposit.into(),
+        ))
+        .add_attribute("method", "execute")
+        .add_attribute("action", "cw20_deposit")
+        .add_attribute("token", token.as_str())
+        .add_attribute("amount", amount))
+}
+
+pub fn handle_cw20_deposit_collect_rewards_reply(
+    deps: DepsMut,
+    env: Env,
+    _data: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let mut deposit = CURRENT_CW20_DEPOSIT.load(deps.storage)?;
+    deposit.balance_before = deps
+        .querier
+        .query_balance(&env.contract.address, &deposit.denom)?
+        .amount;
+    CURRENT_CW20_DEPOSIT.save(deps.storage, &deposit)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "reply")
+        .add_attribute("action", "handle_cw20_deposit_collect_rewards")
+        .add_attribute("balance_before", deposit.balance_before))
+}
+
+/// Handle the unwrap of the CW20 tokens and the swap that may follow it. Once the deposit is held
+/// in a pool token, it continues as an AnyDeposit of the depositor.
+pub fn handle_cw20_deposit_reply(
+    deps: DepsMut,
+    env: Env,
+    _data: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let mut deposit = CURRENT_CW20_DEPOSIT.load(deps.storage)?;
+    let received = deps
+        .querier
+        .query_balance(&env.contract.address, &deposit.denom)?
+        .amount
+        .checked_sub(deposit.balance_before)?;
+    if received.is_zero() {
+        return Err(ContractError::IncorrectDepositFunds);
+    }
+    let token_in = coin(received.u128(), &deposit.denom);
+
+    let pool_config = POOL_CONFIG.load(deps.storage)?;
+    if pool_config.pool_contains_token(&deposit.denom) {
+        CURRENT_CW20_DEPOSIT.remove(deps.storage);
+        release_operation_lock(deps.storage);
+
+        let info = MessageInfo {
+            sender: deposit.depositor,
+            funds: vec![token_in],
+        };
+        return execute_any_deposit(
+            deps,
+            env,
+            info,
+            Some(deposit.recipient.to_string()),
+            deposit.referrer.map(|referrer| referrer.to_string()),
+            deposit.max_slippage,
+        );
+    }
+
+    // the unwrapped token is not a pool token, swap it into token0 first
+    let vault_config = VAULT_CONFIG.load(deps.storage)?;
+    let best_path: Option<BestPathForPairResponse> = deps.querier.query_wasm_smart(
+        &vault_config.dex_router,
+        &DexRouterQueryMsg::BestPathForPair {
+            offer: token_in.clone(),
+            ask_denom: pool_config.token0.clone(),
+        },
+    )?;
+    let best_path = best_path.ok_or(ContractError::MissingRecommendedSwapRoute {})?;
+    // the best path return is a spot quote that can be moved within the block, so the minimum
+    // output is set from the TWAP of every hop of the path instead
+    let mut twap_price = Decimal::one();
+    let mut denom_in = deposit.denom.clone();
+    for route in &best_path.path {
+        twap_price = twap_price.checked_mul(get_twap_price(
+            &deps.querier,
+            env.block.time,
+            vault_config.twap_window_seconds,
+            route.pool_id,
+            denom_in,
+            route.token_out_denom.clone(),
+        )?)?;
+        denom_in = route.token_out_denom.clone();
+    }
+    let min_receive = estimate_swap_min_out_amount(received, twap_price, deposit.max_slippage)?;
+
+    deposit.balance_before = deps
+        .querier
+        .query_balance(&env.contract.address, &pool_config.token0)?
+        .amount;
+    deposit.denom = pool_config.token0.clone();
+    CURRENT_CW20_DEPOSIT.save(deps.storage, &deposit)?;
+
+    let swap_msg = swap_msg(
+        vault_config.dex_router,
+        token_in.clone(),
+        coin(min_receive.u128(), pool_config.token0),
+        Some(best_path.path),
+    )?;
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(
+            swap_msg,
+            Replies::Cw20Deposit.into(),
+        ))
+        .add_attribute("method", "reply")
+        .add_attribute("action", "handle_cw20_deposit")
+        .add_attribute("token_in", token_in.to_string())
+        .add_attribute("token_out_min_amount", min_receive))
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR},
+        to_json_binary, Binary, SubMsgResponse, WasmMsg,
+    };
+    use cw20::Cw20ExecuteMsg;
+
+    use super::*;
+    use crate::{
+        contract::execute,
+        msg::ContractExecuteMsg,
+        state::{COLLECT_REWARDS_BEFORE_DEPOSIT, OPERATION_LOCK},
+        test_helpers::{instantiate_contract, mock_deps_with_querier, BASE_DENOM, POOL_ID},
+    };
+
+    fn add_cw20_deposit_asset(deps: DepsMut, native_denom: &str) {
+        execute_update_cw20_deposit_asset(
+            deps,
+            mock_info("admin", &[]),
+            "cw20".to_string(),
+            Some(Cw20DepositAsset {
+                converter: Addr::unchecked("converter"),
+                native_denom: native_denom.to_string(),
+                unwrap_msg: Binary::from(b"{}"),
+            }),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn cw20_deposit_balance_is_taken_after_collecting_rewards() {
+        let mut deps = mock_deps_with_querier();
+        let env = mock_env();
+        instantiate_contract(deps.as_mut(), env.clone(), "admin");
+        add_cw20_deposit_asset(deps.as_mut(), "uother");
+        COLLECT_REWARDS_BEFORE_DEPOSIT
+            .save(deps.as_mut().storage, &true)
+            .unwrap();
+
+        let response = execute_cw20_any_deposit(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            "cw20".to_string(),
+            Uint128::new(100),
+            None,
+            None,
+            Decimal::percent(90),
+        )
+        .unwrap();
+        // the rewards are collected before the tokens are pulled and unwrapped
+        assert_eq!(
+            response.messages[0].id,
+            Replies::Cw20DepositCollectRewards as u64
+        );
+        assert_eq!(response.messages[0].msg, claim_msg(&env).unwrap());
+        assert_eq!(response.messages[2].id, Replies::Cw20Deposit as u64);
+
+        // the collection pays out 90 of the native denom as incentives
+        deps.querier
+            .update_balances(&[(MOCK_CONTRACT_ADDR, &[coin(90, "uother")])]);
+        handle_cw20_deposit_collect_rewards_reply(
+            deps.as_mut(),
+            env.clone(),
+            SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        )
+        .unwrap();
+        let deposit = CURRENT_CW20_DEPOSIT.load(deps.as_ref().storage).unwrap();
+        assert_eq!(deposit.balance_before, Uint128::new(90));
+
+        // only the unwrapped tokens are credited to the depositor
+        deps.querier
+            .update_balances(&[(MOCK_CONTRACT_ADDR, &[coin(190, "uother")])]);
+        let response = handle_cw20_deposit_reply(
+            deps.as_mut(),
+            env,
+            SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        )
+        .unwrap();
+        assert!(response
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "token_in" && attr.value == coin(100, "uother").to_string()));
+    }
+
+    #[test]
+    fn cw20_deposit_swap_min_out_follows_the_twap() {
+        let mut deps = mock_deps_with_querier();
+        let env = mock_env();
+        instantiate_contract(deps.as_mut(), env.clone(), "admin");
+        add_cw20_deposit_asset(deps.as_mut(), "uother");
+
+        execute_cw20_any_deposit(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            "cw20".to_string(),
+            Uint128::new(1000),
+            None,
+            None,
+            Decimal::percent(90),
+        )
+        .unwrap();
+        deps.querier
+            .update_balances(&[(MOCK_CONTRACT_ADDR, &[coin(1000, "uother")])]);
+        let response = handle_cw20_deposit_reply(
+            deps.as_mut(),
+            env.clone(),
+            SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        )
+        .unwrap();
+
+        // the spot quote of the dex router is twice the offer, the minimum output follows the
+        // TWAP instead
+        let twap = get_twap_price(
+            &deps.as_ref().querier,
+            env.block.time,
+            VAULT_CONFIG
+                .load(deps.as_ref().storage)
+                .unwrap()
+                .twap_window_seconds,
+            POOL_ID,
+            "uother".to_string(),
+            BASE_DENOM.to_string(),
+        )
+        .unwrap();
+        let min_receive = Uint128::new(1000)
+            .checked_mul_floor(twap)
+            .unwrap()
+            .checked_mul_floor(Decimal::percent(90))
+            .unwrap();
+        assert!(min_receive < Uint128::new(1800));
+        assert!(response.attributes.iter().any(
+            |attr| attr.key == "token_out_min_amount" && attr.value == min_receive.to_string()
+        ));
+    }
+
+    #[test]
+    fn cw20_deposits_are_sent_to_their_converter() {
+        let mut deps = mock_deps_with_querier();
+        let env = mock_env();
+        instantiate_contract(deps.as_mut(), env.clone(), "admin");
+
+        let receive_msg = Cw20ReceiveMsg {
+            sender: "alice".to_string(),
+            amount: Uint128::new(100),
+            msg: to_json_binary(&Cw20HookMsg::Deposit {
+                recipient: None,
+                referrer: None,
+                max_slippage: Decimal::percent(90),
+            })
+            .unwrap(),
+        };
+        let err = execute_receive_cw20(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("cw20", &[]),
+            receive_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::UnsupportedCw20Deposit {
+                token: "cw20".to_string()
+            }
+        );
+
+        let asset = Cw20DepositAsset {
+            converter: Addr::unchecked("converter"),
+            native_denom: BASE_DENOM.to_string(),
+            unwrap_msg: Binary::from(b"{}"),
+        };
+        let err = execute_update_cw20_deposit_asset(
+            deps.as_mut(),
+            mock_info("alice", &[]),
+            "cw20".to_string(),
+            Some(asset.clone()),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute_update_cw20_deposit_asset(
+            deps.as_mut(),
+            mock_info("admin", &[]),
+            "cw20".to_string(),
+            Some(asset),
+        )
+        .unwrap();
+
+        // tokens pulled from an allowance are transferred to the vault before they are unwrapped
+        let response = execute_cw20_any_deposit(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            "cw20".to_string(),
+            Uint128::new(100),
+            None,
+            None,
+            Decimal::percent(90),
+        )
+        .unwrap();
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "cw20".to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: "alice".to_string(),
+                    recipient: MOCK_CONTRACT_ADDR.to_string(),
+                    amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+        assert_eq!(response.messages[1].id, Replies::Cw20Deposit as u64);
+        assert_eq!(
+            response.messages[1].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "cw20".to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: "converter".to_string(),
+                    amount: Uint128::new(100),
+                    msg: Binary::from(b"{}"),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+        let deposit = CURRENT_CW20_DEPOSIT.load(deps.as_ref().storage).unwrap();
+        assert_eq!(deposit.recipient, Addr::unchecked("alice"));
+        assert_eq!(deposit.denom, BASE_DENOM);
+
+        // a second deposit can not start while the first one is in progress
+        let err = execute_receive_cw20(deps.as_mut(), env, mock_info("cw20", &[]), receive_msg)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::OperationInProgress {
+                operation: "cw20_deposit".to_string()
+            }
+        );
+        assert!(OPERATION_LOCK
+            .may_load(deps.as_ref().storage)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn cw20_send_reaches_the_receive_hook() {
+        let mut deps = mock_deps_with_querier();
+        let env = mock_env();
+        instantiate_contract(deps.as_mut(), env.clone(), "admin");
+        execute_update_cw20_deposit_asset(
+            deps.as_mut(),
+            mock_info("admin", &[]),
+            "cw20".to_string(),
+            Some(Cw20DepositAsset {
+                converter: Addr::unchecked("converter"),
+                native_denom: BASE_DENOM.to_string(),
+                unwrap_msg: Binary::from(b"{}"),
+            }),
+        )
+        .unwrap();
+
+        // the message a CW20 contract sends to the vault on a Send
+        let CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) = Cw20ReceiveMsg {
+            sender: "alice".to_string(),
+            amount: Uint128::new(100),
+            msg: to_json_binary(&Cw20HookMsg::Deposit {
+                recipient: None,
+                referrer: None,
+                max_slippage: Decimal::percent(90),
+            })
+            .unwrap(),
+        }
+        .into_cosmos_msg(MOCK_CONTRACT_ADDR)
+        .unwrap() else {
+            panic!("expected a wasm execute");
+        };
+        let msg: ContractExecuteMsg = from_json(msg).unwrap();
+        assert!(matches!(msg, ContractExecuteMsg::Receive { .. }));
+
+        let response = execute(deps.as_mut(), env, mock_info("cw20", &[]), msg).unwrap();
+        assert!(response
+            .messages
+            .iter()
+            .any(|msg| msg.id == Replies::Cw20Deposit as u64));
+        let deposit = CURRENT_CW20_DEPOSIT.load(deps.as_ref().storage).unwrap();
+        assert_eq!(deposit.depositor, Addr::unchecked("alice"));
+        assert_eq!(deposit.denom, BASE_DENOM);
+    }
+}
@@ -1,16 +1,28 @@
 use crate::{
     error::assert_deposits,
     helpers::{
+        fees::apply_deposit_fee,
         getters::{
             get_depositable_tokens, get_single_sided_deposit_0_to_1_swap_amount,
             get_single_sided_deposit_1_to_0_swap_amount, get_twap_price, get_unused_pair,
             get_value_wrt_asset0, DepositInfo,
         },
         msgs::refund_bank_msg,
+        operation_lock::{acquire_operation_lock, release_operation_lock},
+        prepend::claim_msg,
+        shares::update_user_shares,
     },
     query::{query_total_assets, query_total_vault_token_supply},
     reply::Replies,
-    state::{CurrentSwap, CURRENT_SWAP_INFO, POOL_CONFIG, SHARES, VAULT_CONFIG, VAULT_DENOM},
+    state::{
+        CurrentSwap, FeeDestination, PendingDeposit, CURRENT_SWAP_INFO, PENDING_DEPOSIT,
+        POOL_CONFIG, VAULT_CONFIG, VAULT_DENOM,
+    },
+    vault::{
+        allowlist::assert_allowed_depositors,
+        cost_basis::record_deposit,
+        referral::{attribute_referral, validate_referrer},
+    },
     vault::{
         concentrated_liquidity::{get_cl_pool_info, get_position},
         swap::{estimate_swap_min_out_amount, swap_msg},
@@ -29,13 +41,16 @@ pub(crate) fn execute_exact_deposit(
     env: Env,
     info: MessageInfo,
     recipient: Option<String>,
+    referrer: Option<String>,
 ) -> Result<Response, ContractError> {
     let pool_config = POOL_CONFIG.load(deps.storage)?;
     assert_deposits(&info.funds, &pool_config)?;
     let recipient = recipient.map_or(Ok(info.sender.clone()), |x| deps.api.addr_validate(&x))?;
+    assert_allowed_depositors(deps.as_ref(), &info.sender, &recipient)?;
+    let referrer = validate_referrer(deps.api, referrer, &info.sender, &recipient)?;
     let deposit_info = get_depositable_tokens(&deps.as_ref(), info.funds, &pool_config)?;
 
-    execute_deposit(&mut deps, env, recipient, deposit_info)
+    execute_deposit(&mut deps, env, recipient, referrer, deposit_info)
 }
 
 pub(crate) fn execute_any_deposit(
@@ -43,11 +58,14 @@ pub(crate) fn execute_any_deposit(
     env: Env,
     info: MessageInfo,
     recipient: Option<String>,
+    referrer: Option<String>,
     max_slippage: Decimal,
 ) -> Result<Response, ContractError> {
     let pool_config = POOL_CONFIG.load(deps.storage)?;
     assert_deposits(&info.funds, &pool_config)?;
     let recipient = recipient.map_or(Ok(info.sender.clone()), |x| deps.api.addr_validate(&x))?;
+    assert_allowed_depositors(deps.as_ref(), &info.sender, &recipient)?;
+    let referrer = validate_referrer(deps.api, referrer, &info.sender, &recipient)?;
 
     let pool_details = get_cl_pool_info(&deps.querier, pool_config.pool_id)?;
     let position = get_position(deps.storage, &deps.querier)?
@@ -56,7 +74,7 @@ pub(crate) fn execute_any_deposit(
 
     let deposit_info = get_depositable_tokens(&deps.as_ref(), info.funds, &pool_config)?;
     if deposit_info.base_refund.amount.is_zero() && deposit_info.quote_refund.amount.is_zero() {
-        return execute_deposit(&mut deps, env, recipient, deposit_info);
+        return execute_deposit(&mut deps, env, recipient, referrer, deposit_info);
     }
 
     let vault_config = VAULT_CONFIG.load(deps.storage)?;
@@ -106,10 +124,12 @@ pub(crate) fn execute_any_deposit(
     let quote_funds = deposit_info
         .quote_deposit
         .checked_add(deposit_info.quote_refund.amount)?;
+    acquire_operation_lock(deps.storage, &env, "any_deposit_swap")?;
     CURRENT_SWAP_INFO.save(
         deps.storage,
         &CurrentSwap {
             recipient,
+            referrer,
             vault_balance: PoolPair::new(
                 coin(
                     unused.base.amount.checked_sub(base_funds)?.into(),
@@ -152,6 +172,7 @@ pub fn handle_any_deposit_swap_reply(
 ) -> Result<Response, ContractError> {
     let info: CurrentSwap = CURRENT_SWAP_INFO.load(deps.storage)?;
     CURRENT_SWAP_INFO.remove(deps.storage);
+    release_operation_lock(deps.storage);
 
     let pool_config = POOL_CONFIG.load(deps.storage)?;
     let balances = get_unused_pair(&deps.as_ref(), &env.contract.address, &pool_config)?;
@@ -161,6 +182,7 @@ pub fn handle_any_deposit_swap_reply(
         &mut deps,
         env,
         info.recipient,
+        info.referrer,
         DepositInfo {
             base_deposit: user_balances.base.amount,
             quote_deposit: user_balances.quote.amount,
@@ -170,12 +192,70 @@ pub fn handle_any_deposit_swap_reply(
     )
 }
 
+/// Collect the rewards of the vault before a deposit, so the shares of the deposit are priced with
+/// the collected rewards in the vault. The deposit continues in the reply of the collection.
+pub(crate) fn execute_collect_rewards_and_deposit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+    referrer: Option<String>,
+    max_slippage: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    acquire_operation_lock(deps.storage, &env, "collect_rewards_before_deposit")?;
+    PENDING_DEPOSIT.save(
+        deps.storage,
+        &PendingDeposit {
+            sender: info.sender,
+            funds: info.funds,
+            recipient,
+            referrer,
+            max_slippage,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(
+            claim_msg(&env)?,
+            Replies::DepositCollectRewards.into(),
+        ))
+        .add_attribute("method", "execute")
+        .add_attribute("action", "collect_rewards_before_deposit"))
+}
+
+pub fn handle_deposit_collect_rewards_reply(
+    deps: DepsMut,
+    env: Env,
+    _data: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let pending = PENDING_DEPOSIT.load(deps.storage)?;
+    PENDING_DEPOSIT.remove(deps.storage);
+    release_operation_lock(deps.storage);
+
+    let info = MessageInfo {
+        sender: pending.sender,
+        funds: pending.funds,
+    };
+    match pending.max_slippage {
+        Some(max_slippage) => execute_any_deposit(
+            deps,
+            env,
+            info,
+            pending.recipient,
+            pending.referrer,
+            max_slippage,
+        ),
+        None => execute_exact_deposit(deps, env, info, pending.recipient, pending.referrer),
+    }
+}
+
 /// Try to deposit as much user funds as we can in the current ratio of the vault and
 /// refund the rest to the caller.
 fn execute_deposit(
     deps: &mut DepsMut,
     env: Env,
     recipient: Addr,
+    referrer: Option<Addr>,
     deposit_info: DepositInfo,
 ) -> Result<Response, ContractError> {
     let vault_denom = VAULT_DENOM.load(deps.storage)?;
@@ -218,16 +298,38 @@ fn execute_deposit(
             .try_into()?
     };
 
-    SHARES.update(
+    let minted_shares = user_shares;
+    let vault_config = VAULT_CONFIG.load(deps.storage)?;
+    let (user_shares, fee_shares) = apply_deposit_fee(&vault_config, minted_shares);
+
+    let mut hook_msgs =
+        update_user_shares(deps.storage, &env, &recipient, |existing_user_shares| {
+            Ok(existing_user_shares.checked_add(user_shares)?)
+        })?;
+    // a fee that stays in the vault is not minted at all, so the deposited value is shared
+    // among all holders
+    let minted_shares = match vault_config.deposit_exit_fee_destination {
+        FeeDestination::Treasury if !fee_shares.is_zero() => {
+            hook_msgs.extend(update_user_shares(
+                deps.storage,
+                &env,
+                &vault_config.treasury,
+                |treasury_shares| Ok(treasury_shares.checked_add(fee_shares)?),
+            )?);
+            minted_shares
+        }
+        _ => user_shares,
+    };
+    if let Some(referrer) = &referrer {
+        attribute_referral(deps.storage, &recipient, referrer, user_shares)?;
+    }
+    record_deposit(
         deps.storage,
-        recipient.clone(),
-        |old| -> Result<Uint128, ContractError> {
-            if let Some(existing_user_shares) = old {
-                Ok(user_shares + existing_user_shares)
-            } else {
-                Ok(user_shares)
-            }
-        },
+        &recipient,
+        deposit_info.base_deposit,
+        deposit_info.quote_deposit,
+        user_value,
+        user_shares,
     )?;
 
     // TODO the locking of minted shares is a band-aid for giving out rewards to users,
@@ -237,7 +339,7 @@ fn execute_deposit(
     // this is blocked by Osmosis v17 update
     let mint_msg = MsgMint {
         sender: env.clone().contract.address.to_string(),
-        amount: Some(coin(user_shares.into(), vault_denom).into()),
+        amount: Some(coin(minted_shares.into(), vault_denom).into()),
         mint_to_address: env.clone().contract.address.to_string(),
     };
 
@@ -248,6 +350,7 @@ fn execute_deposit(
         .add_attribute("amount1", deposit_info.quote_deposit)
         .add_message(mint_msg)
         .add_attribute("mint_shares_amount", user_shares)
+        .add_attribute("deposit_fee_shares", fee_shares)
         .add_attribute("receiver", recipient.as_str());
 
     if let Some((bank_msg, bank_attr)) = refund_bank_msg(
@@ -258,7 +361,7 @@ fn execute_deposit(
         resp = resp.add_message(bank_msg).add_attributes(bank_attr);
     }
 
-    Ok(resp)
+    Ok(resp.add_submessages(hook_msgs))
 }
 
 #[cfg(test)]
@@ -273,7 +376,8 @@ mod tests {
     use crate::{
         contract::{execute, reply},
         helpers::msgs::refund_bank_msg,
-        msg::ExecuteMsg,
+        msg::{ExecuteMsg, ExtensionExecuteMsg},
+        state::{COLLECT_REWARDS_BEFORE_DEPOSIT, REFERRALS, SHARES, TOTAL_REFERRED_SHARES},
         test_helpers::{
             instantiate_contract, mock_deps_with_querier, mock_deps_with_querier_with_balance,
             BASE_DENOM, QUOTE_DENOM, TEST_VAULT_DENOM, TEST_VAULT_TOKEN_SUPPLY,
@@ -282,6 +386,56 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn execute_exact_deposit_attributes_referrer() {
+        let mut deps = mock_deps_with_querier();
+        let env = mock_env();
+        instantiate_contract(deps.as_mut(), env.clone(), "admin");
+
+        let info = mock_info("alice", &[coin(100, BASE_DENOM), coin(100, QUOTE_DENOM)]);
+        let err = execute_exact_deposit(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            None,
+            Some("alice".to_string()),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::SelfReferral {});
+
+        // a deposit to someone else can not pick the referrer of the recipient
+        let err = execute_exact_deposit(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("carol", &[coin(1, BASE_DENOM), coin(1, QUOTE_DENOM)]),
+            Some("alice".to_string()),
+            Some("carol".to_string()),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::ReferrerForOtherRecipient {});
+
+        execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::VaultExtension(ExtensionExecuteMsg::ReferredExactDeposit {
+                recipient: None,
+                referrer: "bob".to_string(),
+            })
+            .into(),
+        )
+        .unwrap();
+        let referral = REFERRALS
+            .load(deps.as_ref().storage, Addr::unchecked("alice"))
+            .unwrap();
+        assert_eq!(referral.referrer, Addr::unchecked("bob"));
+        assert_eq!(referral.shares, Uint128::new(9));
+        assert_eq!(
+            TOTAL_REFERRED_SHARES.load(deps.as_ref().storage).unwrap(),
+            Uint128::new(9)
+        );
+    }
+
     #[test]
     fn execute_exact_deposit_works() {
         let mut deps = mock_deps_with_querier();
@@ -291,7 +445,7 @@ mod tests {
         instantiate_contract(deps.as_mut(), env.clone(), sender);
 
         let info = mock_info(sender, &[coin(100, BASE_DENOM), coin(100, QUOTE_DENOM)]);
-        execute_exact_deposit(deps.as_mut(), env, info, None).unwrap();
+        execute_exact_deposit(deps.as_mut(), env, info, None, None).unwrap();
 
         // we currently have 100_000 total_vault_shares outstanding and the equivalent of 1999500token0, the user deposits the equivalent of 199token0, thus shares are
         // 199 * 100000 / 1999500 = 9.95, which we round down. Thus we expect 9 shares in this example
@@ -392,7 +546,7 @@ mod tests {
             deps.as_mut(),
             env,
             info,
-            ExecuteMsg::ExactDeposit { recipient: None },
+            ExecuteMsg::ExactDeposit { recipient: None }.into(),
         )
         .unwrap_err();
         assert_eq!(err, ContractError::IncorrectDepositFunds);
@@ -416,7 +570,7 @@ mod tests {
             deps.as_mut(),
             env,
             info,
-            ExecuteMsg::ExactDeposit { recipient: None },
+            ExecuteMsg::ExactDeposit { recipient: None }.into(),
         )
         .unwrap_err();
         assert_eq!(err, ContractError::IncorrectDepositFunds);
@@ -457,12 +611,14 @@ mod tests {
             deps.as_mut(),
             env.clone(),
             info.clone(),
-            ExecuteMsg::ExactDeposit { recipient: None },
+            ExecuteMsg::ExactDeposit { recipient: None }.into(),
         )
         .unwrap();
         assert_eq!(response.messages.len(), 1);
 
-        let expected_minted_tokens = TEST_VAULT_TOKEN_SUPPLY / 4;
+        // the vault holds 100 of each token in the position, 100 idle and 90 in claimable rewards
+        // net of the performance fee, so the deposit is worth 50 / 290 of the vault assets
+        let expected_minted_tokens = 17_241;
         let msg = response.messages[0].msg.clone();
         match msg {
             CosmosMsg::Stargate { type_url: _, value } => {
@@ -498,7 +654,8 @@ mod tests {
                 asset: String::default(),
                 recipient: None,
                 max_slippage: Decimal::percent(90),
-            },
+            }
+            .into(),
         )
         .unwrap_err();
         assert_eq!(err, ContractError::IncorrectDepositFunds);
@@ -527,7 +684,8 @@ mod tests {
                 asset: String::default(),
                 recipient: None,
                 max_slippage: Decimal::percent(90),
-            },
+            }
+            .into(),
         )
         .unwrap_err();
         assert_eq!(err, ContractError::IncorrectDepositFunds);
@@ -572,12 +730,15 @@ mod tests {
                 asset: String::default(),
                 recipient: None,
                 max_slippage: Decimal::percent(90),
-            },
+            }
+            .into(),
         )
         .unwrap();
         assert_eq!(response.messages.len(), 1);
 
-        let expected_minted_tokens = TEST_VAULT_TOKEN_SUPPLY / 4;
+        // the vault holds 100 of each token in the position, 100 idle and 90 in claimable rewards
+        // net of the performance fee, so the deposit is worth 50 / 290 of the vault assets
+        let expected_minted_tokens = 17_241;
         let msg = response.messages[0].msg.clone();
         match msg {
             CosmosMsg::Stargate { type_url: _, value } => {
@@ -642,7 +803,8 @@ mod tests {
                 asset: String::default(),
                 recipient: None,
                 max_slippage: Decimal::percent(90),
-            },
+            }
+            .into(),
         )
         .unwrap();
         assert_eq!(response.messages.len(), 1);
@@ -692,7 +854,8 @@ mod tests {
             },
         )
         .unwrap();
-        let expected_minted_tokens = 50_000;
+        // 90 of each token in claimable rewards net of the performance fee count toward the assets
+        let expected_minted_tokens = 37_383;
         let msg = response.messages[0].msg.clone();
         match msg {
             CosmosMsg::Stargate { type_url: _, value } => {
@@ -712,6 +875,178 @@ mod tests {
         }
     }
 
+    #[test]
+    fn exact_deposit_is_priced_after_collecting_rewards() {
+        let vault_balance = 100u128;
+        let deposit_amount = 50u128;
+        let collected = 90u128;
+        let env = mock_env();
+        let mut deps = mock_deps_with_querier_with_balance(
+            100,
+            100,
+            0,
+            100,
+            1000,
+            &[(
+                MOCK_CONTRACT_ADDR,
+                &[
+                    coin(vault_balance + deposit_amount, BASE_DENOM),
+                    coin(vault_balance + deposit_amount, QUOTE_DENOM),
+                    coin(50000, TEST_VAULT_DENOM),
+                ],
+            )],
+        );
+        instantiate_contract(deps.as_mut(), env.clone(), ADMIN);
+        COLLECT_REWARDS_BEFORE_DEPOSIT
+            .save(deps.as_mut().storage, &true)
+            .unwrap();
+
+        let info = mock_info(
+            SENDER,
+            &[
+                coin(deposit_amount, BASE_DENOM),
+                coin(deposit_amount, QUOTE_DENOM),
+            ],
+        );
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::ExactDeposit { recipient: None }.into(),
+        )
+        .unwrap();
+        // only the collection is sent, no shares are minted before it
+        assert_eq!(response.messages.len(), 1);
+        assert_eq!(
+            response.messages[0].id,
+            Replies::DepositCollectRewards as u64
+        );
+        assert_eq!(response.messages[0].msg, claim_msg(&env).unwrap());
+        assert!(SHARES
+            .may_load(deps.as_ref().storage, Addr::unchecked(SENDER))
+            .unwrap()
+            .is_none());
+
+        // the collection pays out incentives that were not claimable before
+        deps.querier.update_balances(&[(
+            MOCK_CONTRACT_ADDR,
+            &[
+                coin(vault_balance + deposit_amount + collected, BASE_DENOM),
+                coin(vault_balance + deposit_amount + collected, QUOTE_DENOM),
+                coin(50000, TEST_VAULT_DENOM),
+            ],
+        )]);
+        let response = reply(
+            deps.as_mut(),
+            env.clone(),
+            Reply {
+                id: Replies::DepositCollectRewards.into(),
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            },
+        )
+        .unwrap();
+
+        // the deposit is worth 50 / 380 of the vault assets instead of the 50 / 290 it would be
+        // worth without the collected incentives
+        let m: MsgMint = match response.messages[0].msg.clone() {
+            CosmosMsg::Stargate { type_url: _, value } => value.try_into().unwrap(),
+            _ => panic!("unreachable"),
+        };
+        assert_eq!(m.amount.unwrap().amount, "13157");
+        assert!(PENDING_DEPOSIT
+            .may_load(deps.as_ref().storage)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn any_deposit_swap_excludes_rewards_collected_before_it() {
+        let vault_balance = 150u128;
+        let deposit_amount = 150u128;
+        let collected = 90u128;
+        let env = mock_env();
+        let mut deps = mock_deps_with_querier_with_balance(
+            100,
+            200,
+            1_000_000,
+            900_000,
+            1_101_000,
+            &[(
+                MOCK_CONTRACT_ADDR,
+                &[
+                    coin(vault_balance + deposit_amount, BASE_DENOM),
+                    coin(vault_balance, QUOTE_DENOM),
+                    coin(TEST_VAULT_TOKEN_SUPPLY, TEST_VAULT_DENOM),
+                ],
+            )],
+        );
+        instantiate_contract(deps.as_mut(), env.clone(), ADMIN);
+        COLLECT_REWARDS_BEFORE_DEPOSIT
+            .save(deps.as_mut().storage, &true)
+            .unwrap();
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(SENDER, &[coin(deposit_amount, BASE_DENOM)]),
+            ExecuteMsg::AnyDeposit {
+                amount: Uint128::zero(),
+                asset: String::default(),
+                recipient: None,
+                max_slippage: Decimal::percent(90),
+            }
+            .into(),
+        )
+        .unwrap();
+        assert_eq!(response.messages.len(), 1);
+        assert_eq!(
+            response.messages[0].id,
+            Replies::DepositCollectRewards as u64
+        );
+        assert!(CURRENT_SWAP_INFO
+            .may_load(deps.as_ref().storage)
+            .unwrap()
+            .is_none());
+
+        deps.querier.update_balances(&[(
+            MOCK_CONTRACT_ADDR,
+            &[
+                coin(vault_balance + deposit_amount + collected, BASE_DENOM),
+                coin(vault_balance + collected, QUOTE_DENOM),
+                coin(TEST_VAULT_TOKEN_SUPPLY, TEST_VAULT_DENOM),
+            ],
+        )]);
+        deps.querier.clear_claimable_rewards();
+        let response = reply(
+            deps.as_mut(),
+            env.clone(),
+            Reply {
+                id: Replies::DepositCollectRewards.into(),
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            },
+        )
+        .unwrap();
+        assert_eq!(response.messages[0].id, Replies::AnyDepositSwap as u64);
+
+        // the collected rewards belong to the vault, so the swap reply does not credit them to the
+        // depositor
+        let swap = CURRENT_SWAP_INFO.load(deps.as_ref().storage).unwrap();
+        assert_eq!(
+            swap.vault_balance.base,
+            coin(vault_balance + collected, BASE_DENOM)
+        );
+        assert_eq!(
+            swap.vault_balance.quote,
+            coin(vault_balance + collected, QUOTE_DENOM)
+        );
+    }
+
     #[test]
     fn successful_inexact_any_deposit_mints_fund_tokens_according_to_share_of_assets_one_sided_position_base_only(
     ) {
@@ -759,7 +1094,8 @@ mod tests {
                 asset: String::default(),
                 recipient: None,
                 max_slippage: Decimal::percent(90),
-            },
+            }
+            .into(),
         )
         .unwrap();
         assert_eq!(response.messages.len(), 1);
@@ -810,7 +1146,8 @@ mod tests {
             },
         )
         .unwrap();
-        let expected_minted_tokens = 40_000;
+        // 90 of each token in claimable rewards net of the performance fee count toward the assets
+        let expected_minted_tokens = 25_974;
         let msg = response.messages[0].msg.clone();
         match msg {
             CosmosMsg::Stargate { type_url: _, value } => {
@@ -877,7 +1214,8 @@ mod tests {
                 asset: String::default(),
                 recipient: None,
                 max_slippage: Decimal::percent(90),
-            },
+            }
+            .into(),
         )
         .unwrap();
         assert_eq!(response.messages.len(), 1);
@@ -928,7 +1266,8 @@ mod tests {
             },
         )
         .unwrap();
-        let expected_minted_tokens = 50_000;
+        // 90 of each token in claimable rewards net of the performance fee count toward the assets
+        let expected_minted_tokens = 29_850;
         let msg = response.messages[0].msg.clone();
         match msg {
             CosmosMsg::Stargate { type_url: _, value } => {
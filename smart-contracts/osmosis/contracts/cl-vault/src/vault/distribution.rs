@@ -1,4 +1,4 @@
-use cosmwasm_std::{BankMsg, DepsMut, Env, Response, StdError, SubMsg, SubMsgResult};
+use cosmwasm_std::{BankMsg, DepsMut, Env, Response, StdError, Storage, SubMsg, SubMsgResult};
 use osmosis_std::try_proto_to_cosmwasm_coins;
 use osmosis_std::types::osmosis::concentratedliquidity::v1beta1::{
     MsgCollectIncentivesResponse, MsgCollectSpreadRewardsResponse,
@@ -7,14 +7,20 @@ use osmosis_std::types::osmosis::concentratedliquidity::v1beta1::{
 use crate::helpers::coinlist::CoinList;
 use crate::helpers::generic::sort_tokens;
 use crate::helpers::msgs::{collect_incentives_msg, collect_spread_rewards_msg};
-use crate::state::POSITION;
+use crate::helpers::share_price::record_share_price_snapshot;
+use crate::query::query_total_vault_token_supply;
+use crate::state::{RewardLedger, POSITION, REWARD_LEDGER};
+use crate::vault::referral::take_referral_fee;
+use crate::vault::superfluid::collect_superfluid_rewards;
 use crate::{reply::Replies, state::VAULT_CONFIG, ContractError};
 
 /// claim_rewards claims rewards from Osmosis and update the rewards map to reflect each users rewards
-pub fn execute_collect_rewards(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+pub fn execute_collect_rewards(mut deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    // staking rewards are paid out by the chain, they only have to be accounted for
+    let response = collect_superfluid_rewards(deps.branch())?;
     let msg = collect_spread_rewards_msg(deps.as_ref(), env)?;
 
-    Ok(Response::new()
+    Ok(response
         .add_attribute("method", "execute")
         .add_attribute("action", "collect_rewards")
         .add_submessage(SubMsg::reply_on_success(
@@ -42,10 +48,19 @@ pub fn handle_collect_spread_rewards_reply(
     response_coin_list.merge(try_proto_to_cosmwasm_coins(
         response.clone().collected_spread_rewards,
     )?)?;
+    let collected = response_coin_list.clone();
 
     // calculate the strategist fee and remove the share at source
     let vault_config = VAULT_CONFIG.load(deps.storage)?;
-    let strategist_fee = response_coin_list.sub_ratio(vault_config.performance_fee)?;
+    let mut strategist_fee = response_coin_list.sub_ratio(vault_config.performance_fee)?;
+    let total_shares = query_total_vault_token_supply(deps.as_ref())?.total;
+    let referral_fee = take_referral_fee(deps.storage, &mut strategist_fee, total_shares)?;
+
+    update_reward_ledger(deps.storage, |ledger| {
+        ledger.spread_rewards.add(collected)?;
+        ledger.strategist_fees.add(strategist_fee.clone())?;
+        ledger.referral_fees.add(referral_fee)
+    })?;
 
     let mut response = Response::new()
         .add_attribute(
@@ -78,6 +93,10 @@ pub fn handle_collect_spread_rewards_reply(
             msg,
             Replies::CollectIncentives as u64,
         ));
+    } else {
+        // the strategist fee is still held by the vault until the bank send above executes
+        let snapshot = record_share_price_snapshot(deps, &env, &strategist_fee)?;
+        response = response.add_attribute("price_per_share", snapshot.price_per_share.to_string());
     }
 
     Ok(response)
@@ -85,7 +104,7 @@ pub fn handle_collect_spread_rewards_reply(
 
 pub fn handle_collect_incentives_reply(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     data: SubMsgResult,
 ) -> Result<Response, ContractError> {
     let data: Result<MsgCollectIncentivesResponse, ContractError> = data
@@ -103,10 +122,24 @@ pub fn handle_collect_incentives_reply(
     response_coin_list.merge(try_proto_to_cosmwasm_coins(
         response.clone().collected_incentives,
     )?)?;
+    let collected = response_coin_list.clone();
 
     // calculate the strategist fee and remove the share at source
     let vault_config = VAULT_CONFIG.load(deps.storage)?;
-    let strategist_fee: CoinList = response_coin_list.sub_ratio(vault_config.performance_fee)?;
+    let mut strategist_fee: CoinList =
+        response_coin_list.sub_ratio(vault_config.performance_fee)?;
+    let total_shares = query_total_vault_token_supply(deps.as_ref())?.total;
+    let referral_fee = take_referral_fee(deps.storage, &mut strategist_fee, total_shares)?;
+
+    let forfeited = CoinList::from_coins(try_proto_to_cosmwasm_coins(
+        response.clone().forfeited_incentives,
+    )?);
+    update_reward_ledger(deps.storage, |ledger| {
+        ledger.incentives.add(collected)?;
+        ledger.forfeited_incentives.add(forfeited)?;
+        ledger.strategist_fees.add(strategist_fee.clone())?;
+        ledger.referral_fees.add(referral_fee)
+    })?;
 
     // Create the base response object
     let mut response = Response::new()
@@ -128,5 +161,65 @@ pub fn handle_collect_incentives_reply(
             .add_attribute("strategist_fee", format!("{:?}", strategist_fee.coins()));
     }
 
+    // the strategist fee is still held by the vault until the bank send above executes
+    let snapshot = record_share_price_snapshot(deps, &env, &strategist_fee)?;
+    response = response.add_attribute("price_per_share", snapshot.price_per_share.to_string());
+
     Ok(response)
 }
+
+/// Load the reward ledger, apply `action` to it and save it again
+pub fn update_reward_ledger(
+    storage: &mut dyn Storage,
+    action: impl FnOnce(&mut RewardLedger) -> Result<(), ContractError>,
+) -> Result<(), ContractError> {
+    let mut ledger = REWARD_LEDGER.may_load(storage)?.unwrap_or_default();
+    action(&mut ledger)?;
+    REWARD_LEDGER.save(storage, &ledger)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{coin, testing::mock_env, SubMsgResponse};
+    use osmosis_std::types::cosmos::base::v1beta1::Coin as OsmoCoin;
+
+    use crate::test_helpers::{instantiate_contract, mock_deps_with_querier};
+
+    use super::*;
+
+    #[test]
+    fn collected_incentives_are_added_to_ledger() {
+        let mut deps = mock_deps_with_querier();
+        let env = mock_env();
+        instantiate_contract(deps.as_mut(), env.clone(), "admin");
+
+        let reply = |collected: u128, forfeited: u128| {
+            SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(
+                    MsgCollectIncentivesResponse {
+                        collected_incentives: vec![OsmoCoin {
+                            denom: "uion".to_string(),
+                            amount: collected.to_string(),
+                        }],
+                        forfeited_incentives: vec![OsmoCoin {
+                            denom: "uion".to_string(),
+                            amount: forfeited.to_string(),
+                        }],
+                    }
+                    .into(),
+                ),
+            })
+        };
+        handle_collect_incentives_reply(deps.as_mut(), env.clone(), reply(1000, 10)).unwrap();
+        handle_collect_incentives_reply(deps.as_mut(), env, reply(500, 0)).unwrap();
+
+        // the performance fee of the test config is 10%
+        let ledger = REWARD_LEDGER.load(deps.as_ref().storage).unwrap();
+        assert_eq!(ledger.incentives.coins(), vec![coin(1500, "uion")]);
+        assert_eq!(ledger.forfeited_incentives.coins(), vec![coin(10, "uion")]);
+        assert_eq!(ledger.strategist_fees.coins(), vec![coin(150, "uion")]);
+        assert!(ledger.spread_rewards.is_empty());
+    }
+}
@@ -0,0 +1,349 @@
+use cosmwasm_std::{
+    Addr, BankMsg, Coin, DepsMut, Env, MessageInfo, Response, StdError, Storage, SubMsg,
+    SubMsgResult, Uint128,
+};
+use quasar_types::ibc::{
+    ibc_callback_memo, IbcLifecycleComplete, MsgTransfer, MsgTransferResponse,
+};
+
+use crate::{
+    reply::Replies,
+    state::{
+        IbcDestination, IbcTransfer, CURRENT_WITHDRAWER_IBC, IBC_TRANSFERS, PENDING_IBC_TRANSFERS,
+    },
+    vault::{withdraw::execute_withdraw, withdraw_queue::should_queue_withdraw},
+    ContractError,
+};
+
+pub const DEFAULT_IBC_TRANSFERS_LIMIT: u32 = 50;
+pub const MAX_IBC_TRANSFERS_LIMIT: u32 = 200;
+
+/// Withdraw `amount` shares of the sender from the position, the withdraw reply transfers the
+/// assets to `destination` instead of sending them to the sender
+pub fn execute_redeem_to_ibc(
+    mut deps: DepsMut,
+    env: &Env,
+    info: MessageInfo,
+    amount: Uint128,
+    destination: IbcDestination,
+) -> Result<Response, ContractError> {
+    if destination.timeout_seconds == 0 {
+        return Err(ContractError::InvalidIbcTimeout {});
+    }
+    // a queued redeem is claimed locally later on, so only direct withdraws can go over ibc
+    if should_queue_withdraw(deps.as_ref(), env, amount)? {
+        return Err(ContractError::IbcRedeemQueued {});
+    }
+
+    let response = execute_withdraw(deps.branch(), env, info, None, amount.into())?;
+    CURRENT_WITHDRAWER_IBC.save(deps.storage, &destination)?;
+
+    Ok(response
+        .add_attribute("ibc_channel", destination.channel_id)
+        .add_attribute("ibc_receiver", destination.receiver))
+}
+
+/// Transfer every non zero coin to `destination`, with the vault as the ibc-hooks callback so
+/// failed transfers can be refunded to `owner`
+pub fn ibc_transfer_msgs(
+    storage: &mut dyn Storage,
+    env: &Env,
+    owner: &Addr,
+    destination: &IbcDestination,
+    coins: Vec<Coin>,
+) -> Result<Vec<SubMsg>, ContractError> {
+    let memo = ibc_callback_memo(env.contract.address.as_str())
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    let timeout = env.block.time.plus_seconds(destination.timeout_seconds);
+
+    coins
+        .into_iter()
+        .filter(|c| !c.amount.is_zero())
+        .map(|token| {
+            PENDING_IBC_TRANSFERS.push_back(
+                storage,
+                &IbcTransfer {
+                    owner: owner.clone(),
+                    receiver: destination.receiver.clone(),
+                    channel_id: destination.channel_id.clone(),
+                    sequence: 0,
+                    token: token.clone(),
+                },
+            )?;
+            let transfer = MsgTransfer {
+                source_port: "transfer".to_string(),
+                source_channel: destination.channel_id.clone(),
+                token: Some(token.into()),
+                sender: env.contract.address.to_string(),
+                receiver: destination.receiver.clone(),
+                timeout_height: None,
+                timeout_timestamp: Some(timeout.nanos()),
+                memo: memo.clone(),
+            };
+            Ok(SubMsg::reply_on_success(
+                transfer,
+                Replies::IbcTransfer as u64,
+            ))
+        })
+        .collect()
+}
+
+/// Track the sent transfer by its packet sequence until ibc-hooks reports its ack or timeout
+pub fn handle_ibc_transfer_reply(
+    deps: DepsMut,
+    data: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let data = data
+        .into_result()
+        .map_err(StdError::generic_err)?
+        .data
+        .ok_or(StdError::generic_err("missing MsgTransferResponse data"))?;
+    let response: MsgTransferResponse = prost::Message::decode(data.as_slice())?;
+
+    let mut transfer = PENDING_IBC_TRANSFERS
+        .pop_front(deps.storage)?
+        .ok_or(StdError::generic_err("no pending ibc transfer"))?;
+    transfer.sequence = response.seq;
+    IBC_TRANSFERS.save(
+        deps.storage,
+        (&transfer.channel_id, transfer.sequence),
+        &transfer,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "reply")
+        .add_attribute("action", "ibc_transfer")
+        .add_attribute("channel", transfer.channel_id)
+        .add_attribute("sequence", transfer.sequence.to_string()))
+}
+
+/// Settle a tracked transfer. The ICS20 module refunds failed and timed out transfers to the
+/// vault, those funds are passed on to the owner of the redeemed shares. Unknown transfers are
+/// ignored, as a failing sudo call would leave refunds in the vault.
+pub fn handle_ibc_lifecycle_complete(
+    deps: DepsMut,
+    msg: IbcLifecycleComplete,
+) -> Result<Response, ContractError> {
+    let (channel, sequence, success) = match msg {
+        IbcLifecycleComplete::IbcAck {
+            channel,
+            sequence,
+            success,
+            ..
+        } => (channel, sequence, success),
+        IbcLifecycleComplete::IbcTimeout { channel, sequence } => (channel, sequence, false),
+    };
+
+    let response = Response::new()
+        .add_attribute("method", "sudo")
+        .add_attribute("action", "ibc_lifecycle_complete")
+        .add_attribute("channel", &channel)
+        .add_attribute("sequence", sequence.to_string())
+        .add_attribute("success", success.to_string());
+
+    let Some(transfer) = IBC_TRANSFERS.may_load(deps.storage, (&channel, sequence))? else {
+        return Ok(response);
+    };
+    IBC_TRANSFERS.remove(deps.storage, (&channel, sequence));
+
+    if success {
+        return Ok(response);
+    }
+    Ok(response
+        .add_attribute("refund_to", transfer.owner.as_str())
+        .add_message(BankMsg::Send {
+            to_address: transfer.owner.to_string(),
+            amount: vec![transfer.token],
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        coin,
+        testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR},
+        Addr, Binary, CosmosMsg, SubMsgResponse,
+    };
+    use osmosis_std::types::osmosis::concentratedliquidity::v1beta1::MsgWithdrawPositionResponse;
+
+    use super::*;
+    use crate::{
+        state::{CURRENT_WITHDRAWER, SHARES},
+        test_helpers::{
+            instantiate_contract, mock_deps_with_querier_with_balance, BASE_DENOM, QUOTE_DENOM,
+        },
+        vault::withdraw::handle_withdraw_user_reply,
+    };
+
+    fn destination(timeout_seconds: u64) -> IbcDestination {
+        IbcDestination {
+            channel_id: "channel-0".to_string(),
+            receiver: "cosmos1receiver".to_string(),
+            timeout_seconds,
+        }
+    }
+
+    #[test]
+    fn redeem_to_ibc_transfers_withdrawn_assets() {
+        let info = mock_info("bolice", &[]);
+        let mut deps = mock_deps_with_querier_with_balance(
+            100_000,
+            100_000,
+            0,
+            100,
+            1000,
+            &[(
+                MOCK_CONTRACT_ADDR,
+                &[coin(2000, BASE_DENOM), coin(3000, QUOTE_DENOM)],
+            )],
+        );
+        let env = mock_env();
+        instantiate_contract(deps.as_mut(), env.clone(), info.sender.as_str());
+        SHARES
+            .save(
+                deps.as_mut().storage,
+                Addr::unchecked("bolice"),
+                &Uint128::new(1000),
+            )
+            .unwrap();
+
+        let err = execute_redeem_to_ibc(
+            deps.as_mut(),
+            &env,
+            info.clone(),
+            Uint128::new(1000),
+            destination(0),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidIbcTimeout {});
+
+        execute_redeem_to_ibc(
+            deps.as_mut(),
+            &env,
+            info,
+            Uint128::new(1000),
+            destination(600),
+        )
+        .unwrap();
+        assert_eq!(
+            CURRENT_WITHDRAWER.load(deps.as_ref().storage).unwrap(),
+            Addr::unchecked("bolice")
+        );
+
+        // the withdraw reply transfers both tokens instead of sending them locally
+        let response = handle_withdraw_user_reply(
+            deps.as_mut(),
+            env.clone(),
+            SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(
+                    MsgWithdrawPositionResponse {
+                        amount0: "1000".to_string(),
+                        amount1: "1000".to_string(),
+                    }
+                    .into(),
+                ),
+            }),
+        )
+        .unwrap();
+        assert!(CURRENT_WITHDRAWER_IBC
+            .may_load(deps.as_ref().storage)
+            .unwrap()
+            .is_none());
+        assert_eq!(response.messages.len(), 2);
+        assert_eq!(response.messages[0].id, Replies::IbcTransfer as u64);
+        let CosmosMsg::Stargate { type_url, value } = &response.messages[0].msg else {
+            panic!("expected a stargate msg")
+        };
+        assert_eq!(type_url, "/ibc.applications.transfer.v1.MsgTransfer");
+        let transfer: MsgTransfer = prost::Message::decode(value.as_slice()).unwrap();
+        assert_eq!(transfer.source_channel, "channel-0");
+        assert_eq!(transfer.receiver, "cosmos1receiver");
+        assert_eq!(transfer.token.unwrap().amount, "1020");
+        assert_eq!(
+            transfer.timeout_timestamp,
+            Some(env.block.time.plus_seconds(600).nanos())
+        );
+        assert_eq!(
+            transfer.memo,
+            format!(r#"{{"ibc_callback":"{MOCK_CONTRACT_ADDR}"}}"#)
+        );
+
+        // the transfer reply tracks the transfer by its sequence
+        let data = prost::Message::encode_to_vec(&MsgTransferResponse { seq: 7 });
+        handle_ibc_transfer_reply(
+            deps.as_mut(),
+            SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(Binary::from(data)),
+            }),
+        )
+        .unwrap();
+        let transfer = IBC_TRANSFERS
+            .load(deps.as_ref().storage, ("channel-0", 7))
+            .unwrap();
+        assert_eq!(transfer.owner, Addr::unchecked("bolice"));
+        assert_eq!(transfer.token, coin(1020, BASE_DENOM));
+        assert_eq!(PENDING_IBC_TRANSFERS.len(deps.as_ref().storage).unwrap(), 1);
+    }
+
+    #[test]
+    fn failed_ibc_transfers_are_refunded_to_owner() {
+        let mut deps = mock_dependencies();
+        for sequence in [1, 2] {
+            IBC_TRANSFERS
+                .save(
+                    deps.as_mut().storage,
+                    ("channel-0", sequence),
+                    &IbcTransfer {
+                        owner: Addr::unchecked("bolice"),
+                        receiver: "cosmos1receiver".to_string(),
+                        channel_id: "channel-0".to_string(),
+                        sequence,
+                        token: coin(100, "uosmo"),
+                    },
+                )
+                .unwrap();
+        }
+
+        let response = handle_ibc_lifecycle_complete(
+            deps.as_mut(),
+            IbcLifecycleComplete::IbcAck {
+                channel: "channel-0".to_string(),
+                sequence: 1,
+                ack: "".to_string(),
+                success: true,
+            },
+        )
+        .unwrap();
+        assert!(response.messages.is_empty());
+
+        let response = handle_ibc_lifecycle_complete(
+            deps.as_mut(),
+            IbcLifecycleComplete::IbcTimeout {
+                channel: "channel-0".to_string(),
+                sequence: 2,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "bolice".to_string(),
+                amount: vec![coin(100, "uosmo")],
+            })
+        );
+        assert!(IBC_TRANSFERS.is_empty(deps.as_ref().storage));
+
+        // unknown transfers are ignored
+        let response = handle_ibc_lifecycle_complete(
+            deps.as_mut(),
+            IbcLifecycleComplete::IbcTimeout {
+                channel: "channel-0".to_string(),
+                sequence: 2,
+            },
+        )
+        .unwrap();
+        assert!(response.messages.is_empty());
+    }
+}
@@ -0,0 +1,373 @@
+use cosmwasm_std::{
+    coin, to_json_binary, BankMsg, Coin, CosmosMsg, Decimal, Decimal256, Deps, DepsMut, Env,
+    MessageInfo, Response, Uint128, WasmMsg,
+};
+use cw_utils::nonpayable;
+use osmosis_std::types::osmosis::tokenfactory::v1beta1::MsgBurn;
+
+use crate::{
+    error::assert_idle_buffer_keeper,
+    helpers::{
+        fees::{apply_exit_fee, exit_fee_msg},
+        generic::sort_tokens,
+        getters::get_unused_pair_balances,
+        shares::update_user_shares,
+    },
+    msg::{ExecuteMsg, ExtensionExecuteMsg},
+    query::{query_assets_from_shares, query_total_assets, query_total_vault_token_supply},
+    state::{IDLE_BUFFER_CONFIG, POOL_CONFIG, VAULT_CONFIG, VAULT_DENOM},
+    vault::{
+        concentrated_liquidity::{get_position, withdraw_from_position},
+        cost_basis::record_withdraw,
+        superfluid::undelegate_over_max_ratio,
+    },
+    ContractError,
+};
+
+/// The idle amounts the buffer aims for, as [token0, token1]. The target is a ratio of the
+/// total vault assets of each token, so a buffer at target holds the same ratio of both tokens.
+pub fn get_buffer_targets(
+    deps: Deps,
+    env: &Env,
+    target_ratio: Decimal,
+) -> Result<Vec<Coin>, ContractError> {
+    let total_assets = query_total_assets(deps, env.clone())?;
+    Ok(vec![
+        coin(
+            total_assets.token0.amount.mul_floor(target_ratio).u128(),
+            total_assets.token0.denom,
+        ),
+        coin(
+            total_assets.token1.amount.mul_floor(target_ratio).u128(),
+            total_assets.token1.denom,
+        ),
+    ])
+}
+
+/// Returns the assets belonging to `shares` if the idle buffer is enabled, the redemption is
+/// not larger than the configured maximum and the buffer holds enough of both tokens to serve
+/// it without withdrawing from the position
+pub fn get_buffer_withdraw_assets(
+    deps: Deps,
+    env: &Env,
+    shares: Uint128,
+) -> Result<Option<Vec<Coin>>, ContractError> {
+    let Some(config) = IDLE_BUFFER_CONFIG.may_load(deps.storage)? else {
+        return Ok(None);
+    };
+    if shares.is_zero() {
+        return Ok(None);
+    }
+    let total_shares = query_total_vault_token_supply(deps)?.total;
+    if shares > total_shares.checked_mul_floor(config.max_withdraw_ratio)? {
+        return Ok(None);
+    }
+
+    let pool_config = POOL_CONFIG.load(deps.storage)?;
+    let idle = get_unused_pair_balances(&deps, env, &pool_config)?;
+    let assets = query_assets_from_shares(deps, env.clone(), shares)?.balances;
+
+    let fits = assets.iter().all(|asset| {
+        idle.iter()
+            .any(|c| c.denom == asset.denom && c.amount >= asset.amount)
+    });
+    Ok(fits.then_some(assets))
+}
+
+/// Redeem `shares` of the sender out of the idle buffer. Since the position is not touched, no
+/// liquidity is withdrawn and no rewards have to be collected first.
+pub fn execute_buffer_withdraw(
+    mut deps: DepsMut,
+    env: &Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+    shares: Uint128,
+    assets: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    let recipient = recipient.map_or(Ok(info.sender.clone()), |x| deps.api.addr_validate(&x))?;
+
+    let hook_msgs = update_user_shares(deps.storage, env, &info.sender, |user_shares| {
+        user_shares
+            .checked_sub(shares)
+            .map_err(|_| ContractError::InsufficientFunds)
+    })?;
+    record_withdraw(deps.branch(), env, &info.sender, shares)?;
+    let undelegate_response = undelegate_over_max_ratio(deps.branch(), env, shares)?;
+
+    let vault_denom = VAULT_DENOM.load(deps.storage)?;
+    let burn_msg: CosmosMsg = MsgBurn {
+        sender: env.contract.address.to_string(),
+        amount: Some(coin(shares.u128(), vault_denom).into()),
+        burn_from_address: env.contract.address.to_string(),
+    }
+    .into();
+
+    let mut response = Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "buffer_withdraw")
+        .add_attribute("recipient", recipient.as_str())
+        .add_attribute("share_amount", shares)
+        .add_message(burn_msg);
+
+    let vault_config = VAULT_CONFIG.load(deps.storage)?;
+    let (payout, fee) = apply_exit_fee(&vault_config, assets);
+    let coins: Vec<Coin> = payout.into_iter().filter(|c| !c.amount.is_zero()).collect();
+    if !coins.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: sort_tokens(coins),
+        });
+    }
+    if let Some(fee_msg) = exit_fee_msg(&vault_config, fee) {
+        response = response.add_message(fee_msg);
+    }
+
+    Ok(response
+        .add_attributes(undelegate_response.attributes)
+        .add_submessages(undelegate_response.messages)
+        .add_submessages(hook_msgs))
+}
+
+/// Move the idle buffer back toward its target. If the buffer holds less than the target of
+/// either token, the missing part is withdrawn from the position, otherwise any surplus is
+/// deposited into the position through an autocompound, which keeps the target idle.
+pub fn execute_rebalance_idle_buffer(
+    deps: DepsMut,
+    env: &Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    assert_idle_buffer_keeper(deps.storage, &info.sender)?;
+
+    let config = IDLE_BUFFER_CONFIG.load(deps.storage)?;
+    let pool_config = POOL_CONFIG.load(deps.storage)?;
+    let total_assets = query_total_assets(deps.as_ref(), env.clone())?;
+    let idle = get_unused_pair_balances(&deps.as_ref(), env, &pool_config)?;
+    let targets = get_buffer_targets(deps.as_ref(), env, config.target_ratio)?;
+    let totals = [total_assets.token0, total_assets.token1];
+
+    // the ratio of the position to withdraw to bring both tokens up to their target,
+    // this never exceeds the target ratio, so the position is never fully withdrawn
+    let mut withdraw_ratio = Decimal::zero();
+    let mut surplus = false;
+    for ((idle, target), total) in idle.iter().zip(targets.iter()).zip(totals.iter()) {
+        let in_position = total.amount.checked_sub(idle.amount)?;
+        if idle.amount < target.amount && !in_position.is_zero() {
+            withdraw_ratio = withdraw_ratio.max(Decimal::from_ratio(
+                target.amount - idle.amount,
+                in_position,
+            ));
+        } else if idle.amount > target.amount {
+            surplus = true;
+        }
+    }
+
+    let response = Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "rebalance_idle_buffer");
+
+    if !withdraw_ratio.is_zero() {
+        let liquidity: Decimal256 = get_position(deps.storage, &deps.querier)?
+            .position
+            .ok_or(ContractError::PositionNotFound)?
+            .liquidity
+            .parse()?;
+        let withdraw_msg = withdraw_from_position(
+            deps.storage,
+            env,
+            liquidity.checked_mul(Decimal256::from(withdraw_ratio))?,
+        )?;
+        Ok(response
+            .add_attribute("status", "top_up")
+            .add_attribute("liquidity_amount", withdraw_msg.liquidity_amount.as_str())
+            .add_message(withdraw_msg))
+    } else if surplus {
+        Ok(response
+            .add_attribute("status", "drain")
+            .add_message(WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                msg: to_json_binary(&ExecuteMsg::VaultExtension(
+                    ExtensionExecuteMsg::Autocompound {},
+                ))?,
+                funds: vec![],
+            }))
+    } else {
+        Ok(response.add_attribute("status", "balanced"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR},
+        Addr, StdResult,
+    };
+    use std::str::FromStr;
+
+    use crate::{
+        state::{IdleBufferConfig, SHARES},
+        test_helpers::{
+            instantiate_contract, mock_deps_with_querier_with_balance, BASE_DENOM, QUOTE_DENOM,
+        },
+    };
+
+    use super::*;
+
+    const USER: &str = "alice";
+
+    fn setup_buffer(
+        idle0: u128,
+        idle1: u128,
+        target_ratio: Decimal,
+    ) -> cosmwasm_std::OwnedDeps<
+        cosmwasm_std::testing::MockStorage,
+        cosmwasm_std::testing::MockApi,
+        crate::test_helpers::QuasarQuerier,
+    > {
+        let mut deps = mock_deps_with_querier_with_balance(
+            100_000,
+            100_000,
+            0,
+            100,
+            1000,
+            &[(
+                MOCK_CONTRACT_ADDR,
+                &[coin(idle0, BASE_DENOM), coin(idle1, QUOTE_DENOM)],
+            )],
+        );
+        instantiate_contract(deps.as_mut(), mock_env(), "admin");
+        IDLE_BUFFER_CONFIG
+            .save(
+                deps.as_mut().storage,
+                &IdleBufferConfig {
+                    target_ratio,
+                    max_withdraw_ratio: Decimal::percent(50),
+                    keeper: Some(Addr::unchecked("keeper")),
+                },
+            )
+            .unwrap();
+        SHARES
+            .save(
+                deps.as_mut().storage,
+                Addr::unchecked(USER),
+                &Uint128::new(10_000),
+            )
+            .unwrap();
+        deps
+    }
+
+    #[test]
+    fn small_withdraws_are_served_from_buffer() {
+        // 100_000 of each token in the position, 20_000 idle and 90 in claimable rewards
+        let mut deps = setup_buffer(20_000, 20_000, Decimal::percent(10));
+        let env = mock_env();
+
+        // 10% of the supply is entitled to 12_009 of each token, which fits in the buffer
+        let assets = get_buffer_withdraw_assets(deps.as_ref(), &env, Uint128::new(10_000))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            assets,
+            sort_tokens(vec![coin(12_009, BASE_DENOM), coin(12_009, QUOTE_DENOM)])
+        );
+        // 20% of the supply is entitled to 24_018 of each token, which does not
+        assert!(
+            get_buffer_withdraw_assets(deps.as_ref(), &env, Uint128::new(20_000))
+                .unwrap()
+                .is_none()
+        );
+
+        let res = execute_buffer_withdraw(
+            deps.as_mut(),
+            &env,
+            mock_info(USER, &[]),
+            None,
+            Uint128::new(10_000),
+            assets.clone(),
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: USER.to_string(),
+                amount: assets,
+            })
+        );
+        assert!(SHARES
+            .load(deps.as_ref().storage, Addr::unchecked(USER))
+            .unwrap()
+            .is_zero());
+
+        IDLE_BUFFER_CONFIG.remove(deps.as_mut().storage);
+        assert!(
+            get_buffer_withdraw_assets(deps.as_ref(), &env, Uint128::new(1))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn large_withdraws_skip_buffer() {
+        let mut deps = setup_buffer(20_000, 20_000, Decimal::percent(10));
+        let env = mock_env();
+        IDLE_BUFFER_CONFIG
+            .update(deps.as_mut().storage, |mut config| -> StdResult<_> {
+                config.max_withdraw_ratio = Decimal::percent(5);
+                Ok(config)
+            })
+            .unwrap();
+
+        // the mocked total supply is 100_000, so at most 5000 shares are served from the buffer
+        assert_eq!(
+            get_buffer_withdraw_assets(deps.as_ref(), &env, Uint128::new(5000))
+                .unwrap()
+                .unwrap(),
+            sort_tokens(vec![coin(6004, BASE_DENOM), coin(6004, QUOTE_DENOM)])
+        );
+        assert!(
+            get_buffer_withdraw_assets(deps.as_ref(), &env, Uint128::new(5001))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn rebalance_tops_up_and_drains_buffer() {
+        let env = mock_env();
+
+        // total assets are 105_090 of each token including the claimable rewards,
+        // so the 10% target is 10_509
+        let mut deps = setup_buffer(5000, 5000, Decimal::percent(10));
+        let err =
+            execute_rebalance_idle_buffer(deps.as_mut(), &env, mock_info(USER, &[])).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let res =
+            execute_rebalance_idle_buffer(deps.as_mut(), &env, mock_info("keeper", &[])).unwrap();
+        // 5509 out of the 100_090 in the position and its rewards is withdrawn
+        let expected = withdraw_from_position(
+            deps.as_ref().storage,
+            &env,
+            Decimal256::from_str("1000000.1").unwrap()
+                * Decimal256::from(Decimal::from_ratio(5509u128, 100_090u128)),
+        )
+        .unwrap();
+        assert_eq!(res.messages[0].msg, expected.into());
+
+        let mut deps = setup_buffer(20_000, 20_000, Decimal::percent(10));
+        let res =
+            execute_rebalance_idle_buffer(deps.as_mut(), &env, mock_info("keeper", &[])).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_json_binary(&ExecuteMsg::VaultExtension(
+                    ExtensionExecuteMsg::Autocompound {}
+                ))
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+    }
+}
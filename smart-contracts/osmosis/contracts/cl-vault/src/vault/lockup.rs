@@ -0,0 +1,211 @@
+use cosmwasm_std::{DepsMut, Env, Event, MessageInfo, Response, Uint128};
+use cw_utils::nonpayable;
+use quasar_types::cw_vault_multi_standard::extensions::lockup::{
+    UnlockingPosition, UNLOCKING_POSITION_ATTR_KEY, UNLOCKING_POSITION_CREATED_EVENT_TYPE,
+};
+
+use crate::{
+    helpers::shares::update_user_shares,
+    state::{
+        LOCKUP_DURATION, UNLOCKING_POSITIONS, UNLOCKING_POSITION_COUNT, UNLOCKING_POSITION_OWNERS,
+    },
+    vault::{cost_basis::record_withdraw, withdraw::withdraw_shares},
+    ContractError,
+};
+
+/// The amount of unlocking positions returned by a query if no limit is given
+pub const DEFAULT_UNLOCKING_POSITIONS_LIMIT: u32 = 50;
+pub const MAX_UNLOCKING_POSITIONS_LIMIT: u32 = 200;
+
+/// Returns an error if the vault is in lockup mode, in which case shares can only be redeemed
+/// through Unlock and WithdrawUnlocked
+pub fn assert_not_locked(deps: &DepsMut) -> Result<(), ContractError> {
+    if LOCKUP_DURATION.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::LockupEnabled {});
+    }
+    Ok(())
+}
+
+/// Escrow `amount` shares of the sender in a new unlocking position that can be withdrawn once
+/// the lockup duration has passed. The shares stay part of the vault token supply, and thus keep
+/// their claim on the vault assets, until they are withdrawn.
+/// Unlocking has no side effects on the position, so it is used for both Unlock and EmergencyUnlock.
+pub fn execute_unlock(
+    deps: DepsMut,
+    env: &Env,
+    info: MessageInfo,
+    amount: Uint128,
+    action: &str,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    if amount.is_zero() {
+        return Err(ContractError::InsufficientFunds);
+    }
+    let duration = LOCKUP_DURATION
+        .may_load(deps.storage)?
+        .ok_or(ContractError::LockupDisabled {})?;
+
+    let hook_msgs = update_user_shares(deps.storage, env, &info.sender, |user_shares| {
+        user_shares
+            .checked_sub(amount)
+            .map_err(|_| ContractError::InsufficientFunds)
+    })?;
+
+    let id = UNLOCKING_POSITION_COUNT
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    UNLOCKING_POSITION_COUNT.save(deps.storage, &(id + 1))?;
+
+    let position = UnlockingPosition {
+        id,
+        owner: info.sender.clone(),
+        release_at: duration.after(&env.block),
+        base_token_amount: amount,
+    };
+    UNLOCKING_POSITIONS.save(deps.storage, (info.sender.clone(), id), &position)?;
+    UNLOCKING_POSITION_OWNERS.save(deps.storage, id, &info.sender)?;
+
+    Ok(Response::new()
+        .add_event(
+            Event::new(UNLOCKING_POSITION_CREATED_EVENT_TYPE)
+                .add_attribute(UNLOCKING_POSITION_ATTR_KEY, id.to_string()),
+        )
+        .add_attribute("method", "execute")
+        .add_attribute("action", action)
+        .add_attribute("owner", info.sender)
+        .add_attribute("share_amount", amount)
+        .add_attribute("release_at", position.release_at.to_string())
+        .add_submessages(hook_msgs))
+}
+
+/// Withdraw the assets of an unlocking position of the sender that has finished unlocking
+pub fn execute_withdraw_unlocked(
+    mut deps: DepsMut,
+    env: &Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+    lockup_id: u64,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    let recipient = recipient.map_or(Ok(info.sender.clone()), |x| deps.api.addr_validate(&x))?;
+
+    let position = UNLOCKING_POSITIONS
+        .may_load(deps.storage, (info.sender.clone(), lockup_id))?
+        .ok_or(ContractError::UnlockingPositionNotFound { id: lockup_id })?;
+    if !position.release_at.is_expired(&env.block) {
+        return Err(ContractError::UnlockingPositionNotExpired { id: lockup_id });
+    }
+
+    UNLOCKING_POSITIONS.remove(deps.storage, (info.sender.clone(), lockup_id));
+    UNLOCKING_POSITION_OWNERS.remove(deps.storage, lockup_id);
+    record_withdraw(deps.branch(), env, &info.sender, position.base_token_amount)?;
+
+    Ok(
+        withdraw_shares(deps, env, recipient, position.base_token_amount.into())?
+            .add_attribute("lockup_id", lockup_id.to_string()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        coin,
+        testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR},
+        Addr,
+    };
+    use cw_utils::{Duration, Expiration};
+
+    use crate::{
+        query::{query_unlocking_position, query_unlocking_positions},
+        state::{CURRENT_WITHDRAWER, SHARES},
+        test_helpers::{
+            instantiate_contract, mock_deps_with_querier_with_balance, BASE_DENOM, QUOTE_DENOM,
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn unlock_and_withdraw_unlocked_works() {
+        let mut deps = mock_deps_with_querier_with_balance(
+            100_000,
+            100_000,
+            0,
+            100,
+            1000,
+            &[(
+                MOCK_CONTRACT_ADDR,
+                &[coin(2000, BASE_DENOM), coin(3000, QUOTE_DENOM)],
+            )],
+        );
+        let mut env = mock_env();
+        instantiate_contract(deps.as_mut(), env.clone(), "admin");
+        let user = Addr::unchecked("alice");
+        SHARES
+            .save(deps.as_mut().storage, user.clone(), &Uint128::new(1000))
+            .unwrap();
+
+        let err = execute_unlock(
+            deps.as_mut(),
+            &env,
+            mock_info("alice", &[]),
+            Uint128::new(400),
+            "unlock",
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::LockupDisabled {});
+
+        LOCKUP_DURATION
+            .save(deps.as_mut().storage, &Duration::Time(100))
+            .unwrap();
+        assert_eq!(
+            assert_not_locked(&deps.as_mut()).unwrap_err(),
+            ContractError::LockupEnabled {}
+        );
+
+        let res = execute_unlock(
+            deps.as_mut(),
+            &env,
+            mock_info("alice", &[]),
+            Uint128::new(400),
+            "unlock",
+        )
+        .unwrap();
+        assert_eq!(res.events[0].ty, UNLOCKING_POSITION_CREATED_EVENT_TYPE);
+        assert_eq!(
+            SHARES.load(deps.as_ref().storage, user.clone()).unwrap(),
+            Uint128::new(600)
+        );
+
+        let position = query_unlocking_position(deps.as_ref(), 0).unwrap();
+        assert_eq!(
+            position,
+            UnlockingPosition {
+                id: 0,
+                owner: user.clone(),
+                release_at: Expiration::AtTime(env.block.time.plus_seconds(100)),
+                base_token_amount: Uint128::new(400),
+            }
+        );
+        assert_eq!(
+            query_unlocking_positions(deps.as_ref(), "alice".to_string(), None, None).unwrap(),
+            vec![position]
+        );
+
+        let err = execute_withdraw_unlocked(deps.as_mut(), &env, mock_info("alice", &[]), None, 0)
+            .unwrap_err();
+        assert_eq!(err, ContractError::UnlockingPositionNotExpired { id: 0 });
+
+        env.block.time = env.block.time.plus_seconds(100);
+        let err = execute_withdraw_unlocked(deps.as_mut(), &env, mock_info("bob", &[]), None, 0)
+            .unwrap_err();
+        assert_eq!(err, ContractError::UnlockingPositionNotFound { id: 0 });
+
+        execute_withdraw_unlocked(deps.as_mut(), &env, mock_info("alice", &[]), None, 0).unwrap();
+        assert_eq!(
+            CURRENT_WITHDRAWER.load(deps.as_ref().storage).unwrap(),
+            user
+        );
+        assert!(query_unlocking_position(deps.as_ref(), 0).is_err());
+    }
+}
@@ -12,6 +12,7 @@ use osmosis_std::types::osmosis::concentratedliquidity::v1beta1::{
 };
 
 use crate::{
+    helpers::operation_lock::{acquire_operation_lock, release_operation_lock},
     msg::MergePositionMsg,
     reply::Replies,
     state::{CurrentMergePosition, CURRENT_MERGE, CURRENT_MERGE_POSITION, POOL_CONFIG},
@@ -70,6 +71,7 @@ pub fn execute_merge_position(
         })
         .collect();
 
+    acquire_operation_lock(deps.storage, &env, "merge")?;
     CURRENT_MERGE_POSITION.save(deps.storage, &range.unwrap())?;
 
     // push all items on the queue
@@ -183,11 +185,13 @@ pub fn handle_merge_withdraw_position_reply(
 }
 
 pub fn handle_merge_create_position_reply(
-    _deps: DepsMut,
+    deps: DepsMut,
     _env: Env,
     msg: SubMsgResult,
 ) -> Result<Response, ContractError> {
     let response: MsgCreatePositionResponse = msg.try_into()?;
+    CURRENT_MERGE_POSITION.remove(deps.storage);
+    release_operation_lock(deps.storage);
     // TODO decide if we want any healthchecks here
     Ok(Response::new()
         .set_data(
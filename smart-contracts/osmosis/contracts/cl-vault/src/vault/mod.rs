@@ -1,9 +1,21 @@
 pub mod admin;
+pub mod allowlist;
+pub mod auto_withdraw;
 pub mod autocompound;
 pub mod concentrated_liquidity;
+pub mod cost_basis;
+pub mod cw20_deposit;
 pub mod deposit;
 pub mod distribution;
+pub mod ibc_redeem;
+pub mod idle_buffer;
+pub mod lockup;
 pub mod merge;
+pub mod operator;
 pub mod range;
+pub mod redeem_and_call;
+pub mod referral;
+pub mod superfluid;
 pub mod swap;
 pub mod withdraw;
+pub mod withdraw_queue;
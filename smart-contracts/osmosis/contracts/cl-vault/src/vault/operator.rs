@@ -0,0 +1,248 @@
+use cosmwasm_std::{Addr, DepsMut, Env, MessageInfo, Response, Storage, Uint128};
+use cw_utils::{nonpayable, Expiration};
+
+use crate::{
+    state::{OperatorAllowance, OPERATOR_ALLOWANCES},
+    ContractError,
+};
+
+/// The amount of operator allowances returned by a query if no limit is given
+pub const DEFAULT_OPERATOR_ALLOWANCES_LIMIT: u32 = 50;
+pub const MAX_OPERATOR_ALLOWANCES_LIMIT: u32 = 200;
+
+/// Set the allowance of `operator` over the shares of the sender, replacing any existing allowance
+pub fn execute_approve_operator(
+    deps: DepsMut,
+    env: &Env,
+    info: MessageInfo,
+    operator: String,
+    amount: Uint128,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    let operator = deps.api.addr_validate(&operator)?;
+    if operator == info.sender {
+        return Err(ContractError::CannotApproveSelf {});
+    }
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::OperatorAllowanceAlreadyExpired {});
+    }
+
+    OPERATOR_ALLOWANCES.save(
+        deps.storage,
+        (info.sender.clone(), operator.clone()),
+        &OperatorAllowance {
+            allowance: amount,
+            expires,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "approve_operator")
+        .add_attribute("owner", info.sender)
+        .add_attribute("operator", operator)
+        .add_attribute("allowance", amount)
+        .add_attribute("expires", expires.to_string()))
+}
+
+pub fn execute_revoke_operator(
+    deps: DepsMut,
+    info: MessageInfo,
+    operator: String,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    let operator = deps.api.addr_validate(&operator)?;
+    OPERATOR_ALLOWANCES.remove(deps.storage, (info.sender.clone(), operator.clone()));
+
+    Ok(Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "revoke_operator")
+        .add_attribute("owner", info.sender)
+        .add_attribute("operator", operator))
+}
+
+/// Deduct `amount` from the allowance `owner` approved for `operator`, removing the allowance
+/// once it is used up
+pub fn spend_operator_allowance(
+    storage: &mut dyn Storage,
+    env: &Env,
+    owner: &Addr,
+    operator: &Addr,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let key = (owner.clone(), operator.clone());
+    let mut allowance = OPERATOR_ALLOWANCES.may_load(storage, key.clone())?.ok_or(
+        ContractError::OperatorNotApproved {
+            owner: owner.to_string(),
+            operator: operator.to_string(),
+        },
+    )?;
+    if allowance.expires.is_expired(&env.block) {
+        return Err(ContractError::OperatorAllowanceExpired {
+            operator: operator.to_string(),
+        });
+    }
+    allowance.allowance = allowance.allowance.checked_sub(amount).map_err(|_| {
+        ContractError::InsufficientOperatorAllowance {
+            allowance: allowance.allowance,
+            amount,
+        }
+    })?;
+
+    if allowance.allowance.is_zero() {
+        OPERATOR_ALLOWANCES.remove(storage, key);
+    } else {
+        OPERATOR_ALLOWANCES.save(storage, key, &allowance)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        coin,
+        testing::{mock_dependencies, mock_env, mock_info},
+    };
+
+    use super::*;
+
+    #[test]
+    fn operator_allowance_is_spent_until_used_up() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner = Addr::unchecked("owner");
+        let operator = Addr::unchecked("operator");
+
+        let err = spend_operator_allowance(
+            deps.as_mut().storage,
+            &env,
+            &owner,
+            &operator,
+            Uint128::new(1),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::OperatorNotApproved {
+                owner: "owner".to_string(),
+                operator: "operator".to_string()
+            }
+        );
+
+        execute_approve_operator(
+            deps.as_mut(),
+            &env,
+            mock_info("owner", &[]),
+            "operator".to_string(),
+            Uint128::new(100),
+            None,
+        )
+        .unwrap();
+
+        spend_operator_allowance(
+            deps.as_mut().storage,
+            &env,
+            &owner,
+            &operator,
+            Uint128::new(60),
+        )
+        .unwrap();
+        let err = spend_operator_allowance(
+            deps.as_mut().storage,
+            &env,
+            &owner,
+            &operator,
+            Uint128::new(60),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientOperatorAllowance {
+                allowance: Uint128::new(40),
+                amount: Uint128::new(60)
+            }
+        );
+
+        spend_operator_allowance(
+            deps.as_mut().storage,
+            &env,
+            &owner,
+            &operator,
+            Uint128::new(40),
+        )
+        .unwrap();
+        assert!(OPERATOR_ALLOWANCES
+            .may_load(deps.as_ref().storage, (owner, operator))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn expired_or_revoked_allowances_cannot_be_spent() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let owner = Addr::unchecked("owner");
+        let operator = Addr::unchecked("operator");
+
+        let err = execute_approve_operator(
+            deps.as_mut(),
+            &env,
+            mock_info("owner", &[coin(1, "uosmo")]),
+            "operator".to_string(),
+            Uint128::new(100),
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NonPayable {});
+
+        let err = execute_approve_operator(
+            deps.as_mut(),
+            &env,
+            mock_info("owner", &[]),
+            "operator".to_string(),
+            Uint128::new(100),
+            Some(Expiration::AtHeight(env.block.height)),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::OperatorAllowanceAlreadyExpired {});
+
+        execute_approve_operator(
+            deps.as_mut(),
+            &env,
+            mock_info("owner", &[]),
+            "operator".to_string(),
+            Uint128::new(100),
+            Some(Expiration::AtHeight(env.block.height + 10)),
+        )
+        .unwrap();
+
+        env.block.height += 10;
+        let err = spend_operator_allowance(
+            deps.as_mut().storage,
+            &env,
+            &owner,
+            &operator,
+            Uint128::new(1),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::OperatorAllowanceExpired {
+                operator: "operator".to_string()
+            }
+        );
+
+        execute_revoke_operator(
+            deps.as_mut(),
+            mock_info("owner", &[]),
+            "operator".to_string(),
+        )
+        .unwrap();
+        assert!(OPERATOR_ALLOWANCES
+            .may_load(deps.as_ref().storage, (owner, operator))
+            .unwrap()
+            .is_none());
+    }
+}
@@ -1,20 +1,27 @@
 use crate::{
-    error::{assert_range_admin, assert_ratio},
+    error::{assert_admin, assert_range_admin, assert_ratio},
     helpers::getters::{
         get_single_sided_deposit_0_to_1_swap_amount, get_single_sided_deposit_1_to_0_swap_amount,
         get_twap_price, get_unused_pair_balances,
     },
+    helpers::{
+        coinlist::CoinList,
+        generic::round_up_to_nearest_multiple,
+        operation_lock::{acquire_operation_lock, release_operation_lock},
+        share_price::record_share_price_snapshot,
+    },
     math::tick::{price_to_tick, tick_to_price},
     reply::Replies,
     state::{
-        ModifyRangeState, Position, SwapDepositMergeState, MODIFY_RANGE_STATE, POOL_CONFIG,
-        POSITION, SWAP_DEPOSIT_MERGE_STATE, VAULT_CONFIG,
+        ModifyRangeState, PoolConfig, Position, SwapDepositMergeState, MODIFY_RANGE_STATE,
+        POOL_CONFIG, POSITION, SUPERFLUID_POSITIONS, SWAP_DEPOSIT_MERGE_STATE, VAULT_CONFIG,
     },
     vault::{
         concentrated_liquidity::{
             create_position, get_amount_from_liquidity_for_base_token,
             get_amount_from_liquidity_for_quote_token, get_cl_pool_info,
             get_liquidity_for_base_token, get_liquidity_for_quote_token, get_position,
+            record_range_entry,
         },
         swap::{estimate_swap_min_out_amount, swap_msg},
     },
@@ -24,6 +31,7 @@ use cosmwasm_std::{
     coin, Decimal, Decimal256, DepsMut, Env, Fraction, MessageInfo, Response, StdResult, SubMsg,
     SubMsgResult, Uint128, Uint256,
 };
+use cw_utils::nonpayable;
 use osmosis_std::types::osmosis::{
     concentratedliquidity::v1beta1::{MsgCreatePositionResponse, MsgWithdrawPosition},
     poolmanager::v1beta1::SwapAmountInRoute,
@@ -62,6 +70,7 @@ pub fn execute_update_range(
             .to_string(),
     };
 
+    acquire_operation_lock(deps.storage, env, "modify_range")?;
     MODIFY_RANGE_STATE.save(
         deps.storage,
         &Some(ModifyRangeState {
@@ -93,6 +102,103 @@ pub fn execute_update_range(
         .add_attribute("liquidity_amount", position.liquidity))
 }
 
+/// Move the liquidity of the vault to `new_pool_id`, a pool of the same token pair. The position is
+/// withdrawn from the current pool and recreated in the new pool within the given price range by
+/// the modify range replies, which read the pool from POOL_CONFIG. Shares are left untouched.
+/// The range is rounded to the tick spacing of the new pool, and the swap into the range is
+/// checked against the TWAP of the new pool over `twap_window_seconds`, so a young pool needs
+/// a window shorter than its history.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_migrate_pool(
+    deps: DepsMut,
+    env: &Env,
+    info: MessageInfo,
+    new_pool_id: u64,
+    lower_price: Decimal,
+    upper_price: Decimal,
+    max_slippage: Decimal,
+    twap_window_seconds: Option<u64>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    assert_admin(deps.storage, &info.sender)?;
+
+    if !SUPERFLUID_POSITIONS.is_empty(deps.storage) {
+        return Err(ContractError::SuperfluidPositionsOpen {});
+    }
+    let pool_config = POOL_CONFIG.load(deps.storage)?;
+    if new_pool_id == pool_config.pool_id {
+        return Err(ContractError::SamePool {
+            pool_id: new_pool_id,
+        });
+    }
+    let new_pool = get_cl_pool_info(&deps.querier, new_pool_id)?;
+    if new_pool.token0 != pool_config.token0 || new_pool.token1 != pool_config.token1 {
+        return Err(ContractError::PoolTokenMismatch {});
+    }
+
+    // the position is created at the ticks rounded up to the tick spacing of the new pool
+    let tick_spacing: i64 = new_pool.tick_spacing.try_into()?;
+    let lower_tick: i64 = price_to_tick(deps.storage, lower_price.into())?.try_into()?;
+    let upper_tick: i64 = price_to_tick(deps.storage, upper_price.into())?.try_into()?;
+    let lower_tick = round_up_to_nearest_multiple(lower_tick, tick_spacing);
+    let upper_tick = round_up_to_nearest_multiple(upper_tick, tick_spacing);
+    if lower_tick >= upper_tick {
+        return Err(ContractError::EmptyMigrationRange { tick_spacing });
+    }
+
+    let position = get_position(deps.storage, &deps.querier)?
+        .position
+        .ok_or(ContractError::MissingPosition {})?;
+    let withdraw_msg = MsgWithdrawPosition {
+        position_id: position.position_id,
+        sender: env.contract.address.to_string(),
+        liquidity_amount: Decimal256::from_str(position.liquidity.as_str())?
+            .atomics()
+            .to_string(),
+    };
+
+    let vault_config = VAULT_CONFIG.load(deps.storage)?;
+    acquire_operation_lock(deps.storage, env, "migrate_pool")?;
+    MODIFY_RANGE_STATE.save(
+        deps.storage,
+        &Some(ModifyRangeState {
+            lower_tick,
+            upper_tick,
+            max_slippage,
+            new_range_position_ids: vec![],
+            ratio_of_swappable_funds_to_use: Decimal::one(),
+            twap_window_seconds: twap_window_seconds.unwrap_or(vault_config.twap_window_seconds),
+            forced_swap_route: None,
+        }),
+    )?;
+    POOL_CONFIG.save(
+        deps.storage,
+        &PoolConfig {
+            pool_id: new_pool_id,
+            token0: pool_config.token0,
+            token1: pool_config.token1,
+        },
+    )?;
+    POSITION.update(deps.storage, |position| -> StdResult<Position> {
+        let mut position = position;
+        position.join_time = env.block.time.seconds();
+        position.claim_after = None;
+        Ok(position)
+    })?;
+
+    Ok(Response::default()
+        .add_submessage(SubMsg::reply_on_success(
+            withdraw_msg,
+            Replies::WithdrawPosition.into(),
+        ))
+        .add_attribute("method", "execute")
+        .add_attribute("action", "migrate_pool")
+        .add_attribute("old_pool_id", pool_config.pool_id.to_string())
+        .add_attribute("new_pool_id", new_pool_id.to_string())
+        .add_attribute("position_id", position.position_id.to_string())
+        .add_attribute("liquidity_amount", position.liquidity))
+}
+
 fn requires_swap(
     sqrt_p: Decimal256,
     sqrt_pl: Decimal256,
@@ -286,6 +392,8 @@ pub fn handle_create_position(
     data: SubMsgResult,
 ) -> Result<Response, ContractError> {
     let create_position_message: MsgCreatePositionResponse = data.try_into()?;
+    MODIFY_RANGE_STATE.save(deps.storage, &None)?;
+    release_operation_lock(deps.storage);
 
     POSITION.update(deps.storage, |position| -> StdResult<Position> {
         let mut position = position;
@@ -293,8 +401,16 @@ pub fn handle_create_position(
         position.join_time = env.block.time.seconds();
         Ok(position)
     })?;
+    record_range_entry(
+        deps.storage,
+        &deps.querier,
+        &env,
+        create_position_message.lower_tick,
+        create_position_message.upper_tick,
+    )?;
+    let snapshot = record_share_price_snapshot(deps, &env, &CoinList::new())?;
 
-    Ok(Response::default())
+    Ok(Response::default().add_attribute("price_per_share", snapshot.price_per_share.to_string()))
 }
 
 #[cfg(test)]
@@ -310,12 +426,14 @@ mod tests {
     use crate::{
         helpers::getters::get_range_admin,
         math::tick::build_tick_exp_cache,
-        state::{MODIFY_RANGE_STATE, RANGE_ADMIN},
+        reply::Replies,
+        state::{MODIFY_RANGE_STATE, POOL_CONFIG, RANGE_ADMIN},
         test_helpers::{
             instantiate_contract, mock_deps_with_querier, mock_deps_with_querier_with_balance,
-            BASE_DENOM, POSITION_ID, QUOTE_DENOM,
+            BASE_DENOM, POOL_ID, POSITION_ID, QUOTE_DENOM,
         },
         vault::range::requires_swap,
+        ContractError,
     };
 
     #[test]
@@ -365,6 +483,129 @@ mod tests {
         assert_eq!(res.attributes[3].value, "1000000.1");
     }
 
+    #[test]
+    fn test_execute_migrate_pool() {
+        let admin = "admin".to_string();
+        let mut deps = mock_deps_with_querier();
+        let env = mock_env();
+        build_tick_exp_cache(deps.as_mut().storage).unwrap();
+        instantiate_contract(deps.as_mut(), env.clone(), &admin);
+
+        let lower_price = Decimal::from_str("100").unwrap();
+        let upper_price = Decimal::from_str("100.20").unwrap();
+        let max_slippage = Decimal::from_str("0.5").unwrap();
+
+        let err = super::execute_migrate_pool(
+            deps.as_mut(),
+            &env,
+            mock_info(&admin, &[]),
+            POOL_ID,
+            lower_price,
+            upper_price,
+            max_slippage,
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::SamePool { pool_id: POOL_ID });
+
+        let err = super::execute_migrate_pool(
+            deps.as_mut(),
+            &env,
+            mock_info("range_admin", &[]),
+            2,
+            lower_price,
+            upper_price,
+            max_slippage,
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let res = super::execute_migrate_pool(
+            deps.as_mut(),
+            &env,
+            mock_info(&admin, &[]),
+            2,
+            lower_price,
+            upper_price,
+            max_slippage,
+            Some(10),
+        )
+        .unwrap();
+
+        // the position is withdrawn and recreated by the modify range replies
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].id, u64::from(Replies::WithdrawPosition));
+        let pool_config = POOL_CONFIG.load(deps.as_ref().storage).unwrap();
+        assert_eq!(pool_config.pool_id, 2);
+        assert_eq!(pool_config.token0, BASE_DENOM);
+        assert_eq!(pool_config.token1, QUOTE_DENOM);
+        let modify_range_state = MODIFY_RANGE_STATE
+            .load(deps.as_ref().storage)
+            .unwrap()
+            .unwrap();
+        assert_eq!(modify_range_state.max_slippage, max_slippage);
+        assert_eq!(
+            modify_range_state.ratio_of_swappable_funds_to_use,
+            Decimal::one()
+        );
+        // the swap is checked against a TWAP that fits in the history of the new pool
+        assert_eq!(modify_range_state.twap_window_seconds, 10);
+        // the ticks are rounded up to the tick spacing of 100 of the new pool
+        assert_eq!(modify_range_state.lower_tick, 18_000_000);
+        assert_eq!(modify_range_state.upper_tick, 18_002_000);
+    }
+
+    #[test]
+    fn test_execute_migrate_pool_failures() {
+        let admin = "admin".to_string();
+        let mut deps = mock_deps_with_querier();
+        let env = mock_env();
+        build_tick_exp_cache(deps.as_mut().storage).unwrap();
+        instantiate_contract(deps.as_mut(), env.clone(), &admin);
+
+        // both prices round up to tick 18_000_100 at a tick spacing of 100
+        let err = super::execute_migrate_pool(
+            deps.as_mut(),
+            &env,
+            mock_info(&admin, &[]),
+            2,
+            Decimal::from_str("100.001").unwrap(),
+            Decimal::from_str("100.005").unwrap(),
+            Decimal::percent(50),
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::EmptyMigrationRange { tick_spacing: 100 }
+        );
+
+        let err = super::execute_migrate_pool(
+            deps.as_mut(),
+            &env,
+            mock_info(&admin, &[coin(1, BASE_DENOM)]),
+            2,
+            Decimal::from_str("100").unwrap(),
+            Decimal::from_str("100.20").unwrap(),
+            Decimal::percent(50),
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NonPayable {});
+
+        // nothing is changed by the failed migrations
+        assert_eq!(
+            POOL_CONFIG.load(deps.as_ref().storage).unwrap().pool_id,
+            POOL_ID
+        );
+        assert!(MODIFY_RANGE_STATE
+            .may_load(deps.as_ref().storage)
+            .unwrap()
+            .flatten()
+            .is_none());
+    }
+
     #[test]
     fn test_handle_withdraw_position_reply_selects_correct_next_step_for_new_range() {
         let info = mock_info("addr0000", &[]);
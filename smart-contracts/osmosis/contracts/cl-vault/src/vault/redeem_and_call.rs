@@ -0,0 +1,150 @@
+use cosmwasm_std::{Binary, DepsMut, Env, MessageInfo, Response, Uint128};
+
+use crate::{
+    state::{RedeemCallback, CURRENT_WITHDRAWER_CALLBACK},
+    vault::{withdraw::execute_withdraw, withdraw_queue::should_queue_withdraw},
+    ContractError,
+};
+
+/// Withdraw `amount` shares of the sender from the position, the withdraw reply sends the assets
+/// to `contract` in a `ReceiveRedeem` call carrying `msg` instead of a bank send
+pub fn execute_redeem_and_call(
+    mut deps: DepsMut,
+    env: &Env,
+    info: MessageInfo,
+    amount: Uint128,
+    contract: String,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    let contract = deps.api.addr_validate(&contract)?;
+    // a queued redeem is claimed later on, so the contract can only be called on direct withdraws
+    if should_queue_withdraw(deps.as_ref(), env, amount)? {
+        return Err(ContractError::RedeemAndCallQueued {});
+    }
+
+    let owner = info.sender.clone();
+    let response = execute_withdraw(
+        deps.branch(),
+        env,
+        info,
+        Some(contract.to_string()),
+        amount.into(),
+    )?;
+    CURRENT_WITHDRAWER_CALLBACK.save(
+        deps.storage,
+        &RedeemCallback {
+            owner,
+            shares: amount,
+            msg,
+        },
+    )?;
+
+    Ok(response.add_attribute("callback_contract", contract))
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        coin,
+        testing::{mock_env, mock_info},
+        to_json_binary, Addr, Decimal, SubMsgResponse, SubMsgResult,
+    };
+    use osmosis_std::types::osmosis::concentratedliquidity::v1beta1::MsgWithdrawPositionResponse;
+
+    use super::*;
+    use crate::{
+        helpers::generic::sort_tokens,
+        msg::RedeemReceiveMsg,
+        state::{WithdrawQueueConfig, CURRENT_WITHDRAWER_DUST, SHARES, WITHDRAW_QUEUE_CONFIG},
+        test_helpers::{instantiate_contract, mock_deps_with_querier, BASE_DENOM, QUOTE_DENOM},
+        vault::withdraw::handle_withdraw_user_reply,
+    };
+
+    #[test]
+    fn redeem_and_call_sends_the_assets_to_the_contract() {
+        let mut deps = mock_deps_with_querier();
+        let env = mock_env();
+        instantiate_contract(deps.as_mut(), env.clone(), "admin");
+        SHARES
+            .save(
+                deps.as_mut().storage,
+                Addr::unchecked("alice"),
+                &Uint128::new(1000),
+            )
+            .unwrap();
+        let hook = to_json_binary(&"zap").unwrap();
+
+        execute_redeem_and_call(
+            deps.as_mut(),
+            &env,
+            mock_info("alice", &[]),
+            Uint128::new(1000),
+            "zap".to_string(),
+            hook.clone(),
+        )
+        .unwrap();
+
+        let (dust0, dust1) = CURRENT_WITHDRAWER_DUST.load(deps.as_ref().storage).unwrap();
+        let response = handle_withdraw_user_reply(
+            deps.as_mut(),
+            env,
+            SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(
+                    MsgWithdrawPositionResponse {
+                        amount0: "100".to_string(),
+                        amount1: "200".to_string(),
+                    }
+                    .into(),
+                ),
+            }),
+        )
+        .unwrap();
+        let funds = sort_tokens(vec![
+            coin(100 + dust0.u128(), BASE_DENOM),
+            coin(200 + dust1.u128(), QUOTE_DENOM),
+        ]);
+        assert_eq!(
+            response.messages[0].msg,
+            RedeemReceiveMsg {
+                owner: "alice".to_string(),
+                shares: Uint128::new(1000),
+                msg: hook,
+            }
+            .into_cosmos_msg("zap".to_string(), funds)
+            .unwrap()
+        );
+        assert!(CURRENT_WITHDRAWER_CALLBACK
+            .may_load(deps.as_ref().storage)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn redeem_and_call_cannot_be_queued() {
+        let mut deps = mock_deps_with_querier();
+        let env = mock_env();
+        instantiate_contract(deps.as_mut(), env.clone(), "admin");
+        WITHDRAW_QUEUE_CONFIG
+            .save(
+                deps.as_mut().storage,
+                &WithdrawQueueConfig {
+                    share_threshold: Decimal::percent(1),
+                    epoch_seconds: 0,
+                    keeper: None,
+                },
+            )
+            .unwrap();
+
+        let err = execute_redeem_and_call(
+            deps.as_mut(),
+            &env,
+            mock_info("alice", &[]),
+            Uint128::new(50_000),
+            "zap".to_string(),
+            Binary::default(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::RedeemAndCallQueued {});
+    }
+}
@@ -0,0 +1,285 @@
+use cosmwasm_std::{
+    coin, Addr, Api, BankMsg, Decimal, Decimal256, DepsMut, MessageInfo, Response, Storage,
+    Uint128, Uint256,
+};
+use cw_utils::nonpayable;
+
+use crate::{
+    helpers::coinlist::CoinList,
+    state::{
+        Referral, Referrer, REFERRALS, REFERRAL_FEES_RESERVED, REFERRAL_FEE_INDEX, REFERRERS,
+        TOTAL_REFERRED_SHARES, VAULT_CONFIG,
+    },
+    ContractError,
+};
+
+pub fn validate_referral_fee_share(referral_fee_share: Decimal) -> Result<(), ContractError> {
+    if referral_fee_share > Decimal::one() {
+        return Err(ContractError::InvalidReferralFeeShare {});
+    }
+    Ok(())
+}
+
+/// Validate the referrer of a deposit, depositors cannot refer themselves. Since a user stays with
+/// the referrer of their first referred deposit, only the user's own deposits can set a referrer,
+/// otherwise anyone could claim a user with a dust deposit to them.
+pub fn validate_referrer(
+    api: &dyn Api,
+    referrer: Option<String>,
+    depositor: &Addr,
+    recipient: &Addr,
+) -> Result<Option<Addr>, ContractError> {
+    let Some(referrer) = referrer else {
+        return Ok(None);
+    };
+    if depositor != recipient {
+        return Err(ContractError::ReferrerForOtherRecipient {});
+    }
+    let referrer = api.addr_validate(&referrer)?;
+    if referrer == *recipient {
+        return Err(ContractError::SelfReferral {});
+    }
+    Ok(Some(referrer))
+}
+
+/// Attribute `shares` minted to `user` in a deposit referred by `referrer`. A user stays with the
+/// referrer of their first referred deposit, later referred deposits add to that referrer.
+pub fn attribute_referral(
+    storage: &mut dyn Storage,
+    user: &Addr,
+    referrer: &Addr,
+    shares: Uint128,
+) -> Result<(), ContractError> {
+    if shares.is_zero() {
+        return Ok(());
+    }
+    let mut referral = REFERRALS
+        .may_load(storage, user.clone())?
+        .unwrap_or(Referral {
+            referrer: referrer.clone(),
+            shares: Uint128::zero(),
+        });
+    referral.shares = referral.shares.checked_add(shares)?;
+    REFERRALS.save(storage, user.clone(), &referral)?;
+
+    update_referrer_shares(storage, &referral.referrer, |referred| {
+        Ok(referred.checked_add(shares)?)
+    })
+}
+
+/// Cap the shares of `user` attributed to its referrer at the new share balance of the user, so
+/// referrers only earn on shares their users still hold
+pub fn cap_referral(
+    storage: &mut dyn Storage,
+    user: &Addr,
+    balance: Uint128,
+) -> Result<(), ContractError> {
+    let Some(mut referral) = REFERRALS.may_load(storage, user.clone())? else {
+        return Ok(());
+    };
+    if referral.shares <= balance {
+        return Ok(());
+    }
+    let removed = referral.shares - balance;
+    referral.shares = balance;
+    REFERRALS.save(storage, user.clone(), &referral)?;
+
+    update_referrer_shares(storage, &referral.referrer, |referred| {
+        Ok(referred.checked_sub(removed)?)
+    })
+}
+
+fn update_referrer_shares(
+    storage: &mut dyn Storage,
+    referrer: &Addr,
+    action: impl FnOnce(Uint128) -> Result<Uint128, ContractError>,
+) -> Result<(), ContractError> {
+    let mut state = get_settled_referrer(storage, referrer)?;
+    let old = state.shares;
+    state.shares = action(old)?;
+    REFERRERS.save(storage, referrer.clone(), &state)?;
+
+    let total = TOTAL_REFERRED_SHARES
+        .may_load(storage)?
+        .unwrap_or_default()
+        .checked_sub(old)?
+        .checked_add(state.shares)?;
+    TOTAL_REFERRED_SHARES.save(storage, &total)?;
+    Ok(())
+}
+
+/// The state of `referrer` with the fees paid since its last settlement moved into the accrued
+/// fees. Settling before every change of the referred shares keeps the accrued fees exact.
+pub fn get_settled_referrer(
+    storage: &dyn Storage,
+    referrer: &Addr,
+) -> Result<Referrer, ContractError> {
+    let index = REFERRAL_FEE_INDEX.may_load(storage)?.unwrap_or_default();
+    let mut state = REFERRERS
+        .may_load(storage, referrer.clone())?
+        .unwrap_or_default();
+
+    let pending = index
+        .iter()
+        .map(|(denom, per_share)| {
+            let checkpoint = state
+                .index
+                .iter()
+                .find(|(d, _)| d == denom)
+                .map(|(_, value)| *value)
+                .unwrap_or_default();
+            let amount = Uint256::from(state.shares).checked_mul_floor(*per_share - checkpoint)?;
+            Ok(coin(Uint128::try_from(amount)?.u128(), denom))
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+    state.accrued.add(CoinList::from_coins(pending))?;
+    state.index = index;
+    Ok(state)
+}
+
+/// Take the referral fee out of `strategist_fee`. The referral fee is the referral fee share of
+/// the performance fee on the rewards of all referred shares, it is held by the vault and split
+/// among the referrers by their referred shares.
+pub fn take_referral_fee(
+    storage: &mut dyn Storage,
+    strategist_fee: &mut CoinList,
+    total_shares: Uint128,
+) -> Result<CoinList, ContractError> {
+    let referral_fee_share = VAULT_CONFIG.load(storage)?.referral_fee_share;
+    let total_referred = TOTAL_REFERRED_SHARES.may_load(storage)?.unwrap_or_default();
+    if referral_fee_share.is_zero() || total_referred.is_zero() || total_shares.is_zero() {
+        return Ok(CoinList::new());
+    }
+
+    let referred_ratio = Decimal::from_ratio(total_referred.min(total_shares), total_shares);
+    let referral_fee = strategist_fee.sub_ratio(referral_fee_share.checked_mul(referred_ratio)?)?;
+
+    let mut index = REFERRAL_FEE_INDEX.may_load(storage)?.unwrap_or_default();
+    for fee in referral_fee.coins() {
+        let per_share = Decimal256::from_ratio(fee.amount, total_referred);
+        match index.iter_mut().find(|(denom, _)| *denom == fee.denom) {
+            Some((_, value)) => *value = value.checked_add(per_share)?,
+            None => index.push((fee.denom, per_share)),
+        }
+    }
+    REFERRAL_FEE_INDEX.save(storage, &index)?;
+
+    let mut reserved = REFERRAL_FEES_RESERVED
+        .may_load(storage)?
+        .unwrap_or_default();
+    reserved.add(referral_fee.clone())?;
+    REFERRAL_FEES_RESERVED.save(storage, &reserved)?;
+
+    Ok(referral_fee)
+}
+
+/// Send the referral fees accrued by the sender to the sender
+pub fn execute_claim_referral_fees(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+
+    let mut state = get_settled_referrer(deps.storage, &info.sender)?;
+    let fees = state.accrued.coins();
+    if fees.is_empty() {
+        return Err(ContractError::NoReferralFees {});
+    }
+    state.accrued = CoinList::new();
+    REFERRERS.save(deps.storage, info.sender.clone(), &state)?;
+
+    let mut reserved = REFERRAL_FEES_RESERVED.load(deps.storage)?;
+    reserved.sub(&CoinList::from_coins(fees.clone()))?;
+    REFERRAL_FEES_RESERVED.save(deps.storage, &reserved)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "claim_referral_fees")
+        .add_attribute("referrer", info.sender.as_str())
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: fees,
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        testing::{mock_dependencies, mock_info},
+        Coin,
+    };
+
+    use super::*;
+    use crate::test_helpers::get_init_msg;
+
+    #[test]
+    fn referral_fees_are_split_by_referred_shares() {
+        let mut deps = mock_dependencies();
+        let mut vault_config = get_init_msg("admin").config;
+        vault_config.referral_fee_share = Decimal::percent(50);
+        VAULT_CONFIG
+            .save(deps.as_mut().storage, &vault_config)
+            .unwrap();
+        let (alice, bob) = (Addr::unchecked("alice"), Addr::unchecked("bob"));
+        let (partner1, partner2) = (Addr::unchecked("partner1"), Addr::unchecked("partner2"));
+
+        // 400 of 1000 shares are referred, 300 by partner1 and 100 by partner2
+        attribute_referral(deps.as_mut().storage, &alice, &partner1, Uint128::new(300)).unwrap();
+        attribute_referral(deps.as_mut().storage, &bob, &partner2, Uint128::new(100)).unwrap();
+
+        // half of the performance fee on the 40% referred shares goes to the referrers
+        let mut strategist_fee = CoinList::from_coins(vec![coin(1000, "uosmo")]);
+        let referral_fee = take_referral_fee(
+            deps.as_mut().storage,
+            &mut strategist_fee,
+            Uint128::new(1000),
+        )
+        .unwrap();
+        assert_eq!(referral_fee.coins(), vec![coin(200, "uosmo")]);
+        assert_eq!(strategist_fee.coins(), vec![coin(800, "uosmo")]);
+
+        // later deposits of alice stay with partner1, even when referred by another partner
+        attribute_referral(deps.as_mut().storage, &alice, &partner2, Uint128::new(100)).unwrap();
+        // bob redeems half of his shares
+        cap_referral(deps.as_mut().storage, &bob, Uint128::new(50)).unwrap();
+        assert_eq!(
+            TOTAL_REFERRED_SHARES.load(deps.as_ref().storage).unwrap(),
+            Uint128::new(450)
+        );
+
+        let mut strategist_fee = CoinList::from_coins(vec![coin(900, "uosmo")]);
+        take_referral_fee(
+            deps.as_mut().storage,
+            &mut strategist_fee,
+            Uint128::new(900),
+        )
+        .unwrap();
+
+        // partner1: 150 of the first fee, 200 of the 225 of the second
+        let response =
+            execute_claim_referral_fees(deps.as_mut(), mock_info("partner1", &[])).unwrap();
+        assert_eq!(
+            response.messages[0].msg,
+            BankMsg::Send {
+                to_address: "partner1".to_string(),
+                amount: vec![coin(350, "uosmo")]
+            }
+            .into()
+        );
+        let err =
+            execute_claim_referral_fees(deps.as_mut(), mock_info("partner1", &[])).unwrap_err();
+        assert_eq!(err, ContractError::NoReferralFees {});
+
+        // partner2: 50 of the first fee, 25 of the second
+        let partner2_state = get_settled_referrer(deps.as_ref().storage, &partner2).unwrap();
+        assert_eq!(partner2_state.shares, Uint128::new(50));
+        assert_eq!(partner2_state.accrued.coins(), vec![coin(75, "uosmo")]);
+        assert_eq!(
+            REFERRAL_FEES_RESERVED
+                .load(deps.as_ref().storage)
+                .unwrap()
+                .coins(),
+            vec![Coin::new(75, "uosmo")]
+        );
+    }
+}
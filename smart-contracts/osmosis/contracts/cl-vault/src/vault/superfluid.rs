@@ -0,0 +1,880 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use cosmwasm_std::{
+    coin, BankMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Order, QuerierWrapper, Response,
+    StdError, Storage, SubMsg, SubMsgResult, Uint128,
+};
+use cw_utils::nonpayable;
+use osmosis_std::try_proto_to_cosmwasm_coins;
+use osmosis_std::types::osmosis::concentratedliquidity::v1beta1::{
+    ConcentratedliquidityQuerier, MsgWithdrawPosition,
+};
+use osmosis_std::types::osmosis::incentives::{Gauge, IncentivesQuerier};
+use osmosis_std::types::osmosis::lockup::LockupQuerier;
+use osmosis_std::types::osmosis::superfluid::{
+    MsgCreateFullRangePositionAndSuperfluidDelegate,
+    MsgCreateFullRangePositionAndSuperfluidDelegateResponse, MsgSuperfluidUndelegateAndUnbondLock,
+    SuperfluidQuerier,
+};
+
+use crate::{
+    error::{assert_admin, assert_range_admin},
+    helpers::{
+        coinlist::CoinList,
+        generic::sort_tokens,
+        getters::{get_claimable_pool_rewards, get_unused_balances, get_value_wrt_asset0},
+        operation_lock::{acquire_operation_lock, release_operation_lock},
+    },
+    query::{query_total_assets, query_total_vault_token_supply},
+    reply::Replies,
+    state::{
+        SuperfluidConfig, SuperfluidPosition, CURRENT_SUPERFLUID_VALIDATOR, POOL_CONFIG,
+        SUPERFLUID_CONFIG, SUPERFLUID_GAUGE_CHECKPOINTS, SUPERFLUID_POSITIONS, VAULT_CONFIG,
+    },
+    vault::{distribution::update_reward_ledger, referral::take_referral_fee},
+    ContractError,
+};
+
+/// Superfluid staking is only available for pools paired with OSMO
+pub const OSMO_DENOM: &str = "uosmo";
+
+/// Enable superfluid positions, or stop creating new ones if `config` is None. Existing superfluid
+/// positions can still be undelegated and withdrawn after the config is removed.
+pub fn execute_update_superfluid_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: Option<SuperfluidConfig>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    assert_admin(deps.storage, &info.sender)?;
+
+    match &config {
+        Some(config) => {
+            let pool_config = POOL_CONFIG.load(deps.storage)?;
+            if !pool_config.pool_contains_token(OSMO_DENOM) {
+                return Err(ContractError::SuperfluidNotOsmoPaired {});
+            }
+            if config.validators.is_empty() || config.max_ratio >= Decimal::one() {
+                return Err(ContractError::InvalidSuperfluidConfig {});
+            }
+            SUPERFLUID_CONFIG.save(deps.storage, config)?;
+        }
+        None => SUPERFLUID_CONFIG.remove(deps.storage),
+    }
+
+    Ok(Response::default()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "update_superfluid_config")
+        .add_attribute("updates", format!("{:?}", config)))
+}
+
+/// Create a full range position of unused funds and superfluid delegate it to the configured
+/// validator with the fewest superfluid positions. Staking rewards are paid out to the vault by
+/// the chain every epoch and accounted for when rewards are collected.
+pub fn execute_superfluid_delegate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount0: Uint128,
+    amount1: Uint128,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    assert_range_admin(deps.storage, &info.sender)?;
+
+    let config = SUPERFLUID_CONFIG
+        .may_load(deps.storage)?
+        .ok_or(ContractError::SuperfluidDisabled {})?;
+    let pool_config = POOL_CONFIG.load(deps.storage)?;
+    let unused = get_unused_balances(deps.storage, &deps.querier, &env.contract.address)?;
+    if amount0 > unused.find(&pool_config.token0).amount
+        || amount1 > unused.find(&pool_config.token1).amount
+    {
+        return Err(ContractError::InsufficientFunds);
+    }
+
+    // the superfluid positions can not be withdrawn on demand, so they are capped to keep enough
+    // liquid assets for redemptions
+    let total_assets = query_total_assets(deps.as_ref(), env.clone())?;
+    let total_value = get_value_wrt_asset0(
+        deps.storage,
+        &deps.querier,
+        total_assets.token0.amount,
+        total_assets.token1.amount,
+    )?;
+    let superfluid = get_superfluid_assets(deps.storage, &deps.querier)?;
+    let superfluid_value = get_value_wrt_asset0(
+        deps.storage,
+        &deps.querier,
+        superfluid
+            .find(&pool_config.token0)
+            .amount
+            .checked_add(amount0)?,
+        superfluid
+            .find(&pool_config.token1)
+            .amount
+            .checked_add(amount1)?,
+    )?;
+    if superfluid_value > total_value.checked_mul_floor(config.max_ratio)? {
+        return Err(ContractError::SuperfluidRatioExceeded {
+            max_ratio: config.max_ratio,
+        });
+    }
+
+    // account the rewards at the current shares of the gauges before the new position joins one
+    let response = collect_superfluid_rewards(deps.branch())?;
+
+    let validator = next_validator(deps.storage, &config)?;
+    acquire_operation_lock(deps.storage, &env, "superfluid_delegate")?;
+    CURRENT_SUPERFLUID_VALIDATOR.save(deps.storage, &validator)?;
+
+    let coins = sort_tokens(
+        [
+            coin(amount0.u128(), pool_config.token0),
+            coin(amount1.u128(), pool_config.token1),
+        ]
+        .into_iter()
+        .filter(|c| !c.amount.is_zero())
+        .collect(),
+    );
+    if coins.is_empty() {
+        return Err(ContractError::InsufficientFunds);
+    }
+    let delegate_msg = MsgCreateFullRangePositionAndSuperfluidDelegate {
+        sender: env.contract.address.to_string(),
+        coins: coins.into_iter().map(Into::into).collect(),
+        val_addr: validator.clone(),
+        pool_id: pool_config.pool_id,
+    };
+
+    Ok(response
+        .add_submessage(SubMsg::reply_on_success(
+            delegate_msg,
+            Replies::SuperfluidDelegate.into(),
+        ))
+        .add_attribute("method", "execute")
+        .add_attribute("action", "superfluid_delegate")
+        .add_attribute("validator", validator)
+        .add_attribute("amount0", amount0)
+        .add_attribute("amount1", amount1))
+}
+
+/// The configured validator with the fewest superfluid positions of the vault
+fn next_validator(
+    storage: &dyn Storage,
+    config: &SuperfluidConfig,
+) -> Result<String, ContractError> {
+    let positions = SUPERFLUID_POSITIONS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| Ok(item?.1.validator))
+        .collect::<Result<Vec<_>, StdError>>()?;
+    config
+        .validators
+        .iter()
+        .min_by_key(|validator| positions.iter().filter(|v| v == validator).count())
+        .cloned()
+        .ok_or(ContractError::InvalidSuperfluidConfig {})
+}
+
+pub fn handle_superfluid_delegate_reply(
+    deps: DepsMut,
+    data: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let response: MsgCreateFullRangePositionAndSuperfluidDelegateResponse = data.try_into()?;
+    let validator = CURRENT_SUPERFLUID_VALIDATOR.load(deps.storage)?;
+    CURRENT_SUPERFLUID_VALIDATOR.remove(deps.storage);
+    release_operation_lock(deps.storage);
+
+    let gauge_id = SuperfluidQuerier::new(&deps.querier)
+        .connected_intermediary_account(response.lock_id)?
+        .account
+        .ok_or(ContractError::SuperfluidGaugeNotFound {
+            lock_id: response.lock_id,
+        })?
+        .gauge_id;
+    // only rewards the gauge distributes from now on are paid to the new position
+    if SUPERFLUID_GAUGE_CHECKPOINTS
+        .may_load(deps.storage, gauge_id)?
+        .is_none()
+    {
+        let gauge = query_gauge(&deps.querier, gauge_id, response.lock_id)?;
+        SUPERFLUID_GAUGE_CHECKPOINTS.save(
+            deps.storage,
+            gauge_id,
+            &get_distributed_osmo(&gauge)?,
+        )?;
+    }
+
+    SUPERFLUID_POSITIONS.save(
+        deps.storage,
+        response.position_id,
+        &SuperfluidPosition {
+            position_id: response.position_id,
+            lock_id: response.lock_id,
+            validator,
+            gauge_id,
+            unbonding_since: None,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "reply")
+        .add_attribute("action", "handle_superfluid_delegate")
+        .add_attribute("position_id", response.position_id.to_string())
+        .add_attribute("lock_id", response.lock_id.to_string()))
+}
+
+/// Undelegate a superfluid position and start unbonding its lock. The position keeps counting
+/// toward the vault assets until it is withdrawn after the unbonding period.
+pub fn execute_superfluid_undelegate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    position_id: u64,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    assert_range_admin(deps.storage, &info.sender)?;
+
+    let mut position = SUPERFLUID_POSITIONS
+        .may_load(deps.storage, position_id)?
+        .ok_or(ContractError::SuperfluidPositionNotFound { position_id })?;
+    if position.unbonding_since.is_some() {
+        return Err(ContractError::SuperfluidPositionUnbonding { position_id });
+    }
+
+    // the position stops earning staking rewards once it is undelegated
+    let response = collect_superfluid_rewards(deps.branch())?;
+    let undelegate_msg = start_unbonding(deps, &env, &mut position)?;
+
+    Ok(response
+        .add_message(undelegate_msg)
+        .add_attribute("method", "execute")
+        .add_attribute("action", "superfluid_undelegate")
+        .add_attribute("position_id", position_id.to_string())
+        .add_attribute("lock_id", position.lock_id.to_string()))
+}
+
+/// Mark a bonded position as unbonding and build the message undelegating and unbonding its lock.
+/// Its staking rewards are expected to be collected already.
+fn start_unbonding(
+    deps: DepsMut,
+    env: &Env,
+    position: &mut SuperfluidPosition,
+) -> Result<MsgSuperfluidUndelegateAndUnbondLock, ContractError> {
+    let lock = LockupQuerier::new(&deps.querier)
+        .locked_by_id(position.lock_id)?
+        .lock
+        .ok_or(ContractError::SuperfluidPositionNotFound {
+            position_id: position.position_id,
+        })?;
+
+    position.unbonding_since = Some(env.block.time.seconds());
+    SUPERFLUID_POSITIONS.save(deps.storage, position.position_id, position)?;
+    if !get_bonded_locks_by_gauge(deps.storage)?.contains_key(&position.gauge_id) {
+        SUPERFLUID_GAUGE_CHECKPOINTS.remove(deps.storage, position.gauge_id);
+    }
+
+    Ok(MsgSuperfluidUndelegateAndUnbondLock {
+        sender: env.contract.address.to_string(),
+        lock_id: position.lock_id,
+        coin: lock.coins.into_iter().next(),
+    })
+}
+
+/// Undelegate bonded superfluid positions until they are back within the max ratio of the vault
+/// value left once `shares` are redeemed. Redemptions are paid out of the liquid assets only, so
+/// without this the liquid side would run dry while the superfluid positions stay bonded. Without
+/// a superfluid config the max ratio is zero and every bonded position is undelegated.
+pub fn undelegate_over_max_ratio(
+    mut deps: DepsMut,
+    env: &Env,
+    shares: Uint128,
+) -> Result<Response, ContractError> {
+    let mut bonded = SUPERFLUID_POSITIONS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| Ok(item?.1))
+        .collect::<Result<Vec<_>, StdError>>()?;
+    bonded.retain(|position| position.unbonding_since.is_none());
+    let total_supply = query_total_vault_token_supply(deps.as_ref())?.total;
+    if bonded.is_empty() || total_supply.is_zero() {
+        return Ok(Response::new());
+    }
+
+    let max_ratio = SUPERFLUID_CONFIG
+        .may_load(deps.storage)?
+        .map(|config| config.max_ratio)
+        .unwrap_or_default();
+    let total_assets = query_total_assets(deps.as_ref(), env.clone())?;
+    let total_value = get_value_wrt_asset0(
+        deps.storage,
+        &deps.querier,
+        total_assets.token0.amount,
+        total_assets.token1.amount,
+    )?;
+    let remaining_value = total_value
+        .checked_sub(total_value.multiply_ratio(shares.min(total_supply), total_supply))?;
+    let max_value = remaining_value.checked_mul_floor(max_ratio)?;
+
+    let mut bonded_values = vec![];
+    let mut bonded_value = Uint128::zero();
+    for position in bonded {
+        let value = get_superfluid_position_value(deps.as_ref(), position.position_id)?;
+        bonded_value = bonded_value.checked_add(value)?;
+        bonded_values.push((position, value));
+    }
+    if bonded_value <= max_value {
+        return Ok(Response::new());
+    }
+
+    let mut response = collect_superfluid_rewards(deps.branch())?;
+    for (mut position, value) in bonded_values {
+        if bonded_value <= max_value {
+            break;
+        }
+        let undelegate_msg = start_unbonding(deps.branch(), env, &mut position)?;
+        bonded_value = bonded_value.saturating_sub(value);
+        response = response
+            .add_message(undelegate_msg)
+            .add_attribute("undelegated_position_id", position.position_id.to_string());
+    }
+    Ok(response)
+}
+
+/// Withdraw an unbonded superfluid position, its assets become unused funds of the vault. Anyone
+/// can withdraw a position once its lock has unbonded, so redemptions waiting on the unbonding do
+/// not depend on the range admin.
+pub fn execute_superfluid_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    position_id: u64,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+
+    let position = SUPERFLUID_POSITIONS
+        .may_load(deps.storage, position_id)?
+        .ok_or(ContractError::SuperfluidPositionNotFound { position_id })?;
+    if position.unbonding_since.is_none() {
+        return Err(ContractError::SuperfluidPositionNotUnbonding { position_id });
+    }
+    let liquidity = ConcentratedliquidityQuerier::new(&deps.querier)
+        .position_by_id(position_id)?
+        .position
+        .and_then(|breakdown| breakdown.position)
+        .ok_or(ContractError::PositionNotFound)?
+        .liquidity;
+    SUPERFLUID_POSITIONS.remove(deps.storage, position_id);
+
+    // the chain rejects the withdraw while the lock is still unbonding
+    let withdraw_msg = MsgWithdrawPosition {
+        position_id,
+        sender: env.contract.address.to_string(),
+        liquidity_amount: liquidity.clone(),
+    };
+
+    Ok(Response::new()
+        .add_message(withdraw_msg)
+        .add_attribute("method", "execute")
+        .add_attribute("action", "superfluid_withdraw")
+        .add_attribute("position_id", position_id.to_string())
+        .add_attribute("liquidity_amount", liquidity))
+}
+
+/// Account the staking rewards the chain paid to the bonded superfluid positions since the last
+/// collection. Superfluid rewards can not be claimed, every epoch the chain moves the delegation
+/// rewards of each intermediary account into its gauge, which pays them out in OSMO to the bonded
+/// locks in proportion to their size. The vault share of what a gauge distributed since its
+/// checkpoint is already in the unused funds, the performance fee is taken from it like from the
+/// pool rewards.
+pub fn collect_superfluid_rewards(deps: DepsMut) -> Result<Response, ContractError> {
+    let lockup_querier = LockupQuerier::new(&deps.querier);
+    let mut rewards = Uint128::zero();
+    for (gauge_id, lock_ids) in get_bonded_locks_by_gauge(deps.storage)? {
+        let gauge = query_gauge(&deps.querier, gauge_id, lock_ids[0])?;
+        let distributed = get_distributed_osmo(&gauge)?;
+        let paid = distributed.saturating_sub(
+            SUPERFLUID_GAUGE_CHECKPOINTS
+                .may_load(deps.storage, gauge_id)?
+                .unwrap_or(distributed),
+        );
+        if !paid.is_zero() {
+            let condition = gauge
+                .distribute_to
+                .ok_or(ContractError::SuperfluidGaugeNotFound {
+                    lock_id: lock_ids[0],
+                })?;
+            let total_locked = Uint128::from_str(
+                &lockup_querier
+                    .locked_denom(condition.denom, condition.duration)?
+                    .amount,
+            )?;
+            let mut vault_locked = Uint128::zero();
+            for lock_id in lock_ids {
+                let lock = lockup_querier
+                    .locked_by_id(lock_id)?
+                    .lock
+                    .ok_or(ContractError::SuperfluidGaugeNotFound { lock_id })?;
+                for coin in try_proto_to_cosmwasm_coins(lock.coins)? {
+                    vault_locked = vault_locked.checked_add(coin.amount)?;
+                }
+            }
+            if !total_locked.is_zero() {
+                rewards = rewards.checked_add(
+                    paid.multiply_ratio(vault_locked.min(total_locked), total_locked),
+                )?;
+            }
+        }
+        SUPERFLUID_GAUGE_CHECKPOINTS.save(deps.storage, gauge_id, &distributed)?;
+    }
+    if rewards.is_zero() {
+        return Ok(Response::new());
+    }
+
+    let mut rewards_coin_list = CoinList::from_coins(vec![coin(rewards.u128(), OSMO_DENOM)]);
+    let collected = rewards_coin_list.clone();
+    // calculate the strategist fee and remove the share at source
+    let vault_config = VAULT_CONFIG.load(deps.storage)?;
+    let mut strategist_fee = rewards_coin_list.sub_ratio(vault_config.performance_fee)?;
+    let total_shares = query_total_vault_token_supply(deps.as_ref())?.total;
+    let referral_fee = take_referral_fee(deps.storage, &mut strategist_fee, total_shares)?;
+    update_reward_ledger(deps.storage, |ledger| {
+        ledger.staking_rewards.add(collected)?;
+        ledger.strategist_fees.add(strategist_fee.clone())?;
+        ledger.referral_fees.add(referral_fee)
+    })?;
+
+    let mut response = Response::new().add_attribute("collected_staking_rewards", rewards);
+    if !strategist_fee.is_empty() {
+        response = response
+            .add_message(BankMsg::Send {
+                to_address: vault_config.treasury.to_string(),
+                amount: strategist_fee.coins(),
+            })
+            .add_attribute(
+                "staking_strategist_fee",
+                format!("{:?}", strategist_fee.coins()),
+            );
+    }
+    Ok(response)
+}
+
+/// The locks of the bonded superfluid positions, by the gauge paying their staking rewards
+fn get_bonded_locks_by_gauge(
+    storage: &dyn Storage,
+) -> Result<BTreeMap<u64, Vec<u64>>, ContractError> {
+    let mut locks = BTreeMap::<u64, Vec<u64>>::new();
+    for item in SUPERFLUID_POSITIONS.range(storage, None, None, Order::Ascending) {
+        let (_, position) = item?;
+        if position.unbonding_since.is_none() {
+            locks
+                .entry(position.gauge_id)
+                .or_default()
+                .push(position.lock_id);
+        }
+    }
+    Ok(locks)
+}
+
+fn query_gauge(
+    querier: &QuerierWrapper,
+    gauge_id: u64,
+    lock_id: u64,
+) -> Result<Gauge, ContractError> {
+    IncentivesQuerier::new(querier)
+        .gauge_by_id(gauge_id)?
+        .gauge
+        .ok_or(ContractError::SuperfluidGaugeNotFound { lock_id })
+}
+
+/// The OSMO a gauge distributed over its lifetime
+fn get_distributed_osmo(gauge: &Gauge) -> Result<Uint128, ContractError> {
+    Ok(CoinList::from_coins(try_proto_to_cosmwasm_coins(
+        gauge.distributed_coins.clone(),
+    )?)
+    .find(OSMO_DENOM)
+    .amount)
+}
+
+/// The ids of the superfluid positions, their rewards are collected with the range position
+pub fn get_superfluid_position_ids(storage: &dyn Storage) -> Result<Vec<u64>, ContractError> {
+    Ok(SUPERFLUID_POSITIONS
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<Result<_, StdError>>()?)
+}
+
+/// The assets in the superfluid positions, including their claimable pool rewards net of the
+/// performance fee
+pub fn get_superfluid_assets(
+    storage: &dyn Storage,
+    querier: &QuerierWrapper,
+) -> Result<CoinList, ContractError> {
+    let mut assets = CoinList::new();
+    for position_id in get_superfluid_position_ids(storage)? {
+        assets.add(get_superfluid_position_assets(
+            storage,
+            querier,
+            position_id,
+        )?)?;
+    }
+    Ok(assets)
+}
+
+/// The assets in a single superfluid position, including its claimable pool rewards net of the
+/// performance fee
+fn get_superfluid_position_assets(
+    storage: &dyn Storage,
+    querier: &QuerierWrapper,
+    position_id: u64,
+) -> Result<CoinList, ContractError> {
+    let position = ConcentratedliquidityQuerier::new(querier)
+        .position_by_id(position_id)?
+        .position
+        .ok_or(ContractError::PositionNotFound)?;
+    let mut assets = CoinList::from_coins(try_proto_to_cosmwasm_coins(
+        position
+            .asset0
+            .clone()
+            .into_iter()
+            .chain(position.asset1.clone()),
+    )?);
+    assets.add(get_claimable_pool_rewards(storage, &position)?)?;
+    Ok(assets)
+}
+
+/// The value of a single superfluid position in asset0
+fn get_superfluid_position_value(deps: Deps, position_id: u64) -> Result<Uint128, ContractError> {
+    let pool_config = POOL_CONFIG.load(deps.storage)?;
+    let assets = get_superfluid_position_assets(deps.storage, &deps.querier, position_id)?;
+    get_value_wrt_asset0(
+        deps.storage,
+        &deps.querier,
+        assets.find(&pool_config.token0).amount,
+        assets.find(&pool_config.token1).amount,
+    )
+}
+
+/// The share supply the liquid assets, the range position and the unused funds, are split over
+/// on redemption. Superfluid positions can not be withdrawn without unbonding, so redemptions are
+/// paid their full share of the vault value out of the liquid assets, and redemptions above this
+/// supply have to wait in the withdraw queue until the unbonded positions are withdrawn.
+pub fn get_redeemable_supply(
+    deps: Deps,
+    env: &Env,
+    total_supply: Uint128,
+) -> Result<Uint128, ContractError> {
+    if SUPERFLUID_POSITIONS.is_empty(deps.storage) {
+        return Ok(total_supply);
+    }
+    let pool_config = POOL_CONFIG.load(deps.storage)?;
+    let total_assets = query_total_assets(deps, env.clone())?;
+    let total_value = get_value_wrt_asset0(
+        deps.storage,
+        &deps.querier,
+        total_assets.token0.amount,
+        total_assets.token1.amount,
+    )?;
+    if total_value.is_zero() {
+        return Ok(total_supply);
+    }
+    let superfluid = get_superfluid_assets(deps.storage, &deps.querier)?;
+    let superfluid_value = get_value_wrt_asset0(
+        deps.storage,
+        &deps.querier,
+        superfluid.find(&pool_config.token0).amount,
+        superfluid.find(&pool_config.token1).amount,
+    )?;
+
+    Ok(total_supply.multiply_ratio(total_value.checked_sub(superfluid_value)?, total_value))
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_env, mock_info};
+    use cosmwasm_std::{Addr, CosmosMsg};
+
+    use super::*;
+    use crate::{
+        contract::redeem,
+        state::{
+            WithdrawQueueConfig, REWARD_LEDGER, SHARES, WITHDRAW_QUEUE_CONFIG, WITHDRAW_REQUESTS,
+        },
+        test_helpers::{
+            instantiate_contract, mock_deps_with_querier, GAUGE_DISTRIBUTED_OSMO, POSITION_ID,
+        },
+    };
+
+    fn is_undelegate_msg(msg: &CosmosMsg) -> bool {
+        matches!(
+            msg,
+            CosmosMsg::Stargate { type_url, .. }
+                if type_url == MsgSuperfluidUndelegateAndUnbondLock::TYPE_URL
+        )
+    }
+
+    /// Bond the position known to the mock querier as a superfluid position and allow slightly
+    /// more than its current share of the vault value
+    fn setup_bonded_position(deps: DepsMut, env: &Env) {
+        SUPERFLUID_POSITIONS
+            .save(
+                deps.storage,
+                POSITION_ID,
+                &SuperfluidPosition {
+                    position_id: POSITION_ID,
+                    lock_id: 1,
+                    validator: "val".to_string(),
+                    gauge_id: 7,
+                    unbonding_since: None,
+                },
+            )
+            .unwrap();
+        let total_assets = query_total_assets(deps.as_ref(), env.clone()).unwrap();
+        let total_value = get_value_wrt_asset0(
+            deps.storage,
+            &deps.querier,
+            total_assets.token0.amount,
+            total_assets.token1.amount,
+        )
+        .unwrap();
+        let superfluid_value = get_superfluid_position_value(deps.as_ref(), POSITION_ID).unwrap();
+        SUPERFLUID_CONFIG
+            .save(
+                deps.storage,
+                &SuperfluidConfig {
+                    validators: vec!["val".to_string()],
+                    max_ratio: Decimal::from_ratio(superfluid_value, total_value)
+                        * Decimal::percent(101),
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn superfluid_positions_count_toward_assets_but_not_redemptions() {
+        let mut deps = mock_deps_with_querier();
+        let env = mock_env();
+        instantiate_contract(deps.as_mut(), env.clone(), "admin");
+
+        // the test pool is not paired with OSMO
+        let err = execute_update_superfluid_config(
+            deps.as_mut(),
+            mock_info("admin", &[]),
+            Some(SuperfluidConfig {
+                validators: vec!["osmovaloper1".to_string()],
+                max_ratio: Decimal::percent(50),
+            }),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::SuperfluidNotOsmoPaired {});
+
+        let total_supply = query_total_vault_token_supply(deps.as_ref()).unwrap().total;
+        assert_eq!(
+            get_redeemable_supply(deps.as_ref(), &env, total_supply).unwrap(),
+            total_supply
+        );
+        let assets_before = query_total_assets(deps.as_ref(), env.clone()).unwrap();
+
+        // the mock querier only knows the range position, a superfluid position of the same size
+        // holds half of the vault assets in the position
+        SUPERFLUID_POSITIONS
+            .save(
+                deps.as_mut().storage,
+                POSITION_ID,
+                &SuperfluidPosition {
+                    position_id: POSITION_ID,
+                    lock_id: 1,
+                    validator: "osmovaloper1".to_string(),
+                    gauge_id: 1,
+                    unbonding_since: None,
+                },
+            )
+            .unwrap();
+        let superfluid =
+            get_superfluid_assets(deps.as_ref().storage, &deps.as_ref().querier).unwrap();
+        let assets_after = query_total_assets(deps.as_ref(), env.clone()).unwrap();
+        assert_eq!(
+            assets_after.token0.amount,
+            assets_before.token0.amount + superfluid.find(&assets_after.token0.denom).amount
+        );
+
+        let redeemable = get_redeemable_supply(deps.as_ref(), &env, total_supply).unwrap();
+        assert!(redeemable < total_supply);
+
+        // a position has to be unbonding before it can be withdrawn
+        let err = execute_superfluid_withdraw(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            POSITION_ID,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::SuperfluidPositionNotUnbonding {
+                position_id: POSITION_ID
+            }
+        );
+    }
+
+    #[test]
+    fn validators_are_used_in_turn() {
+        let mut deps = mock_deps_with_querier();
+        let config = SuperfluidConfig {
+            validators: vec!["val1".to_string(), "val2".to_string()],
+            max_ratio: Decimal::percent(50),
+        };
+        assert_eq!(
+            next_validator(deps.as_ref().storage, &config).unwrap(),
+            "val1"
+        );
+
+        SUPERFLUID_POSITIONS
+            .save(
+                deps.as_mut().storage,
+                1,
+                &SuperfluidPosition {
+                    position_id: 1,
+                    lock_id: 1,
+                    validator: "val1".to_string(),
+                    gauge_id: 1,
+                    unbonding_since: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            next_validator(deps.as_ref().storage, &config).unwrap(),
+            "val2"
+        );
+    }
+
+    #[test]
+    fn staking_rewards_are_collected_at_the_vault_share_of_the_gauge() {
+        let mut deps = mock_deps_with_querier();
+        let env = mock_env();
+        instantiate_contract(deps.as_mut(), env.clone(), "admin");
+
+        SUPERFLUID_POSITIONS
+            .save(
+                deps.as_mut().storage,
+                POSITION_ID,
+                &SuperfluidPosition {
+                    position_id: POSITION_ID,
+                    lock_id: 1,
+                    validator: "val".to_string(),
+                    gauge_id: 7,
+                    unbonding_since: None,
+                },
+            )
+            .unwrap();
+        SUPERFLUID_GAUGE_CHECKPOINTS
+            .save(deps.as_mut().storage, 7, &Uint128::new(1000))
+            .unwrap();
+
+        // the lock of the vault holds 100 of the 400 locked in the gauge
+        let response = collect_superfluid_rewards(deps.as_mut()).unwrap();
+        let ledger = REWARD_LEDGER.load(deps.as_ref().storage).unwrap();
+        assert_eq!(ledger.staking_rewards.coins(), vec![coin(250, OSMO_DENOM)]);
+        // the performance fee of the test config is 10%
+        assert_eq!(ledger.strategist_fees.coins(), vec![coin(25, OSMO_DENOM)]);
+        assert!(matches!(
+            &response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send { amount, .. }) if amount == &vec![coin(25, OSMO_DENOM)]
+        ));
+        assert_eq!(
+            SUPERFLUID_GAUGE_CHECKPOINTS
+                .load(deps.as_ref().storage, 7)
+                .unwrap(),
+            Uint128::new(GAUGE_DISTRIBUTED_OSMO)
+        );
+
+        // nothing new was distributed since
+        let response = collect_superfluid_rewards(deps.as_mut()).unwrap();
+        assert!(response.messages.is_empty());
+
+        // the gauge is no longer tracked once the vault has no bonded position in it
+        execute_superfluid_undelegate(deps.as_mut(), env, mock_info("admin", &[]), POSITION_ID)
+            .unwrap();
+        assert!(SUPERFLUID_GAUGE_CHECKPOINTS
+            .may_load(deps.as_ref().storage, 7)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn redemptions_undelegate_above_the_max_ratio() {
+        let mut deps = mock_deps_with_querier();
+        let env = mock_env();
+        instantiate_contract(deps.as_mut(), env.clone(), "admin");
+        setup_bonded_position(deps.as_mut(), &env);
+        let total_supply = query_total_vault_token_supply(deps.as_ref()).unwrap().total;
+
+        // the position stays within the max ratio of what is left after a small redemption
+        let response =
+            undelegate_over_max_ratio(deps.as_mut(), &env, total_supply / Uint128::new(1000))
+                .unwrap();
+        assert!(response.messages.is_empty());
+
+        let response =
+            undelegate_over_max_ratio(deps.as_mut(), &env, total_supply / Uint128::new(10))
+                .unwrap();
+        assert!(response
+            .messages
+            .iter()
+            .any(|submsg| is_undelegate_msg(&submsg.msg)));
+        let position = SUPERFLUID_POSITIONS
+            .load(deps.as_ref().storage, POSITION_ID)
+            .unwrap();
+        assert_eq!(position.unbonding_since, Some(env.block.time.seconds()));
+
+        // an unbonding position is not undelegated again
+        let response = undelegate_over_max_ratio(deps.as_mut(), &env, total_supply).unwrap();
+        assert!(response.messages.is_empty());
+    }
+
+    #[test]
+    fn redeems_past_the_liquid_side_are_queued_behind_an_unbond() {
+        let mut deps = mock_deps_with_querier();
+        let env = mock_env();
+        instantiate_contract(deps.as_mut(), env.clone(), "admin");
+        setup_bonded_position(deps.as_mut(), &env);
+        WITHDRAW_QUEUE_CONFIG
+            .save(
+                deps.as_mut().storage,
+                &WithdrawQueueConfig {
+                    share_threshold: Decimal::one(),
+                    epoch_seconds: 0,
+                    keeper: None,
+                },
+            )
+            .unwrap();
+        let total_supply = query_total_vault_token_supply(deps.as_ref()).unwrap().total;
+        SHARES
+            .save(
+                deps.as_mut().storage,
+                Addr::unchecked("user"),
+                &total_supply,
+            )
+            .unwrap();
+
+        let redeemable = get_redeemable_supply(deps.as_ref(), &env, total_supply).unwrap();
+        let response = redeem(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("user", &[]),
+            None,
+            redeemable + Uint128::one(),
+        )
+        .unwrap();
+
+        let request = WITHDRAW_REQUESTS.load(deps.as_ref().storage, 0).unwrap();
+        assert_eq!(request.shares, redeemable + Uint128::one());
+        assert!(response
+            .messages
+            .iter()
+            .any(|submsg| is_undelegate_msg(&submsg.msg)));
+        assert!(SUPERFLUID_POSITIONS
+            .load(deps.as_ref().storage, POSITION_ID)
+            .unwrap()
+            .unbonding_since
+            .is_some());
+    }
+}
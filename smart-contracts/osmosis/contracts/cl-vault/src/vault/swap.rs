@@ -7,7 +7,7 @@ use osmosis_std::types::osmosis::poolmanager::v1beta1::SwapAmountInRoute;
 
 use crate::{
     error::assert_swap_admin,
-    helpers::getters::get_twap_price,
+    helpers::getters::{get_twap_price, get_unused_balances},
     msg::SwapOperation,
     state::{POOL_CONFIG, VAULT_CONFIG},
     ContractError,
@@ -37,10 +37,11 @@ pub fn execute_swap_non_vault_funds(
             return Err(ContractError::InvalidSwapAssets {});
         }
 
-        let token_in_balance = deps
-            .querier
-            .query_balance(env.clone().contract.address, token_in_denom.clone())?
-            .amount;
+        // referral fees are reserved in the reward denoms until they are claimed
+        let token_in_balance =
+            get_unused_balances(deps.storage, &deps.querier, &env.contract.address)?
+                .find(token_in_denom)
+                .amount;
         if token_in_balance.is_zero() {
             return Err(ContractError::InsufficientFundsForSwap {
                 balance: token_in_balance,
@@ -1,6 +1,6 @@
 use cosmwasm_std::{
-    coin, to_json_binary, BankMsg, CosmosMsg, Decimal256, DepsMut, Env, MessageInfo, Response,
-    SubMsg, SubMsgResult, Uint128, Uint256, WasmMsg,
+    coin, to_json_binary, Addr, BankMsg, CosmosMsg, Decimal256, DepsMut, Env, MessageInfo,
+    Response, SubMsg, SubMsgResult, Uint128, Uint256, WasmMsg,
 };
 use osmosis_std::types::osmosis::{
     concentratedliquidity::v1beta1::{MsgWithdrawPosition, MsgWithdrawPositionResponse},
@@ -8,10 +8,25 @@ use osmosis_std::types::osmosis::{
 };
 
 use crate::{
-    helpers::{generic::sort_tokens, getters::get_unused_balances},
+    helpers::{
+        fees::{apply_exit_fee, exit_fee_msg},
+        generic::sort_tokens,
+        getters::get_unused_balances,
+        operation_lock::{acquire_operation_lock, release_operation_lock},
+        shares::update_user_shares,
+    },
+    msg::RedeemReceiveMsg,
     reply::Replies,
-    state::{CURRENT_WITHDRAWER, CURRENT_WITHDRAWER_DUST, POOL_CONFIG, SHARES, VAULT_DENOM},
-    vault::concentrated_liquidity::{get_position, withdraw_from_position},
+    state::{
+        CURRENT_WITHDRAWER, CURRENT_WITHDRAWER_CALLBACK, CURRENT_WITHDRAWER_DUST,
+        CURRENT_WITHDRAWER_IBC, POOL_CONFIG, VAULT_CONFIG, VAULT_DENOM,
+    },
+    vault::{
+        concentrated_liquidity::{get_position, withdraw_from_position},
+        cost_basis::record_withdraw,
+        ibc_redeem::ibc_transfer_msgs,
+        superfluid::{get_redeemable_supply, undelegate_over_max_ratio},
+    },
     ContractError,
 };
 use crate::{
@@ -23,7 +38,7 @@ use crate::{
 // currently that functions as a bandaid
 #[allow(clippy::unnecessary_fallible_conversions)]
 pub fn execute_withdraw(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: &Env,
     info: MessageInfo,
     recipient: Option<String>,
@@ -36,25 +51,41 @@ pub fn execute_withdraw(
 
     let recipient = recipient.map_or(Ok(info.sender.clone()), |x| deps.api.addr_validate(&x))?;
 
-    let vault_denom = VAULT_DENOM.load(deps.storage)?;
-
     // get the sent along shares
     // let shares = must_pay(&info, vault_denom.as_str())?;
 
     // get the amount from SHARES state
-    let user_shares: Uint256 = SHARES.load(deps.storage, info.sender.clone())?.into();
+    let shares_to_withdraw_u128: Uint128 = shares_to_withdraw.try_into()?;
+    let hook_msgs = update_user_shares(deps.storage, env, &info.sender, |user_shares| {
+        user_shares
+            .checked_sub(shares_to_withdraw_u128)
+            .map_err(|_| ContractError::InsufficientFunds)
+    })?;
+    record_withdraw(deps.branch(), env, &info.sender, shares_to_withdraw_u128)?;
+
+    Ok(withdraw_shares(deps, env, recipient, shares_to_withdraw)?.add_submessages(hook_msgs))
+}
 
-    let left_over = user_shares
-        .checked_sub(shares_to_withdraw)
-        .map_err(|_| ContractError::InsufficientFunds)?;
-    SHARES.save(deps.storage, info.sender, &left_over.try_into()?)?;
+/// Burn `shares_to_withdraw` and withdraw the corresponding liquidity and dust to `recipient`.
+/// The shares are expected to be debited from the owner already.
+#[allow(clippy::unnecessary_fallible_conversions)]
+pub(crate) fn withdraw_shares(
+    mut deps: DepsMut,
+    env: &Env,
+    recipient: Addr,
+    shares_to_withdraw: Uint256,
+) -> Result<Response, ContractError> {
+    let undelegate_response =
+        undelegate_over_max_ratio(deps.branch(), env, shares_to_withdraw.try_into()?)?;
+    let vault_denom = VAULT_DENOM.load(deps.storage)?;
 
-    let total_shares: Uint256 = query_total_vault_token_supply(deps.as_ref())?.total.into();
+    let total_supply = query_total_vault_token_supply(deps.as_ref())?.total;
+    let total_shares: Uint256 = get_redeemable_supply(deps.as_ref(), env, total_supply)?.into();
 
     // get the dust amounts belonging to the user
     let pool_config = POOL_CONFIG.load(deps.storage)?;
     // TODO replace dust with queries for balance
-    let unused_balances = get_unused_balances(&deps.querier, &env.contract.address)?;
+    let unused_balances = get_unused_balances(deps.storage, &deps.querier, &env.contract.address)?;
     let dust0: Uint256 = unused_balances.find(&pool_config.token0).amount.into();
     let dust1: Uint256 = unused_balances.find(&pool_config.token1).amount.into();
 
@@ -68,6 +99,7 @@ pub fn execute_withdraw(
         .try_into()?;
     // save the new total amount of dust available for other actions
 
+    acquire_operation_lock(deps.storage, env, "withdraw")?;
     CURRENT_WITHDRAWER_DUST.save(deps.storage, &(user_dust0, user_dust1))?;
 
     let shares_to_withdraw_u128: Uint128 = shares_to_withdraw.try_into()?;
@@ -104,11 +136,14 @@ pub fn execute_withdraw(
         .add_submessage(SubMsg::reply_on_success(
             withdraw_msg,
             Replies::WithdrawUser as u64,
-        )))
+        ))
+        .add_attributes(undelegate_response.attributes)
+        .add_submessages(undelegate_response.messages))
 }
 
 pub fn handle_withdraw_user_reply(
     deps: DepsMut,
+    env: Env,
     data: SubMsgResult,
 ) -> Result<Response, ContractError> {
     // parse the reply and instantiate the funds we want to send
@@ -117,26 +152,59 @@ pub fn handle_withdraw_user_reply(
     let pool_config = POOL_CONFIG.load(deps.storage)?;
 
     let (user_dust0, user_dust1) = CURRENT_WITHDRAWER_DUST.load(deps.storage)?;
+    CURRENT_WITHDRAWER.remove(deps.storage);
+    CURRENT_WITHDRAWER_DUST.remove(deps.storage);
+    release_operation_lock(deps.storage);
     let amount0 = Uint128::new(response.amount0.parse()?).checked_add(user_dust0)?;
     let amount1 = Uint128::new(response.amount1.parse()?).checked_add(user_dust1)?;
 
-    let coin0 = coin(amount0.u128(), pool_config.token0);
-    let coin1 = coin(amount1.u128(), pool_config.token1);
-
-    // send the funds to the user
-    let msg = BankMsg::Send {
-        to_address: user.to_string(),
-        amount: sort_tokens(vec![coin0.clone(), coin1.clone()]),
+    let vault_config = VAULT_CONFIG.load(deps.storage)?;
+    let (payout, fee) = apply_exit_fee(
+        &vault_config,
+        vec![
+            coin(amount0.u128(), pool_config.token0),
+            coin(amount1.u128(), pool_config.token1),
+        ],
+    );
+    let (coin0, coin1) = (payout[0].clone(), payout[1].clone());
+
+    let mut response = if let Some(callback) = CURRENT_WITHDRAWER_CALLBACK.may_load(deps.storage)? {
+        // send the funds to the receiving contract together with the message of the owner
+        CURRENT_WITHDRAWER_CALLBACK.remove(deps.storage);
+        let funds = payout.into_iter().filter(|c| !c.amount.is_zero()).collect();
+        Response::new().add_message(
+            RedeemReceiveMsg {
+                owner: callback.owner.to_string(),
+                shares: callback.shares,
+                msg: callback.msg,
+            }
+            .into_cosmos_msg(user.to_string(), sort_tokens(funds))?,
+        )
+    } else if let Some(destination) = CURRENT_WITHDRAWER_IBC.may_load(deps.storage)? {
+        // send the funds to the user on the destination chain
+        CURRENT_WITHDRAWER_IBC.remove(deps.storage);
+        let transfers = ibc_transfer_msgs(deps.storage, &env, &user, &destination, payout)?;
+        Response::new().add_submessages(transfers)
+    } else {
+        // send the funds to the user
+        Response::new().add_message(BankMsg::Send {
+            to_address: user.to_string(),
+            amount: sort_tokens(payout),
+        })
     };
-    Ok(Response::new()
-        .add_message(msg)
+    if let Some(fee_msg) = exit_fee_msg(&vault_config, fee.clone()) {
+        response = response.add_message(fee_msg);
+    }
+    Ok(response
         .add_attribute("method", "reply")
         .add_attribute("action", "handle_withdraw_user")
-        .add_attribute("amount0", coin0.clone().amount)
-        .add_attribute("amount1", coin1.clone().amount))
+        .add_attribute("amount0", coin0.amount)
+        .add_attribute("amount1", coin1.amount)
+        .add_attribute("exit_fee0", fee[0].amount)
+        .add_attribute("exit_fee1", fee[1].amount))
 }
 
-fn withdraw_msg(
+pub(crate) fn withdraw_msg(
     deps: DepsMut,
     env: &Env,
     user_shares: Uint128,
@@ -149,6 +217,10 @@ fn withdraw_msg(
         .parse()?;
 
     let total_supply = query_total_vault_token_supply(deps.as_ref())?.total;
+    let total_supply = get_redeemable_supply(deps.as_ref(), env, total_supply)?;
+    if user_shares > total_supply {
+        return Err(ContractError::InsufficientLiquidAssets {});
+    }
 
     let user_liquidity = Decimal256::from_ratio(user_shares, 1_u128)
         .checked_mul(existing_liquidity)?
@@ -160,9 +232,10 @@ fn withdraw_msg(
 #[cfg(test)]
 mod tests {
     use crate::{
-        state::PoolConfig,
+        state::{PoolConfig, SHARES},
         test_helpers::{
-            instantiate_contract, mock_deps_with_querier_with_balance, BASE_DENOM, QUOTE_DENOM,
+            get_init_msg, instantiate_contract, mock_deps_with_querier_with_balance, BASE_DENOM,
+            QUOTE_DENOM,
         },
     };
     use cosmwasm_std::{
@@ -230,6 +303,12 @@ mod tests {
             )
             .unwrap();
 
+        let mut vault_config = get_init_msg("admin").config;
+        vault_config.exit_fee_bps = 100;
+        VAULT_CONFIG
+            .save(deps.as_mut().storage, &vault_config)
+            .unwrap();
+
         let msg = MsgWithdrawPositionResponse {
             amount0: "1000".to_string(),
             amount1: "1000".to_string(),
@@ -238,18 +317,27 @@ mod tests {
 
         let response = handle_withdraw_user_reply(
             deps.as_mut(),
+            mock_env(),
             SubMsgResult::Ok(SubMsgResponse {
                 events: vec![],
                 data: Some(msg),
             }),
         )
         .unwrap();
+        // 1% of the withdrawn amounts and dust is taken as exit fee
         assert_eq!(
             response.messages[0].msg,
             CosmosMsg::Bank(BankMsg::Send {
                 to_address: to_address.to_string(),
-                amount: sort_tokens(vec![coin(1123, "uosmo"), coin(1234, "uatom")])
+                amount: sort_tokens(vec![coin(1112, "uosmo"), coin(1222, "uatom")])
             })
-        )
+        );
+        assert_eq!(
+            response.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: vault_config.treasury.to_string(),
+                amount: sort_tokens(vec![coin(11, "uosmo"), coin(12, "uatom")])
+            })
+        );
     }
 }
@@ -0,0 +1,641 @@
+use cosmwasm_std::{
+    coin, BankMsg, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, SubMsg,
+    SubMsgResult, Uint128, Uint256,
+};
+use cw_utils::nonpayable;
+use osmosis_std::types::osmosis::{
+    concentratedliquidity::v1beta1::MsgWithdrawPositionResponse, tokenfactory::v1beta1::MsgBurn,
+};
+use quasar_types::queue::{dequeue, enqueue};
+
+use crate::{
+    error::assert_withdraw_queue_keeper,
+    helpers::{
+        coinlist::CoinList,
+        fees::{apply_exit_fee, exit_fee_msg},
+        generic::sort_tokens,
+        getters::get_unused_balances,
+        shares::update_user_shares,
+    },
+    query::query_total_vault_token_supply,
+    reply::Replies,
+    state::{
+        PoolConfig, WithdrawBatch, WithdrawRequest, CURRENT_WITHDRAW_BATCH, CURRENT_WITHDRAW_EPOCH,
+        POOL_CONFIG, VAULT_CONFIG, VAULT_DENOM, WITHDRAW_BATCHES, WITHDRAW_QUEUE,
+        WITHDRAW_QUEUE_CONFIG, WITHDRAW_QUEUE_RESERVED, WITHDRAW_REQUESTS, WITHDRAW_REQUEST_COUNT,
+    },
+    vault::{
+        cost_basis::record_withdraw,
+        superfluid::{get_redeemable_supply, undelegate_over_max_ratio},
+        withdraw::withdraw_msg,
+    },
+    ContractError,
+};
+
+/// The amount of requests processed in a single batch if no limit is given
+pub const DEFAULT_BATCH_LIMIT: u32 = 50;
+pub const MAX_BATCH_LIMIT: u32 = 200;
+
+/// Returns true if a redemption of `shares` exceeds the configured share threshold, or more than
+/// the liquid assets can pay while superfluid positions unbond, and should be queued instead of
+/// being withdrawn from the position directly
+pub fn should_queue_withdraw(
+    deps: Deps,
+    env: &Env,
+    shares: Uint128,
+) -> Result<bool, ContractError> {
+    let Some(config) = WITHDRAW_QUEUE_CONFIG.may_load(deps.storage)? else {
+        return Ok(false);
+    };
+    let total_shares = query_total_vault_token_supply(deps)?.total;
+    if shares > total_shares.checked_mul_floor(config.share_threshold)? {
+        return Ok(true);
+    }
+
+    Ok(shares > get_redeemable_supply(deps, env, total_shares)?)
+}
+
+/// Escrow the shares of the sender and append a withdraw request to the withdraw queue.
+/// The shares are debited from the user right away, so they can not be redeemed twice,
+/// but are only burned once the request is processed in a batch.
+pub fn execute_queue_withdraw(
+    mut deps: DepsMut,
+    env: &Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+    shares: Uint128,
+) -> Result<Response, ContractError> {
+    if shares.is_zero() {
+        return Err(ContractError::InsufficientFunds);
+    }
+    let recipient = recipient.map_or(Ok(info.sender.clone()), |x| deps.api.addr_validate(&x))?;
+
+    let hook_msgs = update_user_shares(deps.storage, env, &info.sender, |user_shares| {
+        user_shares
+            .checked_sub(shares)
+            .map_err(|_| ContractError::InsufficientFunds)
+    })?;
+    record_withdraw(deps.branch(), env, &info.sender, shares)?;
+    // start unbonding now, so the liquid assets cover the request by the time it is processed
+    let undelegate_response = undelegate_over_max_ratio(deps.branch(), env, shares)?;
+
+    let id = WITHDRAW_REQUEST_COUNT
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    WITHDRAW_REQUEST_COUNT.save(deps.storage, &(id + 1))?;
+
+    WITHDRAW_REQUESTS.save(
+        deps.storage,
+        id,
+        &WithdrawRequest {
+            id,
+            owner: info.sender.clone(),
+            recipient: recipient.clone(),
+            shares,
+            created_at: env.block.time.seconds(),
+            epoch: None,
+        },
+    )?;
+    enqueue(deps.branch(), id, WITHDRAW_QUEUE)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "queue_withdraw")
+        .add_attribute("request_id", id.to_string())
+        .add_attribute("owner", info.sender)
+        .add_attribute("recipient", recipient)
+        .add_attribute("share_amount", shares)
+        .add_attributes(undelegate_response.attributes)
+        .add_submessages(undelegate_response.messages)
+        .add_submessages(hook_msgs))
+}
+
+/// Process up to `limit` queued withdraw requests as a single batch. The liquidity of all
+/// requests is withdrawn from the position in one message and the batch settles in the reply,
+/// after which the owners of the requests can claim their part of the batch.
+pub fn execute_process_withdraw_queue(
+    mut deps: DepsMut,
+    env: &Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+    assert_withdraw_queue_keeper(deps.storage, &info.sender)?;
+
+    let config = WITHDRAW_QUEUE_CONFIG.load(deps.storage)?;
+    let epoch = CURRENT_WITHDRAW_EPOCH
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    if epoch > 0 {
+        let last_batch = WITHDRAW_BATCHES.load(deps.storage, epoch - 1)?;
+        let next_batch_time = last_batch.processed_at + config.epoch_seconds;
+        if env.block.time.seconds() < next_batch_time {
+            return Err(ContractError::WithdrawEpochNotEnded { next_batch_time });
+        }
+    }
+
+    let limit = limit.unwrap_or(DEFAULT_BATCH_LIMIT).min(MAX_BATCH_LIMIT);
+    let mut batch_shares = Uint128::zero();
+    let mut request_ids = vec![];
+    for _ in 0..limit {
+        let Some(id) = dequeue(deps.branch(), WITHDRAW_QUEUE) else {
+            break;
+        };
+        let mut request = WITHDRAW_REQUESTS.load(deps.storage, id)?;
+        request.epoch = Some(epoch);
+        WITHDRAW_REQUESTS.save(deps.storage, id, &request)?;
+
+        batch_shares = batch_shares.checked_add(request.shares)?;
+        request_ids.push(id);
+    }
+
+    if batch_shares.is_zero() {
+        return Err(ContractError::EmptyWithdrawQueue {});
+    }
+
+    // the batch gets its part of the unused balances, same as a regular withdraw
+    let pool_config = POOL_CONFIG.load(deps.storage)?;
+    let total_supply = query_total_vault_token_supply(deps.as_ref())?.total;
+    let total_shares: Uint256 = get_redeemable_supply(deps.as_ref(), env, total_supply)?.into();
+    let unused_balances = get_unused_balances(deps.storage, &deps.querier, &env.contract.address)?;
+    let dust0: Uint128 = Uint256::from(unused_balances.find(&pool_config.token0).amount)
+        .checked_mul(batch_shares.into())?
+        .checked_div(total_shares)?
+        .try_into()?;
+    let dust1: Uint128 = Uint256::from(unused_balances.find(&pool_config.token1).amount)
+        .checked_mul(batch_shares.into())?
+        .checked_div(total_shares)?
+        .try_into()?;
+
+    WITHDRAW_BATCHES.save(
+        deps.storage,
+        epoch,
+        &WithdrawBatch {
+            epoch,
+            total_shares: batch_shares,
+            processed_at: env.block.time.seconds(),
+            amount0: dust0,
+            amount1: dust1,
+            settled: false,
+            claimed_shares: Uint128::zero(),
+            claimed_amount0: Uint128::zero(),
+            claimed_amount1: Uint128::zero(),
+        },
+    )?;
+    CURRENT_WITHDRAW_EPOCH.save(deps.storage, &(epoch + 1))?;
+    CURRENT_WITHDRAW_BATCH.save(deps.storage, &epoch)?;
+
+    let vault_denom = VAULT_DENOM.load(deps.storage)?;
+    let burn_msg: CosmosMsg = MsgBurn {
+        sender: env.contract.address.to_string(),
+        amount: Some(coin(batch_shares.u128(), vault_denom).into()),
+        burn_from_address: env.contract.address.to_string(),
+    }
+    .into();
+
+    let withdraw_msg = withdraw_msg(deps.branch(), env, batch_shares)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "process_withdraw_queue")
+        .add_attribute("epoch", epoch.to_string())
+        .add_attribute("request_ids", format!("{:?}", request_ids))
+        .add_attribute("share_amount", batch_shares)
+        .add_attribute("liquidity_amount", withdraw_msg.liquidity_amount.as_str())
+        .add_message(burn_msg)
+        .add_submessage(SubMsg::reply_on_success(
+            withdraw_msg,
+            Replies::WithdrawQueueBatch as u64,
+        )))
+}
+
+pub fn handle_withdraw_queue_batch_reply(
+    deps: DepsMut,
+    data: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let response: MsgWithdrawPositionResponse = data.try_into()?;
+    let epoch = CURRENT_WITHDRAW_BATCH.load(deps.storage)?;
+    CURRENT_WITHDRAW_BATCH.remove(deps.storage);
+
+    let mut batch = WITHDRAW_BATCHES.load(deps.storage, epoch)?;
+    batch.amount0 = batch
+        .amount0
+        .checked_add(Uint128::new(response.amount0.parse()?))?;
+    batch.amount1 = batch
+        .amount1
+        .checked_add(Uint128::new(response.amount1.parse()?))?;
+    batch.settled = true;
+    WITHDRAW_BATCHES.save(deps.storage, epoch, &batch)?;
+
+    // the batch assets stay in the contract until claimed, so they are reserved for the requests
+    let pool_config = POOL_CONFIG.load(deps.storage)?;
+    let mut reserved = WITHDRAW_QUEUE_RESERVED
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    reserved.merge(vec![
+        coin(batch.amount0.u128(), pool_config.token0),
+        coin(batch.amount1.u128(), pool_config.token1),
+    ])?;
+    WITHDRAW_QUEUE_RESERVED.save(deps.storage, &reserved)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "reply")
+        .add_attribute("action", "handle_withdraw_queue_batch")
+        .add_attribute("epoch", epoch.to_string())
+        .add_attribute("amount0", batch.amount0)
+        .add_attribute("amount1", batch.amount1))
+}
+
+/// Send the assets of a settled withdraw request to the recipient of that request
+pub fn execute_claim_queued_withdraw(
+    deps: DepsMut,
+    info: MessageInfo,
+    request_id: u64,
+) -> Result<Response, ContractError> {
+    nonpayable(&info).map_err(|_| ContractError::NonPayable {})?;
+
+    let request = WITHDRAW_REQUESTS
+        .may_load(deps.storage, request_id)?
+        .ok_or(ContractError::WithdrawRequestNotFound { id: request_id })?;
+    if request.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut batch = request
+        .epoch
+        .map(|epoch| WITHDRAW_BATCHES.load(deps.storage, epoch))
+        .transpose()?
+        .filter(|batch| batch.settled)
+        .ok_or(ContractError::WithdrawRequestNotSettled { id: request_id })?;
+
+    let pool_config = POOL_CONFIG.load(deps.storage)?;
+    let assets = get_request_assets(&batch, request.shares, &pool_config)?;
+    WITHDRAW_REQUESTS.remove(deps.storage, request_id);
+
+    batch.claimed_shares = batch.claimed_shares.checked_add(request.shares)?;
+    batch.claimed_amount0 = batch.claimed_amount0.checked_add(assets[0].amount)?;
+    batch.claimed_amount1 = batch.claimed_amount1.checked_add(assets[1].amount)?;
+    let mut released = CoinList::from_coins(assets.clone());
+    if batch.claimed_shares == batch.total_shares {
+        // the rounding dust of the last claimed batch goes back to the vault
+        released.merge(vec![
+            coin(
+                batch.amount0.checked_sub(batch.claimed_amount0)?.u128(),
+                pool_config.token0.clone(),
+            ),
+            coin(
+                batch.amount1.checked_sub(batch.claimed_amount1)?.u128(),
+                pool_config.token1.clone(),
+            ),
+        ])?;
+    }
+    WITHDRAW_BATCHES.save(deps.storage, batch.epoch, &batch)?;
+
+    let mut reserved = WITHDRAW_QUEUE_RESERVED.load(deps.storage)?;
+    reserved.sub(&released)?;
+    WITHDRAW_QUEUE_RESERVED.save(deps.storage, &reserved)?;
+
+    // the exit fee of a queued withdraw is taken on claim, a fee that stays in the vault is
+    // released from the reserved assets with the rest of the request
+    let vault_config = VAULT_CONFIG.load(deps.storage)?;
+    let (payout, fee) = apply_exit_fee(&vault_config, assets);
+
+    let mut response = Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "claim_queued_withdraw")
+        .add_attribute("request_id", request_id.to_string())
+        .add_attribute("recipient", request.recipient.as_str())
+        .add_attribute("amount0", payout[0].amount)
+        .add_attribute("amount1", payout[1].amount);
+
+    let coins: Vec<Coin> = payout.into_iter().filter(|c| !c.amount.is_zero()).collect();
+    if !coins.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: request.recipient.to_string(),
+            amount: sort_tokens(coins),
+        });
+    }
+    if let Some(fee_msg) = exit_fee_msg(&vault_config, fee) {
+        response = response.add_message(fee_msg);
+    }
+
+    Ok(response)
+}
+
+/// The assets a request of `shares` is entitled to out of a settled batch, as [token0, token1]
+pub fn get_request_assets(
+    batch: &WithdrawBatch,
+    shares: Uint128,
+    pool_config: &PoolConfig,
+) -> Result<Vec<Coin>, ContractError> {
+    let amount0 = batch
+        .amount0
+        .checked_multiply_ratio(shares, batch.total_shares)?;
+    let amount1 = batch
+        .amount1
+        .checked_multiply_ratio(shares, batch.total_shares)?;
+
+    Ok(vec![
+        coin(amount0.u128(), pool_config.token0.clone()),
+        coin(amount1.u128(), pool_config.token1.clone()),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR},
+        Addr, Decimal, SubMsgResponse,
+    };
+
+    use crate::{
+        state::{WithdrawQueueConfig, ADMIN_ADDRESS, SHARES},
+        test_helpers::{
+            instantiate_contract, mock_deps_with_querier_with_balance, BASE_DENOM, QUOTE_DENOM,
+        },
+    };
+
+    use super::*;
+
+    const ADMIN: &str = "admin";
+    const USER: &str = "alice";
+
+    fn setup_queue() -> cosmwasm_std::OwnedDeps<
+        cosmwasm_std::testing::MockStorage,
+        cosmwasm_std::testing::MockApi,
+        crate::test_helpers::QuasarQuerier,
+    > {
+        let mut deps = mock_deps_with_querier_with_balance(
+            100_000,
+            100_000,
+            0,
+            100,
+            1000,
+            &[(
+                MOCK_CONTRACT_ADDR,
+                &[coin(2000, BASE_DENOM), coin(3000, QUOTE_DENOM)],
+            )],
+        );
+        instantiate_contract(deps.as_mut(), mock_env(), ADMIN);
+        WITHDRAW_QUEUE_CONFIG
+            .save(
+                deps.as_mut().storage,
+                &WithdrawQueueConfig {
+                    share_threshold: Decimal::percent(10),
+                    epoch_seconds: 3600,
+                    keeper: Some(Addr::unchecked("keeper")),
+                },
+            )
+            .unwrap();
+        SHARES
+            .save(
+                deps.as_mut().storage,
+                Addr::unchecked(USER),
+                &Uint128::new(50_000),
+            )
+            .unwrap();
+        deps
+    }
+
+    #[test]
+    fn should_queue_withdraw_respects_threshold() {
+        let mut deps = setup_queue();
+
+        // the mocked total supply is 100_000, so the threshold is 10_000 shares
+        let env = mock_env();
+        assert!(!should_queue_withdraw(deps.as_ref(), &env, Uint128::new(10_000)).unwrap());
+        assert!(should_queue_withdraw(deps.as_ref(), &env, Uint128::new(10_001)).unwrap());
+
+        WITHDRAW_QUEUE_CONFIG.remove(deps.as_mut().storage);
+        assert!(!should_queue_withdraw(deps.as_ref(), &env, Uint128::new(50_000)).unwrap());
+    }
+
+    #[test]
+    fn queue_process_and_claim_works() {
+        let mut deps = setup_queue();
+        let env = mock_env();
+
+        execute_queue_withdraw(
+            deps.as_mut(),
+            &env,
+            mock_info(USER, &[]),
+            None,
+            Uint128::new(20_000),
+        )
+        .unwrap();
+        execute_queue_withdraw(
+            deps.as_mut(),
+            &env,
+            mock_info(USER, &[]),
+            Some("bob".to_string()),
+            Uint128::new(30_000),
+        )
+        .unwrap();
+
+        // shares are escrowed right away
+        assert_eq!(
+            SHARES
+                .load(deps.as_ref().storage, Addr::unchecked(USER))
+                .unwrap(),
+            Uint128::zero()
+        );
+
+        // only the admin or keeper can process the queue
+        let err = execute_process_withdraw_queue(deps.as_mut(), &env, mock_info(USER, &[]), None)
+            .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let res =
+            execute_process_withdraw_queue(deps.as_mut(), &env, mock_info("keeper", &[]), None)
+                .unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert!(WITHDRAW_QUEUE.is_empty(deps.as_ref().storage));
+
+        // half of the supply is withdrawn, so the batch gets half of the dust
+        let batch = WITHDRAW_BATCHES.load(deps.as_ref().storage, 0).unwrap();
+        assert_eq!(batch.total_shares, Uint128::new(50_000));
+        assert_eq!(batch.amount0, Uint128::new(1000));
+        assert_eq!(batch.amount1, Uint128::new(1500));
+        assert!(!batch.settled);
+
+        // claiming before the batch settled fails
+        let err =
+            execute_claim_queued_withdraw(deps.as_mut(), mock_info(USER, &[]), 0).unwrap_err();
+        assert_eq!(err, ContractError::WithdrawRequestNotSettled { id: 0 });
+
+        handle_withdraw_queue_batch_reply(
+            deps.as_mut(),
+            SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(
+                    MsgWithdrawPositionResponse {
+                        amount0: "49000".to_string(),
+                        amount1: "48500".to_string(),
+                    }
+                    .into(),
+                ),
+            }),
+        )
+        .unwrap();
+        assert!(CURRENT_WITHDRAW_BATCH
+            .may_load(deps.as_ref().storage)
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            WITHDRAW_QUEUE_RESERVED
+                .load(deps.as_ref().storage)
+                .unwrap()
+                .coins(),
+            sort_tokens(vec![coin(50_000, BASE_DENOM), coin(50_000, QUOTE_DENOM)])
+        );
+
+        let res = execute_claim_queued_withdraw(deps.as_mut(), mock_info(USER, &[]), 1).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "bob".to_string(),
+                amount: sort_tokens(vec![coin(30_000, BASE_DENOM), coin(30_000, QUOTE_DENOM)]),
+            })
+        );
+        assert!(WITHDRAW_REQUESTS
+            .may_load(deps.as_ref().storage, 1)
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            WITHDRAW_QUEUE_RESERVED
+                .load(deps.as_ref().storage)
+                .unwrap()
+                .coins(),
+            sort_tokens(vec![coin(20_000, BASE_DENOM), coin(20_000, QUOTE_DENOM)])
+        );
+
+        // a request can only be claimed once
+        let err =
+            execute_claim_queued_withdraw(deps.as_mut(), mock_info(USER, &[]), 1).unwrap_err();
+        assert_eq!(err, ContractError::WithdrawRequestNotFound { id: 1 });
+    }
+
+    #[test]
+    fn last_claim_releases_batch_dust() {
+        let mut deps = setup_queue();
+        WITHDRAW_BATCHES
+            .save(
+                deps.as_mut().storage,
+                0,
+                &WithdrawBatch {
+                    epoch: 0,
+                    total_shares: Uint128::new(3),
+                    processed_at: 0,
+                    amount0: Uint128::new(100),
+                    amount1: Uint128::new(200),
+                    settled: true,
+                    claimed_shares: Uint128::zero(),
+                    claimed_amount0: Uint128::zero(),
+                    claimed_amount1: Uint128::zero(),
+                },
+            )
+            .unwrap();
+        for id in 0..3 {
+            WITHDRAW_REQUESTS
+                .save(
+                    deps.as_mut().storage,
+                    id,
+                    &WithdrawRequest {
+                        id,
+                        owner: Addr::unchecked(USER),
+                        recipient: Addr::unchecked(USER),
+                        shares: Uint128::one(),
+                        created_at: 0,
+                        epoch: Some(0),
+                    },
+                )
+                .unwrap();
+        }
+        WITHDRAW_QUEUE_RESERVED
+            .save(
+                deps.as_mut().storage,
+                &CoinList::from_coins(vec![coin(100, BASE_DENOM), coin(200, QUOTE_DENOM)]),
+            )
+            .unwrap();
+
+        for id in 0..2 {
+            execute_claim_queued_withdraw(deps.as_mut(), mock_info(USER, &[]), id).unwrap();
+        }
+        assert_eq!(
+            WITHDRAW_QUEUE_RESERVED
+                .load(deps.as_ref().storage)
+                .unwrap()
+                .coins(),
+            sort_tokens(vec![coin(34, BASE_DENOM), coin(68, QUOTE_DENOM)])
+        );
+
+        // every request is paid its rounded down share, the dust is released with the last claim
+        let res = execute_claim_queued_withdraw(deps.as_mut(), mock_info(USER, &[]), 2).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: USER.to_string(),
+                amount: sort_tokens(vec![coin(33, BASE_DENOM), coin(66, QUOTE_DENOM)]),
+            })
+        );
+        assert!(WITHDRAW_QUEUE_RESERVED
+            .load(deps.as_ref().storage)
+            .unwrap()
+            .coins()
+            .is_empty());
+    }
+
+    #[test]
+    fn process_withdraw_queue_respects_epoch() {
+        let mut deps = setup_queue();
+        let mut env = mock_env();
+        ADMIN_ADDRESS
+            .save(deps.as_mut().storage, &Addr::unchecked(ADMIN))
+            .unwrap();
+
+        let err = execute_process_withdraw_queue(deps.as_mut(), &env, mock_info(ADMIN, &[]), None)
+            .unwrap_err();
+        assert_eq!(err, ContractError::EmptyWithdrawQueue {});
+
+        for _ in 0..2 {
+            execute_queue_withdraw(
+                deps.as_mut(),
+                &env,
+                mock_info(USER, &[]),
+                None,
+                Uint128::new(20_000),
+            )
+            .unwrap();
+        }
+
+        execute_process_withdraw_queue(deps.as_mut(), &env, mock_info(ADMIN, &[]), Some(1))
+            .unwrap();
+        CURRENT_WITHDRAW_BATCH.remove(deps.as_mut().storage);
+        assert_eq!(
+            WITHDRAW_REQUESTS
+                .load(deps.as_ref().storage, 1)
+                .unwrap()
+                .epoch,
+            None
+        );
+
+        let err = execute_process_withdraw_queue(deps.as_mut(), &env, mock_info(ADMIN, &[]), None)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::WithdrawEpochNotEnded {
+                next_batch_time: env.block.time.seconds() + 3600
+            }
+        );
+
+        env.block.time = env.block.time.plus_seconds(3600);
+        execute_process_withdraw_queue(deps.as_mut(), &env, mock_info(ADMIN, &[]), None).unwrap();
+        assert_eq!(
+            WITHDRAW_REQUESTS
+                .load(deps.as_ref().storage, 1)
+                .unwrap()
+                .epoch,
+            Some(1)
+        );
+    }
+}
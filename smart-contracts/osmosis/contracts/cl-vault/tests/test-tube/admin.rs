@@ -116,11 +116,14 @@ fn admin_execute_auto_claim_works() {
             contract_address.as_str(),
             &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Admin(
                 AdminExtensionExecuteMsg::AutoWithdraw {
-                    users: users
-                        .clone()
-                        .into_iter()
-                        .map(|(u, a)| (u.to_string(), a))
-                        .collect(),
+                    users: Some(
+                        users
+                            .clone()
+                            .into_iter()
+                            .map(|(u, a)| (u.to_string(), a))
+                            .collect(),
+                    ),
+                    limit: None,
                 },
             )),
             &[],
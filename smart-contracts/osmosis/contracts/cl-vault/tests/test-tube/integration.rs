@@ -7,3 +7,4 @@ mod deposit_withdraw;
 mod initialize;
 mod range;
 mod rewards;
+mod superfluid;
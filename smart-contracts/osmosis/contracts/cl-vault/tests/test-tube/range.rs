@@ -1,6 +1,6 @@
 use crate::setup::{
-    fixture_dex_router, init_test_contract, ADMIN_BALANCE_AMOUNT, DENOM_BASE, DENOM_QUOTE,
-    MAX_SLIPPAGE_HIGH, PERFORMANCE_FEE_DEFAULT,
+    fixture_default, fixture_dex_router, init_test_contract, ADMIN_BALANCE_AMOUNT, DENOM_BASE,
+    DENOM_QUOTE, MAX_SLIPPAGE_HIGH, PERFORMANCE_FEE_DEFAULT, SPREAD_FACTOR_HIGH,
 };
 
 use cosmwasm_std::{coin, Coin, Decimal, Uint128};
@@ -9,20 +9,24 @@ use osmosis_std::types::{
     osmosis::{
         concentratedliquidity::{
             poolmodel::concentrated::v1beta1::MsgCreateConcentratedPool,
-            v1beta1::{MsgCreatePosition, Pool, PoolsRequest, PositionByIdRequest},
+            v1beta1::{
+                CreateConcentratedLiquidityPoolsProposal, MsgCreatePosition, Pool, PoolRecord,
+                PoolsRequest, PositionByIdRequest,
+            },
         },
         poolmanager::v1beta1::SwapAmountInRoute,
     },
 };
-use osmosis_test_tube::{Account, ConcentratedLiquidity, Module, Wasm};
+use osmosis_test_tube::{Account, ConcentratedLiquidity, GovWithAppAccess, Module, Wasm};
 use prost::Message;
 use std::str::FromStr;
 
 use cl_vault::{
     msg::{
-        ClQueryMsg, ExecuteMsg, ExtensionExecuteMsg, ExtensionQueryMsg, ModifyRangeMsg, QueryMsg,
+        AdminExtensionExecuteMsg, ClQueryMsg, ExecuteMsg, ExtensionExecuteMsg, ExtensionQueryMsg,
+        ModifyRangeMsg, QueryMsg,
     },
-    query::PositionResponse,
+    query::{PoolResponse, PositionResponse},
 };
 
 const DO_SWAP_DEPOSIT_TOKEN_IN_OFFSET: usize = 1;
@@ -500,3 +504,129 @@ fn test_swap_math_poc() {
     };
     let _position = cl.create_position(initial_position, &alice).unwrap();
 }
+
+#[test]
+fn migrate_pool_works() {
+    let (app, contract_address, cl_pool_id, admin, _) = fixture_default(PERFORMANCE_FEE_DEFAULT);
+    let wasm = Wasm::new(&app);
+    let cl = ConcentratedLiquidity::new(&app);
+
+    // a second pool of the same pair, with liquidity around the same spot price
+    GovWithAppAccess::new(&app)
+        .propose_and_execute(
+            CreateConcentratedLiquidityPoolsProposal::TYPE_URL.to_string(),
+            CreateConcentratedLiquidityPoolsProposal {
+                title: "CL Pool".to_string(),
+                description: "So that we can migrate to it".to_string(),
+                pool_records: vec![PoolRecord {
+                    denom0: DENOM_BASE.to_string(),
+                    denom1: DENOM_QUOTE.to_string(),
+                    tick_spacing: 100,
+                    spread_factor: Decimal::from_str(SPREAD_FACTOR_HIGH)
+                        .unwrap()
+                        .atomics()
+                        .to_string(),
+                }],
+            },
+            admin.address(),
+            &admin,
+        )
+        .unwrap();
+    let pools = cl.query_pools(&PoolsRequest { pagination: None }).unwrap();
+    let new_pool: Pool = Pool::decode(pools.pools[1].value.as_slice()).unwrap();
+    assert_ne!(new_pool.id, cl_pool_id);
+    cl.create_position(
+        MsgCreatePosition {
+            pool_id: new_pool.id,
+            sender: admin.address(),
+            lower_tick: -5000000,
+            upper_tick: 500000,
+            tokens_provided: vec![
+                v1beta1::Coin {
+                    denom: DENOM_BASE.to_string(),
+                    amount: "100000000000000000000".to_string(),
+                },
+                v1beta1::Coin {
+                    denom: DENOM_QUOTE.to_string(),
+                    amount: "100000000000000000000".to_string(),
+                },
+            ],
+            token_min_amount0: "0".to_string(),
+            token_min_amount1: "0".to_string(),
+        },
+        &admin,
+    )
+    .unwrap();
+    app.increase_time(60);
+
+    let migrate = |twap_window_seconds: Option<u64>| {
+        ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Admin(
+            AdminExtensionExecuteMsg::MigratePool {
+                new_pool_id: new_pool.id,
+                lower_price: Decimal::from_str("0.65").unwrap(),
+                upper_price: Decimal::from_str("1.3").unwrap(),
+                max_slippage: Decimal::percent(89),
+                twap_window_seconds,
+            },
+        ))
+    };
+
+    // the new pool has no TWAP history that far back, which fails the migration as a whole
+    wasm.execute(
+        contract_address.as_str(),
+        &migrate(Some(100_000)),
+        &[],
+        &admin,
+    )
+    .unwrap_err();
+    let pool: PoolResponse = wasm
+        .query(
+            contract_address.as_str(),
+            &QueryMsg::VaultExtension(ExtensionQueryMsg::ConcentratedLiquidity(
+                ClQueryMsg::Pool {},
+            )),
+        )
+        .unwrap();
+    assert_eq!(pool.pool_config.pool_id, cl_pool_id);
+
+    // only the vault admin can migrate
+    let alice = app
+        .init_account(&[Coin::new(1_000_000_000, "uosmo")])
+        .unwrap();
+    wasm.execute(contract_address.as_str(), &migrate(Some(30)), &[], &alice)
+        .unwrap_err();
+
+    wasm.execute(contract_address.as_str(), &migrate(Some(30)), &[], &admin)
+        .unwrap();
+    let pool: PoolResponse = wasm
+        .query(
+            contract_address.as_str(),
+            &QueryMsg::VaultExtension(ExtensionQueryMsg::ConcentratedLiquidity(
+                ClQueryMsg::Pool {},
+            )),
+        )
+        .unwrap();
+    assert_eq!(pool.pool_config.pool_id, new_pool.id);
+
+    let position: PositionResponse = wasm
+        .query(
+            contract_address.as_str(),
+            &QueryMsg::VaultExtension(ExtensionQueryMsg::ConcentratedLiquidity(
+                ClQueryMsg::Position {},
+            )),
+        )
+        .unwrap();
+    assert_eq!(position.position_ids.len(), 1);
+    let position = cl
+        .query_position_by_id(&PositionByIdRequest {
+            position_id: position.position_ids[0],
+        })
+        .unwrap()
+        .position
+        .unwrap()
+        .position
+        .unwrap();
+    assert_eq!(position.pool_id, new_pool.id);
+    assert_eq!(position.lower_tick % 100, 0);
+    assert_eq!(position.upper_tick % 100, 0);
+}
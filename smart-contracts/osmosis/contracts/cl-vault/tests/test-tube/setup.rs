@@ -1,6 +1,10 @@
 #![allow(dead_code)]
 
-use cl_vault::{helpers::generic::sort_tokens, msg::InstantiateMsg, state::VaultConfig};
+use cl_vault::{
+    helpers::generic::sort_tokens,
+    msg::InstantiateMsg,
+    state::{FeeDestination, VaultConfig},
+};
 use cosmwasm_std::{coin, Addr, Attribute, Coin, Decimal, Uint128};
 use dex_router_osmosis::msg::{ExecuteMsg as DexExecuteMsg, InstantiateMsg as DexInstantiate};
 use osmosis_std::{
@@ -79,6 +83,44 @@ pub fn fixture_default(performance_fee: u64) -> (OsmosisTestApp, Addr, u64, Sign
     )
 }
 
+/// A vault on an OSMO paired pool, so its positions can be superfluid delegated
+pub fn fixture_superfluid(
+    performance_fee: u64,
+) -> (OsmosisTestApp, Addr, u64, SigningAccount, f64) {
+    init_test_contract(
+        "./test-tube-build/wasm32-unknown-unknown/release/cl_vault.wasm",
+        &[
+            Coin::new(ADMIN_BALANCE_AMOUNT, "uosmo"),
+            Coin::new(ADMIN_BALANCE_AMOUNT, DENOM_BASE),
+        ],
+        MsgCreateConcentratedPool {
+            sender: "overwritten".to_string(),
+            denom0: DENOM_BASE.to_string(),
+            denom1: "uosmo".to_string(),
+            tick_spacing: 100,
+            spread_factor: Decimal::from_str(SPREAD_FACTOR_HIGH)
+                .unwrap()
+                .atomics()
+                .to_string(),
+        },
+        -5000000, // 0.5 spot price
+        500000,   // 1.5 spot price
+        vec![
+            v1beta1::Coin {
+                denom: DENOM_BASE.to_string(),
+                amount: TOKENS_PROVIDED_AMOUNT_HIGH.to_string(),
+            },
+            v1beta1::Coin {
+                denom: "uosmo".to_string(),
+                amount: TOKENS_PROVIDED_AMOUNT_HIGH.to_string(),
+            },
+        ],
+        Uint128::zero(),
+        Uint128::zero(),
+        performance_fee,
+    )
+}
+
 pub fn fixture_dex_router(
     performance_fee: u64,
 ) -> (
@@ -234,6 +276,10 @@ pub fn init_test_contract(
                     dex_router: Addr::unchecked(admin.address()),
                     swap_admin: Addr::unchecked(admin.address()),
                     twap_window_seconds: 24u64,
+                    deposit_fee_bps: 0,
+                    exit_fee_bps: 0,
+                    deposit_exit_fee_destination: FeeDestination::Treasury,
+                    referral_fee_share: Decimal::zero(),
                 },
                 vault_token_subdenom: "utestvault".to_string(),
                 range_admin: admin.address(),
@@ -463,6 +509,10 @@ pub fn init_test_contract_with_dex_router_and_swap_pools(
                     dex_router: Addr::unchecked(contract_dex_router.clone().data.address),
                     swap_admin: Addr::unchecked(admin.address()),
                     twap_window_seconds: 24u64,
+                    deposit_fee_bps: 0,
+                    exit_fee_bps: 0,
+                    deposit_exit_fee_destination: FeeDestination::Treasury,
+                    referral_fee_share: Decimal::zero(),
                 },
                 vault_token_subdenom: "utestvault".to_string(),
                 range_admin: admin.address(),
@@ -0,0 +1,186 @@
+use cl_vault::{
+    msg::{
+        AdminExtensionExecuteMsg, ExecuteMsg, ExtensionExecuteMsg, ExtensionQueryMsg, QueryMsg,
+        SuperfluidExecuteMsg,
+    },
+    query::SuperfluidResponse,
+    state::{RewardLedger, SuperfluidConfig},
+};
+use cosmwasm_std::{Decimal, Uint128};
+use osmosis_std::types::{
+    cosmos::{bank::v1beta1::MsgSend, base::v1beta1::Coin as OsmoCoin},
+    osmosis::superfluid::{
+        v1beta1::SetSuperfluidAssetsProposal, SuperfluidAsset, SuperfluidAssetType,
+    },
+};
+use osmosis_test_tube::{Account, Bank, GovWithAppAccess, Module, Wasm};
+
+use crate::setup::{fixture_superfluid, get_balance_amount, DENOM_BASE, PERFORMANCE_FEE_DEFAULT};
+
+const UNUSED_AMOUNT: u128 = 2_000_000;
+const DELEGATE_AMOUNT: u128 = 1_000_000;
+// longer than the unbonding period of the test chain
+const UNBONDING_SECONDS: u64 = 30 * 24 * 60 * 60;
+
+#[test]
+fn superfluid_delegate_undelegate_and_withdraw_works() {
+    let (app, contract_address, cl_pool_id, admin, _) = fixture_superfluid(PERFORMANCE_FEE_DEFAULT);
+    let wasm = Wasm::new(&app);
+    let bank = Bank::new(&app);
+    let validator = app.get_first_validator_address().unwrap();
+
+    // full range positions of the pool can be superfluid delegated
+    GovWithAppAccess::new(&app)
+        .propose_and_execute(
+            SetSuperfluidAssetsProposal::TYPE_URL.to_string(),
+            SetSuperfluidAssetsProposal {
+                title: "Superfluid CL pool".to_string(),
+                description: "So that the vault can superfluid delegate".to_string(),
+                assets: vec![SuperfluidAsset {
+                    denom: format!("cl/pool/{cl_pool_id}"),
+                    asset_type: SuperfluidAssetType::ConcentratedShare.into(),
+                }],
+            },
+            admin.address(),
+            &admin,
+        )
+        .unwrap();
+
+    wasm.execute(
+        contract_address.as_str(),
+        &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Admin(
+            AdminExtensionExecuteMsg::UpdateSuperfluidConfig {
+                config: Some(SuperfluidConfig {
+                    validators: vec![validator.clone()],
+                    max_ratio: Decimal::percent(50),
+                }),
+            },
+        )),
+        &[],
+        &admin,
+    )
+    .unwrap();
+
+    // the superfluid position is created out of the unused funds of the vault
+    bank.send(
+        MsgSend {
+            from_address: admin.address(),
+            to_address: contract_address.to_string(),
+            amount: vec![
+                OsmoCoin {
+                    denom: DENOM_BASE.to_string(),
+                    amount: UNUSED_AMOUNT.to_string(),
+                },
+                OsmoCoin {
+                    denom: "uosmo".to_string(),
+                    amount: UNUSED_AMOUNT.to_string(),
+                },
+            ],
+        },
+        &admin,
+    )
+    .unwrap();
+
+    let superfluid_msg = |msg: SuperfluidExecuteMsg| {
+        ExecuteMsg::VaultExtension(ExtensionExecuteMsg::Superfluid(msg))
+    };
+    let query_superfluid = || -> SuperfluidResponse {
+        wasm.query(
+            contract_address.as_str(),
+            &QueryMsg::VaultExtension(ExtensionQueryMsg::Superfluid {}),
+        )
+        .unwrap()
+    };
+
+    wasm.execute(
+        contract_address.as_str(),
+        &superfluid_msg(SuperfluidExecuteMsg::Delegate {
+            amount0: Uint128::new(DELEGATE_AMOUNT),
+            amount1: Uint128::new(DELEGATE_AMOUNT),
+        }),
+        &[],
+        &admin,
+    )
+    .unwrap();
+    let superfluid = query_superfluid();
+    assert_eq!(superfluid.positions.len(), 1);
+    let position = superfluid.positions[0].clone();
+    assert_eq!(position.validator, validator);
+    assert!(position.unbonding_since.is_none());
+    assert!(!superfluid.assets.is_empty());
+
+    // rewards are collected with a bonded superfluid position, over a few epochs
+    app.increase_time(3 * 24 * 60 * 60);
+    wasm.execute(
+        contract_address.as_str(),
+        &ExecuteMsg::VaultExtension(ExtensionExecuteMsg::CollectRewards {}),
+        &[],
+        &admin,
+    )
+    .unwrap();
+    let ledger: RewardLedger = wasm
+        .query(
+            contract_address.as_str(),
+            &QueryMsg::VaultExtension(ExtensionQueryMsg::RewardLedger {}),
+        )
+        .unwrap();
+    // superfluid staking rewards are paid in OSMO
+    assert!(ledger
+        .staking_rewards
+        .coins()
+        .iter()
+        .all(|coin| coin.denom == "uosmo"));
+
+    // a bonded position can not be withdrawn
+    wasm.execute(
+        contract_address.as_str(),
+        &superfluid_msg(SuperfluidExecuteMsg::Withdraw {
+            position_id: position.position_id,
+        }),
+        &[],
+        &admin,
+    )
+    .unwrap_err();
+
+    wasm.execute(
+        contract_address.as_str(),
+        &superfluid_msg(SuperfluidExecuteMsg::Undelegate {
+            position_id: position.position_id,
+        }),
+        &[],
+        &admin,
+    )
+    .unwrap();
+    assert!(query_superfluid().positions[0].unbonding_since.is_some());
+
+    // the chain rejects the withdraw until the lock is unbonded
+    wasm.execute(
+        contract_address.as_str(),
+        &superfluid_msg(SuperfluidExecuteMsg::Withdraw {
+            position_id: position.position_id,
+        }),
+        &[],
+        &admin,
+    )
+    .unwrap_err();
+
+    app.increase_time(UNBONDING_SECONDS);
+    let base_before =
+        get_balance_amount(&app, contract_address.to_string(), DENOM_BASE.to_string());
+    wasm.execute(
+        contract_address.as_str(),
+        &superfluid_msg(SuperfluidExecuteMsg::Withdraw {
+            position_id: position.position_id,
+        }),
+        &[],
+        &admin,
+    )
+    .unwrap();
+
+    // the assets of the position are unused funds of the vault again
+    let superfluid = query_superfluid();
+    assert!(superfluid.positions.is_empty());
+    assert!(superfluid.assets.is_empty());
+    let base_after = get_balance_amount(&app, contract_address.to_string(), DENOM_BASE.to_string());
+    assert!(base_after > base_before);
+}
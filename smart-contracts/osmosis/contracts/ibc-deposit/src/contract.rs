@@ -0,0 +1,218 @@
+use cl_vault::msg::ExecuteMsg as VaultExecuteMsg;
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_json_binary, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdError,
+    StdResult, WasmMsg,
+};
+use cw2::set_contract_version;
+
+use crate::error::ContractError;
+use crate::helpers::derive_address;
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::state::{Config, CONFIG};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:ibc-deposit";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            address_prefix: msg.address_prefix,
+        },
+    )?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Deposit {
+            vault,
+            recipient,
+            remote_address,
+            max_slippage,
+        } => execute_deposit(
+            deps,
+            env,
+            info,
+            vault,
+            recipient,
+            remote_address,
+            max_slippage,
+        ),
+    }
+}
+
+/// Forward the transferred funds to `AnyDeposit` of the vault. Any error fails the ibc-hooks
+/// execution, which acknowledges the transfer with an error so the source chain refunds it.
+fn execute_deposit(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    vault: String,
+    recipient: Option<String>,
+    remote_address: Option<String>,
+    max_slippage: Decimal,
+) -> Result<Response, ContractError> {
+    let [first, ..] = info.funds.as_slice() else {
+        return Err(ContractError::NoFunds {});
+    };
+    let recipient = match (recipient, remote_address) {
+        (Some(recipient), _) => recipient,
+        (None, Some(remote_address)) => {
+            let config = CONFIG.load(deps.storage)?;
+            derive_address(&remote_address, &config.address_prefix)?
+        }
+        (None, None) => return Err(ContractError::NoRecipient {}),
+    };
+    let recipient = deps.api.addr_validate(&recipient)?;
+    let vault = deps.api.addr_validate(&vault)?;
+
+    let deposit = WasmMsg::Execute {
+        contract_addr: vault.to_string(),
+        msg: to_json_binary(&VaultExecuteMsg::AnyDeposit {
+            amount: first.amount,
+            asset: first.denom.clone(),
+            recipient: Some(recipient.to_string()),
+            max_slippage,
+        })?,
+        funds: info.funds,
+    };
+
+    Ok(Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "ibc_deposit")
+        .add_attribute("vault", vault.as_str())
+        .add_attribute("recipient", recipient.as_str())
+        .add_message(deposit))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&CONFIG.load(deps.storage)?),
+        QueryMsg::DerivedAddress { remote_address } => {
+            let config = CONFIG.load(deps.storage)?;
+            to_json_binary(
+                &derive_address(&remote_address, &config.address_prefix)
+                    .map_err(|e| StdError::generic_err(e.to_string()))?,
+            )
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let old_version =
+        cw2::ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("old version", old_version.to_string())
+        .add_attribute("new version", CONTRACT_VERSION))
+}
+
+#[cfg(test)]
+mod tests {
+    use bech32::{ToBase32, Variant};
+    use cosmwasm_std::{
+        coin, from_json,
+        testing::{mock_dependencies, mock_env, mock_info},
+        CosmosMsg,
+    };
+
+    use super::*;
+
+    fn address(prefix: &str) -> String {
+        bech32::encode(prefix, [7u8; 20].to_base32(), Variant::Bech32).unwrap()
+    }
+
+    #[test]
+    fn deposit_credits_derived_or_given_recipient() {
+        let mut deps = mock_dependencies();
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            InstantiateMsg {
+                address_prefix: "osmo".to_string(),
+            },
+        )
+        .unwrap();
+        let deposit =
+            |recipient: Option<&str>, remote_address: Option<String>| ExecuteMsg::Deposit {
+                vault: "vault".to_string(),
+                recipient: recipient.map(str::to_string),
+                remote_address,
+                max_slippage: Decimal::percent(99),
+            };
+        let hooks_sender = mock_info("hooks_sender", &[coin(100, "ibc/atom")]);
+
+        // the cosmos hub address maps to the osmosis address of the same key
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            hooks_sender.clone(),
+            deposit(None, Some(address("cosmos"))),
+        )
+        .unwrap();
+        let CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            msg,
+            funds,
+        }) = &response.messages[0].msg
+        else {
+            panic!("expected a wasm execute")
+        };
+        assert_eq!(contract_addr, "vault");
+        assert_eq!(funds, &vec![coin(100, "ibc/atom")]);
+        assert_eq!(
+            from_json::<VaultExecuteMsg>(msg).unwrap(),
+            VaultExecuteMsg::AnyDeposit {
+                amount: 100u128.into(),
+                asset: "ibc/atom".to_string(),
+                recipient: Some(address("osmo")),
+                max_slippage: Decimal::percent(99),
+            }
+        );
+
+        // a given recipient takes precedence
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            hooks_sender.clone(),
+            deposit(Some("bob"), Some(address("cosmos"))),
+        )
+        .unwrap();
+        assert_eq!(response.attributes[3].value, "bob");
+
+        let err =
+            execute(deps.as_mut(), mock_env(), hooks_sender, deposit(None, None)).unwrap_err();
+        assert_eq!(err, ContractError::NoRecipient {});
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("hooks_sender", &[]),
+            deposit(Some("bob"), None),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoFunds {});
+    }
+}
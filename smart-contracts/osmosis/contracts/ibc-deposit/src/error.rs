@@ -0,0 +1,21 @@
+use cosmwasm_std::StdError;
+use cw2::VersionError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Version(#[from] VersionError),
+
+    #[error("{0}")]
+    Bech32(#[from] bech32::Error),
+
+    #[error("Either a recipient or a remote address to derive the recipient from is needed")]
+    NoRecipient {},
+
+    #[error("No funds were transferred along")]
+    NoFunds {},
+}
@@ -0,0 +1,8 @@
+use crate::ContractError;
+
+/// Re-encode a bech32 address of another chain with `prefix`. Both chains need to derive
+/// addresses from the same key type and coin type, as the Cosmos Hub, Neutron and Osmosis do.
+pub fn derive_address(remote_address: &str, prefix: &str) -> Result<String, ContractError> {
+    let (_, data, variant) = bech32::decode(remote_address)?;
+    Ok(bech32::encode(prefix, data, variant)?)
+}
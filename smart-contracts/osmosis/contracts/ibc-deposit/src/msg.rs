@@ -0,0 +1,36 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Decimal;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::state::Config;
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub address_prefix: String,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Deposit the funds of an incoming ICS20 transfer into `vault` through `AnyDeposit`. This is
+    /// the message carried by the ibc-hooks memo. The vault shares go to `recipient`, or if not
+    /// set, to the address derived from `remote_address` with the prefix of this chain.
+    Deposit {
+        vault: String,
+        recipient: Option<String>,
+        remote_address: Option<String>,
+        max_slippage: Decimal,
+    },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(Config)]
+    Config {},
+    /// Get the address on this chain shares are credited to for `remote_address`
+    #[returns(String)]
+    DerivedAddress { remote_address: String },
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
@@ -0,0 +1,10 @@
+use cosmwasm_schema::cw_serde;
+use cw_storage_plus::Item;
+
+#[cw_serde]
+pub struct Config {
+    /// Bech32 prefix of the addresses of this chain, remote addresses are re-encoded with it
+    pub address_prefix: String,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
@@ -0,0 +1,110 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, Uint128,
+};
+use cw2::set_contract_version;
+use cw_utils::nonpayable;
+use osmosis_std::types::osmosis::tokenfactory::v1beta1::MsgCreateDenom;
+
+use crate::error::ContractError;
+use crate::helpers::{
+    validate_allocations, validate_slippage, SWAP_DEPOSIT_REPLY_ID, UNLOCK_REPLY_ID,
+};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::query::{query_allocations, query_config, query_total_value};
+use crate::state::{Config, ADMIN, ALLOCATIONS, CONFIG, SHARE_DENOM, TOTAL_SHARES};
+use crate::vault::admin::execute_admin_msg;
+use crate::vault::deposit::{execute_deposit, handle_swap_deposit_reply};
+use crate::vault::rebalance::execute_receive_redeem;
+use crate::vault::redeem::{execute_claim_unlocked, execute_redeem, handle_unlock_reply};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:meta-vault";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    nonpayable(&info)?;
+
+    validate_slippage(msg.swap_max_slippage)?;
+    validate_slippage(msg.deposit_max_slippage)?;
+    let allocations = validate_allocations(deps.as_ref(), msg.allocations)?;
+
+    ADMIN.save(deps.storage, &deps.api.addr_validate(&msg.admin)?)?;
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            base_denom: msg.base_denom,
+            dex_router: deps.api.addr_validate(&msg.dex_router)?,
+            swap_max_slippage: msg.swap_max_slippage,
+            deposit_max_slippage: msg.deposit_max_slippage,
+            twap_window_seconds: msg.twap_window_seconds,
+        },
+    )?;
+    ALLOCATIONS.save(deps.storage, &allocations)?;
+
+    let share_denom = format!("factory/{}/{}", env.contract.address, msg.share_subdenom);
+    SHARE_DENOM.save(deps.storage, &share_denom)?;
+    TOTAL_SHARES.save(deps.storage, &Uint128::zero())?;
+
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("share_denom", share_denom)
+        .add_message(MsgCreateDenom {
+            sender: env.contract.address.to_string(),
+            subdenom: msg.share_subdenom,
+        }))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Deposit { recipient } => execute_deposit(deps, env, info, recipient),
+        ExecuteMsg::Redeem { recipient } => execute_redeem(deps, env, info, recipient),
+        ExecuteMsg::ClaimUnlocked { vault, lockup_id } => {
+            execute_claim_unlocked(deps, info, vault, lockup_id)
+        }
+        ExecuteMsg::ReceiveRedeem(msg) => execute_receive_redeem(deps, info, msg),
+        ExecuteMsg::Admin(admin_msg) => execute_admin_msg(deps, env, info, admin_msg),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    match msg {
+        QueryMsg::Config {} => Ok(to_json_binary(&query_config(deps)?)?),
+        QueryMsg::Allocations {} => Ok(to_json_binary(&query_allocations(deps, env)?)?),
+        QueryMsg::TotalValue {} => Ok(to_json_binary(&query_total_value(deps, env)?)?),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        SWAP_DEPOSIT_REPLY_ID => handle_swap_deposit_reply(deps, env),
+        UNLOCK_REPLY_ID => handle_unlock_reply(deps, msg),
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let old_version =
+        cw2::ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("old version", old_version.to_string())
+        .add_attribute("new version", CONTRACT_VERSION))
+}
@@ -0,0 +1,85 @@
+use cosmwasm_std::{
+    CheckedMultiplyFractionError, CheckedMultiplyRatioError, OverflowError, StdError,
+};
+use cw2::VersionError;
+use cw_utils::PaymentError;
+use std::num::TryFromIntError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Version(#[from] VersionError),
+
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
+    #[error("{0}")]
+    CheckedMultiplyFraction(#[from] CheckedMultiplyFractionError),
+
+    #[error("{0}")]
+    CheckedMultiplyRatio(#[from] CheckedMultiplyRatioError),
+
+    #[error("{0}")]
+    TryFromIntError(#[from] TryFromIntError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Allocations cannot be empty")]
+    EmptyAllocations {},
+
+    #[error("Allocation weights should be >0 and sum up to 1, got a sum of {sum}")]
+    InvalidWeights { sum: String },
+
+    #[error("Vault {address} is allocated more than once")]
+    DuplicateVault { address: String },
+
+    #[error("Vault {address} is not part of the allocations")]
+    VaultNotAllocated { address: String },
+
+    #[error("Vault {address} still holds {shares} shares of the meta vault")]
+    VaultNotEmpty { address: String, shares: String },
+
+    #[error("Slippage should be >0 and <=1")]
+    InvalidSlippage {},
+
+    #[error("No swap path from {offer} to {ask} on the dex router")]
+    NoSwapPath { offer: String, ask: String },
+
+    #[error("Deposit is too small to mint any shares")]
+    ZeroShares {},
+
+    #[error("Amount should be greater than zero")]
+    ZeroAmount {},
+
+    #[error("No deposit is waiting for a swap")]
+    NoPendingSwapDeposit {},
+
+    #[error("No rebalance is pending")]
+    NoPendingRebalance {},
+
+    #[error("Unexpected callback for rebalance {id}")]
+    UnexpectedCallback { id: String },
+
+    #[error("The redeem of rebalance {id} returned no assets")]
+    NothingRedeemed { id: String },
+
+    #[error("Vault {vault} did not return the id of the unlocking position")]
+    MissingLockupId { vault: String },
+
+    #[error("No redeem is waiting for an unlocking position")]
+    NoPendingUnlock {},
+
+    #[error("No redeem holds unlocking position {lockup_id} of vault {vault}")]
+    UnlockingRedeemNotFound { vault: String, lockup_id: u64 },
+
+    #[error("Unknown reply id {id}")]
+    UnknownReplyId { id: u64 },
+}
@@ -0,0 +1,382 @@
+use std::{collections::HashSet, str::FromStr};
+
+use cl_vault::{
+    msg::{
+        ClQueryMsg, ExecuteMsg as VaultExecuteMsg, ExtensionExecuteMsg as VaultExtensionExecuteMsg,
+        ExtensionQueryMsg, QueryMsg as VaultQueryMsg, UserBalanceQueryMsg,
+    },
+    query::{AssetsBalanceResponse, PoolResponse, UserSharesBalanceResponse},
+    state::PoolConfig,
+};
+use cosmwasm_std::{
+    to_json_binary, Addr, Binary, Coin, CosmosMsg, Decimal, Deps, Env, QuerierWrapper, StdResult,
+    Storage, SubMsg, Timestamp, Uint128, WasmMsg,
+};
+use cw_utils::Duration;
+use dex_router_osmosis::msg::{
+    BestPathForPairResponse, ExecuteMsg as DexRouterExecuteMsg, QueryMsg as DexRouterQueryMsg,
+};
+use osmosis_std::{
+    shim::Timestamp as OsmoTimestamp,
+    types::osmosis::{poolmanager::v1beta1::SwapAmountInRoute, twap::v1beta1::TwapQuerier},
+};
+use quasar_types::cw_vault_multi_standard::extensions::lockup::{LockupExecuteMsg, LockupQueryMsg};
+
+use crate::{
+    msg::AllocationMsg,
+    state::{Allocation, Config, PendingSwapDeposit, ALLOCATIONS, PENDING_SWAP_DEPOSITS},
+    ContractError,
+};
+
+pub const SWAP_DEPOSIT_REPLY_ID: u64 = 1;
+pub const UNLOCK_REPLY_ID: u64 = 2;
+
+pub fn query_vault_pool(querier: &QuerierWrapper, vault: &Addr) -> StdResult<PoolConfig> {
+    let pool: PoolResponse = querier.query_wasm_smart(
+        vault,
+        &VaultQueryMsg::VaultExtension(ExtensionQueryMsg::ConcentratedLiquidity(
+            ClQueryMsg::Pool {},
+        )),
+    )?;
+    Ok(pool.pool_config)
+}
+
+pub fn query_vault_shares(
+    querier: &QuerierWrapper,
+    vault: &Addr,
+    user: &Addr,
+) -> StdResult<Uint128> {
+    let shares: UserSharesBalanceResponse = querier.query_wasm_smart(
+        vault,
+        &VaultQueryMsg::VaultExtension(ExtensionQueryMsg::Balances(
+            UserBalanceQueryMsg::UserSharesBalance {
+                user: user.to_string(),
+            },
+        )),
+    )?;
+    Ok(shares.balance)
+}
+
+/// Whether `vault` is in lockup mode, in which case its shares can only be redeemed through an
+/// unlock. The vault errors on the lockup duration query while lockup mode is off.
+pub fn query_vault_locked(querier: &QuerierWrapper, vault: &Addr) -> bool {
+    querier
+        .query_wasm_smart::<Duration>(
+            vault,
+            &VaultQueryMsg::VaultExtension(ExtensionQueryMsg::Lockup(
+                LockupQueryMsg::LockupDuration {},
+            )),
+        )
+        .is_ok()
+}
+
+pub fn query_vault_assets(
+    querier: &QuerierWrapper,
+    vault: &Addr,
+    user: &Addr,
+) -> StdResult<Vec<Coin>> {
+    let assets: AssetsBalanceResponse = querier.query_wasm_smart(
+        vault,
+        &VaultQueryMsg::VaultExtension(ExtensionQueryMsg::Balances(
+            UserBalanceQueryMsg::UserAssetsBalance {
+                user: user.to_string(),
+            },
+        )),
+    )?;
+    Ok(assets.balances)
+}
+
+pub fn query_best_path(
+    querier: &QuerierWrapper,
+    config: &Config,
+    offer: Coin,
+    ask_denom: &str,
+) -> Result<BestPathForPairResponse, ContractError> {
+    let offer_denom = offer.denom.clone();
+    let best_path: Option<BestPathForPairResponse> = querier.query_wasm_smart(
+        &config.dex_router,
+        &DexRouterQueryMsg::BestPathForPair {
+            offer,
+            ask_denom: ask_denom.to_string(),
+        },
+    )?;
+    best_path.ok_or(ContractError::NoSwapPath {
+        offer: offer_denom,
+        ask: ask_denom.to_string(),
+    })
+}
+
+/// The TWAP price of `denom` in the base asset along the first path the dex router has
+/// registered for the pair. Unlike the best path return, the price of every hop is averaged
+/// over the TWAP window, so it can not be moved within a single block.
+pub fn query_twap_price_in_base(
+    querier: &QuerierWrapper,
+    block_time: Timestamp,
+    config: &Config,
+    denom: &str,
+) -> Result<Decimal, ContractError> {
+    let paths: Vec<Vec<SwapAmountInRoute>> = querier.query_wasm_smart(
+        &config.dex_router,
+        &DexRouterQueryMsg::PathsForPair {
+            offer_denom: denom.to_string(),
+            ask_denom: config.base_denom.clone(),
+        },
+    )?;
+    let path = paths.into_iter().next().ok_or(ContractError::NoSwapPath {
+        offer: denom.to_string(),
+        ask: config.base_denom.clone(),
+    })?;
+
+    let start_of_window = block_time.minus_seconds(config.twap_window_seconds);
+    let twap_querier = TwapQuerier::new(querier);
+    let mut price = Decimal::one();
+    let mut denom_in = denom.to_string();
+    for route in path {
+        let twap = twap_querier.arithmetic_twap_to_now(
+            route.pool_id,
+            denom_in,
+            route.token_out_denom.clone(),
+            Some(OsmoTimestamp {
+                seconds: start_of_window.seconds().try_into()?,
+                nanos: 0,
+            }),
+        )?;
+        price = price.checked_mul(Decimal::from_str(&twap.arithmetic_twap)?)?;
+        denom_in = route.token_out_denom;
+    }
+    Ok(price)
+}
+
+/// Value `coins` in the base asset at the TWAP price of their dex router path
+pub fn value_in_base(
+    querier: &QuerierWrapper,
+    block_time: Timestamp,
+    config: &Config,
+    coins: &[Coin],
+) -> Result<Uint128, ContractError> {
+    coins
+        .iter()
+        .filter(|coin| !coin.amount.is_zero())
+        .try_fold(Uint128::zero(), |total, coin| {
+            let value = if coin.denom == config.base_denom {
+                coin.amount
+            } else {
+                let price = query_twap_price_in_base(querier, block_time, config, &coin.denom)?;
+                coin.amount.checked_mul_floor(price)?
+            };
+            Ok(total.checked_add(value)?)
+        })
+}
+
+pub struct VaultValue {
+    pub allocation: Allocation,
+    /// Shares of the underlying vault held by the meta vault
+    pub shares: Uint128,
+    /// Value of those shares in the base asset
+    pub value: Uint128,
+}
+
+pub fn get_vault_values(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+) -> Result<Vec<VaultValue>, ContractError> {
+    ALLOCATIONS
+        .load(deps.storage)?
+        .into_iter()
+        .map(|allocation| {
+            let shares =
+                query_vault_shares(&deps.querier, &allocation.vault, &env.contract.address)?;
+            let value = if shares.is_zero() {
+                Uint128::zero()
+            } else {
+                let assets =
+                    query_vault_assets(&deps.querier, &allocation.vault, &env.contract.address)?;
+                value_in_base(&deps.querier, env.block.time, config, &assets)?
+            };
+            Ok(VaultValue {
+                allocation,
+                shares,
+                value,
+            })
+        })
+        .collect()
+}
+
+pub fn get_total_value(deps: Deps, env: &Env, config: &Config) -> Result<Uint128, ContractError> {
+    get_vault_values(deps, env, config)?
+        .iter()
+        .try_fold(Uint128::zero(), |total, vault| {
+            Ok(total.checked_add(vault.value)?)
+        })
+}
+
+/// Validate the allocations and cache the pool tokens of every vault
+pub fn validate_allocations(
+    deps: Deps,
+    allocations: Vec<AllocationMsg>,
+) -> Result<Vec<Allocation>, ContractError> {
+    if allocations.is_empty() {
+        return Err(ContractError::EmptyAllocations {});
+    }
+
+    let sum = allocations
+        .iter()
+        .try_fold(Decimal::zero(), |sum, a| sum.checked_add(a.weight))?;
+    if sum != Decimal::one() || allocations.iter().any(|a| a.weight.is_zero()) {
+        return Err(ContractError::InvalidWeights {
+            sum: sum.to_string(),
+        });
+    }
+
+    let mut seen = HashSet::new();
+    allocations
+        .into_iter()
+        .map(|a| {
+            let vault = deps.api.addr_validate(&a.vault)?;
+            if !seen.insert(vault.clone()) {
+                return Err(ContractError::DuplicateVault { address: a.vault });
+            }
+            let pool = query_vault_pool(&deps.querier, &vault)?;
+            Ok(Allocation {
+                vault,
+                weight: a.weight,
+                token0: pool.token0,
+                token1: pool.token1,
+            })
+        })
+        .collect()
+}
+
+pub fn find_allocation(
+    allocations: &[Allocation],
+    vault: &Addr,
+) -> Result<Allocation, ContractError> {
+    allocations
+        .iter()
+        .find(|a| a.vault == *vault)
+        .cloned()
+        .ok_or(ContractError::VaultNotAllocated {
+            address: vault.to_string(),
+        })
+}
+
+pub fn validate_slippage(slippage: Decimal) -> Result<(), ContractError> {
+    if slippage.is_zero() || slippage > Decimal::one() {
+        return Err(ContractError::InvalidSlippage {});
+    }
+    Ok(())
+}
+
+/// Deposit `funds` into `vault` through `AnyDeposit`, the vault shares are credited to the
+/// meta vault
+pub fn deposit_msg(
+    vault: &Addr,
+    mut funds: Vec<Coin>,
+    max_slippage: Decimal,
+) -> StdResult<CosmosMsg> {
+    funds.sort_by(|a, b| a.denom.cmp(&b.denom));
+    // amount and asset are not used by the cl-vault, the deposit is read from the funds
+    let first = funds[0].clone();
+    Ok(WasmMsg::Execute {
+        contract_addr: vault.to_string(),
+        msg: to_json_binary(&VaultExecuteMsg::AnyDeposit {
+            amount: first.amount,
+            asset: first.denom,
+            recipient: None,
+            max_slippage,
+        })?,
+        funds,
+    }
+    .into())
+}
+
+/// Redeem `amount` shares of `vault` through `RedeemAndCall`, the vault sends the assets to
+/// `contract` together with `msg` once they are paid out
+pub fn redeem_and_call_msg(
+    vault: &Addr,
+    amount: Uint128,
+    contract: &Addr,
+    msg: Binary,
+) -> StdResult<CosmosMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: vault.to_string(),
+        msg: to_json_binary(&VaultExecuteMsg::VaultExtension(
+            VaultExtensionExecuteMsg::RedeemAndCall {
+                amount,
+                contract: contract.to_string(),
+                msg,
+            },
+        ))?,
+        funds: vec![],
+    }
+    .into())
+}
+
+/// Start unlocking `amount` shares of `vault`, the vault emits the id of the unlocking position
+pub fn unlock_msg(vault: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+    lockup_msg(vault, LockupExecuteMsg::Unlock { amount })
+}
+
+/// Withdraw unlocking position `lockup_id` of `vault` to `recipient`
+pub fn withdraw_unlocked_msg(
+    vault: &Addr,
+    lockup_id: u64,
+    recipient: &Addr,
+) -> StdResult<CosmosMsg> {
+    lockup_msg(
+        vault,
+        LockupExecuteMsg::WithdrawUnlocked {
+            recipient: Some(recipient.to_string()),
+            lockup_id,
+        },
+    )
+}
+
+fn lockup_msg(vault: &Addr, msg: LockupExecuteMsg) -> StdResult<CosmosMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: vault.to_string(),
+        msg: to_json_binary(&VaultExecuteMsg::VaultExtension(
+            VaultExtensionExecuteMsg::Lockup(msg),
+        ))?,
+        funds: vec![],
+    }
+    .into())
+}
+
+/// Swap `offer` into `out_denom` through the dex router and queue a deposit of the output into
+/// `vault`. The deposit is made in the reply of the swap.
+pub fn swap_deposit_msg(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    config: &Config,
+    vault: &Addr,
+    offer: Coin,
+    out_denom: &str,
+) -> Result<SubMsg, ContractError> {
+    let best_path = query_best_path(querier, config, offer.clone(), out_denom)?;
+    let minimum_receive = best_path
+        .return_amount
+        .checked_mul_floor(config.swap_max_slippage)?;
+
+    PENDING_SWAP_DEPOSITS.push_back(
+        storage,
+        &PendingSwapDeposit {
+            vault: vault.clone(),
+            denom: out_denom.to_string(),
+        },
+    )?;
+
+    Ok(SubMsg::reply_on_success(
+        WasmMsg::Execute {
+            contract_addr: config.dex_router.to_string(),
+            msg: to_json_binary(&DexRouterExecuteMsg::Swap {
+                out_denom: out_denom.to_string(),
+                path: Some(best_path.path),
+                minimum_receive: Some(minimum_receive),
+            })?,
+            funds: vec![offer],
+        },
+        SWAP_DEPOSIT_REPLY_ID,
+    ))
+}
@@ -0,0 +1,12 @@
+pub mod contract;
+mod error;
+pub mod helpers;
+pub mod msg;
+pub mod query;
+pub mod state;
+pub mod vault;
+
+#[cfg(test)]
+mod test_helpers;
+
+pub use crate::error::ContractError;
@@ -0,0 +1,96 @@
+use cl_vault::msg::RedeemReceiveMsg;
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Decimal, Uint128};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::query::{AllocationsResponse, ConfigResponse, TotalValueResponse};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// Address that manages the allocations and triggers rebalances
+    pub admin: String,
+    pub base_denom: String,
+    pub dex_router: String,
+    pub swap_max_slippage: Decimal,
+    pub deposit_max_slippage: Decimal,
+    pub twap_window_seconds: u64,
+    /// Subdenom of the tokenfactory share denom created for the meta vault
+    pub share_subdenom: String,
+    pub allocations: Vec<AllocationMsg>,
+}
+
+#[cw_serde]
+pub struct AllocationMsg {
+    /// Address of the underlying cl-vault
+    pub vault: String,
+    /// Target part of the deposits going to this vault
+    pub weight: Decimal,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Deposit the base asset, the deposit is split over the vaults by their target weights.
+    /// Shares are minted to `recipient`, or to the sender if not set.
+    Deposit { recipient: Option<String> },
+    /// Redeem the sent along shares for their part of every underlying vault, the assets are
+    /// sent to `recipient`, or to the sender if not set. Shares of a vault in lockup mode are
+    /// unlocked for the recipient instead, see `ClaimUnlocked`.
+    Redeem { recipient: Option<String> },
+    /// Withdraw an unlocking position a redeem left in a vault in lockup mode once it is
+    /// unlocked, the vault sends the assets to the recipient of that redeem
+    ClaimUnlocked { vault: String, lockup_id: u64 },
+    /// Assets of an underlying vault redeemed through its `RedeemAndCall`. The vault sends this
+    /// once it paid out the redeem, the inner msg is the `quasar_types` callback of the async
+    /// step that is waiting for the assets.
+    ReceiveRedeem(RedeemReceiveMsg),
+    /// Admin operations
+    Admin(AdminExecuteMsg),
+}
+
+/// The msg the meta vault passes along with the `RedeemAndCall` of a redeem, the vault hands it
+/// back in `ReceiveRedeem` together with the assets
+#[cw_serde]
+pub enum RedeemCallback {
+    /// Send the assets on to the recipient of the redeem
+    Forward { recipient: String },
+}
+
+#[cw_serde]
+pub enum AdminExecuteMsg {
+    UpdateAdmin {
+        address: String,
+    },
+    UpdateConfig {
+        dex_router: Option<String>,
+        swap_max_slippage: Option<Decimal>,
+        deposit_max_slippage: Option<Decimal>,
+        twap_window_seconds: Option<u64>,
+    },
+    /// Replace the target weights. Vaults that are dropped from the allocations must be
+    /// rebalanced out first.
+    UpdateAllocations {
+        allocations: Vec<AllocationMsg>,
+    },
+    /// Redeem `shares` of `from_vault` and deposit the returned assets into `to_vault`
+    Rebalance {
+        from_vault: String,
+        to_vault: String,
+        shares: Uint128,
+    },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(ConfigResponse)]
+    Config {},
+    /// Get the target and current weights of every underlying vault
+    #[returns(AllocationsResponse)]
+    Allocations {},
+    /// Get the value of all holdings in the base asset together with the total shares
+    #[returns(TotalValueResponse)]
+    TotalValue {},
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
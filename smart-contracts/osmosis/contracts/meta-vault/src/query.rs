@@ -0,0 +1,85 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Decimal, Deps, Env, Uint128};
+
+use crate::{
+    helpers::{get_total_value, get_vault_values},
+    state::{Allocation, Config, ADMIN, CONFIG, SHARE_DENOM, TOTAL_SHARES},
+    ContractError,
+};
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub admin: Addr,
+    pub config: Config,
+    pub share_denom: String,
+}
+
+#[cw_serde]
+pub struct AllocationState {
+    pub allocation: Allocation,
+    /// Shares of the underlying vault held by the meta vault
+    pub shares: Uint128,
+    /// Value of those shares in the base asset
+    pub value: Uint128,
+    /// Part of the total value currently in this vault
+    pub current_weight: Decimal,
+}
+
+#[cw_serde]
+pub struct AllocationsResponse {
+    pub allocations: Vec<AllocationState>,
+    pub total_value: Uint128,
+}
+
+#[cw_serde]
+pub struct TotalValueResponse {
+    /// Value of all holdings in the base asset
+    pub total_value: Uint128,
+    pub total_shares: Uint128,
+}
+
+pub fn query_config(deps: Deps) -> Result<ConfigResponse, ContractError> {
+    Ok(ConfigResponse {
+        admin: ADMIN.load(deps.storage)?,
+        config: CONFIG.load(deps.storage)?,
+        share_denom: SHARE_DENOM.load(deps.storage)?,
+    })
+}
+
+pub fn query_allocations(deps: Deps, env: Env) -> Result<AllocationsResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let values = get_vault_values(deps, &env, &config)?;
+    let total_value = values
+        .iter()
+        .try_fold(Uint128::zero(), |total, v| total.checked_add(v.value))?;
+
+    let allocations = values
+        .into_iter()
+        .map(|v| {
+            let current_weight = if total_value.is_zero() {
+                Decimal::zero()
+            } else {
+                Decimal::from_ratio(v.value, total_value)
+            };
+            AllocationState {
+                allocation: v.allocation,
+                shares: v.shares,
+                value: v.value,
+                current_weight,
+            }
+        })
+        .collect();
+
+    Ok(AllocationsResponse {
+        allocations,
+        total_value,
+    })
+}
+
+pub fn query_total_value(deps: Deps, env: Env) -> Result<TotalValueResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(TotalValueResponse {
+        total_value: get_total_value(deps, &env, &config)?,
+        total_shares: TOTAL_SHARES.load(deps.storage)?,
+    })
+}
@@ -0,0 +1,81 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Decimal, Uint128};
+use cw_storage_plus::{Deque, Item, Map};
+
+#[cw_serde]
+pub struct Config {
+    /// The asset deposits are made in, holdings are valued in this asset
+    pub base_denom: String,
+    /// The dex router used to swap the base asset into the pool tokens of the vaults
+    pub dex_router: Addr,
+    /// The minimum part of the simulated swap return that has to be received, between 0 and 1
+    pub swap_max_slippage: Decimal,
+    /// The max slippage passed along to the `AnyDeposit` of the underlying vaults
+    pub deposit_max_slippage: Decimal,
+    /// The window of the pool TWAPs holdings are valued at
+    pub twap_window_seconds: u64,
+}
+
+pub const ADMIN: Item<Addr> = Item::new("admin");
+pub const CONFIG: Item<Config> = Item::new("config");
+
+/// The tokenfactory denom of the meta vault shares
+pub const SHARE_DENOM: Item<String> = Item::new("share_denom");
+/// The total amount of meta vault shares, shares are only minted and burned by this contract
+pub const TOTAL_SHARES: Item<Uint128> = Item::new("total_shares");
+
+#[cw_serde]
+pub struct Allocation {
+    /// Address of the underlying cl-vault
+    pub vault: Addr,
+    /// Target part of the deposits going to this vault, the weights of all allocations sum up to 1
+    pub weight: Decimal,
+    /// Pool tokens of the vault, cached from the vault when the allocation is set
+    pub token0: String,
+    pub token1: String,
+}
+
+impl Allocation {
+    pub fn contains(&self, denom: &str) -> bool {
+        self.token0 == denom || self.token1 == denom
+    }
+}
+
+pub const ALLOCATIONS: Item<Vec<Allocation>> = Item::new("allocations");
+
+#[cw_serde]
+pub struct PendingSwapDeposit {
+    /// The vault the output of the swap is deposited in
+    pub vault: Addr,
+    /// The denom the swap outputs
+    pub denom: String,
+}
+
+/// Vaults waiting for the output of a dex router swap, in the order the swaps are executed
+pub const PENDING_SWAP_DEPOSITS: Deque<PendingSwapDeposit> = Deque::new("pending_swap_deposits");
+
+#[cw_serde]
+pub struct PendingRebalance {
+    pub id: u64,
+    pub from_vault: Addr,
+    pub to_vault: Addr,
+}
+
+/// The rebalance waiting for the assets of its redeem, only set within a single rebalance
+/// transaction since the vault pays out a `RedeemAndCall` right away or rejects it
+pub const PENDING_REBALANCE: Item<PendingRebalance> = Item::new("pending_rebalance");
+pub const REBALANCE_COUNT: Item<u64> = Item::new("rebalance_count");
+
+#[cw_serde]
+pub struct PendingUnlock {
+    /// The vault in lockup mode the shares are unlocked in
+    pub vault: Addr,
+    /// The recipient of the redeem the shares are unlocked for
+    pub recipient: Addr,
+}
+
+/// Redeems from vaults in lockup mode waiting for the id of their unlocking position, in the
+/// order the unlocks are executed
+pub const PENDING_UNLOCKS: Deque<PendingUnlock> = Deque::new("pending_unlocks");
+/// The recipients of the unlocking positions the meta vault holds, by vault and lockup id
+pub const UNLOCKING_REDEEMS: Map<(&Addr, u64), Addr> = Map::new("unlocking_redeems");
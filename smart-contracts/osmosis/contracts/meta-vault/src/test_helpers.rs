@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use cl_vault::{
+    msg::{ClQueryMsg, ExtensionQueryMsg, QueryMsg as VaultQueryMsg, UserBalanceQueryMsg},
+    query::{AssetsBalanceResponse, PoolResponse, UserSharesBalanceResponse},
+    state::PoolConfig,
+};
+use cosmwasm_std::{
+    from_json,
+    testing::{mock_env, mock_info, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR},
+    to_json_binary, Coin, ContractResult, Decimal, DepsMut, Empty, OwnedDeps, Querier,
+    QuerierResult, QueryRequest, SystemError, SystemResult, WasmQuery,
+};
+use cw_utils::Duration;
+use dex_router_osmosis::msg::{BestPathForPairResponse, QueryMsg as DexRouterQueryMsg};
+use osmosis_std::types::osmosis::{
+    poolmanager::v1beta1::SwapAmountInRoute, twap::v1beta1::ArithmeticTwapToNowResponse,
+};
+
+use quasar_types::cw_vault_multi_standard::extensions::lockup::LockupQueryMsg;
+
+use crate::{
+    contract::instantiate,
+    msg::{AllocationMsg, InstantiateMsg},
+};
+
+pub const ADMIN: &str = "admin";
+pub const DEX_ROUTER: &str = "dex_router";
+pub const BASE_DENOM: &str = "uosmo";
+pub const USDC: &str = "uusdc";
+pub const ATOM: &str = "uatom";
+/// vault of an OSMO/USDC pool
+pub const VAULT_A: &str = "vault_a";
+/// vault of an ATOM/USDC pool, deposits into it are swapped first
+pub const VAULT_B: &str = "vault_b";
+
+/// Shares and assets the meta vault holds in an underlying vault
+pub struct Holding {
+    pub shares: u128,
+    pub assets: Vec<Coin>,
+    /// Whether the vault is in lockup mode
+    pub locked: bool,
+}
+
+/// Mock querier that answers the pool TWAP queries with `twap_price` and forwards everything
+/// else to the wrapped mock querier
+pub struct MetaVaultQuerier {
+    pub base: MockQuerier,
+    pub twap_price: Decimal,
+}
+
+impl MetaVaultQuerier {
+    pub fn update_balance(&mut self, addr: impl Into<String>, balance: Vec<Coin>) {
+        self.base.update_balance(addr, balance);
+    }
+}
+
+impl Querier for MetaVaultQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        match from_json::<QueryRequest<Empty>>(bin_request) {
+            Ok(QueryRequest::Stargate { path, .. })
+                if path == "/osmosis.twap.v1beta1.Query/ArithmeticTwapToNow" =>
+            {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&ArithmeticTwapToNowResponse {
+                        arithmetic_twap: self.twap_price.to_string(),
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => self.base.raw_query(bin_request),
+        }
+    }
+}
+
+/// Mock deps with VAULT_A, VAULT_B and a dex router that swaps every pair 1:1 over a single pool
+/// with a TWAP of 1
+pub fn mock_deps_with_vaults(
+    holdings: Vec<(&str, Holding)>,
+    balances: &[Coin],
+) -> OwnedDeps<MockStorage, MockApi, MetaVaultQuerier> {
+    let holdings: HashMap<String, Holding> = holdings
+        .into_iter()
+        .map(|(vault, holding)| (vault.to_string(), holding))
+        .collect();
+
+    let mut querier = MockQuerier::new(&[(MOCK_CONTRACT_ADDR, balances)]);
+    querier.update_wasm(move |query| match query {
+        WasmQuery::Smart { contract_addr, msg } if contract_addr == DEX_ROUTER => {
+            match from_json(msg).unwrap() {
+                DexRouterQueryMsg::BestPathForPair { offer, .. } => {
+                    SystemResult::Ok(ContractResult::Ok(
+                        to_json_binary(&Some(BestPathForPairResponse {
+                            path: vec![],
+                            return_amount: offer.amount,
+                        }))
+                        .unwrap(),
+                    ))
+                }
+                DexRouterQueryMsg::PathsForPair { ask_denom, .. } => {
+                    SystemResult::Ok(ContractResult::Ok(
+                        to_json_binary(&vec![vec![SwapAmountInRoute {
+                            pool_id: 3,
+                            token_out_denom: ask_denom,
+                        }]])
+                        .unwrap(),
+                    ))
+                }
+                _ => unimplemented!(),
+            }
+        }
+        WasmQuery::Smart { contract_addr, msg } => {
+            let pool_config = match contract_addr.as_str() {
+                VAULT_A => PoolConfig {
+                    pool_id: 1,
+                    token0: BASE_DENOM.to_string(),
+                    token1: USDC.to_string(),
+                },
+                VAULT_B => PoolConfig {
+                    pool_id: 2,
+                    token0: ATOM.to_string(),
+                    token1: USDC.to_string(),
+                },
+                _ => {
+                    return SystemResult::Err(SystemError::NoSuchContract {
+                        addr: contract_addr.clone(),
+                    })
+                }
+            };
+            let holding = holdings.get(contract_addr);
+            let response = match from_json(msg).unwrap() {
+                VaultQueryMsg::VaultExtension(ExtensionQueryMsg::ConcentratedLiquidity(
+                    ClQueryMsg::Pool {},
+                )) => to_json_binary(&PoolResponse { pool_config }),
+                VaultQueryMsg::VaultExtension(ExtensionQueryMsg::Balances(
+                    UserBalanceQueryMsg::UserSharesBalance { .. },
+                )) => to_json_binary(&UserSharesBalanceResponse {
+                    balance: holding.map(|h| h.shares).unwrap_or_default().into(),
+                }),
+                VaultQueryMsg::VaultExtension(ExtensionQueryMsg::Balances(
+                    UserBalanceQueryMsg::UserAssetsBalance { .. },
+                )) => to_json_binary(&AssetsBalanceResponse {
+                    balances: holding.map(|h| h.assets.clone()).unwrap_or_default(),
+                }),
+                VaultQueryMsg::VaultExtension(ExtensionQueryMsg::Lockup(
+                    LockupQueryMsg::LockupDuration {},
+                )) => {
+                    if !holding.is_some_and(|h| h.locked) {
+                        return SystemResult::Ok(ContractResult::Err(
+                            "Lockup is disabled".to_string(),
+                        ));
+                    }
+                    to_json_binary(&Duration::Time(86400))
+                }
+                _ => unimplemented!(),
+            };
+            SystemResult::Ok(ContractResult::Ok(response.unwrap()))
+        }
+        _ => unimplemented!(),
+    });
+
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: MetaVaultQuerier {
+            base: querier,
+            twap_price: Decimal::one(),
+        },
+        custom_query_type: Default::default(),
+    }
+}
+
+pub fn get_init_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        admin: ADMIN.to_string(),
+        base_denom: BASE_DENOM.to_string(),
+        dex_router: DEX_ROUTER.to_string(),
+        swap_max_slippage: Decimal::percent(99),
+        deposit_max_slippage: Decimal::percent(99),
+        twap_window_seconds: 24,
+        share_subdenom: "umeta".to_string(),
+        allocations: vec![
+            AllocationMsg {
+                vault: VAULT_A.to_string(),
+                weight: Decimal::percent(60),
+            },
+            AllocationMsg {
+                vault: VAULT_B.to_string(),
+                weight: Decimal::percent(40),
+            },
+        ],
+    }
+}
+
+pub fn instantiate_contract(deps: DepsMut) {
+    instantiate(deps, mock_env(), mock_info(ADMIN, &[]), get_init_msg()).unwrap();
+}
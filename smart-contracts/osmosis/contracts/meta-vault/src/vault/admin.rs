@@ -0,0 +1,240 @@
+use cosmwasm_std::{Addr, Decimal, DepsMut, Env, MessageInfo, Response, Storage};
+use cw_utils::nonpayable;
+
+use crate::{
+    helpers::{query_vault_shares, validate_allocations, validate_slippage},
+    msg::{AdminExecuteMsg, AllocationMsg},
+    state::{ADMIN, ALLOCATIONS, CONFIG},
+    vault::rebalance::execute_rebalance,
+    ContractError,
+};
+
+pub fn assert_admin(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    if ADMIN.load(storage)? != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+pub fn execute_admin_msg(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    admin_msg: AdminExecuteMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+    match admin_msg {
+        AdminExecuteMsg::UpdateAdmin { address } => execute_update_admin(deps, info, address),
+        AdminExecuteMsg::UpdateConfig {
+            dex_router,
+            swap_max_slippage,
+            deposit_max_slippage,
+            twap_window_seconds,
+        } => execute_update_config(
+            deps,
+            info,
+            dex_router,
+            swap_max_slippage,
+            deposit_max_slippage,
+            twap_window_seconds,
+        ),
+        AdminExecuteMsg::UpdateAllocations { allocations } => {
+            execute_update_allocations(deps, env, info, allocations)
+        }
+        AdminExecuteMsg::Rebalance {
+            from_vault,
+            to_vault,
+            shares,
+        } => execute_rebalance(deps, env, info, from_vault, to_vault, shares),
+    }
+}
+
+fn execute_update_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+    let new_admin = deps.api.addr_validate(&address)?;
+    ADMIN.save(deps.storage, &new_admin)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "update_admin")
+        .add_attribute("previous_admin", info.sender.as_str())
+        .add_attribute("new_admin", new_admin.as_str()))
+}
+
+fn execute_update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    dex_router: Option<String>,
+    swap_max_slippage: Option<Decimal>,
+    deposit_max_slippage: Option<Decimal>,
+    twap_window_seconds: Option<u64>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+    let mut config = CONFIG.load(deps.storage)?;
+    if let Some(dex_router) = dex_router {
+        config.dex_router = deps.api.addr_validate(&dex_router)?;
+    }
+    if let Some(swap_max_slippage) = swap_max_slippage {
+        validate_slippage(swap_max_slippage)?;
+        config.swap_max_slippage = swap_max_slippage;
+    }
+    if let Some(deposit_max_slippage) = deposit_max_slippage {
+        validate_slippage(deposit_max_slippage)?;
+        config.deposit_max_slippage = deposit_max_slippage;
+    }
+    if let Some(twap_window_seconds) = twap_window_seconds {
+        config.twap_window_seconds = twap_window_seconds;
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "update_config")
+        .add_attribute("updates", format!("{:?}", config)))
+}
+
+/// Replace the allocations, a vault can only be dropped once the meta vault holds none of its
+/// shares anymore
+fn execute_update_allocations(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    allocations: Vec<AllocationMsg>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+    let allocations = validate_allocations(deps.as_ref(), allocations)?;
+
+    for old in ALLOCATIONS.load(deps.storage)? {
+        if allocations.iter().any(|a| a.vault == old.vault) {
+            continue;
+        }
+        let shares = query_vault_shares(&deps.querier, &old.vault, &env.contract.address)?;
+        if !shares.is_zero() {
+            return Err(ContractError::VaultNotEmpty {
+                address: old.vault.to_string(),
+                shares: shares.to_string(),
+            });
+        }
+    }
+    ALLOCATIONS.save(deps.storage, &allocations)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "update_allocations")
+        .add_attribute("updates", format!("{:?}", allocations)))
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        coin,
+        testing::{mock_env, mock_info},
+    };
+
+    use super::*;
+    use crate::test_helpers::{
+        instantiate_contract, mock_deps_with_vaults, Holding, ADMIN, BASE_DENOM, VAULT_A, VAULT_B,
+    };
+
+    #[test]
+    fn update_allocations() {
+        let mut deps = mock_deps_with_vaults(
+            vec![(
+                VAULT_B,
+                Holding {
+                    shares: 10,
+                    assets: vec![],
+                    locked: false,
+                },
+            )],
+            &[],
+        );
+        instantiate_contract(deps.as_mut());
+        let update = |weights: Vec<(&str, u64)>| AdminExecuteMsg::UpdateAllocations {
+            allocations: weights
+                .into_iter()
+                .map(|(vault, weight)| AllocationMsg {
+                    vault: vault.to_string(),
+                    weight: Decimal::percent(weight),
+                })
+                .collect(),
+        };
+
+        let err = execute_admin_msg(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            update(vec![(VAULT_A, 50), (VAULT_B, 50)]),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let err = execute_admin_msg(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[coin(1, BASE_DENOM)]),
+            update(vec![(VAULT_A, 50), (VAULT_B, 50)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Payment(_)));
+
+        let err = execute_admin_msg(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[]),
+            update(vec![(VAULT_A, 50), (VAULT_B, 40)]),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidWeights {
+                sum: "0.9".to_string()
+            }
+        );
+
+        let err = execute_admin_msg(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[]),
+            update(vec![(VAULT_A, 50), (VAULT_A, 50)]),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DuplicateVault {
+                address: VAULT_A.to_string()
+            }
+        );
+
+        // vault b still holds shares of the meta vault
+        let err = execute_admin_msg(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[]),
+            update(vec![(VAULT_A, 100)]),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::VaultNotEmpty {
+                address: VAULT_B.to_string(),
+                shares: "10".to_string()
+            }
+        );
+
+        execute_admin_msg(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN, &[]),
+            update(vec![(VAULT_A, 25), (VAULT_B, 75)]),
+        )
+        .unwrap();
+        let allocations = ALLOCATIONS.load(deps.as_ref().storage).unwrap();
+        assert_eq!(allocations[1].weight, Decimal::percent(75));
+        assert_eq!(allocations[1].token0, "uatom");
+    }
+}
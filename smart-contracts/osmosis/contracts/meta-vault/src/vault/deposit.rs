@@ -0,0 +1,273 @@
+use cosmwasm_std::{coin, DepsMut, Env, MessageInfo, Response, Uint128};
+use cw_utils::must_pay;
+use osmosis_std::types::osmosis::tokenfactory::v1beta1::MsgMint;
+
+use crate::{
+    helpers::{deposit_msg, get_total_value, swap_deposit_msg},
+    state::{ALLOCATIONS, CONFIG, PENDING_SWAP_DEPOSITS, SHARE_DENOM, TOTAL_SHARES},
+    ContractError,
+};
+
+/// Mint shares for the deposited base asset and split the deposit over the vaults by their
+/// target weights. Vaults with the base asset in their pool get it deposited directly, for the
+/// other vaults the base asset is swapped into their token0 first.
+pub fn execute_deposit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let amount = must_pay(&info, &config.base_denom)?;
+    let recipient = recipient.map_or(Ok(info.sender.clone()), |x| deps.api.addr_validate(&x))?;
+
+    // the deposit is already in the contract balance, but not in any vault yet
+    let total_value = get_total_value(deps.as_ref(), &env, &config)?;
+    let total_shares = TOTAL_SHARES.load(deps.storage)?;
+    let shares = if total_shares.is_zero() || total_value.is_zero() {
+        amount
+    } else {
+        amount.checked_multiply_ratio(total_shares, total_value)?
+    };
+    if shares.is_zero() {
+        return Err(ContractError::ZeroShares {});
+    }
+    TOTAL_SHARES.save(deps.storage, &total_shares.checked_add(shares)?)?;
+
+    let share_denom = SHARE_DENOM.load(deps.storage)?;
+    let mut response = Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "deposit")
+        .add_attribute("amount", amount)
+        .add_attribute("shares", shares)
+        .add_attribute("recipient", recipient.as_str())
+        .add_message(MsgMint {
+            sender: env.contract.address.to_string(),
+            amount: Some(coin(shares.u128(), share_denom).into()),
+            mint_to_address: recipient.to_string(),
+        });
+
+    let allocations = ALLOCATIONS.load(deps.storage)?;
+    let mut remaining = amount;
+    for (i, allocation) in allocations.iter().enumerate() {
+        // the last vault gets the rounding remainder
+        let part = if i == allocations.len() - 1 {
+            remaining
+        } else {
+            amount.checked_mul_floor(allocation.weight)?
+        };
+        remaining = remaining.checked_sub(part)?;
+        if part.is_zero() {
+            continue;
+        }
+
+        let offer = coin(part.u128(), &config.base_denom);
+        if allocation.contains(&config.base_denom) {
+            response = response.add_message(deposit_msg(
+                &allocation.vault,
+                vec![offer],
+                config.deposit_max_slippage,
+            )?);
+        } else {
+            response = response.add_submessage(swap_deposit_msg(
+                deps.storage,
+                &deps.querier,
+                &config,
+                &allocation.vault,
+                offer,
+                &allocation.token0,
+            )?);
+        }
+    }
+
+    Ok(response)
+}
+
+/// Deposit the output of a dex router swap into the vault that is waiting for it
+pub fn handle_swap_deposit_reply(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let pending = PENDING_SWAP_DEPOSITS
+        .pop_front(deps.storage)?
+        .ok_or(ContractError::NoPendingSwapDeposit {})?;
+    let config = CONFIG.load(deps.storage)?;
+
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &pending.denom)?;
+
+    let response = Response::new()
+        .add_attribute("method", "reply")
+        .add_attribute("action", "swap_deposit")
+        .add_attribute("vault", pending.vault.as_str())
+        .add_attribute("amount", balance.to_string());
+
+    if balance.amount == Uint128::zero() {
+        return Ok(response);
+    }
+    Ok(response.add_message(deposit_msg(
+        &pending.vault,
+        vec![balance],
+        config.deposit_max_slippage,
+    )?))
+}
+
+#[cfg(test)]
+mod tests {
+    use cl_vault::msg::ExecuteMsg as VaultExecuteMsg;
+    use cosmwasm_std::{
+        coin, from_json,
+        testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR},
+        CosmosMsg, Decimal, ReplyOn, WasmMsg,
+    };
+    use dex_router_osmosis::msg::ExecuteMsg as DexRouterExecuteMsg;
+
+    use super::*;
+    use crate::test_helpers::{
+        instantiate_contract, mock_deps_with_vaults, Holding, ATOM, BASE_DENOM, USDC, VAULT_A,
+        VAULT_B,
+    };
+
+    #[test]
+    fn deposit_is_split_by_target_weights() {
+        let mut deps = mock_deps_with_vaults(vec![], &[]);
+        instantiate_contract(deps.as_mut());
+        let env = mock_env();
+
+        let response = execute_deposit(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[coin(1001, BASE_DENOM)]),
+            None,
+        )
+        .unwrap();
+
+        // the first deposit mints shares 1:1
+        assert_eq!(
+            TOTAL_SHARES.load(deps.as_ref().storage).unwrap(),
+            Uint128::new(1001)
+        );
+        assert_eq!(response.messages.len(), 3);
+
+        // 60% is deposited in the OSMO/USDC vault directly
+        let CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            msg,
+            funds,
+        }) = &response.messages[1].msg
+        else {
+            panic!("expected a wasm execute")
+        };
+        assert_eq!(contract_addr, VAULT_A);
+        assert_eq!(funds, &vec![coin(600, BASE_DENOM)]);
+        assert!(matches!(
+            from_json(msg).unwrap(),
+            VaultExecuteMsg::AnyDeposit {
+                recipient: None,
+                ..
+            }
+        ));
+
+        // the remaining 40% is swapped into ATOM for the ATOM/USDC vault
+        let swap = &response.messages[2];
+        assert_eq!(swap.reply_on, ReplyOn::Success);
+        let CosmosMsg::Wasm(WasmMsg::Execute { msg, funds, .. }) = &swap.msg else {
+            panic!("expected a wasm execute")
+        };
+        assert_eq!(funds, &vec![coin(401, BASE_DENOM)]);
+        assert_eq!(
+            from_json::<DexRouterExecuteMsg>(msg).unwrap(),
+            DexRouterExecuteMsg::Swap {
+                out_denom: ATOM.to_string(),
+                path: Some(vec![]),
+                minimum_receive: Some(Uint128::new(396)),
+            }
+        );
+
+        // the swap output is deposited in the reply
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, vec![coin(400, ATOM)]);
+        let response = handle_swap_deposit_reply(deps.as_mut(), env).unwrap();
+        let CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            funds,
+            ..
+        }) = &response.messages[0].msg
+        else {
+            panic!("expected a wasm execute")
+        };
+        assert_eq!(contract_addr, VAULT_B);
+        assert_eq!(funds, &vec![coin(400, ATOM)]);
+        assert!(PENDING_SWAP_DEPOSITS
+            .front(deps.as_ref().storage)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn deposit_mints_shares_relative_to_total_value() {
+        let mut deps = mock_deps_with_vaults(
+            vec![
+                (
+                    VAULT_A,
+                    Holding {
+                        shares: 10,
+                        assets: vec![coin(1000, BASE_DENOM), coin(500, USDC)],
+                        locked: false,
+                    },
+                ),
+                (
+                    VAULT_B,
+                    Holding {
+                        shares: 10,
+                        assets: vec![coin(500, ATOM)],
+                        locked: false,
+                    },
+                ),
+            ],
+            &[],
+        );
+        instantiate_contract(deps.as_mut());
+        TOTAL_SHARES
+            .save(deps.as_mut().storage, &Uint128::new(1000))
+            .unwrap();
+
+        execute_deposit(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[coin(500, BASE_DENOM)]),
+            Some("bob".to_string()),
+        )
+        .unwrap();
+
+        // total value is 2000, so 500 base mints a quarter of the existing shares
+        assert_eq!(
+            TOTAL_SHARES.load(deps.as_ref().storage).unwrap(),
+            Uint128::new(1250)
+        );
+
+        // holdings are valued at the TWAP instead of the dex router return, so the 1000 of
+        // other tokens are worth 500 base when their TWAP halves
+        deps.querier.twap_price = Decimal::percent(50);
+        execute_deposit(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[coin(500, BASE_DENOM)]),
+            None,
+        )
+        .unwrap();
+        // the total value drops from 2000 to 1500 base, which mints 500 / 1500 of the shares
+        assert_eq!(
+            TOTAL_SHARES.load(deps.as_ref().storage).unwrap(),
+            Uint128::new(1250 + 416)
+        );
+
+        // only the base asset is accepted
+        let err = execute_deposit(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[coin(500, ATOM)]),
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Payment(_)));
+    }
+}
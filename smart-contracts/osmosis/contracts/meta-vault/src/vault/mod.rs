@@ -0,0 +1,4 @@
+pub mod admin;
+pub mod deposit;
+pub mod rebalance;
+pub mod redeem;
@@ -0,0 +1,277 @@
+use cl_vault::msg::RedeemReceiveMsg;
+use cosmwasm_std::{from_json, to_json_binary, DepsMut, Env, MessageInfo, Response, Uint128};
+use quasar_types::callback::{Callback, UnbondResponse};
+
+use crate::{
+    helpers::{deposit_msg, find_allocation, redeem_and_call_msg, swap_deposit_msg},
+    msg::RedeemCallback,
+    state::{PendingRebalance, ALLOCATIONS, CONFIG, PENDING_REBALANCE, REBALANCE_COUNT},
+    vault::{admin::assert_admin, redeem::execute_forward_redeem},
+    ContractError,
+};
+
+/// Redeem `shares` of `from_vault` back to the meta vault through `RedeemAndCall`. The vault
+/// sends the assets along with an unbond callback once they are paid out, which deposits them
+/// into `to_vault`. A redeem the vault would queue is rejected by the vault, so the rebalance
+/// either completes in the same transaction or fails as a whole.
+pub fn execute_rebalance(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    from_vault: String,
+    to_vault: String,
+    shares: Uint128,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+    if shares.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+
+    let allocations = ALLOCATIONS.load(deps.storage)?;
+    let from_vault = find_allocation(&allocations, &deps.api.addr_validate(&from_vault)?)?.vault;
+    let to_vault = find_allocation(&allocations, &deps.api.addr_validate(&to_vault)?)?.vault;
+
+    let id = REBALANCE_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    REBALANCE_COUNT.save(deps.storage, &id)?;
+    PENDING_REBALANCE.save(
+        deps.storage,
+        &PendingRebalance {
+            id,
+            from_vault: from_vault.clone(),
+            to_vault: to_vault.clone(),
+        },
+    )?;
+
+    let callback = to_json_binary(&Callback::UnbondResponse(UnbondResponse {
+        unbond_id: id.to_string(),
+    }))?;
+
+    Ok(Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "rebalance")
+        .add_attribute("rebalance_id", id.to_string())
+        .add_attribute("from_vault", from_vault.as_str())
+        .add_attribute("to_vault", to_vault.as_str())
+        .add_attribute("shares", shares)
+        .add_message(redeem_and_call_msg(
+            &from_vault,
+            shares,
+            &env.contract.address,
+            callback,
+        )?))
+}
+
+/// Handle the assets an underlying vault redeemed for the meta vault, the callback inside the
+/// message tells whether they belong to a redeem or to an async step
+pub fn execute_receive_redeem(
+    deps: DepsMut,
+    info: MessageInfo,
+    receive: RedeemReceiveMsg,
+) -> Result<Response, ContractError> {
+    if let Ok(RedeemCallback::Forward { recipient }) = from_json(&receive.msg) {
+        return execute_forward_redeem(deps.as_ref(), info, recipient);
+    }
+    match from_json(&receive.msg)? {
+        Callback::UnbondResponse(UnbondResponse { unbond_id }) => {
+            handle_rebalance_unbond(deps, info, unbond_id)
+        }
+        Callback::BondResponse(response) => Err(ContractError::UnexpectedCallback {
+            id: response.bond_id,
+        }),
+        Callback::StartUnbondResponse(response) => Err(ContractError::UnexpectedCallback {
+            id: response.unbond_id,
+        }),
+    }
+}
+
+/// Deposit the assets sent along by the `from_vault` of the pending rebalance into its
+/// `to_vault`. Tokens of the target pool are deposited directly, the other tokens are swapped
+/// into the token0 of the target pool first. Only the sent assets are moved, any other balance
+/// of the meta vault is left alone.
+fn handle_rebalance_unbond(
+    deps: DepsMut,
+    info: MessageInfo,
+    unbond_id: String,
+) -> Result<Response, ContractError> {
+    let pending = PENDING_REBALANCE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingRebalance {})?;
+    if info.sender != pending.from_vault {
+        return Err(ContractError::Unauthorized {});
+    }
+    if pending.id.to_string() != unbond_id {
+        return Err(ContractError::UnexpectedCallback { id: unbond_id });
+    }
+    let received: Vec<_> = info
+        .funds
+        .into_iter()
+        .filter(|c| !c.amount.is_zero())
+        .collect();
+    if received.is_empty() {
+        return Err(ContractError::NothingRedeemed { id: unbond_id });
+    }
+    PENDING_REBALANCE.remove(deps.storage);
+
+    let config = CONFIG.load(deps.storage)?;
+    let allocations = ALLOCATIONS.load(deps.storage)?;
+    let to = find_allocation(&allocations, &pending.to_vault)?;
+
+    let mut response = Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "rebalance_callback")
+        .add_attribute("rebalance_id", unbond_id);
+
+    let mut direct = vec![];
+    let mut swaps = vec![];
+    for received in received {
+        if to.contains(&received.denom) {
+            direct.push(received);
+        } else {
+            swaps.push(swap_deposit_msg(
+                deps.storage,
+                &deps.querier,
+                &config,
+                &to.vault,
+                received,
+                &to.token0,
+            )?);
+        }
+    }
+
+    if !direct.is_empty() {
+        response =
+            response.add_message(deposit_msg(&to.vault, direct, config.deposit_max_slippage)?);
+    }
+    Ok(response.add_submessages(swaps))
+}
+
+#[cfg(test)]
+mod tests {
+    use cl_vault::msg::{ExecuteMsg as VaultExecuteMsg, ExtensionExecuteMsg};
+    use cosmwasm_std::{
+        coin, from_json,
+        testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR},
+        CosmosMsg, WasmMsg,
+    };
+
+    use super::*;
+    use crate::test_helpers::{
+        instantiate_contract, mock_deps_with_vaults, ADMIN, ATOM, BASE_DENOM, USDC, VAULT_A,
+        VAULT_B,
+    };
+
+    #[test]
+    fn rebalance_redeems_and_deposits_in_callback() {
+        let mut deps = mock_deps_with_vaults(vec![], &[]);
+        instantiate_contract(deps.as_mut());
+        let env = mock_env();
+
+        let err = execute_rebalance(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            VAULT_A.to_string(),
+            VAULT_B.to_string(),
+            Uint128::new(100),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let response = execute_rebalance(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADMIN, &[]),
+            VAULT_A.to_string(),
+            VAULT_B.to_string(),
+            Uint128::new(100),
+        )
+        .unwrap();
+        assert_eq!(response.messages.len(), 1);
+        let CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) = &response.messages[0].msg
+        else {
+            panic!("expected a wasm execute")
+        };
+        assert_eq!(contract_addr, VAULT_A);
+        let VaultExecuteMsg::VaultExtension(ExtensionExecuteMsg::RedeemAndCall {
+            amount,
+            contract,
+            msg,
+        }) = from_json(msg).unwrap()
+        else {
+            panic!("expected a redeem and call")
+        };
+        assert_eq!(amount, Uint128::new(100));
+        assert_eq!(contract, MOCK_CONTRACT_ADDR);
+        let receive = RedeemReceiveMsg {
+            owner: MOCK_CONTRACT_ADDR.to_string(),
+            shares: amount,
+            msg,
+        };
+
+        // only the vault that is redeemed from can send the assets
+        let err = execute_receive_redeem(
+            deps.as_mut(),
+            mock_info(VAULT_B, &[coin(100, BASE_DENOM)]),
+            receive.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // a redeem that paid out nothing fails the rebalance
+        let err = execute_receive_redeem(deps.as_mut(), mock_info(VAULT_A, &[]), receive.clone())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NothingRedeemed {
+                id: "1".to_string()
+            }
+        );
+
+        // the redeemed OSMO is swapped into ATOM, the USDC is deposited directly, any other
+        // balance of the meta vault is left alone
+        deps.querier.update_balance(
+            MOCK_CONTRACT_ADDR,
+            vec![coin(1100, BASE_DENOM), coin(550, USDC)],
+        );
+        let response = execute_receive_redeem(
+            deps.as_mut(),
+            mock_info(VAULT_A, &[coin(100, BASE_DENOM), coin(50, USDC)]),
+            receive.clone(),
+        )
+        .unwrap();
+        assert_eq!(response.messages.len(), 2);
+        let CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            funds,
+            ..
+        }) = &response.messages[0].msg
+        else {
+            panic!("expected a wasm execute")
+        };
+        assert_eq!(contract_addr, VAULT_B);
+        assert_eq!(funds, &vec![coin(50, USDC)]);
+        let CosmosMsg::Wasm(WasmMsg::Execute { funds, .. }) = &response.messages[1].msg else {
+            panic!("expected a wasm execute")
+        };
+        assert_eq!(funds, &vec![coin(100, BASE_DENOM)]);
+        assert_eq!(
+            crate::state::PENDING_SWAP_DEPOSITS
+                .front(deps.as_ref().storage)
+                .unwrap()
+                .unwrap()
+                .denom,
+            ATOM
+        );
+
+        // a callback is only handled once
+        let err = execute_receive_redeem(
+            deps.as_mut(),
+            mock_info(VAULT_A, &[coin(100, BASE_DENOM)]),
+            receive,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoPendingRebalance {});
+    }
+}
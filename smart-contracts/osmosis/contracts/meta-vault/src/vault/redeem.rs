@@ -0,0 +1,392 @@
+use cosmwasm_std::{
+    coin, to_json_binary, BankMsg, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdError,
+    SubMsg,
+};
+use cw_utils::{must_pay, nonpayable};
+use osmosis_std::types::osmosis::tokenfactory::v1beta1::MsgBurn;
+use quasar_types::cw_vault_multi_standard::extensions::lockup::{
+    UNLOCKING_POSITION_ATTR_KEY, UNLOCKING_POSITION_CREATED_EVENT_TYPE,
+};
+
+use crate::{
+    helpers::{
+        query_vault_locked, query_vault_shares, redeem_and_call_msg, unlock_msg,
+        withdraw_unlocked_msg, UNLOCK_REPLY_ID,
+    },
+    msg::RedeemCallback,
+    state::{
+        PendingUnlock, ALLOCATIONS, PENDING_UNLOCKS, SHARE_DENOM, TOTAL_SHARES, UNLOCKING_REDEEMS,
+    },
+    ContractError,
+};
+
+/// Burn the sent along shares and redeem their part of every underlying vault. The vaults pay
+/// the redeem out to the meta vault through `RedeemAndCall`, which forwards the assets to the
+/// recipient. A vault rejects a `RedeemAndCall` it would queue, which fails the redeem as a whole
+/// instead of leaving a withdraw request only the meta vault could claim. Shares of a vault in
+/// lockup mode are unlocked instead, the unlocking position is withdrawn to the recipient
+/// through `ClaimUnlocked`.
+pub fn execute_redeem(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    let share_denom = SHARE_DENOM.load(deps.storage)?;
+    let shares = must_pay(&info, &share_denom)?;
+    let recipient = recipient.map_or(Ok(info.sender.clone()), |x| deps.api.addr_validate(&x))?;
+
+    let total_shares = TOTAL_SHARES.load(deps.storage)?;
+    TOTAL_SHARES.save(deps.storage, &total_shares.checked_sub(shares)?)?;
+
+    let mut response = Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "redeem")
+        .add_attribute("shares", shares)
+        .add_attribute("recipient", recipient.as_str())
+        .add_message(MsgBurn {
+            sender: env.contract.address.to_string(),
+            amount: Some(coin(shares.u128(), share_denom).into()),
+            burn_from_address: env.contract.address.to_string(),
+        });
+
+    let callback = to_json_binary(&RedeemCallback::Forward {
+        recipient: recipient.to_string(),
+    })?;
+    for allocation in ALLOCATIONS.load(deps.storage)? {
+        let vault_shares =
+            query_vault_shares(&deps.querier, &allocation.vault, &env.contract.address)?;
+        let amount = vault_shares.checked_multiply_ratio(shares, total_shares)?;
+        if amount.is_zero() {
+            continue;
+        }
+        if query_vault_locked(&deps.querier, &allocation.vault) {
+            PENDING_UNLOCKS.push_back(
+                deps.storage,
+                &PendingUnlock {
+                    vault: allocation.vault.clone(),
+                    recipient: recipient.clone(),
+                },
+            )?;
+            response = response.add_submessage(SubMsg::reply_on_success(
+                unlock_msg(&allocation.vault, amount)?,
+                UNLOCK_REPLY_ID,
+            ));
+        } else {
+            response = response.add_message(redeem_and_call_msg(
+                &allocation.vault,
+                amount,
+                &env.contract.address,
+                callback.clone(),
+            )?);
+        }
+    }
+
+    Ok(response)
+}
+
+/// Send the assets an underlying vault paid out for a redeem on to its recipient
+pub fn execute_forward_redeem(
+    deps: Deps,
+    info: MessageInfo,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    if !ALLOCATIONS
+        .load(deps.storage)?
+        .iter()
+        .any(|a| a.vault == info.sender)
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+    let recipient = deps.api.addr_validate(&recipient)?;
+    let received: Vec<_> = info
+        .funds
+        .into_iter()
+        .filter(|c| !c.amount.is_zero())
+        .collect();
+
+    let response = Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "forward_redeem")
+        .add_attribute("vault", info.sender.as_str())
+        .add_attribute("recipient", recipient.as_str());
+    if received.is_empty() {
+        return Ok(response);
+    }
+    Ok(response.add_message(BankMsg::Send {
+        to_address: recipient.to_string(),
+        amount: received,
+    }))
+}
+
+/// Record the unlocking position a vault in lockup mode created for a redeem, so its recipient
+/// can claim it once it is unlocked
+pub fn handle_unlock_reply(deps: DepsMut, reply: Reply) -> Result<Response, ContractError> {
+    let pending = PENDING_UNLOCKS
+        .pop_front(deps.storage)?
+        .ok_or(ContractError::NoPendingUnlock {})?;
+
+    let event_type = format!("wasm-{UNLOCKING_POSITION_CREATED_EVENT_TYPE}");
+    let lockup_id = reply
+        .result
+        .into_result()
+        .map_err(StdError::generic_err)?
+        .events
+        .iter()
+        .filter(|event| event.ty == event_type)
+        .flat_map(|event| &event.attributes)
+        .find(|attr| attr.key == UNLOCKING_POSITION_ATTR_KEY)
+        .and_then(|attr| attr.value.parse::<u64>().ok())
+        .ok_or(ContractError::MissingLockupId {
+            vault: pending.vault.to_string(),
+        })?;
+    UNLOCKING_REDEEMS.save(
+        deps.storage,
+        (&pending.vault, lockup_id),
+        &pending.recipient,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "reply")
+        .add_attribute("action", "unlock")
+        .add_attribute("vault", pending.vault.as_str())
+        .add_attribute("lockup_id", lockup_id.to_string())
+        .add_attribute("recipient", pending.recipient.as_str()))
+}
+
+/// Withdraw an unlocking position a redeem left in a vault in lockup mode to the recipient of
+/// that redeem. The vault rejects the withdraw while the position is still unlocking.
+pub fn execute_claim_unlocked(
+    deps: DepsMut,
+    info: MessageInfo,
+    vault: String,
+    lockup_id: u64,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+    let vault = deps.api.addr_validate(&vault)?;
+    let recipient = UNLOCKING_REDEEMS
+        .may_load(deps.storage, (&vault, lockup_id))?
+        .ok_or(ContractError::UnlockingRedeemNotFound {
+            vault: vault.to_string(),
+            lockup_id,
+        })?;
+    UNLOCKING_REDEEMS.remove(deps.storage, (&vault, lockup_id));
+
+    Ok(Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "claim_unlocked")
+        .add_attribute("vault", vault.as_str())
+        .add_attribute("lockup_id", lockup_id.to_string())
+        .add_attribute("recipient", recipient.as_str())
+        .add_message(withdraw_unlocked_msg(&vault, lockup_id, &recipient)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use cl_vault::msg::{ExecuteMsg as VaultExecuteMsg, ExtensionExecuteMsg, RedeemReceiveMsg};
+    use cosmwasm_std::{
+        coin, from_json,
+        testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR},
+        Addr, CosmosMsg, Event, SubMsgResponse, SubMsgResult, Uint128, WasmMsg,
+    };
+    use quasar_types::cw_vault_multi_standard::extensions::lockup::LockupExecuteMsg;
+
+    use super::*;
+    use crate::{
+        contract::{execute, reply},
+        msg::ExecuteMsg,
+        test_helpers::{
+            instantiate_contract, mock_deps_with_vaults, Holding, BASE_DENOM, USDC, VAULT_A,
+            VAULT_B,
+        },
+    };
+
+    #[test]
+    fn redeem_withdraws_share_of_every_vault() {
+        let mut deps = mock_deps_with_vaults(
+            vec![
+                (
+                    VAULT_A,
+                    Holding {
+                        shares: 600,
+                        assets: vec![],
+                        locked: false,
+                    },
+                ),
+                (
+                    VAULT_B,
+                    Holding {
+                        shares: 3,
+                        assets: vec![],
+                        locked: false,
+                    },
+                ),
+            ],
+            &[],
+        );
+        instantiate_contract(deps.as_mut());
+        TOTAL_SHARES
+            .save(deps.as_mut().storage, &Uint128::new(1000))
+            .unwrap();
+        let share_denom = SHARE_DENOM.load(deps.as_ref().storage).unwrap();
+
+        let response = execute_redeem(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[coin(250, &share_denom)]),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            TOTAL_SHARES.load(deps.as_ref().storage).unwrap(),
+            Uint128::new(750)
+        );
+        // burn plus a single redeem, the vault b part rounds down to zero
+        assert_eq!(response.messages.len(), 2);
+        let CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) = &response.messages[1].msg
+        else {
+            panic!("expected a wasm execute")
+        };
+        assert_eq!(contract_addr, VAULT_A);
+        // the vault pays out to the meta vault, a redeem the vault would queue is rejected
+        assert_eq!(
+            from_json::<VaultExecuteMsg>(msg).unwrap(),
+            VaultExecuteMsg::VaultExtension(ExtensionExecuteMsg::RedeemAndCall {
+                amount: Uint128::new(150),
+                contract: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_json_binary(&RedeemCallback::Forward {
+                    recipient: "alice".to_string(),
+                })
+                .unwrap(),
+            })
+        );
+    }
+
+    #[test]
+    fn redeemed_assets_are_forwarded_to_the_recipient() {
+        let mut deps = mock_deps_with_vaults(vec![], &[]);
+        instantiate_contract(deps.as_mut());
+        let receive = ExecuteMsg::ReceiveRedeem(RedeemReceiveMsg {
+            owner: MOCK_CONTRACT_ADDR.to_string(),
+            shares: Uint128::new(150),
+            msg: to_json_binary(&RedeemCallback::Forward {
+                recipient: "alice".to_string(),
+            })
+            .unwrap(),
+        });
+        let funds = vec![coin(100, BASE_DENOM), coin(50, USDC)];
+
+        // only the vaults can hand out redeems
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("mallory", &funds),
+            receive.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VAULT_A, &funds),
+            receive,
+        )
+        .unwrap();
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "alice".to_string(),
+                amount: funds,
+            })
+        );
+    }
+
+    #[test]
+    fn redeem_from_locked_vault_is_claimed_once_unlocked() {
+        let mut deps = mock_deps_with_vaults(
+            vec![(
+                VAULT_A,
+                Holding {
+                    shares: 600,
+                    assets: vec![],
+                    locked: true,
+                },
+            )],
+            &[],
+        );
+        instantiate_contract(deps.as_mut());
+        TOTAL_SHARES
+            .save(deps.as_mut().storage, &Uint128::new(1000))
+            .unwrap();
+        let share_denom = SHARE_DENOM.load(deps.as_ref().storage).unwrap();
+
+        let response = execute_redeem(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[coin(250, &share_denom)]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(response.messages[1].id, UNLOCK_REPLY_ID);
+        assert_eq!(
+            response.messages[1].msg,
+            unlock_msg(&Addr::unchecked(VAULT_A), Uint128::new(150)).unwrap()
+        );
+
+        reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: UNLOCK_REPLY_ID,
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![Event::new("wasm-unlocking_position_created")
+                        .add_attribute("lockup_id", "7")],
+                    data: None,
+                }),
+            },
+        )
+        .unwrap();
+        assert!(PENDING_UNLOCKS.is_empty(deps.as_ref().storage).unwrap());
+
+        let claim = ExecuteMsg::ClaimUnlocked {
+            vault: VAULT_A.to_string(),
+            lockup_id: 7,
+        };
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            claim.clone(),
+        )
+        .unwrap();
+        let CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) = &response.messages[0].msg
+        else {
+            panic!("expected a wasm execute")
+        };
+        assert_eq!(contract_addr, VAULT_A);
+        assert_eq!(
+            from_json::<VaultExecuteMsg>(msg).unwrap(),
+            VaultExecuteMsg::VaultExtension(ExtensionExecuteMsg::Lockup(
+                LockupExecuteMsg::WithdrawUnlocked {
+                    recipient: Some("alice".to_string()),
+                    lockup_id: 7,
+                }
+            ))
+        );
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), claim).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::UnlockingRedeemNotFound {
+                vault: VAULT_A.to_string(),
+                lockup_id: 7,
+            }
+        );
+    }
+}
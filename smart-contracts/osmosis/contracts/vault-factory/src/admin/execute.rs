@@ -0,0 +1,194 @@
+use cl_vault::state::VaultConfig;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{DepsMut, Empty, Env, MessageInfo, Response};
+
+use crate::{
+    state::{APPROVED_CODE_IDS, CONFIG_TEMPLATES, FACTORY_ADMIN},
+    ContractError,
+};
+
+use super::helpers::{is_contract_admin, is_factory_admin};
+
+#[cw_serde]
+pub enum AdminExecuteMsg {
+    /// Update the factory admin, only callable by the contract admin.
+    UpdateFactoryAdmin { new_admin: String },
+    /// Approve a cl-vault code id for new vaults.
+    AddCodeId { code_id: u64 },
+    /// Revoke the approval of a cl-vault code id, existing vaults are not affected.
+    RemoveCodeId { code_id: u64 },
+    /// Create or replace a named vault config template.
+    SetConfigTemplate { name: String, config: VaultConfig },
+    /// Remove a vault config template.
+    RemoveConfigTemplate { name: String },
+}
+
+pub fn execute_admin_msg(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    admin_msg: AdminExecuteMsg,
+) -> Result<Response, ContractError> {
+    match admin_msg {
+        AdminExecuteMsg::UpdateFactoryAdmin { new_admin } => {
+            update_factory_admin(deps, env, info, new_admin)
+        }
+        AdminExecuteMsg::AddCodeId { code_id } => add_code_id(deps, info, code_id),
+        AdminExecuteMsg::RemoveCodeId { code_id } => remove_code_id(deps, info, code_id),
+        AdminExecuteMsg::SetConfigTemplate { name, config } => {
+            set_config_template(deps, info, name, config)
+        }
+        AdminExecuteMsg::RemoveConfigTemplate { name } => remove_config_template(deps, info, name),
+    }
+}
+
+pub fn update_factory_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_admin: String,
+) -> Result<Response, ContractError> {
+    is_contract_admin(&deps.querier, &env, &info.sender)?;
+
+    FACTORY_ADMIN.save(deps.storage, &deps.api.addr_validate(&new_admin)?)?;
+
+    Ok(Response::default().add_attribute("new_factory_admin", new_admin))
+}
+
+pub fn add_code_id(
+    deps: DepsMut,
+    info: MessageInfo,
+    code_id: u64,
+) -> Result<Response, ContractError> {
+    is_factory_admin(deps.as_ref(), &info.sender)?;
+
+    APPROVED_CODE_IDS.save(deps.storage, code_id, &Empty {})?;
+
+    Ok(Response::default().add_attribute("approved_code_id", code_id.to_string()))
+}
+
+pub fn remove_code_id(
+    deps: DepsMut,
+    info: MessageInfo,
+    code_id: u64,
+) -> Result<Response, ContractError> {
+    is_factory_admin(deps.as_ref(), &info.sender)?;
+
+    APPROVED_CODE_IDS.remove(deps.storage, code_id);
+
+    Ok(Response::default().add_attribute("removed_code_id", code_id.to_string()))
+}
+
+pub fn set_config_template(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+    config: VaultConfig,
+) -> Result<Response, ContractError> {
+    is_factory_admin(deps.as_ref(), &info.sender)?;
+
+    // validate the addresses of the template, so vaults created from it can not fail on them
+    deps.api.addr_validate(config.treasury.as_str())?;
+    deps.api.addr_validate(config.dex_router.as_str())?;
+    deps.api.addr_validate(config.swap_admin.as_str())?;
+    CONFIG_TEMPLATES.save(deps.storage, &name, &config)?;
+
+    Ok(Response::default().add_attribute("config_template", name))
+}
+
+pub fn remove_config_template(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+) -> Result<Response, ContractError> {
+    is_factory_admin(deps.as_ref(), &info.sender)?;
+
+    if !CONFIG_TEMPLATES.has(deps.storage, &name) {
+        return Err(ContractError::ConfigTemplateNotFound { name });
+    }
+    CONFIG_TEMPLATES.remove(deps.storage, &name);
+
+    Ok(Response::default().add_attribute("removed_config_template", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use cl_vault::state::FeeDestination;
+    use cosmwasm_std::{
+        testing::{mock_dependencies, mock_info},
+        Addr, Decimal,
+    };
+
+    use super::*;
+
+    fn template() -> VaultConfig {
+        VaultConfig {
+            performance_fee: Decimal::percent(20),
+            treasury: Addr::unchecked("treasury"),
+            swap_max_slippage: Decimal::percent(1),
+            dex_router: Addr::unchecked("dex_router"),
+            swap_admin: Addr::unchecked("swap_admin"),
+            twap_window_seconds: 24,
+            deposit_fee_bps: 0,
+            exit_fee_bps: 0,
+            deposit_exit_fee_destination: FeeDestination::Treasury,
+            referral_fee_share: Decimal::zero(),
+        }
+    }
+
+    #[test]
+    fn test_code_ids_and_templates_are_admin_only() {
+        let mut deps = mock_dependencies();
+        FACTORY_ADMIN
+            .save(deps.as_mut().storage, &Addr::unchecked("admin"))
+            .unwrap();
+
+        let err = add_code_id(deps.as_mut(), mock_info("not_admin", &[]), 1).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = set_config_template(
+            deps.as_mut(),
+            mock_info("not_admin", &[]),
+            "default".to_string(),
+            template(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        add_code_id(deps.as_mut(), mock_info("admin", &[]), 1).unwrap();
+        assert!(APPROVED_CODE_IDS.has(deps.as_ref().storage, 1));
+        remove_code_id(deps.as_mut(), mock_info("admin", &[]), 1).unwrap();
+        assert!(!APPROVED_CODE_IDS.has(deps.as_ref().storage, 1));
+
+        set_config_template(
+            deps.as_mut(),
+            mock_info("admin", &[]),
+            "default".to_string(),
+            template(),
+        )
+        .unwrap();
+        assert_eq!(
+            CONFIG_TEMPLATES
+                .load(deps.as_ref().storage, "default")
+                .unwrap(),
+            template()
+        );
+        remove_config_template(
+            deps.as_mut(),
+            mock_info("admin", &[]),
+            "default".to_string(),
+        )
+        .unwrap();
+        let err = remove_config_template(
+            deps.as_mut(),
+            mock_info("admin", &[]),
+            "default".to_string(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ConfigTemplateNotFound {
+                name: "default".to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use crate::{error::ContractError, state::FACTORY_ADMIN};
+use cosmwasm_std::{Addr, Deps, Env, QuerierWrapper};
+
+pub fn is_contract_admin(
+    querier: &QuerierWrapper,
+    env: &Env,
+    sus_admin: &Addr,
+) -> Result<(), ContractError> {
+    let contract_admin = querier
+        .query_wasm_contract_info(&env.contract.address)?
+        .admin;
+    if let Some(contract_admin) = contract_admin {
+        if contract_admin != *sus_admin {
+            return Err(ContractError::Unauthorized {});
+        }
+    } else {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+pub fn is_factory_admin(deps: Deps, sus_admin: &Addr) -> Result<(), ContractError> {
+    let factory_admin = FACTORY_ADMIN.load(deps.storage)?;
+    if factory_admin != sus_admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
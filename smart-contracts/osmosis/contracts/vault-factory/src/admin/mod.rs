@@ -0,0 +1,3 @@
+pub mod execute;
+pub(crate) mod helpers;
+pub mod query;
@@ -0,0 +1,92 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{to_json_binary, Addr, Binary, Deps, Env, Order, StdResult};
+use cw_storage_plus::Bound;
+
+use cl_vault::state::VaultConfig;
+
+use crate::registry::query::{DEFAULT_LIMIT, MAX_LIMIT};
+use crate::state::{APPROVED_CODE_IDS, CONFIG_TEMPLATES, FACTORY_ADMIN};
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum AdminQueryMsg {
+    /// Get the factory admin
+    #[returns(Addr)]
+    FactoryAdmin {},
+    /// Get the approved cl-vault code ids
+    #[returns(CodeIdsResponse)]
+    CodeIds {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Get a vault config template by name
+    #[returns(VaultConfig)]
+    ConfigTemplate { name: String },
+    /// Get the vault config templates, ordered by name
+    #[returns(ConfigTemplatesResponse)]
+    ConfigTemplates {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct CodeIdsResponse {
+    pub code_ids: Vec<u64>,
+}
+
+#[cw_serde]
+pub struct ConfigTemplatesResponse {
+    pub templates: Vec<(String, VaultConfig)>,
+}
+
+pub fn query_admin(deps: Deps, _env: Env, query_msg: AdminQueryMsg) -> StdResult<Binary> {
+    match query_msg {
+        AdminQueryMsg::FactoryAdmin {} => to_json_binary(&FACTORY_ADMIN.load(deps.storage)?),
+        AdminQueryMsg::CodeIds { start_after, limit } => {
+            to_json_binary(&query_code_ids(deps, start_after, limit)?)
+        }
+        AdminQueryMsg::ConfigTemplate { name } => {
+            to_json_binary(&CONFIG_TEMPLATES.load(deps.storage, &name)?)
+        }
+        AdminQueryMsg::ConfigTemplates { start_after, limit } => {
+            to_json_binary(&query_config_templates(deps, start_after, limit)?)
+        }
+    }
+}
+
+pub fn query_code_ids(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<CodeIdsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let code_ids = APPROVED_CODE_IDS
+        .keys(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+    Ok(CodeIdsResponse { code_ids })
+}
+
+pub fn query_config_templates(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ConfigTemplatesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let templates = CONFIG_TEMPLATES
+        .range(
+            deps.storage,
+            start_after.as_deref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+    Ok(ConfigTemplatesResponse { templates })
+}
@@ -0,0 +1,73 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult};
+use cw2::set_contract_version;
+
+use crate::admin::execute::execute_admin_msg;
+use crate::admin::query::query_admin;
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::registry::execute::{
+    execute_registry_msg, handle_instantiate_vault_reply, INSTANTIATE_VAULT_REPLY_ID,
+};
+use crate::registry::query::query_registry;
+use crate::state::FACTORY_ADMIN;
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:vault-factory";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    FACTORY_ADMIN.save(deps.storage, &deps.api.addr_validate(&msg.admin)?)?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::RegistryMsg(registry_msg) => {
+            execute_registry_msg(deps, env, info, registry_msg)
+        }
+        ExecuteMsg::AdminMsg(admin_msg) => execute_admin_msg(deps, env, info, admin_msg),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::RegistryQuery(registry_query) => query_registry(deps, env, registry_query),
+        QueryMsg::AdminQuery(admin_query) => query_admin(deps, env, admin_query),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        INSTANTIATE_VAULT_REPLY_ID => handle_instantiate_vault_reply(deps, env, msg),
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let old_version =
+        cw2::ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("old version", old_version.to_string())
+        .add_attribute("new version", CONTRACT_VERSION))
+}
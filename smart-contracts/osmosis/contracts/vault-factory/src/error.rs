@@ -0,0 +1,34 @@
+use cosmwasm_std::StdError;
+use cw2::VersionError;
+use cw_utils::ParseReplyError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Version(#[from] VersionError),
+
+    #[error("{0}")]
+    ParseReply(#[from] ParseReplyError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Code id {code_id} is not approved for new vaults")]
+    CodeIdNotApproved { code_id: u64 },
+
+    #[error("Config template {name} not found")]
+    ConfigTemplateNotFound { name: String },
+
+    #[error("Vault {address} is not registered")]
+    VaultNotFound { address: String },
+
+    #[error("Vault {address} is already registered")]
+    VaultAlreadyRegistered { address: String },
+
+    #[error("Unknown reply id {id}")]
+    UnknownReplyId { id: u64 },
+}
@@ -0,0 +1,36 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+#[cfg(not(target_arch = "wasm32"))]
+use cosmwasm_std::Empty;
+
+use crate::{
+    admin::{execute::AdminExecuteMsg, query::AdminQueryMsg},
+    registry::{execute::RegistryExecuteMsg, query::RegistryQueryMsg},
+};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// Address that manages code ids, config templates and the vault registry
+    pub admin: String,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// vault creation and registry operations
+    RegistryMsg(RegistryExecuteMsg),
+    /// admin operations
+    AdminMsg(AdminExecuteMsg),
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// registry queries
+    #[returns(Empty)]
+    RegistryQuery(RegistryQueryMsg),
+    /// admin queries
+    #[returns(Empty)]
+    AdminQuery(AdminQueryMsg),
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
@@ -0,0 +1,451 @@
+use cl_vault::{
+    msg::{
+        ClQueryMsg, ExtensionQueryMsg, InstantiateMsg as VaultInstantiateMsg,
+        QueryMsg as VaultQueryMsg,
+    },
+    query::PoolResponse,
+    state::{FeeDestination, VaultConfig},
+};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_json_binary, Addr, Api, Decimal, DepsMut, Empty, Env, MessageInfo, Reply, Response,
+    StdResult, SubMsg, WasmMsg,
+};
+use cw_utils::parse_reply_instantiate_data;
+
+use crate::{
+    admin::helpers::is_factory_admin,
+    state::{
+        PendingVault, VaultRecord, APPROVED_CODE_IDS, CONFIG_TEMPLATES, PENDING_VAULT, POOL_VAULTS,
+        VAULTS,
+    },
+    ContractError,
+};
+
+pub const INSTANTIATE_VAULT_REPLY_ID: u64 = 1;
+
+/// Per vault overrides of the fields of a config template
+#[cw_serde]
+#[derive(Default)]
+pub struct VaultConfigOverrides {
+    pub performance_fee: Option<Decimal>,
+    pub treasury: Option<String>,
+    pub swap_max_slippage: Option<Decimal>,
+    pub dex_router: Option<String>,
+    pub swap_admin: Option<String>,
+    pub twap_window_seconds: Option<u64>,
+    pub deposit_fee_bps: Option<u16>,
+    pub exit_fee_bps: Option<u16>,
+    pub deposit_exit_fee_destination: Option<FeeDestination>,
+    pub referral_fee_share: Option<Decimal>,
+}
+
+impl VaultConfigOverrides {
+    pub fn apply(self, api: &dyn Api, config: VaultConfig) -> StdResult<VaultConfig> {
+        let validate = |address: Option<String>, default: Addr| {
+            address.map_or(Ok(default), |address| api.addr_validate(&address))
+        };
+        Ok(VaultConfig {
+            performance_fee: self.performance_fee.unwrap_or(config.performance_fee),
+            treasury: validate(self.treasury, config.treasury)?,
+            swap_max_slippage: self.swap_max_slippage.unwrap_or(config.swap_max_slippage),
+            dex_router: validate(self.dex_router, config.dex_router)?,
+            swap_admin: validate(self.swap_admin, config.swap_admin)?,
+            twap_window_seconds: self
+                .twap_window_seconds
+                .unwrap_or(config.twap_window_seconds),
+            deposit_fee_bps: self.deposit_fee_bps.unwrap_or(config.deposit_fee_bps),
+            exit_fee_bps: self.exit_fee_bps.unwrap_or(config.exit_fee_bps),
+            deposit_exit_fee_destination: self
+                .deposit_exit_fee_destination
+                .unwrap_or(config.deposit_exit_fee_destination),
+            referral_fee_share: self.referral_fee_share.unwrap_or(config.referral_fee_share),
+        })
+    }
+}
+
+#[cw_serde]
+pub struct CreateVaultMsg {
+    /// The approved cl-vault code id to instantiate
+    pub code_id: u64,
+    /// The ID of the pool the vault provides liquidity to
+    pub pool_id: u64,
+    /// The config template the vault config starts from
+    pub template: String,
+    pub config_overrides: Option<VaultConfigOverrides>,
+    pub name: String,
+    pub thesis: String,
+    /// The vault admin, this is also set as the contract admin of the vault
+    pub admin: String,
+    pub range_admin: String,
+    pub vault_token_subdenom: String,
+    pub initial_lower_tick: i64,
+    pub initial_upper_tick: i64,
+    pub label: String,
+}
+
+#[cw_serde]
+pub enum RegistryExecuteMsg {
+    /// Instantiate a new cl-vault and add it to the registry, the funds sent along are forwarded
+    /// to the vault as its initial position
+    CreateVault(Box<CreateVaultMsg>),
+    /// Add a cl-vault that was not created by the factory to the registry
+    RegisterVault { address: String },
+    /// Tag a registered vault as deprecated, or remove the tag
+    SetVaultDeprecated { address: String, deprecated: bool },
+}
+
+pub fn execute_registry_msg(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    registry_msg: RegistryExecuteMsg,
+) -> Result<Response, ContractError> {
+    is_factory_admin(deps.as_ref(), &info.sender)?;
+    match registry_msg {
+        RegistryExecuteMsg::CreateVault(msg) => create_vault(deps, info, *msg),
+        RegistryExecuteMsg::RegisterVault { address } => register_vault(deps, env, address),
+        RegistryExecuteMsg::SetVaultDeprecated {
+            address,
+            deprecated,
+        } => set_vault_deprecated(deps, address, deprecated),
+    }
+}
+
+pub fn create_vault(
+    deps: DepsMut,
+    info: MessageInfo,
+    msg: CreateVaultMsg,
+) -> Result<Response, ContractError> {
+    if !APPROVED_CODE_IDS.has(deps.storage, msg.code_id) {
+        return Err(ContractError::CodeIdNotApproved {
+            code_id: msg.code_id,
+        });
+    }
+    let template = CONFIG_TEMPLATES
+        .may_load(deps.storage, &msg.template)?
+        .ok_or(ContractError::ConfigTemplateNotFound {
+            name: msg.template.clone(),
+        })?;
+    let config = msg
+        .config_overrides
+        .unwrap_or_default()
+        .apply(deps.api, template)?;
+    let admin = deps.api.addr_validate(&msg.admin)?;
+
+    PENDING_VAULT.save(
+        deps.storage,
+        &PendingVault {
+            pool_id: msg.pool_id,
+            code_id: msg.code_id,
+        },
+    )?;
+
+    let instantiate_msg = WasmMsg::Instantiate {
+        admin: Some(admin.to_string()),
+        code_id: msg.code_id,
+        msg: to_json_binary(&VaultInstantiateMsg {
+            thesis: msg.thesis,
+            name: msg.name,
+            admin: admin.to_string(),
+            range_admin: msg.range_admin,
+            pool_id: msg.pool_id,
+            config,
+            vault_token_subdenom: msg.vault_token_subdenom,
+            initial_lower_tick: msg.initial_lower_tick,
+            initial_upper_tick: msg.initial_upper_tick,
+        })?,
+        funds: info.funds,
+        label: msg.label,
+    };
+
+    Ok(Response::default()
+        .add_attribute("action", "create_vault")
+        .add_attribute("pool_id", msg.pool_id.to_string())
+        .add_attribute("code_id", msg.code_id.to_string())
+        .add_attribute("template", msg.template)
+        .add_submessage(SubMsg::reply_on_success(
+            instantiate_msg,
+            INSTANTIATE_VAULT_REPLY_ID,
+        )))
+}
+
+pub fn handle_instantiate_vault_reply(
+    deps: DepsMut,
+    env: Env,
+    msg: Reply,
+) -> Result<Response, ContractError> {
+    let address = deps
+        .api
+        .addr_validate(&parse_reply_instantiate_data(msg)?.contract_address)?;
+    let pending = PENDING_VAULT.load(deps.storage)?;
+    PENDING_VAULT.remove(deps.storage);
+
+    save_vault(
+        deps,
+        VaultRecord {
+            address: address.clone(),
+            pool_id: pending.pool_id,
+            code_id: pending.code_id,
+            registered_at: env.block.time.seconds(),
+            deprecated: false,
+        },
+    )?;
+
+    Ok(Response::default()
+        .add_attribute("action", "register_vault")
+        .add_attribute("vault", address))
+}
+
+pub fn register_vault(deps: DepsMut, env: Env, address: String) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    let code_id = deps.querier.query_wasm_contract_info(&address)?.code_id;
+    let pool: PoolResponse = deps.querier.query_wasm_smart(
+        &address,
+        &VaultQueryMsg::VaultExtension(ExtensionQueryMsg::ConcentratedLiquidity(
+            ClQueryMsg::Pool {},
+        )),
+    )?;
+
+    save_vault(
+        deps,
+        VaultRecord {
+            address: address.clone(),
+            pool_id: pool.pool_config.pool_id,
+            code_id,
+            registered_at: env.block.time.seconds(),
+            deprecated: false,
+        },
+    )?;
+
+    Ok(Response::default()
+        .add_attribute("action", "register_vault")
+        .add_attribute("vault", address))
+}
+
+pub fn set_vault_deprecated(
+    deps: DepsMut,
+    address: String,
+    deprecated: bool,
+) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    let mut vault =
+        VAULTS
+            .may_load(deps.storage, &address)?
+            .ok_or(ContractError::VaultNotFound {
+                address: address.to_string(),
+            })?;
+    vault.deprecated = deprecated;
+    VAULTS.save(deps.storage, &address, &vault)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "set_vault_deprecated")
+        .add_attribute("vault", address)
+        .add_attribute("deprecated", deprecated.to_string()))
+}
+
+fn save_vault(deps: DepsMut, vault: VaultRecord) -> Result<(), ContractError> {
+    if VAULTS.has(deps.storage, &vault.address) {
+        return Err(ContractError::VaultAlreadyRegistered {
+            address: vault.address.to_string(),
+        });
+    }
+    VAULTS.save(deps.storage, &vault.address, &vault)?;
+    POOL_VAULTS.save(deps.storage, (vault.pool_id, &vault.address), &Empty {})?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use cl_vault::state::PoolConfig;
+    use cosmwasm_std::{
+        coin, from_json,
+        testing::{mock_dependencies, mock_env, mock_info},
+        ContractInfoResponse, ContractResult, CosmosMsg, SubMsgResponse, SubMsgResult,
+        SystemResult, WasmQuery,
+    };
+
+    use crate::registry::query::{query_registry, RegistryQueryMsg, VaultsResponse};
+    use crate::state::FACTORY_ADMIN;
+
+    use super::*;
+
+    fn create_vault_msg(pool_id: u64) -> CreateVaultMsg {
+        CreateVaultMsg {
+            code_id: 7,
+            pool_id,
+            template: "default".to_string(),
+            config_overrides: Some(VaultConfigOverrides {
+                performance_fee: Some(Decimal::percent(10)),
+                ..Default::default()
+            }),
+            name: "vault".to_string(),
+            thesis: "thesis".to_string(),
+            admin: "vault_admin".to_string(),
+            range_admin: "range_admin".to_string(),
+            vault_token_subdenom: "vault".to_string(),
+            initial_lower_tick: 100,
+            initial_upper_tick: 1000,
+            label: "cl-vault".to_string(),
+        }
+    }
+
+    fn instantiate_reply(contract_address: &str) -> Reply {
+        // protobuf encoding of MsgInstantiateContractResponse { contract_address, data: [] }
+        let mut data = vec![0x0a, contract_address.len() as u8];
+        data.extend_from_slice(contract_address.as_bytes());
+        Reply {
+            id: INSTANTIATE_VAULT_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(data.into()),
+            }),
+        }
+    }
+
+    fn query_pool_vaults(deps: cosmwasm_std::Deps, pool_id: u64) -> Vec<VaultRecord> {
+        let res: VaultsResponse = from_json(
+            query_registry(
+                deps,
+                mock_env(),
+                RegistryQueryMsg::VaultsByPool {
+                    pool_id,
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.vaults
+    }
+
+    #[test]
+    fn test_create_vault_registers_vault_on_reply() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        FACTORY_ADMIN
+            .save(deps.as_mut().storage, &Addr::unchecked("admin"))
+            .unwrap();
+        let template = VaultConfig {
+            performance_fee: Decimal::percent(20),
+            treasury: Addr::unchecked("treasury"),
+            swap_max_slippage: Decimal::percent(1),
+            dex_router: Addr::unchecked("dex_router"),
+            swap_admin: Addr::unchecked("swap_admin"),
+            twap_window_seconds: 24,
+            deposit_fee_bps: 0,
+            exit_fee_bps: 0,
+            deposit_exit_fee_destination: FeeDestination::Treasury,
+            referral_fee_share: Decimal::zero(),
+        };
+        CONFIG_TEMPLATES
+            .save(deps.as_mut().storage, "default", &template)
+            .unwrap();
+
+        let funds = vec![coin(1000, "uatom"), coin(1000, "uosmo")];
+        let err = create_vault(
+            deps.as_mut(),
+            mock_info("admin", &funds),
+            create_vault_msg(1),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::CodeIdNotApproved { code_id: 7 });
+
+        APPROVED_CODE_IDS
+            .save(deps.as_mut().storage, 7, &Empty {})
+            .unwrap();
+        let res = create_vault(
+            deps.as_mut(),
+            mock_info("admin", &funds),
+            create_vault_msg(1),
+        )
+        .unwrap();
+        let CosmosMsg::Wasm(WasmMsg::Instantiate {
+            admin,
+            code_id,
+            msg,
+            funds: sent_funds,
+            ..
+        }) = res.messages[0].msg.clone()
+        else {
+            panic!("expected an instantiate message");
+        };
+        assert_eq!(admin, Some("vault_admin".to_string()));
+        assert_eq!(code_id, 7);
+        assert_eq!(sent_funds, funds);
+        let msg: VaultInstantiateMsg = from_json(msg).unwrap();
+        assert_eq!(msg.pool_id, 1);
+        assert_eq!(
+            msg.config,
+            VaultConfig {
+                performance_fee: Decimal::percent(10),
+                ..template
+            }
+        );
+
+        handle_instantiate_vault_reply(deps.as_mut(), env.clone(), instantiate_reply("vault1"))
+            .unwrap();
+        assert!(PENDING_VAULT
+            .may_load(deps.as_ref().storage)
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            query_pool_vaults(deps.as_ref(), 1),
+            vec![VaultRecord {
+                address: Addr::unchecked("vault1"),
+                pool_id: 1,
+                code_id: 7,
+                registered_at: env.block.time.seconds(),
+                deprecated: false,
+            }]
+        );
+        assert!(query_pool_vaults(deps.as_ref(), 2).is_empty());
+
+        set_vault_deprecated(deps.as_mut(), "vault1".to_string(), true).unwrap();
+        assert!(query_pool_vaults(deps.as_ref(), 1)[0].deprecated);
+        let err = set_vault_deprecated(deps.as_mut(), "vault2".to_string(), true).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::VaultNotFound {
+                address: "vault2".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_register_existing_vault() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::ContractInfo { .. } => {
+                let mut info = ContractInfoResponse::default();
+                info.code_id = 3;
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&info).unwrap()))
+            }
+            WasmQuery::Smart { .. } => SystemResult::Ok(ContractResult::Ok(
+                to_json_binary(&PoolResponse {
+                    pool_config: PoolConfig {
+                        pool_id: 5,
+                        token0: "uatom".to_string(),
+                        token1: "uosmo".to_string(),
+                    },
+                })
+                .unwrap(),
+            )),
+            _ => unimplemented!(),
+        });
+
+        register_vault(deps.as_mut(), mock_env(), "vault1".to_string()).unwrap();
+        let vaults = query_pool_vaults(deps.as_ref(), 5);
+        assert_eq!(vaults.len(), 1);
+        assert_eq!(vaults[0].code_id, 3);
+
+        let err = register_vault(deps.as_mut(), mock_env(), "vault1".to_string()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::VaultAlreadyRegistered {
+                address: "vault1".to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{to_json_binary, Addr, Binary, Deps, Env, Order, StdResult};
+use cw_storage_plus::Bound;
+
+use crate::state::{VaultRecord, POOL_VAULTS, VAULTS};
+
+pub const DEFAULT_LIMIT: u32 = 10;
+pub const MAX_LIMIT: u32 = 50;
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum RegistryQueryMsg {
+    /// Get a registered vault by address
+    #[returns(VaultRecord)]
+    Vault { address: String },
+    /// Get the registered vaults, ordered by address
+    #[returns(VaultsResponse)]
+    Vaults {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Get the registered vaults of a pool, ordered by address
+    #[returns(VaultsResponse)]
+    VaultsByPool {
+        pool_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct VaultsResponse {
+    pub vaults: Vec<VaultRecord>,
+}
+
+pub fn query_registry(deps: Deps, _env: Env, query_msg: RegistryQueryMsg) -> StdResult<Binary> {
+    match query_msg {
+        RegistryQueryMsg::Vault { address } => {
+            to_json_binary(&VAULTS.load(deps.storage, &deps.api.addr_validate(&address)?)?)
+        }
+        RegistryQueryMsg::Vaults { start_after, limit } => {
+            to_json_binary(&query_vaults(deps, start_after, limit)?)
+        }
+        RegistryQueryMsg::VaultsByPool {
+            pool_id,
+            start_after,
+            limit,
+        } => to_json_binary(&query_vaults_by_pool(deps, pool_id, start_after, limit)?),
+    }
+}
+
+pub fn query_vaults(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<VaultsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.map(Addr::unchecked);
+    let vaults = VAULTS
+        .range(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| Ok(item?.1))
+        .collect::<StdResult<_>>()?;
+    Ok(VaultsResponse { vaults })
+}
+
+pub fn query_vaults_by_pool(
+    deps: Deps,
+    pool_id: u64,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<VaultsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.map(Addr::unchecked);
+    let vaults = POOL_VAULTS
+        .prefix(pool_id)
+        .keys(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|address| VAULTS.load(deps.storage, &address?))
+        .collect::<StdResult<_>>()?;
+    Ok(VaultsResponse { vaults })
+}
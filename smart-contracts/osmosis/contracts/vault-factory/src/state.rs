@@ -0,0 +1,33 @@
+use cl_vault::state::VaultConfig;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Empty};
+use cw_storage_plus::{Item, Map};
+
+pub const FACTORY_ADMIN: Item<Addr> = Item::new("factory_admin");
+/// cl-vault code ids that new vaults can be instantiated from
+pub const APPROVED_CODE_IDS: Map<u64, Empty> = Map::new("approved_code_ids");
+/// named default vault configs, new vaults start from a template and apply their own overrides
+pub const CONFIG_TEMPLATES: Map<&str, VaultConfig> = Map::new("config_templates");
+
+#[cw_serde]
+pub struct VaultRecord {
+    pub address: Addr,
+    pub pool_id: u64,
+    pub code_id: u64,
+    /// block time at which the vault was added to the registry
+    pub registered_at: u64,
+    pub deprecated: bool,
+}
+
+pub const VAULTS: Map<&Addr, VaultRecord> = Map::new("vaults");
+/// index of the registered vaults by pool id
+pub const POOL_VAULTS: Map<(u64, &Addr), Empty> = Map::new("pool_vaults");
+
+#[cw_serde]
+pub struct PendingVault {
+    pub pool_id: u64,
+    pub code_id: u64,
+}
+
+/// the vault that is being instantiated, registered once the instantiate reply comes in
+pub const PENDING_VAULT: Item<PendingVault> = Item::new("pending_vault");
@@ -0,0 +1,119 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_json_binary, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+};
+use cw2::set_contract_version;
+
+use crate::error::ContractError;
+use crate::helpers::validate_slippage;
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::state::{Config, ADMIN, CONFIG};
+use crate::zap::{execute_callback, execute_receive_redeem, execute_zap_in};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:zap";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    validate_slippage(msg.swap_max_slippage)?;
+    ADMIN.save(deps.storage, &deps.api.addr_validate(&msg.admin)?)?;
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            dex_router: deps.api.addr_validate(&msg.dex_router)?,
+            swap_max_slippage: msg.swap_max_slippage,
+        },
+    )?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::ZapIn { targets, recipient } => {
+            execute_zap_in(deps, env, info, targets, recipient)
+        }
+        ExecuteMsg::ReceiveRedeem(msg) => execute_receive_redeem(deps, env, info, msg),
+        ExecuteMsg::Callback(callback) => execute_callback(deps, env, info, callback),
+        ExecuteMsg::UpdateConfig {
+            dex_router,
+            swap_max_slippage,
+        } => execute_update_config(deps, info, dex_router, swap_max_slippage),
+        ExecuteMsg::UpdateAdmin { address } => execute_update_admin(deps, info, address),
+    }
+}
+
+fn execute_update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    dex_router: Option<String>,
+    swap_max_slippage: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    if ADMIN.load(deps.storage)? != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut config = CONFIG.load(deps.storage)?;
+    if let Some(dex_router) = dex_router {
+        config.dex_router = deps.api.addr_validate(&dex_router)?;
+    }
+    if let Some(swap_max_slippage) = swap_max_slippage {
+        validate_slippage(swap_max_slippage)?;
+        config.swap_max_slippage = swap_max_slippage;
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "update_config")
+        .add_attribute("updates", format!("{:?}", config)))
+}
+
+fn execute_update_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    if ADMIN.load(deps.storage)? != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let new_admin = deps.api.addr_validate(&address)?;
+    ADMIN.save(deps.storage, &new_admin)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "update_admin")
+        .add_attribute("new_admin", new_admin.as_str()))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&CONFIG.load(deps.storage)?),
+        QueryMsg::Admin {} => to_json_binary(&ADMIN.load(deps.storage)?),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let old_version =
+        cw2::ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("old version", old_version.to_string())
+        .add_attribute("new version", CONTRACT_VERSION))
+}
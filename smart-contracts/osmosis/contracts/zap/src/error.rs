@@ -0,0 +1,50 @@
+use cosmwasm_std::{CheckedMultiplyFractionError, Coin, OverflowError, StdError, Uint128};
+use cw2::VersionError;
+use cw_utils::PaymentError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Version(#[from] VersionError),
+
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
+    #[error("{0}")]
+    CheckedMultiplyFraction(#[from] CheckedMultiplyFractionError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Zap needs at least one target")]
+    NoTargets {},
+
+    #[error("Funds of the targets {expected:?} do not match the sent funds {actual:?}")]
+    FundsMismatch {
+        expected: Vec<Coin>,
+        actual: Vec<Coin>,
+    },
+
+    #[error("Slippage should be >0 and <=1")]
+    InvalidSlippage {},
+
+    #[error("No swap path from {offer} to {ask} on the dex router")]
+    NoSwapPath { offer: String, ask: String },
+
+    #[error("Nothing to deposit into vault {vault}")]
+    NothingToDeposit { vault: String },
+
+    #[error("Vault {vault} minted {minted} shares, less than the minimum of {minimum}")]
+    MinSharesNotReached {
+        vault: String,
+        minted: Uint128,
+        minimum: Uint128,
+    },
+}
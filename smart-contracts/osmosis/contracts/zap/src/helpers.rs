@@ -0,0 +1,138 @@
+use cl_vault::{
+    msg::{
+        ClQueryMsg, ExecuteMsg as VaultExecuteMsg, ExtensionQueryMsg, QueryMsg as VaultQueryMsg,
+        UserBalanceQueryMsg,
+    },
+    query::{PoolResponse, UserSharesBalanceResponse},
+    state::PoolConfig,
+};
+use cosmwasm_std::{
+    to_json_binary, Addr, Coin, CosmosMsg, Decimal, Env, QuerierWrapper, StdResult, Uint128,
+    WasmMsg,
+};
+use dex_router_osmosis::msg::{
+    BestPathForPairResponse, ExecuteMsg as DexRouterExecuteMsg, QueryMsg as DexRouterQueryMsg,
+};
+
+use crate::{
+    msg::{ExecuteMsg, ZapCallback},
+    state::Config,
+    ContractError,
+};
+
+pub fn query_vault_pool(querier: &QuerierWrapper, vault: &Addr) -> StdResult<PoolConfig> {
+    let pool: PoolResponse = querier.query_wasm_smart(
+        vault,
+        &VaultQueryMsg::VaultExtension(ExtensionQueryMsg::ConcentratedLiquidity(
+            ClQueryMsg::Pool {},
+        )),
+    )?;
+    Ok(pool.pool_config)
+}
+
+pub fn query_vault_shares(
+    querier: &QuerierWrapper,
+    vault: &Addr,
+    user: &Addr,
+) -> StdResult<Uint128> {
+    let shares: UserSharesBalanceResponse = querier.query_wasm_smart(
+        vault,
+        &VaultQueryMsg::VaultExtension(ExtensionQueryMsg::Balances(
+            UserBalanceQueryMsg::UserSharesBalance {
+                user: user.to_string(),
+            },
+        )),
+    )?;
+    Ok(shares.balance)
+}
+
+/// Merge coins of the same denom and drop zero amounts, sorted by denom
+pub fn merge_coins<'a>(coins: impl IntoIterator<Item = &'a Coin>) -> StdResult<Vec<Coin>> {
+    let mut merged: Vec<Coin> = vec![];
+    for coin in coins.into_iter().filter(|c| !c.amount.is_zero()) {
+        match merged.iter_mut().find(|c| c.denom == coin.denom) {
+            Some(existing) => existing.amount = existing.amount.checked_add(coin.amount)?,
+            None => merged.push(coin.clone()),
+        }
+    }
+    merged.sort_by(|a, b| a.denom.cmp(&b.denom));
+    Ok(merged)
+}
+
+pub fn validate_slippage(slippage: Decimal) -> Result<(), ContractError> {
+    if slippage.is_zero() || slippage > Decimal::one() {
+        return Err(ContractError::InvalidSlippage {});
+    }
+    Ok(())
+}
+
+/// Swap `offer` into `out_denom` through the dex router, the output is sent back to the zap
+pub fn swap_msg(
+    querier: &QuerierWrapper,
+    config: &Config,
+    offer: Coin,
+    out_denom: &str,
+) -> Result<CosmosMsg, ContractError> {
+    let best_path: Option<BestPathForPairResponse> = querier.query_wasm_smart(
+        &config.dex_router,
+        &DexRouterQueryMsg::BestPathForPair {
+            offer: offer.clone(),
+            ask_denom: out_denom.to_string(),
+        },
+    )?;
+    let best_path = best_path.ok_or(ContractError::NoSwapPath {
+        offer: offer.denom.clone(),
+        ask: out_denom.to_string(),
+    })?;
+    let minimum_receive = best_path
+        .return_amount
+        .checked_mul_floor(config.swap_max_slippage)?;
+
+    Ok(WasmMsg::Execute {
+        contract_addr: config.dex_router.to_string(),
+        msg: to_json_binary(&DexRouterExecuteMsg::Swap {
+            out_denom: out_denom.to_string(),
+            path: Some(best_path.path),
+            minimum_receive: Some(minimum_receive),
+        })?,
+        funds: vec![offer],
+    }
+    .into())
+}
+
+/// Deposit `funds` into `vault` for `recipient`. A single token goes through `AnyDeposit`, which
+/// swaps it into the position ratio within the vault. Both tokens go through `ExactDeposit`,
+/// which refunds the part that does not fit the position to the recipient.
+pub fn deposit_msg(
+    vault: &Addr,
+    funds: Vec<Coin>,
+    recipient: &Addr,
+    max_slippage: Decimal,
+) -> StdResult<CosmosMsg> {
+    let msg = match funds.as_slice() {
+        [single] => VaultExecuteMsg::AnyDeposit {
+            amount: single.amount,
+            asset: single.denom.clone(),
+            recipient: Some(recipient.to_string()),
+            max_slippage,
+        },
+        _ => VaultExecuteMsg::ExactDeposit {
+            recipient: Some(recipient.to_string()),
+        },
+    };
+    Ok(WasmMsg::Execute {
+        contract_addr: vault.to_string(),
+        msg: to_json_binary(&msg)?,
+        funds,
+    }
+    .into())
+}
+
+pub fn callback_msg(env: &Env, callback: ZapCallback) -> StdResult<CosmosMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_json_binary(&ExecuteMsg::Callback(callback))?,
+        funds: vec![],
+    }
+    .into())
+}
@@ -0,0 +1,11 @@
+pub mod contract;
+mod error;
+pub mod helpers;
+pub mod msg;
+pub mod state;
+pub mod zap;
+
+#[cfg(test)]
+mod test_helpers;
+
+pub use crate::error::ContractError;
@@ -0,0 +1,91 @@
+use cl_vault::msg::RedeemReceiveMsg;
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Coin, Decimal, Uint128};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::state::Config;
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// Address that can update the config
+    pub admin: String,
+    pub dex_router: String,
+    pub swap_max_slippage: Decimal,
+}
+
+#[cw_serde]
+pub struct ZapInTarget {
+    /// Address of the cl-vault to deposit into
+    pub vault: String,
+    /// The part of the sent funds going to this vault, coins that are not a pool token of the
+    /// vault are swapped into its token0 first
+    pub funds: Vec<Coin>,
+    /// The minimum amount of vault shares the recipient has to receive
+    pub min_shares_out: Uint128,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Split the sent funds over the target vaults and deposit them with `recipient`, or the
+    /// sender if not set, as the receiver of the vault shares. The funds of all targets together
+    /// have to match the sent funds exactly.
+    ZapIn {
+        targets: Vec<ZapInTarget>,
+        recipient: Option<String>,
+    },
+    /// Receive the assets of a `RedeemAndCall` on a cl-vault, the message of the redeem is a
+    /// `ZapHookMsg`. The assets arrive with the call, so they never sit in the zap.
+    ReceiveRedeem(RedeemReceiveMsg),
+    /// Steps of a zap that run after the swaps, only callable by the zap contract itself
+    Callback(ZapCallback),
+    UpdateConfig {
+        dex_router: Option<String>,
+        swap_max_slippage: Option<Decimal>,
+    },
+    UpdateAdmin {
+        address: String,
+    },
+}
+
+/// Messages passed to the zap in a `RedeemAndCall` of a cl-vault
+#[cw_serde]
+pub enum ZapHookMsg {
+    /// Deposit the redeemed assets into `to_vault` with `recipient`, or the owner of the redeemed
+    /// shares if not set, as the receiver of the vault shares
+    ZapBetween {
+        to_vault: String,
+        min_shares_out: Uint128,
+        recipient: Option<String>,
+    },
+}
+
+#[cw_serde]
+pub enum ZapCallback {
+    /// Deposit the balance of the vault's pool tokens, minus the `reserved` coins that belong
+    /// to later targets of the same zap
+    Deposit {
+        vault: Addr,
+        recipient: Addr,
+        reserved: Vec<Coin>,
+        min_shares_out: Uint128,
+    },
+    /// Check that the recipient got at least `min_shares_out` shares since `shares_before`
+    AssertMinShares {
+        vault: Addr,
+        recipient: Addr,
+        shares_before: Uint128,
+        min_shares_out: Uint128,
+    },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(Config)]
+    Config {},
+    #[returns(Addr)]
+    Admin {},
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
@@ -0,0 +1,14 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Decimal};
+use cw_storage_plus::Item;
+
+#[cw_serde]
+pub struct Config {
+    /// The dex router used to swap input coins into the pool tokens of the target vaults
+    pub dex_router: Addr,
+    /// The minimum part of the simulated swap return that has to be received, between 0 and 1
+    pub swap_max_slippage: Decimal,
+}
+
+pub const ADMIN: Item<Addr> = Item::new("admin");
+pub const CONFIG: Item<Config> = Item::new("config");
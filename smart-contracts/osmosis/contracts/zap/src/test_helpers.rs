@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use cl_vault::{
+    msg::{ClQueryMsg, ExtensionQueryMsg, QueryMsg as VaultQueryMsg, UserBalanceQueryMsg},
+    query::{PoolResponse, UserSharesBalanceResponse},
+    state::PoolConfig,
+};
+use cosmwasm_std::{
+    from_json,
+    testing::{mock_env, mock_info, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR},
+    to_json_binary, Coin, ContractResult, Decimal, DepsMut, OwnedDeps, SystemError, SystemResult,
+    WasmQuery,
+};
+use dex_router_osmosis::msg::{BestPathForPairResponse, QueryMsg as DexRouterQueryMsg};
+
+use crate::{contract::instantiate, msg::InstantiateMsg};
+
+pub const ADMIN: &str = "admin";
+pub const DEX_ROUTER: &str = "dex_router";
+pub const OSMO: &str = "uosmo";
+pub const USDC: &str = "uusdc";
+pub const ATOM: &str = "uatom";
+/// vault of an OSMO/USDC pool
+pub const VAULT_A: &str = "vault_a";
+/// vault of an ATOM/USDC pool
+pub const VAULT_B: &str = "vault_b";
+
+/// Mock deps with VAULT_A, VAULT_B and a dex router that swaps every pair 1:1. Every user holds
+/// `shares` of a vault.
+pub fn mock_deps_with_vaults(
+    shares: Vec<(&str, u128)>,
+    balances: &[Coin],
+) -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+    let shares: HashMap<String, u128> = shares
+        .into_iter()
+        .map(|(vault, shares)| (vault.to_string(), shares))
+        .collect();
+
+    let mut querier = MockQuerier::new(&[(MOCK_CONTRACT_ADDR, balances)]);
+    querier.update_wasm(move |query| match query {
+        WasmQuery::Smart { contract_addr, msg } if contract_addr == DEX_ROUTER => {
+            match from_json(msg).unwrap() {
+                DexRouterQueryMsg::BestPathForPair { offer, .. } => {
+                    SystemResult::Ok(ContractResult::Ok(
+                        to_json_binary(&Some(BestPathForPairResponse {
+                            path: vec![],
+                            return_amount: offer.amount,
+                        }))
+                        .unwrap(),
+                    ))
+                }
+                _ => unimplemented!(),
+            }
+        }
+        WasmQuery::Smart { contract_addr, msg } => {
+            let pool_config = match contract_addr.as_str() {
+                VAULT_A => PoolConfig {
+                    pool_id: 1,
+                    token0: OSMO.to_string(),
+                    token1: USDC.to_string(),
+                },
+                VAULT_B => PoolConfig {
+                    pool_id: 2,
+                    token0: ATOM.to_string(),
+                    token1: USDC.to_string(),
+                },
+                _ => {
+                    return SystemResult::Err(SystemError::NoSuchContract {
+                        addr: contract_addr.clone(),
+                    })
+                }
+            };
+            let response = match from_json(msg).unwrap() {
+                VaultQueryMsg::VaultExtension(ExtensionQueryMsg::ConcentratedLiquidity(
+                    ClQueryMsg::Pool {},
+                )) => to_json_binary(&PoolResponse { pool_config }),
+                VaultQueryMsg::VaultExtension(ExtensionQueryMsg::Balances(
+                    UserBalanceQueryMsg::UserSharesBalance { .. },
+                )) => to_json_binary(&UserSharesBalanceResponse {
+                    balance: shares
+                        .get(contract_addr)
+                        .copied()
+                        .unwrap_or_default()
+                        .into(),
+                }),
+                _ => unimplemented!(),
+            };
+            SystemResult::Ok(ContractResult::Ok(response.unwrap()))
+        }
+        _ => unimplemented!(),
+    });
+
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier,
+        custom_query_type: Default::default(),
+    }
+}
+
+pub fn instantiate_contract(deps: DepsMut) {
+    instantiate(
+        deps,
+        mock_env(),
+        mock_info(ADMIN, &[]),
+        InstantiateMsg {
+            admin: ADMIN.to_string(),
+            dex_router: DEX_ROUTER.to_string(),
+            swap_max_slippage: Decimal::percent(99),
+        },
+    )
+    .unwrap();
+}
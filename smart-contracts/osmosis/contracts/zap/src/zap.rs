@@ -0,0 +1,491 @@
+use cl_vault::msg::RedeemReceiveMsg;
+use cosmwasm_std::{from_json, Addr, Coin, DepsMut, Env, MessageInfo, Response, Uint128};
+
+use crate::{
+    helpers::{
+        callback_msg, deposit_msg, merge_coins, query_vault_pool, query_vault_shares, swap_msg,
+    },
+    msg::{ZapCallback, ZapHookMsg, ZapInTarget},
+    state::CONFIG,
+    ContractError,
+};
+
+/// Swap the funds of every target into the pool tokens of its vault and deposit them. Every
+/// target is finished with a deposit callback before the swaps of the next target run.
+pub fn execute_zap_in(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    targets: Vec<ZapInTarget>,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    if targets.is_empty() {
+        return Err(ContractError::NoTargets {});
+    }
+    let recipient = recipient.map_or(Ok(info.sender.clone()), |x| deps.api.addr_validate(&x))?;
+    let config = CONFIG.load(deps.storage)?;
+
+    let expected = merge_coins(targets.iter().flat_map(|t| t.funds.iter()))?;
+    let actual = merge_coins(info.funds.iter())?;
+    if expected != actual {
+        return Err(ContractError::FundsMismatch { expected, actual });
+    }
+
+    let mut response = Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "zap_in")
+        .add_attribute("recipient", recipient.as_str());
+
+    for (i, target) in targets.iter().enumerate() {
+        let vault = deps.api.addr_validate(&target.vault)?;
+        let pool = query_vault_pool(&deps.querier, &vault)?;
+
+        for coin in merge_coins(target.funds.iter())? {
+            if !pool.pool_contains_token(&coin.denom) {
+                response =
+                    response.add_message(swap_msg(&deps.querier, &config, coin, &pool.token0)?);
+            }
+        }
+
+        // the funds of later targets are still in the zap when this target deposits
+        let reserved = merge_coins(targets[i + 1..].iter().flat_map(|t| t.funds.iter()))?;
+        response = response.add_message(callback_msg(
+            &env,
+            ZapCallback::Deposit {
+                vault,
+                recipient: recipient.clone(),
+                reserved,
+                min_shares_out: target.min_shares_out,
+            },
+        )?);
+    }
+
+    Ok(response)
+}
+
+/// Handle the assets of a `RedeemAndCall` on a cl-vault, the sender is the redeemed vault
+pub fn execute_receive_redeem(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    receive: RedeemReceiveMsg,
+) -> Result<Response, ContractError> {
+    match from_json(&receive.msg)? {
+        ZapHookMsg::ZapBetween {
+            to_vault,
+            min_shares_out,
+            recipient,
+        } => {
+            let recipient = recipient.unwrap_or(receive.owner);
+            execute_zap_between(deps, env, info, to_vault, min_shares_out, recipient)
+        }
+    }
+}
+
+/// Deposit the assets redeemed from the sending vault into `to_vault`, tokens that are not in
+/// the pool of `to_vault` are swapped into its token0 first. Only the assets sent along are
+/// deposited, any other balance of the zap is reserved.
+fn execute_zap_between(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    to_vault: String,
+    min_shares_out: Uint128,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    let recipient = deps.api.addr_validate(&recipient)?;
+    let config = CONFIG.load(deps.storage)?;
+    let to_vault = deps.api.addr_validate(&to_vault)?;
+    let to_pool = query_vault_pool(&deps.querier, &to_vault)?;
+
+    let redeemed = merge_coins(info.funds.iter())?;
+    if redeemed.is_empty() {
+        return Err(ContractError::NothingToDeposit {
+            vault: to_vault.to_string(),
+        });
+    }
+
+    let mut response = Response::new()
+        .add_attribute("method", "execute")
+        .add_attribute("action", "zap_between")
+        .add_attribute("from_vault", info.sender.as_str())
+        .add_attribute("to_vault", to_vault.as_str())
+        .add_attribute("recipient", recipient.as_str());
+
+    for coin in redeemed.iter() {
+        if !to_pool.pool_contains_token(&coin.denom) {
+            response = response.add_message(swap_msg(
+                &deps.querier,
+                &config,
+                coin.clone(),
+                &to_pool.token0,
+            )?);
+        }
+    }
+
+    // the balance already includes the redeemed assets, anything else is not part of this zap
+    let mut reserved = vec![];
+    for denom in [&to_pool.token0, &to_pool.token1] {
+        let mut balance = deps.querier.query_balance(&env.contract.address, denom)?;
+        if let Some(c) = redeemed.iter().find(|c| &c.denom == denom) {
+            balance.amount = balance.amount.checked_sub(c.amount)?;
+        }
+        if !balance.amount.is_zero() {
+            reserved.push(balance);
+        }
+    }
+
+    Ok(response.add_message(callback_msg(
+        &env,
+        ZapCallback::Deposit {
+            vault: to_vault,
+            recipient,
+            reserved,
+            min_shares_out,
+        },
+    )?))
+}
+
+pub fn execute_callback(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    callback: ZapCallback,
+) -> Result<Response, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match callback {
+        ZapCallback::Deposit {
+            vault,
+            recipient,
+            reserved,
+            min_shares_out,
+        } => handle_deposit_callback(deps, env, vault, recipient, reserved, min_shares_out),
+        ZapCallback::AssertMinShares {
+            vault,
+            recipient,
+            shares_before,
+            min_shares_out,
+        } => {
+            let shares = query_vault_shares(&deps.querier, &vault, &recipient)?;
+            let minted = shares.checked_sub(shares_before)?;
+            if minted < min_shares_out {
+                return Err(ContractError::MinSharesNotReached {
+                    vault: vault.to_string(),
+                    minted,
+                    minimum: min_shares_out,
+                });
+            }
+            Ok(Response::new()
+                .add_attribute("method", "callback")
+                .add_attribute("action", "assert_min_shares")
+                .add_attribute("vault", vault.as_str())
+                .add_attribute("minted_shares", minted))
+        }
+    }
+}
+
+fn handle_deposit_callback(
+    deps: DepsMut,
+    env: Env,
+    vault: Addr,
+    recipient: Addr,
+    reserved: Vec<Coin>,
+    min_shares_out: Uint128,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let pool = query_vault_pool(&deps.querier, &vault)?;
+
+    let mut funds = vec![];
+    for denom in [&pool.token0, &pool.token1] {
+        let mut balance = deps.querier.query_balance(&env.contract.address, denom)?;
+        if let Some(r) = reserved.iter().find(|r| &r.denom == denom) {
+            balance.amount = balance.amount.checked_sub(r.amount)?;
+        }
+        if !balance.amount.is_zero() {
+            funds.push(balance);
+        }
+    }
+    if funds.is_empty() {
+        return Err(ContractError::NothingToDeposit {
+            vault: vault.to_string(),
+        });
+    }
+
+    let shares_before = query_vault_shares(&deps.querier, &vault, &recipient)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "callback")
+        .add_attribute("action", "deposit")
+        .add_attribute("vault", vault.as_str())
+        .add_message(deposit_msg(
+            &vault,
+            funds,
+            &recipient,
+            config.swap_max_slippage,
+        )?)
+        .add_message(callback_msg(
+            &env,
+            ZapCallback::AssertMinShares {
+                vault,
+                recipient,
+                shares_before,
+                min_shares_out,
+            },
+        )?))
+}
+
+#[cfg(test)]
+mod tests {
+    use cl_vault::msg::ExecuteMsg as VaultExecuteMsg;
+    use cosmwasm_std::{
+        coin, from_json,
+        testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR},
+        to_json_binary, CosmosMsg, WasmMsg,
+    };
+    use dex_router_osmosis::msg::ExecuteMsg as DexRouterExecuteMsg;
+
+    use super::*;
+    use crate::{
+        contract::execute,
+        msg::ExecuteMsg,
+        test_helpers::{
+            instantiate_contract, mock_deps_with_vaults, ATOM, DEX_ROUTER, OSMO, USDC, VAULT_A,
+            VAULT_B,
+        },
+    };
+
+    fn wasm_execute(msg: &CosmosMsg) -> (&String, &cosmwasm_std::Binary, &Vec<Coin>) {
+        let CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            msg,
+            funds,
+        }) = msg
+        else {
+            panic!("expected a wasm execute")
+        };
+        (contract_addr, msg, funds)
+    }
+
+    #[test]
+    fn zap_in_swaps_and_deposits_every_target() {
+        let mut deps = mock_deps_with_vaults(vec![], &[]);
+        instantiate_contract(deps.as_mut());
+        let targets = vec![
+            ZapInTarget {
+                vault: VAULT_A.to_string(),
+                funds: vec![coin(600, OSMO)],
+                min_shares_out: Uint128::new(1),
+            },
+            ZapInTarget {
+                vault: VAULT_B.to_string(),
+                funds: vec![coin(400, OSMO), coin(500, ATOM)],
+                min_shares_out: Uint128::new(2),
+            },
+        ];
+
+        // the targets have to use exactly the sent funds
+        let err = execute_zap_in(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[coin(1000, OSMO)]),
+            targets.clone(),
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::FundsMismatch { .. }));
+
+        let response = execute_zap_in(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[coin(500, ATOM), coin(1000, OSMO)]),
+            targets,
+            None,
+        )
+        .unwrap();
+        assert_eq!(response.messages.len(), 3);
+
+        // vault a takes the OSMO as is, the funds of vault b are reserved
+        let (contract_addr, msg, _) = wasm_execute(&response.messages[0].msg);
+        assert_eq!(contract_addr, MOCK_CONTRACT_ADDR);
+        assert_eq!(
+            from_json::<ExecuteMsg>(msg).unwrap(),
+            ExecuteMsg::Callback(ZapCallback::Deposit {
+                vault: Addr::unchecked(VAULT_A),
+                recipient: Addr::unchecked("alice"),
+                reserved: vec![coin(500, ATOM), coin(400, OSMO)],
+                min_shares_out: Uint128::new(1),
+            })
+        );
+
+        // the OSMO of vault b is swapped into ATOM before its deposit
+        let (contract_addr, msg, funds) = wasm_execute(&response.messages[1].msg);
+        assert_eq!(contract_addr, DEX_ROUTER);
+        assert_eq!(funds, &vec![coin(400, OSMO)]);
+        assert_eq!(
+            from_json::<DexRouterExecuteMsg>(msg).unwrap(),
+            DexRouterExecuteMsg::Swap {
+                out_denom: ATOM.to_string(),
+                path: Some(vec![]),
+                minimum_receive: Some(Uint128::new(396)),
+            }
+        );
+        let (_, msg, _) = wasm_execute(&response.messages[2].msg);
+        assert!(matches!(
+            from_json::<ExecuteMsg>(msg).unwrap(),
+            ExecuteMsg::Callback(ZapCallback::Deposit { reserved, .. }) if reserved.is_empty()
+        ));
+    }
+
+    #[test]
+    fn deposit_callback_deposits_unreserved_balance() {
+        let mut deps =
+            mock_deps_with_vaults(vec![(VAULT_A, 10)], &[coin(1000, OSMO), coin(10, USDC)]);
+        instantiate_contract(deps.as_mut());
+        let deposit = |reserved| ZapCallback::Deposit {
+            vault: Addr::unchecked(VAULT_A),
+            recipient: Addr::unchecked("alice"),
+            reserved,
+            min_shares_out: Uint128::new(5),
+        };
+
+        // only the zap itself can call back
+        let err = execute_callback(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            deposit(vec![]),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // both tokens go through an exact deposit
+        let response = execute_callback(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            deposit(vec![coin(400, OSMO)]),
+        )
+        .unwrap();
+        let (contract_addr, msg, funds) = wasm_execute(&response.messages[0].msg);
+        assert_eq!(contract_addr, VAULT_A);
+        assert_eq!(funds, &vec![coin(600, OSMO), coin(10, USDC)]);
+        assert_eq!(
+            from_json::<VaultExecuteMsg>(msg).unwrap(),
+            VaultExecuteMsg::ExactDeposit {
+                recipient: Some("alice".to_string())
+            }
+        );
+        let (_, msg, _) = wasm_execute(&response.messages[1].msg);
+        assert_eq!(
+            from_json::<ExecuteMsg>(msg).unwrap(),
+            ExecuteMsg::Callback(ZapCallback::AssertMinShares {
+                vault: Addr::unchecked(VAULT_A),
+                recipient: Addr::unchecked("alice"),
+                shares_before: Uint128::new(10),
+                min_shares_out: Uint128::new(5),
+            })
+        );
+
+        // a single token goes through an any deposit
+        let response = execute_callback(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            deposit(vec![coin(10, USDC)]),
+        )
+        .unwrap();
+        let (_, msg, funds) = wasm_execute(&response.messages[0].msg);
+        assert_eq!(funds, &vec![coin(1000, OSMO)]);
+        assert!(matches!(
+            from_json::<VaultExecuteMsg>(msg).unwrap(),
+            VaultExecuteMsg::AnyDeposit { .. }
+        ));
+
+        // the recipient holds 10 shares, so at most 4 were minted since 6
+        let err = execute_callback(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            ZapCallback::AssertMinShares {
+                vault: Addr::unchecked(VAULT_A),
+                recipient: Addr::unchecked("alice"),
+                shares_before: Uint128::new(6),
+                min_shares_out: Uint128::new(5),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MinSharesNotReached {
+                vault: VAULT_A.to_string(),
+                minted: Uint128::new(4),
+                minimum: Uint128::new(5),
+            }
+        );
+    }
+
+    #[test]
+    fn zap_between_swaps_tokens_missing_in_target_pool() {
+        // 50 USDC were left in the zap outside of this zap
+        let mut deps = mock_deps_with_vaults(vec![], &[coin(300, OSMO), coin(250, USDC)]);
+        instantiate_contract(deps.as_mut());
+
+        // vault a redeemed the shares of alice and calls the zap with the assets
+        let redeem = RedeemReceiveMsg {
+            owner: "alice".to_string(),
+            shares: Uint128::new(1000),
+            msg: to_json_binary(&ZapHookMsg::ZapBetween {
+                to_vault: VAULT_B.to_string(),
+                min_shares_out: Uint128::new(1),
+                recipient: None,
+            })
+            .unwrap(),
+        };
+        let redeem_msg = redeem
+            .clone()
+            .into_cosmos_msg(
+                MOCK_CONTRACT_ADDR.to_string(),
+                vec![coin(300, OSMO), coin(200, USDC)],
+            )
+            .unwrap();
+        let (_, msg, funds) = wasm_execute(&redeem_msg);
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VAULT_A, funds),
+            from_json(msg).unwrap(),
+        )
+        .unwrap();
+
+        // OSMO is not in the ATOM/USDC pool, USDC is deposited as is
+        assert_eq!(response.messages.len(), 2);
+        let (contract_addr, _, funds) = wasm_execute(&response.messages[0].msg);
+        assert_eq!(contract_addr, DEX_ROUTER);
+        assert_eq!(funds, &vec![coin(300, OSMO)]);
+        let (contract_addr, msg, _) = wasm_execute(&response.messages[1].msg);
+        assert_eq!(contract_addr, MOCK_CONTRACT_ADDR);
+        assert_eq!(
+            from_json::<ExecuteMsg>(msg).unwrap(),
+            ExecuteMsg::Callback(ZapCallback::Deposit {
+                vault: Addr::unchecked(VAULT_B),
+                recipient: Addr::unchecked("alice"),
+                reserved: vec![coin(50, USDC)],
+                min_shares_out: Uint128::new(1),
+            })
+        );
+
+        // a redeem without assets has nothing to deposit
+        let err =
+            execute_receive_redeem(deps.as_mut(), mock_env(), mock_info(VAULT_A, &[]), redeem)
+                .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NothingToDeposit {
+                vault: VAULT_B.to_string()
+            }
+        );
+    }
+}
@@ -145,3 +145,94 @@ pub fn enforce_order_and_version(
     }
     Ok(())
 }
+
+/// The memo of an ICS20 transfer that makes ibc-hooks on the receiving chain execute `msg` on
+/// `contract`, with the transferred funds attached
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct IbcHooksMemo<T> {
+    pub wasm: IbcHooksWasm<T>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct IbcHooksWasm<T> {
+    pub contract: String,
+    pub msg: T,
+}
+
+/// Build the ibc-hooks memo executing `msg` on `contract`, to be used as the memo of a `MsgTransfer`
+pub fn ibc_hooks_memo<T: Serialize>(contract: impl Into<String>, msg: T) -> Result<String, Error> {
+    Ok(serde_json_wasm::to_string(&IbcHooksMemo {
+        wasm: IbcHooksWasm {
+            contract: contract.into(),
+            msg,
+        },
+    })
+    .map_err(|e| cosmwasm_std::StdError::serialize_err("IbcHooksMemo", e))?)
+}
+
+/// The memo of an outgoing ICS20 transfer that makes ibc-hooks on the sending chain notify
+/// `contract` of the ack or timeout through `IbcLifecycleComplete`
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct IbcCallbackMemo {
+    pub ibc_callback: String,
+}
+
+pub fn ibc_callback_memo(contract: impl Into<String>) -> Result<String, Error> {
+    Ok(serde_json_wasm::to_string(&IbcCallbackMemo {
+        ibc_callback: contract.into(),
+    })
+    .map_err(|e| cosmwasm_std::StdError::serialize_err("IbcCallbackMemo", e))?)
+}
+
+/// The sudo message ibc-hooks sends to the `ibc_callback` contract of a transfer once the
+/// transfer is acknowledged or timed out
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum IbcLifecycleComplete {
+    IbcAck {
+        /// the source channel of the transfer
+        channel: String,
+        sequence: u64,
+        ack: String,
+        success: bool,
+    },
+    IbcTimeout {
+        /// the source channel of the transfer
+        channel: String,
+        sequence: u64,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "snake_case")]
+    enum TestMsg {
+        Deposit { recipient: Option<String> },
+    }
+
+    #[test]
+    fn ibc_hooks_memo_json() {
+        let memo = ibc_hooks_memo(
+            "osmo1contract",
+            TestMsg::Deposit {
+                recipient: Some("osmo1user".to_string()),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            memo,
+            r#"{"wasm":{"contract":"osmo1contract","msg":{"deposit":{"recipient":"osmo1user"}}}}"#
+        );
+    }
+
+    #[test]
+    fn ibc_callback_memo_json() {
+        assert_eq!(
+            ibc_callback_memo("osmo1contract").unwrap(),
+            r#"{"ibc_callback":"osmo1contract"}"#
+        );
+    }
+}